@@ -0,0 +1,422 @@
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use anyhow::Context;
+use tower_lsp::lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString, Url};
+use crate::config::{ConfigSources, HqlConfig};
+use crate::report::{self, ReportFormat};
+use crate::{formatter, linter, rules};
+
+/// What to do instead of serving LSP.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CliCommand {
+    /// `hql-ls --stdin-filepath <path> [--format]` reads a buffer from stdin and writes either
+    /// the diagnostics or, with `--format`, the formatted text to stdout. `path` need not exist
+    /// on disk; it only locates the config.
+    Stdin { filepath: PathBuf, format: bool },
+    /// `hql-ls lint <paths...> [--exclude <glob>] [--format text|json|sarif]` lints files on
+    /// disk for CI, searching directories for `.hql` and `.sql` files. Files matching an
+    /// `--exclude` glob or a line of `./.hqlintignore` are skipped. Text reports go to stderr;
+    /// JSON and SARIF go to stdout so they can be redirected to a file.
+    Lint { files: Vec<PathBuf>, exclude: Vec<String>, report: ReportFormat },
+    /// `hql-ls rules [--format text|json]` lists every lint rule with its defaults, to stdout.
+    Rules { report: ReportFormat },
+}
+
+/// Command-line options. Config is resolved per file from the nearest `.hqlint.toml`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CliArgs {
+    pub command: CliCommand,
+    /// Rule codes reported as errors, and so failing the run, whatever their configured severity
+    pub error_on: Vec<String>,
+}
+
+/// Returns `Ok(None)` when no CLI command was given and the server should run.
+///
+/// Outside `lint`, unrecognised arguments are ignored, since clients commonly pass flags
+/// such as `--stdio`.
+pub fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Option<CliArgs>, String> {
+    let mut args = args.into_iter().peekable();
+    if args.next_if(|arg| arg == "rules").is_some() {
+        return parse_rules_args(args).map(Some);
+    }
+    let lint = args.next_if(|arg| arg == "lint").is_some();
+
+    let mut stdin_filepath = None;
+    let mut format = false;
+    let mut report = ReportFormat::Text;
+    let mut error_on = Vec::new();
+    let mut files = Vec::new();
+    let mut exclude = Vec::new();
+
+    while let Some(arg) = args.next() {
+        if let Some(path) = arg.strip_prefix("--stdin-filepath=") {
+            stdin_filepath = Some(PathBuf::from(path));
+        } else if arg == "--stdin-filepath" {
+            let path = args.next().ok_or("--stdin-filepath requires a path")?;
+            stdin_filepath = Some(PathBuf::from(path));
+        } else if lint && (arg == "--format" || arg.starts_with("--format=")) {
+            // In `lint`, `--format` picks the report format rather than formatting code
+            let value = match arg.strip_prefix("--format=") {
+                Some(value) => value.to_string(),
+                None => args.next().ok_or("--format requires text, json or sarif")?,
+            };
+            report = value.parse()?;
+        } else if arg == "--format" {
+            format = true;
+        } else if let Some(codes) = arg.strip_prefix("--error-on=") {
+            error_on.extend(parse_codes(codes)?);
+        } else if arg == "--error-on" {
+            let codes = args.next().ok_or("--error-on requires a rule code")?;
+            error_on.extend(parse_codes(&codes)?);
+        } else if lint && let Some(glob) = arg.strip_prefix("--exclude=") {
+            exclude.push(glob.to_string());
+        } else if lint && arg == "--exclude" {
+            exclude.push(args.next().ok_or("--exclude requires a glob")?);
+        } else if lint && arg.starts_with("--") {
+            return Err(format!("unknown option '{}'", arg));
+        } else if lint {
+            files.push(PathBuf::from(arg));
+        }
+    }
+
+    let command = if lint {
+        if stdin_filepath.is_some() {
+            return Err("lint takes files, not --stdin-filepath".to_string());
+        }
+        if files.is_empty() {
+            return Err("lint requires at least one file".to_string());
+        }
+        CliCommand::Lint { files, exclude, report }
+    } else {
+        match stdin_filepath {
+            Some(filepath) => CliCommand::Stdin { filepath, format },
+            None if format => return Err("--format requires --stdin-filepath".to_string()),
+            None if !error_on.is_empty() => return Err("--error-on requires --stdin-filepath or lint".to_string()),
+            None => return Ok(None),
+        }
+    };
+    Ok(Some(CliArgs { command, error_on }))
+}
+
+fn parse_rules_args(mut args: impl Iterator<Item = String>) -> Result<CliArgs, String> {
+    let mut report = ReportFormat::Text;
+    while let Some(arg) = args.next() {
+        let value = match arg.strip_prefix("--format=") {
+            Some(value) => value.to_string(),
+            None if arg == "--format" => args.next().ok_or("--format requires text or json")?,
+            None => return Err(format!("unknown option '{}'", arg)),
+        };
+        report = match value.parse()? {
+            ReportFormat::Sarif => return Err("rules can be listed as text or json".to_string()),
+            format => format,
+        };
+    }
+    Ok(CliArgs { command: CliCommand::Rules { report }, error_on: Vec::new() })
+}
+
+/// Splits a comma-separated list of rule codes, rejecting unknown ones.
+fn parse_codes(list: &str) -> Result<Vec<String>, String> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|code| !code.is_empty())
+        .map(|code| {
+            if rules::find(code).is_some() {
+                Ok(code.to_string())
+            } else {
+                Err(format!("unknown rule code '{}'", code))
+            }
+        })
+        .collect()
+}
+
+/// Runs the command and returns the process exit code: 1 if linting found errors.
+pub fn run(args: &CliArgs) -> anyhow::Result<i32> {
+    match &args.command {
+        CliCommand::Stdin { filepath, format } => {
+            let mut input = String::new();
+            std::io::stdin().read_to_string(&mut input)?;
+            let (output, code) = process_stdin(args, filepath, *format, &input)?;
+            print!("{}", output);
+            Ok(code)
+        }
+        CliCommand::Lint { files, exclude, report } => {
+            let mut globs = exclude.clone();
+            match std::fs::read_to_string(IGNORE_FILE) {
+                Ok(text) => globs.extend(parse_ignore_file(&text)),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(anyhow::Error::new(e).context(format!("cannot read {}", IGNORE_FILE))),
+            }
+            let (files, skipped) = discover(files, &globs)?;
+            if skipped > 0 {
+                eprintln!("Skipped {} excluded file(s)", skipped);
+            }
+
+            let mut results = Vec::new();
+            for file in &files {
+                let text = std::fs::read_to_string(file).with_context(|| format!("cannot read {}", file.display()))?;
+                let diagnostics = lint_file(args, file, &text)?;
+                results.push((file_url(file)?, diagnostics));
+            }
+
+            match report {
+                ReportFormat::Text => {
+                    let named: Vec<_> = files
+                        .iter()
+                        .zip(&results)
+                        .map(|(file, (_, diagnostics))| (file.as_path(), diagnostics.as_slice()))
+                        .collect();
+                    eprint!("{}", report::text(&named))
+                }
+                ReportFormat::Json => println!("{}", report::json(&results)?),
+                ReportFormat::Sarif => println!("{}", report::sarif(&results)?),
+            }
+            let failed = results.iter().any(|(_, diagnostics)| has_errors(diagnostics));
+            Ok(i32::from(failed))
+        }
+        CliCommand::Rules { report } => {
+            let listing = rules::listing();
+            if *report == ReportFormat::Json {
+                println!("{}", serde_json::to_string_pretty(&listing)?);
+            } else {
+                for rule in listing {
+                    let enabled = if rule.enabled_by_default { "on" } else { "off" };
+                    println!("{:<26} {:<7} {:<3} {}", rule.id, rule.default_severity, enabled, rule.description);
+                }
+            }
+            Ok(0)
+        }
+    }
+}
+
+/// Globs of files for `lint` to skip, one per line, read from the working directory.
+const IGNORE_FILE: &str = ".hqlintignore";
+
+/// Extensions of the files `lint` picks up when searching a directory.
+const LINTED_EXTENSIONS: &[&str] = &["hql", "sql"];
+
+/// The globs in an ignore file, skipping blank lines and `#` comments.
+fn parse_ignore_file(text: &str) -> Vec<String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Expands directories in `paths` into the files under them with a linted extension, in
+/// path order, and drops files matching any of `globs`. Returns the files to lint and how
+/// many were skipped.
+fn discover(paths: &[PathBuf], globs: &[String]) -> anyhow::Result<(Vec<PathBuf>, usize)> {
+    let globs = globs.iter().map(|glob| glob_regex(glob)).collect::<anyhow::Result<Vec<_>>>()?;
+    let cwd = std::env::current_dir()?;
+    let mut files = Vec::new();
+    let mut skipped = 0;
+    for path in paths {
+        let mut found = Vec::new();
+        if path.is_dir() {
+            collect_files(path, &mut found)?;
+            found.sort();
+        } else {
+            found.push(path.clone());
+        }
+        for file in found {
+            if is_excluded(&file, &cwd, &globs) {
+                skipped += 1;
+            } else {
+                files.push(file);
+            }
+        }
+    }
+    Ok((files, skipped))
+}
+
+/// Symlinked directories aren't followed, so a link back up the tree can't loop; symlinked
+/// files are linted like any other.
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> anyhow::Result<()> {
+    for entry in std::fs::read_dir(dir).with_context(|| format!("cannot read {}", dir.display()))? {
+        let entry = entry?;
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            collect_files(&path, files)?;
+        } else if path.extension().and_then(|ext| ext.to_str()).is_some_and(|ext| LINTED_EXTENSIONS.contains(&ext)) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Compiles a `.gitignore`-style glob: `*` and `?` stay within a path segment and `**`
+/// crosses segments. A glob without a `/` (other than a trailing one) matches a file or
+/// directory name at any depth; one with a `/` matches from the start of the path.
+fn glob_regex(glob: &str) -> anyhow::Result<regex::Regex> {
+    let glob = glob.trim_end_matches('/');
+    let anchored = glob.contains('/');
+    let glob = glob.trim_start_matches("./").trim_start_matches('/');
+    let mut pattern = String::from(if anchored { "^" } else { "(^|/)" });
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.next_if_eq(&'*').is_some() => {
+                if chars.next_if_eq(&'/').is_some() {
+                    pattern.push_str("(.*/)?");
+                } else {
+                    pattern.push_str(".*");
+                }
+            }
+            '*' => pattern.push_str("[^/]*"),
+            '?' => pattern.push_str("[^/]"),
+            c => pattern.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    // Matching a directory excludes everything under it
+    pattern.push_str("(/|$)");
+    regex::Regex::new(&pattern).with_context(|| format!("invalid glob '{}'", glob))
+}
+
+/// Whether `file`, relative to `cwd` where it is under it, matches any of `globs`.
+fn is_excluded(file: &Path, cwd: &Path, globs: &[regex::Regex]) -> bool {
+    let relative = file.strip_prefix(cwd).unwrap_or(file);
+    let path = relative
+        .components()
+        .filter(|component| !matches!(component, std::path::Component::CurDir))
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+    globs.iter().any(|glob| glob.is_match(&path))
+}
+
+fn process_stdin(args: &CliArgs, filepath: &Path, format: bool, input: &str) -> anyhow::Result<(String, i32)> {
+    if format {
+        let config = resolve_config(filepath)?;
+        if !config.formatting.enabled {
+            return Ok((input.to_string(), 0));
+        }
+        return Ok((formatter::format_string(input, &formatter::default_options(), &config.formatting), 0));
+    }
+
+    let diagnostics = lint_file(args, filepath, input)?;
+    let failed = has_errors(&diagnostics);
+    let output = report::text(&[(filepath, &diagnostics)]);
+    Ok((output, i32::from(failed)))
+}
+
+fn file_url(path: &Path) -> anyhow::Result<Url> {
+    let path = std::env::current_dir()?.join(path);
+    Url::from_file_path(&path).map_err(|()| anyhow::anyhow!("cannot convert {} to a URL", path.display()))
+}
+
+/// The effective config for `path`: defaults overlaid with the nearest `.hqlint.toml`.
+fn resolve_config(path: &Path) -> anyhow::Result<HqlConfig> {
+    let path = std::env::current_dir()?.join(path);
+    let mut sources = ConfigSources::for_file(&path);
+    sources.reload_file()?;
+    Ok(sources.resolve()?)
+}
+
+/// Lints `text` with the config for `path`, applying `--error-on`, sorted by position.
+fn lint_file(args: &CliArgs, path: &Path, text: &str) -> anyhow::Result<Vec<Diagnostic>> {
+    let config = resolve_config(path)?;
+    let mut diagnostics = linter::lint(text, &config.linting);
+    linter::resolve_related(&file_url(path)?, &mut diagnostics);
+    for diagnostic in &mut diagnostics {
+        if let Some(NumberOrString::String(code)) = &diagnostic.code
+            && args.error_on.contains(code)
+        {
+            diagnostic.severity = Some(DiagnosticSeverity::ERROR);
+        }
+    }
+    diagnostics.sort_by_key(|d| d.range.start);
+    Ok(diagnostics)
+}
+
+fn has_errors(diagnostics: &[Diagnostic]) -> bool {
+    diagnostics.iter().any(|d| d.severity == Some(DiagnosticSeverity::ERROR))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Result<Option<CliArgs>, String> {
+        parse_args(list.iter().map(|a| a.to_string()))
+    }
+
+    #[test]
+    fn test_parse_args() {
+        assert_eq!(args(&["--stdio"]), Ok(None));
+        assert_eq!(
+            args(&["--stdin-filepath", "q/a.hql", "--format"]).unwrap().unwrap().command,
+            CliCommand::Stdin { filepath: PathBuf::from("q/a.hql"), format: true }
+        );
+        assert_eq!(
+            args(&["--stdin-filepath=a.hql"]).unwrap().unwrap().command,
+            CliCommand::Stdin { filepath: PathBuf::from("a.hql"), format: false }
+        );
+        assert_eq!(
+            args(&["--stdin-filepath", "a.hql", "--error-on", "select-star,missing-comma", "--error-on=trailing-comma"])
+                .unwrap()
+                .unwrap()
+                .error_on,
+            vec!["select-star", "missing-comma", "trailing-comma"]
+        );
+        assert!(args(&["--stdin-filepath", "a.hql", "--error-on", "no-such-rule"]).is_err());
+        assert!(args(&["--stdin-filepath"]).is_err());
+        assert!(args(&["--format"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_lint_args() {
+        let parsed = args(&["lint", "a.hql", "--error-on", "select-star", "b/c.hql"]).unwrap().unwrap();
+        assert_eq!(
+            parsed.command,
+            CliCommand::Lint {
+                files: vec![PathBuf::from("a.hql"), PathBuf::from("b/c.hql")],
+                exclude: Vec::new(),
+                report: ReportFormat::Text
+            }
+        );
+        assert_eq!(parsed.error_on, vec!["select-star"]);
+        assert_eq!(
+            args(&["lint", "--format", "sarif", "a.hql"]).unwrap().unwrap().command,
+            CliCommand::Lint { files: vec![PathBuf::from("a.hql")], exclude: Vec::new(), report: ReportFormat::Sarif }
+        );
+        assert_eq!(
+            args(&["lint", "queries", "--exclude", "generated/**", "--exclude=*_gen.hql"]).unwrap().unwrap().command,
+            CliCommand::Lint {
+                files: vec![PathBuf::from("queries")],
+                exclude: vec!["generated/**".to_string(), "*_gen.hql".to_string()],
+                report: ReportFormat::Text
+            }
+        );
+        assert!(args(&["lint", "a.hql", "--exclude"]).is_err());
+        assert!(args(&["lint", "--format=xml", "a.hql"]).is_err());
+        assert!(args(&["lint"]).is_err());
+        assert!(args(&["lint", "a.hql", "--stdio"]).is_err());
+    }
+
+    #[test]
+    fn test_exclude_globs() {
+        let excluded = |glob: &str, path: &str| {
+            let globs = vec![glob_regex(glob).unwrap()];
+            is_excluded(Path::new(path), Path::new("/work"), &globs)
+        };
+        assert!(excluded("*_gen.hql", "queries/daily_gen.hql"));
+        assert!(!excluded("*_gen.hql", "queries/daily.hql"));
+        assert!(excluded("vendor/", "queries/vendor/lib.hql"));
+        assert!(excluded("queries/generated/**", "./queries/generated/a/b.hql"));
+        assert!(excluded("queries/generated", "/work/queries/generated/b.hql"));
+        assert!(!excluded("queries/generated", "other/queries/generated/b.hql"));
+        assert!(excluded("**/tmp?.sql", "a/b/tmp1.sql"));
+        assert!(!excluded("q/*.hql", "q/sub/a.hql"));
+
+        assert_eq!(parse_ignore_file("# generated\n\nvendor/\n  *_gen.hql \n"), vec!["vendor/", "*_gen.hql"]);
+    }
+
+    #[test]
+    fn test_parse_rules_args() {
+        assert_eq!(args(&["rules"]).unwrap().unwrap().command, CliCommand::Rules { report: ReportFormat::Text });
+        assert_eq!(args(&["rules", "--format=json"]).unwrap().unwrap().command, CliCommand::Rules { report: ReportFormat::Json });
+        assert!(args(&["rules", "--format", "sarif"]).is_err());
+        assert!(args(&["rules", "a.hql"]).is_err());
+    }
+}
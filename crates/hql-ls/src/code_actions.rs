@@ -0,0 +1,701 @@
+use std::collections::{HashMap, HashSet};
+use tower_lsp::lsp_types::{
+    CodeAction, CodeActionKind, CodeActionOrCommand, Diagnostic, FormattingOptions, NumberOrString, Position, Range,
+    TextEdit, Url, WorkspaceEdit,
+};
+use ropey::Rope;
+use crate::config::{HqlConfig, LintingConfig};
+use crate::document;
+use crate::formatter;
+use crate::linter;
+use crate::schema::Schema;
+
+pub fn get_code_actions(
+    uri: &Url,
+    rope: &Rope,
+    range: Range,
+    diagnostics: &[Diagnostic],
+    config: &LintingConfig,
+) -> Vec<CodeActionOrCommand> {
+    let mut actions = Vec::new();
+
+    let casing_diagnostics: Vec<&Diagnostic> = diagnostics
+        .iter()
+        .filter(|d| has_code(d, "keyword-casing") && ranges_overlap(&d.range, &range))
+        .collect();
+
+    // Literal casing diagnostics carry their replacement text in `data`
+    for diagnostic in diagnostics
+        .iter()
+        .filter(|d| has_code(d, "boolean-literal-casing") && ranges_overlap(&d.range, &range))
+    {
+        let Some(edit) = fix_edit(rope, diagnostic) else { continue };
+        actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+            title: format!("Change '{}' to '{}'", text_in_range(rope, diagnostic.range).unwrap_or_default(), edit.new_text),
+            kind: Some(CodeActionKind::QUICKFIX),
+            diagnostics: Some(vec![diagnostic.clone()]),
+            edit: Some(workspace_edit(uri, vec![edit])),
+            is_preferred: Some(true),
+            ..Default::default()
+        }));
+    }
+
+    // Redundant casts carry the bare operand to replace the whole `CAST(...)` with
+    for diagnostic in diagnostics
+        .iter()
+        .filter(|d| has_code(d, "redundant-cast") && ranges_overlap(&d.range, &range))
+    {
+        let Some(edit) = fix_edit(rope, diagnostic) else { continue };
+        actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+            title: "Remove redundant cast".to_string(),
+            kind: Some(CodeActionKind::QUICKFIX),
+            diagnostics: Some(vec![diagnostic.clone()]),
+            edit: Some(workspace_edit(uri, vec![edit])),
+            is_preferred: Some(true),
+            ..Default::default()
+        }));
+    }
+
+    for diagnostic in diagnostics
+        .iter()
+        .filter(|d| has_code(d, "no-tabs") && ranges_overlap(&d.range, &range))
+    {
+        let Some(edit) = fix_edit(rope, diagnostic) else { continue };
+        actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+            title: "Replace tab with spaces".to_string(),
+            kind: Some(CodeActionKind::QUICKFIX),
+            diagnostics: Some(vec![diagnostic.clone()]),
+            edit: Some(workspace_edit(uri, vec![edit])),
+            is_preferred: Some(true),
+            ..Default::default()
+        }));
+    }
+
+    for diagnostic in diagnostics
+        .iter()
+        .filter(|d| has_code(d, "cte-missing-as") && ranges_overlap(&d.range, &range))
+    {
+        let Some(edit) = fix_edit(rope, diagnostic) else { continue };
+        actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+            title: "Insert AS".to_string(),
+            kind: Some(CodeActionKind::QUICKFIX),
+            diagnostics: Some(vec![diagnostic.clone()]),
+            edit: Some(workspace_edit(uri, vec![edit])),
+            is_preferred: Some(true),
+            ..Default::default()
+        }));
+    }
+
+    for diagnostic in diagnostics
+        .iter()
+        .filter(|d| has_code(d, "type-casing") && ranges_overlap(&d.range, &range))
+    {
+        let Some(edit) = fix_edit(rope, diagnostic) else { continue };
+        actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+            title: format!("Change '{}' to uppercase", text_in_range(rope, diagnostic.range).unwrap_or_default()),
+            kind: Some(CodeActionKind::QUICKFIX),
+            diagnostics: Some(vec![diagnostic.clone()]),
+            edit: Some(workspace_edit(uri, vec![edit])),
+            is_preferred: Some(true),
+            ..Default::default()
+        }));
+    }
+
+    for diagnostic in diagnostics
+        .iter()
+        .filter(|d| has_code(d, "batch-separator") && ranges_overlap(&d.range, &range))
+    {
+        let Some(edit) = fix_edit(rope, diagnostic) else { continue };
+        actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+            title: "Remove GO line".to_string(),
+            kind: Some(CodeActionKind::QUICKFIX),
+            diagnostics: Some(vec![diagnostic.clone()]),
+            edit: Some(workspace_edit(uri, vec![edit])),
+            is_preferred: Some(true),
+            ..Default::default()
+        }));
+    }
+
+    // Backticks make reserved words and keywords usable as identifiers
+    for diagnostic in diagnostics
+        .iter()
+        .filter(|d| (has_code(d, "reserved-identifier") || has_code(d, "keyword-alias")) && ranges_overlap(&d.range, &range))
+    {
+        let Some(edit) = fix_edit(rope, diagnostic) else { continue };
+        actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+            title: format!("Quote '{}' with backticks", text_in_range(rope, diagnostic.range).unwrap_or_default()),
+            kind: Some(CodeActionKind::QUICKFIX),
+            diagnostics: Some(vec![diagnostic.clone()]),
+            edit: Some(workspace_edit(uri, vec![edit])),
+            is_preferred: Some(true),
+            ..Default::default()
+        }));
+    }
+
+    // The heuristic only fires across line breaks, so the comma goes at the end of the first line
+    for diagnostic in diagnostics
+        .iter()
+        .filter(|d| has_code(d, "missing-comma") && ranges_overlap(&d.range, &range))
+    {
+        let Some(edit) = fix_edit(rope, diagnostic) else { continue };
+        actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+            title: "Insert missing comma".to_string(),
+            kind: Some(CodeActionKind::QUICKFIX),
+            diagnostics: Some(vec![diagnostic.clone()]),
+            edit: Some(workspace_edit(uri, vec![edit])),
+            is_preferred: Some(true),
+            ..Default::default()
+        }));
+    }
+
+    let semicolon_diagnostics: Vec<&Diagnostic> = diagnostics
+        .iter()
+        .filter(|d| has_code(d, "missing-semicolon") && ranges_overlap(&d.range, &range))
+        .collect();
+    for diagnostic in &semicolon_diagnostics {
+        actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+            title: "Insert missing semicolon".to_string(),
+            kind: Some(CodeActionKind::QUICKFIX),
+            diagnostics: Some(vec![(*diagnostic).clone()]),
+            edit: Some(workspace_edit(uri, vec![semicolon_edit(diagnostic.range)])),
+            is_preferred: Some(true),
+            ..Default::default()
+        }));
+    }
+
+    for diagnostic in &casing_diagnostics {
+        if let Some(edit) = uppercase_edit(rope, diagnostic.range) {
+            actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+                title: format!("Change '{}' to uppercase", text_in_range(rope, diagnostic.range).unwrap_or_default()),
+                kind: Some(CodeActionKind::QUICKFIX),
+                diagnostics: Some(vec![(*diagnostic).clone()]),
+                edit: Some(workspace_edit(uri, vec![edit])),
+                is_preferred: Some(true),
+                ..Default::default()
+            }));
+        }
+    }
+
+    // The fix-all action re-lints so it also covers keywords outside the requested range
+    if !casing_diagnostics.is_empty() {
+        let all_diagnostics = linter::lint(&rope.to_string(), config);
+        let edits: Vec<TextEdit> = all_diagnostics
+            .iter()
+            .filter(|d| has_code(d, "keyword-casing"))
+            .filter_map(|d| uppercase_edit(rope, d.range))
+            .collect();
+
+        if !edits.is_empty() {
+            actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+                title: "Fix all keyword casing in file".to_string(),
+                kind: Some(CodeActionKind::QUICKFIX),
+                edit: Some(workspace_edit(uri, edits)),
+                ..Default::default()
+            }));
+        }
+    }
+
+    if !semicolon_diagnostics.is_empty() {
+        let edits: Vec<TextEdit> = linter::lint(&rope.to_string(), config)
+            .iter()
+            .filter(|d| has_code(d, "missing-semicolon"))
+            .map(|d| semicolon_edit(d.range))
+            .collect();
+
+        if edits.len() > 1 {
+            actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+                title: "Add all missing semicolons".to_string(),
+                kind: Some(CodeActionKind::QUICKFIX),
+                edit: Some(workspace_edit(uri, edits)),
+                ..Default::default()
+            }));
+        }
+    }
+
+    actions
+}
+
+/// Escape hatches for any of this server's diagnostics with a code, those whose source is the
+/// configured `source`: a `-- hqlint-disable-next-line <code>` comment above its line, or a
+/// `-- hqlint-disable <code>` comment at the top of the file.
+pub fn disable_actions(uri: &Url, rope: &Rope, range: Range, diagnostics: &[Diagnostic], source: &str) -> Vec<CodeActionOrCommand> {
+    let mut actions = Vec::new();
+    let mut seen_lines = HashSet::new();
+    let mut seen_files = HashSet::new();
+
+    let ours = diagnostics.iter().filter(|d| d.source.as_deref() == Some(source));
+    for diagnostic in ours.filter(|d| ranges_overlap(&d.range, &range)) {
+        let Some(NumberOrString::String(code)) = &diagnostic.code else { continue };
+        let line = diagnostic.range.start.line;
+
+        if seen_lines.insert((line, code.clone())) && (line as usize) < rope.len_lines() {
+            // Match the line's indentation so the comment sits with the code it silences
+            let indent: String = rope.line(line as usize).chars().take_while(|c| *c == ' ' || *c == '\t').collect();
+            let start = Position { line, character: 0 };
+            actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+                title: format!("Disable {} for this line", code),
+                kind: Some(CodeActionKind::QUICKFIX),
+                diagnostics: Some(vec![diagnostic.clone()]),
+                edit: Some(workspace_edit(uri, vec![TextEdit {
+                    range: Range { start, end: start },
+                    new_text: format!("{}-- hqlint-disable-next-line {}\n", indent, code),
+                }])),
+                ..Default::default()
+            }));
+        }
+
+        if seen_files.insert(code.clone()) {
+            let start = Position { line: 0, character: 0 };
+            actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+                title: format!("Disable {} for this file", code),
+                kind: Some(CodeActionKind::QUICKFIX),
+                diagnostics: Some(vec![diagnostic.clone()]),
+                edit: Some(workspace_edit(uri, vec![TextEdit {
+                    range: Range { start, end: start },
+                    new_text: format!("-- hqlint-disable {}\n", code),
+                }])),
+                ..Default::default()
+            }));
+        }
+    }
+
+    actions
+}
+
+/// True if a client asking only for `only` kinds (all kinds if unset) wants `kind`, e.g.
+/// `source` covers `source.fixAll`.
+pub fn kind_requested(only: Option<&[CodeActionKind]>, kind: &CodeActionKind) -> bool {
+    only.is_none_or(|only| {
+        only.iter().any(|requested| {
+            kind.as_str() == requested.as_str() || kind.as_str().starts_with(&format!("{}.", requested.as_str()))
+        })
+    })
+}
+
+/// `source.fixAll`: applies every auto-fix in the file, then formats it if formatting is
+/// enabled, as a single edit replacing the document. `None` if that changes nothing.
+/// `schema` is the workspace's, so the fixes match the published diagnostics.
+pub fn fix_all(uri: &Url, rope: &Rope, config: &HqlConfig, schema: Option<&Schema>) -> Option<CodeActionOrCommand> {
+    let original = rope.to_string();
+    let mut fixes: Vec<(usize, usize, String)> = linter::lint_with_schema(&original, &config.linting, schema)
+        .iter()
+        .filter_map(|d| fix_edit(rope, d))
+        .map(|edit| {
+            let start = document::position_to_char(rope, edit.range.start);
+            let end = document::position_to_char(rope, edit.range.end);
+            (start, end, edit.new_text)
+        })
+        .collect();
+
+    // Of two overlapping fixes the first wins
+    fixes.sort_by_key(|&(start, end, _)| (start, end));
+    let mut kept = Vec::new();
+    let mut kept_end = 0;
+    for (start, end, new_text) in fixes {
+        if start < kept_end {
+            continue;
+        }
+        kept_end = end;
+        kept.push((start, end, new_text));
+    }
+
+    // Apply from the end so earlier offsets stay valid
+    let mut fixed = rope.clone();
+    for (start, end, new_text) in kept.into_iter().rev() {
+        fixed.remove(start..end);
+        fixed.insert(start, &new_text);
+    }
+
+    let mut text = fixed.to_string();
+    if config.formatting.enabled {
+        let options = FormattingOptions { tab_size: 2, insert_spaces: true, ..Default::default() };
+        text = formatter::format_string(&text, &options, &config.formatting);
+    }
+    if text == original {
+        return None;
+    }
+
+    let range = Range { start: Position { line: 0, character: 0 }, end: formatter::document_end(&original) };
+    Some(CodeActionOrCommand::CodeAction(CodeAction {
+        title: "Fix all auto-fixable problems".to_string(),
+        kind: Some(CodeActionKind::SOURCE_FIX_ALL),
+        edit: Some(workspace_edit(uri, vec![TextEdit { range, new_text: text }])),
+        ..Default::default()
+    }))
+}
+
+/// The edit that fixes `diagnostic`, for the rules that have one.
+fn fix_edit(rope: &Rope, diagnostic: &Diagnostic) -> Option<TextEdit> {
+    let Some(NumberOrString::String(code)) = &diagnostic.code else { return None };
+    let range = diagnostic.range;
+    let data = diagnostic.data.as_ref().and_then(|data| data.as_str());
+    match code.as_str() {
+        "keyword-casing" | "type-casing" => uppercase_edit(rope, range),
+        // Literal casing carries the replacement, redundant casts the bare operand and
+        // tabs the spaces to use
+        "boolean-literal-casing" | "redundant-cast" | "no-tabs" => {
+            Some(TextEdit { range, new_text: data?.to_string() })
+        }
+        "trailing-whitespace" => Some(TextEdit { range, new_text: String::new() }),
+        "cte-missing-as" => Some(TextEdit { range: Range { start: range.end, end: range.end }, new_text: " AS".to_string() }),
+        "missing-comma" => Some(TextEdit { range: Range { start: range.start, end: range.start }, new_text: ",".to_string() }),
+        "missing-semicolon" => Some(semicolon_edit(range)),
+        // GO stands alone on its line, so the whole line goes
+        "batch-separator" => Some(TextEdit {
+            range: Range {
+                start: Position { line: range.start.line, character: 0 },
+                end: Position { line: range.start.line + 1, character: 0 },
+            },
+            new_text: String::new(),
+        }),
+        "reserved-identifier" | "keyword-alias" => {
+            Some(TextEdit { range, new_text: format!("`{}`", text_in_range(rope, range)?) })
+        }
+        _ => None,
+    }
+}
+
+/// The diagnostic is an empty range at the end of the unterminated statement.
+fn semicolon_edit(range: Range) -> TextEdit {
+    TextEdit { range: Range { start: range.start, end: range.start }, new_text: ";".to_string() }
+}
+
+fn uppercase_edit(rope: &Rope, range: Range) -> Option<TextEdit> {
+    let text = text_in_range(rope, range)?;
+    Some(TextEdit {
+        range,
+        new_text: text.to_uppercase(),
+    })
+}
+
+fn workspace_edit(uri: &Url, edits: Vec<TextEdit>) -> WorkspaceEdit {
+    let mut changes = HashMap::new();
+    changes.insert(uri.clone(), edits);
+    WorkspaceEdit {
+        changes: Some(changes),
+        ..Default::default()
+    }
+}
+
+fn has_code(diagnostic: &Diagnostic, code: &str) -> bool {
+    matches!(&diagnostic.code, Some(NumberOrString::String(c)) if c == code)
+}
+
+fn ranges_overlap(a: &Range, b: &Range) -> bool {
+    a.start <= b.end && b.start <= a.end
+}
+
+fn text_in_range(rope: &Rope, range: Range) -> Option<String> {
+    let start = document::position_to_char(rope, range.start);
+    let end = document::position_to_char(rope, range.end);
+    if start > end {
+        return None;
+    }
+    Some(rope.slice(start..end).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tower_lsp::lsp_types::Position;
+    use crate::config::LintingRules;
+
+    fn casing_config() -> LintingConfig {
+        LintingConfig {
+            rules: LintingRules {
+                keyword_casing: true.into(),
+                ..LintingRules::default()
+            },
+            ..LintingConfig::default()
+        }
+    }
+
+    fn edits_of(action: &CodeActionOrCommand) -> Vec<TextEdit> {
+        match action {
+            CodeActionOrCommand::CodeAction(a) => a
+                .edit
+                .as_ref()
+                .and_then(|e| e.changes.as_ref())
+                .map(|c| c.values().flatten().cloned().collect())
+                .unwrap_or_default(),
+            CodeActionOrCommand::Command(_) => vec![],
+        }
+    }
+
+    #[test]
+    fn test_keyword_casing_quick_fix() {
+        let uri = Url::parse("file:///test.hql").unwrap();
+        let text = "select * from users;";
+        let rope = Rope::from_str(text);
+        let config = casing_config();
+        let diagnostics = linter::lint(text, &config);
+        let first = diagnostics.iter().find(|d| has_code(d, "keyword-casing")).unwrap();
+
+        let actions = get_code_actions(&uri, &rope, first.range, std::slice::from_ref(first), &config);
+
+        let quick_fix = edits_of(&actions[0]);
+        assert_eq!(quick_fix.len(), 1);
+        assert_eq!(quick_fix[0].new_text, "SELECT");
+
+        let fix_all = edits_of(actions.last().unwrap());
+        let texts: Vec<&str> = fix_all.iter().map(|e| e.new_text.as_str()).collect();
+        assert_eq!(texts, vec!["SELECT", "FROM"]);
+    }
+
+    #[test]
+    fn test_no_actions_outside_range() {
+        let uri = Url::parse("file:///test.hql").unwrap();
+        let text = "SELECT *\nfrom users;";
+        let rope = Rope::from_str(text);
+        let config = casing_config();
+        let diagnostics = linter::lint(text, &config);
+        let range = Range::default();
+
+        let actions = get_code_actions(&uri, &rope, range, &diagnostics, &config);
+        assert!(actions.is_empty());
+    }
+
+    #[test]
+    fn test_boolean_literal_casing_quick_fix() {
+        let uri = Url::parse("file:///test.hql").unwrap();
+        let text = "SELECT true FROM t;";
+        let rope = Rope::from_str(text);
+        let config = LintingConfig {
+            rules: LintingRules {
+                boolean_literal_casing: true.into(),
+                ..LintingRules::default()
+            },
+            ..LintingConfig::default()
+        };
+        let diagnostics = linter::lint(text, &config);
+
+        let actions = get_code_actions(&uri, &rope, diagnostics[0].range, &diagnostics, &config);
+        assert_eq!(actions.len(), 1);
+        let edits = edits_of(&actions[0]);
+        assert_eq!(edits[0].new_text, "TRUE");
+    }
+
+    #[test]
+    fn test_redundant_cast_quick_fix() {
+        let uri = Url::parse("file:///test.hql").unwrap();
+        let text = "SELECT CAST(o.id AS INT) FROM orders o;";
+        let rope = Rope::from_str(text);
+        let config = LintingConfig::default();
+        let schema = serde_json::from_str(r#"{ "tables": [ { "name": "orders", "columns": [ { "name": "id", "type": "int" } ] } ] }"#).unwrap();
+        let diagnostics = linter::lint_with_schema(text, &config, Some(&schema));
+        let cast = diagnostics.iter().find(|d| has_code(d, "redundant-cast")).unwrap();
+
+        let actions = get_code_actions(&uri, &rope, cast.range, std::slice::from_ref(cast), &config);
+        assert_eq!(actions.len(), 1);
+        let edits = edits_of(&actions[0]);
+        assert_eq!(edits[0].range, cast.range);
+        assert_eq!(edits[0].new_text, "o.id");
+    }
+
+    #[test]
+    fn test_cte_missing_as_quick_fix() {
+        let uri = Url::parse("file:///test.hql").unwrap();
+        let text = "WITH a (SELECT 1)\nSELECT * FROM a;";
+        let rope = Rope::from_str(text);
+        let config = LintingConfig::default();
+        let diagnostics = linter::lint(text, &config);
+        let missing = diagnostics.iter().find(|d| has_code(d, "cte-missing-as")).unwrap();
+
+        let actions = get_code_actions(&uri, &rope, missing.range, std::slice::from_ref(missing), &config);
+        assert_eq!(actions.len(), 1);
+        let edits = edits_of(&actions[0]);
+        assert_eq!(edits[0].range, Range { start: missing.range.end, end: missing.range.end });
+        assert_eq!(edits[0].new_text, " AS");
+    }
+
+    #[test]
+    fn test_keyword_alias_quick_fix() {
+        let uri = Url::parse("file:///test.hql").unwrap();
+        let text = "SELECT sum(x) AS count FROM t;";
+        let rope = Rope::from_str(text);
+        let config = LintingConfig::default();
+        let diagnostics = linter::lint(text, &config);
+        let alias = diagnostics.iter().find(|d| has_code(d, "keyword-alias")).unwrap();
+
+        let actions = get_code_actions(&uri, &rope, alias.range, std::slice::from_ref(alias), &config);
+        assert_eq!(actions.len(), 1);
+        let CodeActionOrCommand::CodeAction(action) = &actions[0] else { panic!("expected a code action") };
+        assert_eq!(action.title, "Quote 'count' with backticks");
+        let edits = edits_of(&actions[0]);
+        assert_eq!(edits[0].range, alias.range);
+        assert_eq!(edits[0].new_text, "`count`");
+    }
+
+    #[test]
+    fn test_no_tabs_quick_fix() {
+        let uri = Url::parse("file:///test.hql").unwrap();
+        let text = "SELECT\ta FROM t;";
+        let rope = Rope::from_str(text);
+        let mut config = LintingConfig::default();
+        config.rules.no_tabs = true.into();
+        config.rules.no_tabs.options.insert("tabWidth".to_string(), 2.into());
+        let diagnostics = linter::lint(text, &config);
+        let tab = diagnostics.iter().find(|d| has_code(d, "no-tabs")).unwrap();
+
+        let actions = get_code_actions(&uri, &rope, tab.range, std::slice::from_ref(tab), &config);
+        assert_eq!(actions.len(), 1);
+        let edits = edits_of(&actions[0]);
+        assert_eq!(edits[0].range, tab.range);
+        assert_eq!(edits[0].new_text, "  ");
+    }
+
+    #[test]
+    fn test_missing_semicolon_quick_fixes() {
+        let uri = Url::parse("file:///test.hql").unwrap();
+        let text = "SELECT 1\nSELECT 2\nSELECT 3";
+        let rope = Rope::from_str(text);
+        let config = LintingConfig::default();
+        let diagnostics = linter::lint(text, &config);
+        let first = diagnostics.iter().find(|d| has_code(d, "missing-semicolon")).unwrap();
+
+        let actions = get_code_actions(&uri, &rope, first.range, std::slice::from_ref(first), &config);
+        assert_eq!(actions.len(), 2);
+        let edits = edits_of(&actions[0]);
+        assert_eq!(edits[0].range, Range { start: Position { line: 0, character: 8 }, end: Position { line: 0, character: 8 } });
+        assert_eq!(edits[0].new_text, ";");
+
+        // The last statement ends at EOF with no trailing newline
+        let all = edits_of(&actions[1]);
+        assert_eq!(all.len(), 3);
+        assert_eq!(all[2].range.start, Position { line: 2, character: 8 });
+    }
+
+    #[test]
+    fn test_missing_comma_quick_fix() {
+        let uri = Url::parse("file:///test.hql").unwrap();
+        let text = "SELECT\n id\n name\nFROM t;";
+        let mut rope = Rope::from_str(text);
+        let mut config = LintingConfig::default();
+        config.rules.missing_comma = true.into();
+        let diagnostics = linter::lint(text, &config);
+        let missing = diagnostics.iter().find(|d| has_code(d, "missing-comma")).unwrap();
+
+        let actions = get_code_actions(&uri, &rope, missing.range, std::slice::from_ref(missing), &config);
+        assert_eq!(actions.len(), 1);
+        let edits = edits_of(&actions[0]);
+        rope.insert(document::position_to_char(&rope, edits[0].range.start), &edits[0].new_text);
+        assert_eq!(rope.to_string(), "SELECT\n id,\n name\nFROM t;");
+    }
+
+    #[test]
+    fn test_fix_all() {
+        let uri = Url::parse("file:///test.hql").unwrap();
+        let rope = Rope::from_str("select a,\tb \nFROM t\nSELECT 1");
+        let mut config = HqlConfig { linting: casing_config(), ..HqlConfig::default() };
+        config.formatting.enabled = false;
+
+        let action = fix_all(&uri, &rope, &config, None).unwrap();
+        let CodeActionOrCommand::CodeAction(code_action) = &action else { unreachable!() };
+        assert_eq!(code_action.kind, Some(CodeActionKind::SOURCE_FIX_ALL));
+        let edits = edits_of(&action);
+        assert_eq!(edits.len(), 1);
+        assert_eq!(edits[0].range.end, Position { line: 2, character: 8 });
+        assert_eq!(edits[0].new_text, "SELECT a,\tb\nFROM t;\nSELECT 1;");
+
+        // The end is in UTF-16 code units; the emoji takes two
+        let rope = Rope::from_str("SELECT a \nFROM t; -- 😀");
+        let edits = edits_of(&fix_all(&uri, &rope, &config, None).unwrap());
+        assert_eq!(edits[0].range.end, Position { line: 1, character: 13 });
+
+        // Nothing to fix
+        let rope = Rope::from_str("SELECT 1;\n");
+        assert!(fix_all(&uri, &rope, &config, None).is_none());
+
+        // A trailing tab is both trailing whitespace and a tab; the first fix, removing it, wins
+        config.linting.rules.no_tabs = true.into();
+        let rope = Rope::from_str("SELECT 1;\t");
+        assert_eq!(edits_of(&fix_all(&uri, &rope, &config, None).unwrap())[0].new_text, "SELECT 1;");
+    }
+
+    #[test]
+    fn test_kind_requested() {
+        assert!(kind_requested(None, &CodeActionKind::SOURCE_FIX_ALL));
+        assert!(kind_requested(Some(&[CodeActionKind::SOURCE]), &CodeActionKind::SOURCE_FIX_ALL));
+        assert!(kind_requested(Some(&[CodeActionKind::SOURCE_FIX_ALL]), &CodeActionKind::SOURCE_FIX_ALL));
+        assert!(!kind_requested(Some(&[CodeActionKind::SOURCE_FIX_ALL]), &CodeActionKind::QUICKFIX));
+        assert!(!kind_requested(Some(&[CodeActionKind::QUICKFIX]), &CodeActionKind::SOURCE_FIX_ALL));
+    }
+
+    #[test]
+    fn test_disable_actions() {
+        let uri = Url::parse("file:///test.hql").unwrap();
+        let text = "SELECT a\nFROM t;\nSELECT\n    id\n    name\nFROM t;";
+        let rope = Rope::from_str(text);
+        let mut config = LintingConfig::default();
+        config.rules.missing_comma = true.into();
+        let diagnostics = linter::lint(text, &config);
+        let comma = diagnostics.iter().find(|d| has_code(d, "missing-comma")).unwrap();
+
+        let actions = disable_actions(&uri, &rope, comma.range, &diagnostics, &config.source);
+        let titles: Vec<String> = actions
+            .iter()
+            .map(|a| match a {
+                CodeActionOrCommand::CodeAction(a) => a.title.clone(),
+                CodeActionOrCommand::Command(c) => c.title.clone(),
+            })
+            .collect();
+        assert_eq!(titles, vec!["Disable missing-comma for this line", "Disable missing-comma for this file"]);
+
+        let line = edits_of(&actions[0]);
+        assert_eq!(line[0].range.start, Position { line: 3, character: 0 });
+        assert_eq!(line[0].range.end, line[0].range.start);
+        assert_eq!(line[0].new_text, "    -- hqlint-disable-next-line missing-comma\n");
+
+        let file = edits_of(&actions[1]);
+        assert_eq!(file[0].range.start, Position { line: 0, character: 0 });
+        assert_eq!(file[0].new_text, "-- hqlint-disable missing-comma\n");
+
+        // Both directives silence the diagnostic they were offered for
+        for edits in [line, file] {
+            let mut fixed = rope.clone();
+            fixed.insert(document::position_to_char(&fixed, edits[0].range.start), &edits[0].new_text);
+            let fixed = fixed.to_string();
+            assert!(!linter::lint(&fixed, &config).iter().any(|d| has_code(d, "missing-comma")), "{}", fixed);
+        }
+
+        // Another linter's diagnostics can't be silenced by our directives
+        let foreign = Diagnostic { source: Some("sqlfluff".to_string()), ..comma.clone() };
+        assert!(disable_actions(&uri, &rope, comma.range, &[foreign], &config.source).is_empty());
+    }
+
+    #[test]
+    fn test_type_casing_quick_fix() {
+        let uri = Url::parse("file:///test.hql").unwrap();
+        let text = "CREATE TABLE t (id int);";
+        let mut rope = Rope::from_str(text);
+        let mut config = LintingConfig::default();
+        config.rules.type_casing = true.into();
+        let diagnostics = linter::lint(text, &config);
+        let lowercase = diagnostics.iter().find(|d| has_code(d, "type-casing")).unwrap();
+
+        let actions = get_code_actions(&uri, &rope, lowercase.range, std::slice::from_ref(lowercase), &config);
+        assert_eq!(actions.len(), 1);
+        let edits = edits_of(&actions[0]);
+        rope.remove(document::position_to_char(&rope, edits[0].range.start)..document::position_to_char(&rope, edits[0].range.end));
+        rope.insert(document::position_to_char(&rope, edits[0].range.start), &edits[0].new_text);
+        assert_eq!(rope.to_string(), "CREATE TABLE t (id INT);");
+    }
+
+    #[test]
+    fn test_batch_separator_quick_fix() {
+        let uri = Url::parse("file:///test.hql").unwrap();
+        let text = "SELECT 1;
+GO
+SELECT 2;";
+        let mut rope = Rope::from_str(text);
+        let config = LintingConfig::default();
+        let diagnostics = linter::lint(text, &config);
+        let go = diagnostics.iter().find(|d| has_code(d, "batch-separator")).unwrap();
+
+        let actions = get_code_actions(&uri, &rope, go.range, std::slice::from_ref(go), &config);
+        assert_eq!(actions.len(), 1);
+        let edits = edits_of(&actions[0]);
+        rope.remove(document::position_to_char(&rope, edits[0].range.start)..document::position_to_char(&rope, edits[0].range.end));
+        assert_eq!(rope.to_string(), "SELECT 1;\nSELECT 2;");
+    }
+}
@@ -0,0 +1,116 @@
+use sqlparser::tokenizer::{Token, TokenWithSpan};
+use tower_lsp::lsp_types::{CodeLens, Command, Range};
+use crate::syntax::{self, is_keyword, is_significant, span_to_range};
+
+/// Command attached to each lens; clients that don't know it just show the title.
+pub const SHOW_STATEMENT_INFO: &str = "hql/showStatementInfo";
+
+/// Constructs that make a statement harder to read, counted by `statement_lenses`.
+#[derive(Debug, Default, PartialEq)]
+struct Complexity {
+    joins: usize,
+    subqueries: usize,
+    window_functions: usize,
+}
+
+impl Complexity {
+    /// Joins and window functions count once, subqueries twice.
+    fn score(&self) -> usize {
+        self.joins + 2 * self.subqueries + self.window_functions
+    }
+}
+
+/// A lens above each statement with its position in the file and a rough complexity score,
+/// e.g. `Statement 3 of 12 · complexity 4 (2 joins, 1 subquery)`.
+pub fn statement_lenses(tokens: &[TokenWithSpan]) -> Vec<CodeLens> {
+    let statements = syntax::split_statements(tokens);
+    let count = statements.len();
+
+    statements
+        .into_iter()
+        .enumerate()
+        .map(|(i, statement)| {
+            let complexity = complexity(tokens, statement.clone());
+            let start = span_to_range(&tokens[statement.start].span).start;
+            let title = format!("Statement {} of {} · complexity {}{}", i + 1, count, complexity.score(), breakdown(&complexity));
+            CodeLens {
+                range: Range { start, end: start },
+                command: Some(Command { title, command: SHOW_STATEMENT_INFO.to_string(), arguments: None }),
+                data: None,
+            }
+        })
+        .collect()
+}
+
+fn complexity(tokens: &[TokenWithSpan], statement: std::ops::Range<usize>) -> Complexity {
+    let significant: Vec<&Token> = tokens[statement].iter().map(|t| &t.token).filter(|t| is_significant(t)).collect();
+    let mut complexity = Complexity::default();
+    for (i, token) in significant.iter().enumerate() {
+        if is_keyword(token, "JOIN") {
+            complexity.joins += 1;
+        } else if is_keyword(token, "OVER") {
+            complexity.window_functions += 1;
+        } else if matches!(token, Token::LParen)
+            && significant.get(i + 1).is_some_and(|next| is_keyword(next, "SELECT") || is_keyword(next, "WITH"))
+        {
+            complexity.subqueries += 1;
+        }
+    }
+    complexity
+}
+
+/// ` (2 joins, 1 subquery)`, leaving out the constructs the statement doesn't use.
+fn breakdown(complexity: &Complexity) -> String {
+    let parts: Vec<String> = [
+        (complexity.joins, "join"),
+        (complexity.subqueries, "subquery"),
+        (complexity.window_functions, "window function"),
+    ]
+    .into_iter()
+    .filter(|&(n, _)| n > 0)
+    .map(|(n, name)| match (n, name) {
+        (1, _) => format!("1 {}", name),
+        (_, "subquery") => format!("{} subqueries", n),
+        _ => format!("{} {}s", n, name),
+    })
+    .collect();
+    if parts.is_empty() { String::new() } else { format!(" ({})", parts.join(", ")) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tower_lsp::lsp_types::Position;
+
+    fn titles(text: &str) -> Vec<String> {
+        statement_lenses(&syntax::tokenize(text).unwrap())
+            .into_iter()
+            .map(|lens| lens.command.unwrap().title)
+            .collect()
+    }
+
+    #[test]
+    fn test_one_lens_per_statement() {
+        let text = "USE sales;\nSELECT 1;\n\nSELECT a FROM (SELECT a FROM t) x\nJOIN u ON x.a = u.a\nJOIN v ON x.a = v.a;\nSELECT 2";
+        let tokens = syntax::tokenize(text).unwrap();
+        let lenses = statement_lenses(&tokens);
+        assert_eq!(lenses.len(), syntax::split_statements(&tokens).len());
+        assert_eq!(lenses[2].range.start, Position { line: 3, character: 0 });
+        assert_eq!(
+            titles(text),
+            vec![
+                "Statement 1 of 4 · complexity 0",
+                "Statement 2 of 4 · complexity 0",
+                "Statement 3 of 4 · complexity 4 (2 joins, 1 subquery)",
+                "Statement 4 of 4 · complexity 0",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_window_functions_and_subqueries() {
+        let text = "SELECT ROW_NUMBER() OVER (PARTITION BY a ORDER BY b), RANK() OVER w\nFROM t WHERE a IN (SELECT a FROM u) AND b IN (WITH c AS (SELECT 1) SELECT * FROM c);";
+        assert_eq!(titles(text), vec!["Statement 1 of 1 · complexity 8 (3 subqueries, 2 window functions)"]);
+        assert!(titles("").is_empty());
+    }
+}
@@ -1,81 +1,722 @@
-use tower_lsp::lsp_types::{CompletionItem, CompletionItemKind, CompletionResponse, InsertTextFormat};
+use std::collections::HashMap;
+use ropey::Rope;
+use serde::{Deserialize, Serialize};
+use sqlparser::tokenizer::{Location, Token, TokenWithSpan};
+use tower_lsp::lsp_types::{
+    CompletionItem, CompletionItemKind, CompletionResponse, CompletionTextEdit, Documentation, InsertTextFormat,
+    MarkupContent, MarkupKind, Position, Range, TextEdit,
+};
+use crate::config::HqlConfig;
+use crate::document::position_to_char;
+use crate::keywords;
+use crate::schema::{Schema, Table};
+use crate::signature_help::FUNCTIONS;
+use crate::syntax::{self, is_keyword, is_significant};
 
-pub fn get_completions() -> CompletionResponse {
+/// Keywords and snippets, with the user's additional keywords and snippets added; a user
+/// snippet replaces any built-in item with the same label.
+pub fn get_completions(config: &HqlConfig) -> CompletionResponse {
+    CompletionResponse::Array(all_items(config))
+}
+
+fn all_items(config: &HqlConfig) -> Vec<CompletionItem> {
     let mut items = Vec::new();
 
     // Keywords
-    let keywords = [
-        "SELECT", "FROM", "WHERE", "GROUP BY", "ORDER BY", "HAVING", "LIMIT", "OFFSET",
-        "JOIN", "INNER JOIN", "LEFT JOIN", "RIGHT JOIN", "FULL OUTER JOIN", "CROSS JOIN", "ON",
-        "UNION", "UNION ALL", "WITH", "AS", "AND", "OR", "NOT", "IN", "EXISTS", "BETWEEN",
-        "LIKE", "RLIKE", "REGEXP", "CASE", "WHEN", "THEN", "ELSE", "END",
-        "INSERT INTO", "INSERT OVERWRITE", "CREATE TABLE", "DROP TABLE", "ALTER TABLE",
-        "PARTITIONED BY", "STORED AS", "LOCATION", "TBLPROPERTIES",
-        "TRUE", "FALSE", "NULL"
-    ];
+    let mut keywords: Vec<String> = keywords::completion_keywords().into_iter().map(str::to_string).collect();
+    for keyword in &config.linting.additional_keywords {
+        let keyword = keyword.to_uppercase();
+        if !keywords.contains(&keyword) {
+            keywords.push(keyword);
+        }
+    }
 
     for kw in keywords {
-        items.push(CompletionItem {
-            label: kw.to_string(),
-            kind: Some(CompletionItemKind::KEYWORD),
-            detail: Some("HQL Keyword".to_string()),
-            ..Default::default()
-        });
+        items.push(keyword_item(kw));
     }
 
-    // Snippets (Ported from snippets.ts)
-    items.push(create_snippet(
-        "CREATE TABLE",
-        "Create a new table",
-        "CREATE ${1|TABLE,EXTERNAL TABLE|} ${2|IF NOT EXISTS ||}${3:table_name} (\n  ${4:column_name} ${5:data_type}${6:,}\n  ${7}\n)${8:|nPARTITIONED BY (${9:partition_column} ${10:data_type})}${11:|nSTORED AS ${12|PARQUET,ORC,AVRO,TEXTFILE|}}${13:|nLOCATION '${14:/path/to/location}'};\n$0"
-    ));
-
-    items.push(create_snippet(
-        "INSERT OVERWRITE",
-        "Insert overwrite into table",
-        "INSERT OVERWRITE TABLE ${1:target_table}\n${2:PARTITION (${3:partition_column}=${4:value})}\nSELECT ${5:*}\nFROM ${6:source_table}\n${7:WHERE ${8:condition}};\n$0"
-    ));
-    
-    items.push(create_snippet(
-        "SELECT JOIN",
-        "SELECT with JOIN",
-        "SELECT ${1:t1}.${2:column1}, ${3:t2}.${4:column2}\nFROM ${5:table1} ${1:t1}\n${6|INNER,LEFT,RIGHT,FULL OUTER|} JOIN ${7:table2} ${3:t2}\n  ON ${1:t1}.${8:id} = ${3:t2}.${9:id}\n${10:WHERE ${11:condition}}\n${12:ORDER BY ${13:column}};\n$0"
-    ));
-
-    items.push(create_snippet(
-        "WINDOW FUNCTION",
-        "Analytical function",
-        "SELECT\n  ${1:column},\n  ${2|ROW_NUMBER,RANK,DENSE_RANK,LAG,LEAD,FIRST_VALUE,LAST_VALUE|}() OVER (\n    ${3:PARTITION BY ${4:partition_column}}\n    ORDER BY ${5:order_column} ${6|ASC,DESC|}\n    ${7:ROWS BETWEEN ${8|UNBOUNDED PRECEDING,CURRENT ROW,1 PRECEDING|} AND ${9|CURRENT ROW,UNBOUNDED FOLLOWING,1 FOLLOWING|}}\n  ) AS ${10:window_result}\nFROM ${11:table_name};\n$0"
-    ));
-
-    items.push(create_snippet(
-        "CASE WHEN",
-        "CASE expression",
-        "CASE\n  WHEN ${1:condition1} THEN ${2:result1}\n  WHEN ${3:condition2} THEN ${4:result2}\n  ${5:ELSE ${6:default_result}}\nEND AS ${7:result_column}$0"
-    ));
-
-    items.push(create_snippet(
-        "LATERAL VIEW EXPLODE",
-        "Explode array/map",
-        "SELECT ${1:t}.${2:column}, ${3:exploded_value}\nFROM ${4:table_name} ${1:t}\nLATERAL VIEW ${5|EXPLODE,POSEXPLODE|}(${6:array_column}) ${7:exploded_table} AS ${3:exploded_value};\n$0"
-    ));
-
-    items.push(create_snippet(
-        "WITH CTE",
-        "Common Table Expression",
-        "WITH ${1:cte_name} AS (\n  SELECT ${2:columns}\n  FROM ${3:table_name}\n  ${4:WHERE ${5:condition}} \n)${6:,\n${7:cte_name2} AS (\n  SELECT ${8:columns}\n  FROM ${9:table_name}\n)}\nSELECT ${10:*}\nFROM ${1:cte_name};\n$0"
-    ));
+    // Documentation is filled in by `resolve` once an item is selected
+    items.extend(builtin_snippets().into_iter().map(|snippet| CompletionItem {
+        detail: None,
+        documentation: None,
+        data: serde_json::to_value(Lookup::Snippet).ok(),
+        ..snippet
+    }));
 
-    CompletionResponse::Array(items)
+    for snippet in &config.completion.snippets {
+        items.retain(|item| item.label != snippet.label);
+        items.push(create_snippet(&snippet.label, snippet.detail.as_deref().unwrap_or("User snippet"), None, &snippet.body));
+    }
+
+    items
+}
+
+/// The built-in snippets with their details and documentation.
+fn builtin_snippets() -> Vec<CompletionItem> {
+    // Ported from snippets.ts
+    vec![
+        create_snippet(
+            "CREATE TABLE",
+            "Create a new table",
+            Some("Creates a managed or `EXTERNAL` table, optionally partitioned, with a storage format and location."),
+            "CREATE ${1|TABLE,EXTERNAL TABLE|} ${2|IF NOT EXISTS ||}${3:table_name} (\n  ${4:column_name} ${5:data_type}${6:,}\n  ${7}\n)${8:|nPARTITIONED BY (${9:partition_column} ${10:data_type})}${11:|nSTORED AS ${12|PARQUET,ORC,AVRO,TEXTFILE|}}${13:|nLOCATION '${14:/path/to/location}'};\n$0"
+        ),
+        create_snippet(
+            "INSERT OVERWRITE",
+            "Insert overwrite into table",
+            Some("Replaces the contents of a table, or of one partition, with the result of a query."),
+            "INSERT OVERWRITE TABLE ${1:target_table}\n${2:PARTITION (${3:partition_column}=${4:value})}\nSELECT ${5:*}\nFROM ${6:source_table}\n${7:WHERE ${8:condition}};\n$0"
+        ),
+        create_snippet(
+            "SELECT JOIN",
+            "SELECT with JOIN",
+            Some("Selects columns from two tables joined on a key."),
+            "SELECT ${1:t1}.${2:column1}, ${3:t2}.${4:column2}\nFROM ${5:table1} ${1:t1}\n${6|INNER,LEFT,RIGHT,FULL OUTER|} JOIN ${7:table2} ${3:t2}\n  ON ${1:t1}.${8:id} = ${3:t2}.${9:id}\n${10:WHERE ${11:condition}}\n${12:ORDER BY ${13:column}};\n$0"
+        ),
+        create_snippet(
+            "WINDOW FUNCTION",
+            "Analytical function",
+            Some("Computes a value over a window of rows with `OVER (PARTITION BY ... ORDER BY ...)`."),
+            &[
+                "SELECT\n  ${1:column},\n  ${2|",
+                &keywords::ANALYTIC_FUNCTIONS.join(","),
+                "|}() OVER (\n    ${3:PARTITION BY ${4:partition_column}}\n    ORDER BY ${5:order_column} ${6|ASC,DESC|}\n    ${7:ROWS BETWEEN ${8|UNBOUNDED PRECEDING,CURRENT ROW,1 PRECEDING|} AND ${9|CURRENT ROW,UNBOUNDED FOLLOWING,1 FOLLOWING|}}\n  ) AS ${10:window_result}\nFROM ${11:table_name};\n$0",
+            ]
+            .concat()
+        ),
+        create_snippet(
+            "CASE WHEN",
+            "CASE expression",
+            Some("Returns the result of the first `WHEN` condition that holds, or the `ELSE` value."),
+            "CASE\n  WHEN ${1:condition1} THEN ${2:result1}\n  WHEN ${3:condition2} THEN ${4:result2}\n  ${5:ELSE ${6:default_result}}\nEND AS ${7:result_column}$0"
+        ),
+        create_snippet(
+            "LATERAL VIEW EXPLODE",
+            "Explode array/map",
+            Some("Turns each element of an array or map column into its own row with `LATERAL VIEW`."),
+            "SELECT ${1:t}.${2:column}, ${3:exploded_value}\nFROM ${4:table_name} ${1:t}\nLATERAL VIEW ${5|EXPLODE,POSEXPLODE|}(${6:array_column}) ${7:exploded_table} AS ${3:exploded_value};\n$0"
+        ),
+        create_snippet(
+            "WITH CTE",
+            "Common Table Expression",
+            Some("Names a subquery with `WITH name AS (...)` so the main query can refer to it."),
+            "WITH ${1:cte_name} AS (\n  SELECT ${2:columns}\n  FROM ${3:table_name}\n  ${4:WHERE ${5:condition}} \n)${6:,\n${7:cte_name2} AS (\n  SELECT ${8:columns}\n  FROM ${9:table_name}\n)}\nSELECT ${10:*}\nFROM ${1:cte_name};\n$0"
+        ),
+    ]
 }
 
-fn create_snippet(label: &str, detail: &str, insert_text: &str) -> CompletionItem {
+fn create_snippet(label: &str, detail: &str, documentation: Option<&str>, insert_text: &str) -> CompletionItem {
     CompletionItem {
         label: label.to_string(),
         kind: Some(CompletionItemKind::SNIPPET),
         detail: Some(detail.to_string()),
+        documentation: documentation.map(markdown),
         insert_text: Some(insert_text.to_string()),
         insert_text_format: Some(InsertTextFormat::SNIPPET),
+        sort_text: Some(sort_text(Rank::Snippet, label)),
+        filter_text: filter_text(label),
+        ..Default::default()
+    }
+}
+
+/// Keywords common enough to rank above the rest, with what they do.
+const KEYWORD_DOCS: &[(&str, &str)] = &[
+    ("SELECT", "Starts a query, listing the columns or expressions to return."),
+    ("FROM", "Names the tables, views or subqueries a query reads."),
+    ("WHERE", "Keeps only the rows for which the condition is true."),
+    ("GROUP BY", "Collapses rows with equal keys into one row per group, for aggregate functions."),
+    ("ORDER BY", "Sorts the whole result; in Hive this runs through a single reducer."),
+    ("HAVING", "Filters groups after `GROUP BY`, on aggregate values."),
+    ("LIMIT", "Returns at most the given number of rows."),
+    ("JOIN", "Combines rows of two tables that match the `ON` condition."),
+    ("LEFT JOIN", "Keeps every row of the left table, with `NULL`s where the right table has no match."),
+    ("INNER JOIN", "Keeps only rows that match on both sides."),
+    ("UNION ALL", "Appends the rows of another query with the same columns, keeping duplicates."),
+    ("WITH", "Defines named subqueries (CTEs) for the statement that follows."),
+    ("CASE", "Conditional expression: `CASE WHEN condition THEN result ... ELSE default END`."),
+    ("DISTINCT", "Removes duplicate rows from the result."),
+    ("AS", "Names a column, table or subquery."),
+    ("INSERT INTO", "Appends the rows of a query or `VALUES` list to a table."),
+    ("INSERT OVERWRITE", "Replaces the contents of a table or partition with the rows of a query."),
+    ("CREATE TABLE", "Creates a table; add `EXTERNAL` for data Hive doesn't own."),
+    ("PARTITIONED BY", "Declares partition columns, stored as directories rather than in the data files."),
+    ("STORED AS", "Sets the file format, e.g. `ORC`, `PARQUET` or `TEXTFILE`."),
+    ("LATERAL VIEW", "Joins each row with the rows a table-generating function such as `EXPLODE` returns."),
+    ("DISTRIBUTE BY", "Sends rows with equal keys to the same reducer."),
+    ("CLUSTER BY", "Shorthand for `DISTRIBUTE BY` and `SORT BY` on the same columns."),
+    ("SORT BY", "Sorts rows within each reducer, not across the whole result."),
+];
+
+/// Where an item sorts: snippets first, then common keywords, then everything else.
+#[derive(Debug, Clone, Copy)]
+enum Rank {
+    Snippet,
+    CommonKeyword,
+    Keyword,
+}
+
+fn keyword_item(keyword: String) -> CompletionItem {
+    let rank = if keyword_doc(&keyword).is_some() { Rank::CommonKeyword } else { Rank::Keyword };
+    CompletionItem {
+        kind: Some(CompletionItemKind::KEYWORD),
+        sort_text: Some(sort_text(rank, &keyword)),
+        filter_text: filter_text(&keyword),
+        data: serde_json::to_value(Lookup::Keyword).ok(),
+        label: keyword,
+        ..Default::default()
+    }
+}
+
+fn keyword_doc(keyword: &str) -> Option<&'static str> {
+    KEYWORD_DOCS.iter().find(|(kw, _)| *kw == keyword).map(|(_, doc)| *doc)
+}
+
+/// Where `resolve` finds a built-in item's detail and documentation, kept in the item's
+/// `data` so the initial list stays small.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum Lookup {
+    Keyword,
+    Snippet,
+    Function,
+}
+
+/// `completionItem/resolve`: fills in the detail and documentation of a built-in keyword,
+/// snippet or function, found by its label. Other items are returned as they are.
+pub fn resolve(mut item: CompletionItem) -> CompletionItem {
+    let Some(lookup) = item.data.clone().and_then(|data| serde_json::from_value::<Lookup>(data).ok()) else { return item };
+    match lookup {
+        Lookup::Keyword => {
+            item.detail = Some("HQL Keyword".to_string());
+            item.documentation = keyword_doc(&item.label).map(markdown);
+        }
+        Lookup::Snippet => {
+            if let Some(snippet) = builtin_snippets().into_iter().find(|snippet| snippet.label == item.label) {
+                item.detail = snippet.detail;
+                item.documentation = snippet.documentation;
+            }
+        }
+        Lookup::Function => {
+            if let Some((name, params, description)) = FUNCTIONS.iter().find(|(name, _, _)| *name == item.label) {
+                item.detail = Some(format!("{}({})", name, params.join(", ")));
+                item.documentation = Some(Documentation::String(description.to_string()));
+            }
+        }
+    }
+    item
+}
+
+fn sort_text(rank: Rank, label: &str) -> String {
+    format!("{}_{}", rank as u8, label)
+}
+
+/// Multi-word labels such as `GROUP BY` filter as one word, so typing `group` or `groupby`
+/// matches them in clients that stop at the space.
+fn filter_text(label: &str) -> Option<String> {
+    label.contains(' ').then(|| label.replace(' ', ""))
+}
+
+fn markdown(value: &str) -> Documentation {
+    Documentation::MarkupContent(MarkupContent { kind: MarkupKind::Markdown, value: value.to_string() })
+}
+
+/// Keywords offered at the start of a statement, alongside the snippets.
+const STATEMENT_KEYWORDS: &[&str] = &[
+    "SELECT", "WITH", "FROM", "INSERT INTO", "INSERT OVERWRITE", "CREATE TABLE", "ALTER TABLE", "DROP TABLE", "SET",
+    "USE", "LOAD", "EXPLAIN",
+];
+
+/// Keywords and snippets offered in a select list, alongside functions and columns.
+const SELECT_LIST_KEYWORDS: &[&str] = &["DISTINCT", "CASE", "CASE WHEN", "CAST", "NOT", "TRUE", "FALSE", "NULL"];
+
+/// Where the cursor is, judged from the tokens before it.
+#[derive(Debug, PartialEq)]
+enum Context {
+    /// Nothing but whitespace or comments since the last `;`
+    StatementStart,
+    /// Right after `SELECT`, `SELECT DISTINCT` or a comma between select items
+    SelectList,
+    /// Anywhere else, including after `FROM`/`JOIN` without a schema
+    Other,
+}
+
+/// Completions for the cursor's context: schema tables and columns where they apply, then
+/// statement keywords and snippets at the start of a statement, functions and columns in a
+/// select list, and the full list of keywords and snippets everywhere else.
+pub fn completions(rope: &Rope, position: Position, schema: Option<&Schema>, config: &HqlConfig) -> CompletionResponse {
+    if let Some(schema) = schema
+        && let Some(response) = schema_completions(schema, rope, position)
+    {
+        return response;
+    }
+
+    let offset = position_to_char(rope, position);
+    let Some(prefix) = syntax::tokenize(&rope.slice(..offset).to_string()) else {
+        return get_completions(config);
+    };
+    let items = match context(&prefix) {
+        Context::StatementStart => all_items(config)
+            .into_iter()
+            .filter(|item| item.kind == Some(CompletionItemKind::SNIPPET) || STATEMENT_KEYWORDS.contains(&item.label.as_str()))
+            .collect(),
+        Context::SelectList => {
+            let mut items = function_items(&config.linting.additional_functions);
+            if let Some(schema) = schema {
+                let aliases = aliases_at(rope, &prefix, offset);
+                let mut tables: Vec<&Table> = aliases.values().filter_map(|name| schema.table(name)).collect();
+                tables.sort_by(|a, b| a.name.cmp(&b.name));
+                tables.dedup_by(|a, b| a.name == b.name);
+                items.extend(tables.into_iter().flat_map(column_items));
+            }
+            items.extend(all_items(config).into_iter().filter(|item| SELECT_LIST_KEYWORDS.contains(&item.label.as_str())));
+            items
+        }
+        Context::Other => all_items(config),
+    };
+    CompletionResponse::Array(items)
+}
+
+fn context(prefix: &[TokenWithSpan]) -> Context {
+    // Ignore the word being typed; the client filters on it
+    let mut tokens: Vec<&Token> = prefix.iter().map(|t| &t.token).filter(|t| is_significant(t)).collect();
+    if matches!(prefix.last().map(|t| &t.token), Some(Token::Word(_))) {
+        tokens.pop();
+    }
+
+    match tokens.as_slice() {
+        [] | [.., Token::SemiColon] => Context::StatementStart,
+        [.., last] if is_keyword(last, "SELECT") => Context::SelectList,
+        [.., select, last] if is_keyword(select, "SELECT") && (is_keyword(last, "DISTINCT") || is_keyword(last, "ALL")) => {
+            Context::SelectList
+        }
+        [rest @ .., Token::Comma] => {
+            // Walk back to the clause the comma belongs to, skipping nested parentheses
+            let mut depth = 0;
+            for token in rest.iter().rev() {
+                match token {
+                    Token::RParen => depth += 1,
+                    Token::LParen if depth == 0 => return Context::Other,
+                    Token::LParen => depth -= 1,
+                    Token::SemiColon => return Context::Other,
+                    _ if depth > 0 => {}
+                    _ if is_keyword(token, "SELECT") => return Context::SelectList,
+                    _ if ["FROM", "WHERE", "GROUP", "ORDER", "BY", "HAVING", "JOIN", "ON", "SET", "VALUES", "PARTITION"]
+                        .iter()
+                        .any(|kw| is_keyword(token, kw)) =>
+                    {
+                        return Context::Other;
+                    }
+                    _ => {}
+                }
+            }
+            Context::Other
+        }
+        _ => Context::Other,
+    }
+}
+
+fn function_items(additional: &[String]) -> Vec<CompletionItem> {
+    let mut items: Vec<CompletionItem> = FUNCTIONS
+        .iter()
+        .map(|(name, _, _)| CompletionItem {
+            label: name.to_string(),
+            kind: Some(CompletionItemKind::FUNCTION),
+            data: serde_json::to_value(Lookup::Function).ok(),
+            ..Default::default()
+        })
+        .collect();
+    for name in additional {
+        if items.iter().any(|item| item.label.eq_ignore_ascii_case(name)) {
+            continue;
+        }
+        items.push(CompletionItem {
+            label: name.clone(),
+            kind: Some(CompletionItemKind::FUNCTION),
+            detail: Some("User function".to_string()),
+            ..Default::default()
+        });
+    }
+    items
+}
+
+/// Completions drawn from the workspace schema, or `None` when the cursor isn't in a context
+/// the schema can help with.
+///
+/// After `alias.` (or `table.`) the columns of that table are offered, after `db.` the tables
+/// of that database, and after `FROM`/`JOIN` every table.
+pub fn schema_completions(schema: &Schema, rope: &Rope, position: Position) -> Option<CompletionResponse> {
+    let offset = position_to_char(rope, position);
+    let prefix = syntax::tokenize(&rope.slice(..offset).to_string())?;
+
+    // Ignore the identifier being typed; the client filters on it
+    let mut context: Vec<&Token> = prefix.iter().map(|t| &t.token).filter(|t| is_significant(t)).collect();
+    if matches!(prefix.last().map(|t| &t.token), Some(Token::Word(_))) {
+        context.pop();
+    }
+
+    let items = match context.as_slice() {
+        [.., Token::Word(qualifier), Token::Period] => {
+            let aliases = aliases_at(rope, &prefix, offset);
+            match aliases.get(&qualifier.value.to_lowercase()).and_then(|name| schema.table(name)) {
+                Some(table) => column_items(table),
+                None => table_items(schema.tables_in(&qualifier.value).into_iter()),
+            }
+        }
+        [.., last] if is_keyword(last, "FROM") || is_keyword(last, "JOIN") => {
+            table_items(schema.tables.iter().map(|t| (t.name.as_str(), t)))
+        }
+        _ => return None,
+    };
+
+    if items.is_empty() { None } else { Some(CompletionResponse::Array(items)) }
+}
+
+/// Completions inside an unclosed `${` on the cursor's line: `namespace:${1:name}` snippets for
+/// the configured namespaces, and the known variables, each replacing what's been typed since
+/// the `${`. After `namespace:` only that namespace's variables are offered.
+pub fn variable_completions(
+    rope: &Rope,
+    position: Position,
+    namespaces: &[String],
+    variables: &HashMap<String, HashMap<String, String>>,
+) -> Option<CompletionResponse> {
+    let typed = variable_prefix(rope, position)?;
+    let start = Position {
+        line: position.line,
+        character: position.character - typed.encode_utf16().count() as u32,
+    };
+    let item = |label: String, kind, detail: String, new_text: String, snippet: bool| CompletionItem {
+        label,
+        kind: Some(kind),
+        detail: Some(detail),
+        text_edit: Some(CompletionTextEdit::Edit(TextEdit { range: Range { start, end: position }, new_text })),
+        insert_text_format: snippet.then_some(InsertTextFormat::SNIPPET),
         ..Default::default()
+    };
+
+    let mut items = Vec::new();
+    let typed_namespace = typed.split_once(':').map(|(namespace, _)| namespace);
+    if typed_namespace.is_none() {
+        for namespace in namespaces {
+            items.push(item(
+                format!("{}:", namespace),
+                CompletionItemKind::MODULE,
+                "Hive variable namespace".to_string(),
+                format!("{}:${{1:name}}", namespace),
+                true,
+            ));
+        }
+    }
+    let mut known: Vec<(&String, &String, &String)> = variables
+        .iter()
+        .filter(|(namespace, _)| typed_namespace.is_none_or(|typed| typed.eq_ignore_ascii_case(namespace)))
+        .flat_map(|(namespace, names)| names.iter().map(move |(name, value)| (namespace, name, value)))
+        .collect();
+    known.sort();
+    for (namespace, name, value) in known {
+        let reference = format!("{}:{}", namespace, name);
+        items.push(item(reference.clone(), CompletionItemKind::VARIABLE, value.clone(), reference, false));
+    }
+
+    if items.is_empty() { None } else { Some(CompletionResponse::Array(items)) }
+}
+
+/// The text typed since an unclosed `${` before the cursor, scanning back along its line.
+fn variable_prefix(rope: &Rope, position: Position) -> Option<String> {
+    let offset = position_to_char(rope, position);
+    let line_start = rope.line_to_char(rope.char_to_line(offset));
+    let mut start = offset;
+    while start > line_start {
+        match rope.char(start - 1) {
+            '{' if start - 1 > line_start && rope.char(start - 2) == '$' => {
+                return Some(rope.slice(start..offset).to_string());
+            }
+            c if c.is_alphanumeric() || matches!(c, '_' | '.' | ':' | '-') => start -= 1,
+            _ => return None,
+        }
+    }
+    None
+}
+
+/// Maps each table name and alias in the statement around `offset` (lowercased) to its table.
+///
+/// The whole statement is scanned since the select list is usually typed before its `FROM`;
+/// if the rest of the document doesn't tokenize only the text before the cursor is used.
+fn aliases_at(rope: &Rope, prefix: &[TokenWithSpan], offset: usize) -> HashMap<String, String> {
+    let full = syntax::tokenize(&rope.to_string());
+    let tokens = full.as_deref().unwrap_or(prefix);
+
+    let line = rope.char_to_line(offset);
+    let cursor = Location::new(line as u64 + 1, (offset - rope.line_to_char(line)) as u64 + 1);
+    let statement = syntax::split_statements(tokens)
+        .into_iter()
+        .rfind(|s| location_key(tokens[s.start].span.start) <= location_key(cursor))
+        .unwrap_or(0..tokens.len());
+
+    syntax::table_aliases(tokens, statement)
+}
+
+fn location_key(location: Location) -> (u64, u64) {
+    (location.line, location.column)
+}
+
+fn column_items(table: &Table) -> Vec<CompletionItem> {
+    table
+        .columns
+        .iter()
+        .map(|column| CompletionItem {
+            label: column.name.clone(),
+            kind: Some(CompletionItemKind::FIELD),
+            detail: Some(match &column.data_type {
+                Some(data_type) => format!("{} ({})", data_type, table.name),
+                None => table.name.clone(),
+            }),
+            ..Default::default()
+        })
+        .collect()
+}
+
+fn table_items<'a>(tables: impl Iterator<Item = (&'a str, &'a Table)>) -> Vec<CompletionItem> {
+    let mut items: Vec<CompletionItem> = tables
+        .map(|(label, table)| CompletionItem {
+            label: label.to_string(),
+            kind: Some(CompletionItemKind::CLASS),
+            detail: Some(format!("Table ({} columns)", table.columns.len())),
+            ..Default::default()
+        })
+        .collect();
+    items.sort_by(|a, b| a.label.cmp(&b.label));
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema() -> Schema {
+        serde_json::from_str(
+            r#"{ "tables": [
+                { "name": "sales.orders", "columns": [ { "name": "id", "type": "bigint" }, { "name": "amount" } ] },
+                { "name": "sales.customers", "columns": [ { "name": "id" }, { "name": "name" } ] }
+            ] }"#,
+        )
+        .unwrap()
+    }
+
+    /// The document with the `|` removed, and the cursor position it marked.
+    fn cursor(text: &str) -> (Rope, Position) {
+        let (before, after) = text.split_once('|').unwrap();
+        let rope = Rope::from_str(&format!("{}{}", before, after));
+        let last_line = before.rsplit('\n').next().unwrap_or("");
+        let position = Position {
+            line: before.matches('\n').count() as u32,
+            character: last_line.chars().count() as u32,
+        };
+        (rope, position)
+    }
+
+    /// Labels offered with the cursor at `|`.
+    fn labels(text: &str) -> Option<Vec<String>> {
+        let (rope, position) = cursor(text);
+        match schema_completions(&schema(), &rope, position)? {
+            CompletionResponse::Array(items) => Some(items.into_iter().map(|i| i.label).collect()),
+            CompletionResponse::List(list) => Some(list.items.into_iter().map(|i| i.label).collect()),
+        }
+    }
+
+    #[test]
+    fn test_user_snippets() {
+        let config: HqlConfig = serde_json::from_value(serde_json::json!({
+            "completion": { "snippets": [
+                { "label": "SELECT COUNT", "body": "SELECT COUNT(*) FROM ${1:table};$0" },
+                { "label": "CASE WHEN", "detail": "Two-branch CASE", "body": "CASE WHEN $1 THEN $2 ELSE $3 END" }
+            ] }
+        }))
+        .unwrap();
+        let CompletionResponse::Array(items) = get_completions(&config) else { unreachable!() };
+
+        let count = items.iter().find(|i| i.label == "SELECT COUNT").unwrap();
+        assert_eq!(count.kind, Some(CompletionItemKind::SNIPPET));
+        assert_eq!(count.insert_text_format, Some(InsertTextFormat::SNIPPET));
+        assert_eq!(count.insert_text.as_deref(), Some("SELECT COUNT(*) FROM ${1:table};$0"));
+        assert_eq!(count.detail.as_deref(), Some("User snippet"));
+
+        // Replaces the built-in CASE WHEN snippet
+        let case_when: Vec<_> = items.iter().filter(|i| i.label == "CASE WHEN").collect();
+        assert_eq!(case_when.len(), 1);
+        assert_eq!(case_when[0].detail.as_deref(), Some("Two-branch CASE"));
+    }
+
+    #[test]
+    fn test_additional_keywords_and_functions() {
+        let config: HqlConfig = serde_json::from_value(serde_json::json!({
+            "linting": { "additionalKeywords": ["qualify", "SELECT"], "additionalFunctions": ["mask_pii", "concat"] }
+        }))
+        .unwrap();
+        let CompletionResponse::Array(items) = get_completions(&config) else { unreachable!() };
+        assert!(items.iter().any(|i| i.label == "QUALIFY" && i.kind == Some(CompletionItemKind::KEYWORD)));
+        assert_eq!(items.iter().filter(|i| i.label == "SELECT").count(), 1);
+
+        let (rope, position) = cursor("SELECT |");
+        let CompletionResponse::Array(items) = completions(&rope, position, None, &config) else { unreachable!() };
+        let udf = items.iter().find(|i| i.label == "mask_pii").unwrap();
+        assert_eq!(udf.kind, Some(CompletionItemKind::FUNCTION));
+        assert_eq!(udf.detail.as_deref(), Some("User function"));
+        assert!(!items.iter().any(|i| i.label == "concat"));
+    }
+
+    #[test]
+    fn test_columns_after_alias() {
+        // The alias is declared after the cursor
+        assert_eq!(labels("SELECT o.| FROM sales.orders o;").unwrap(), vec!["id", "amount"]);
+        assert_eq!(
+            labels("SELECT o.id, c.na|\nFROM orders AS o\nJOIN customers c ON o.id = c.id;").unwrap(),
+            vec!["id", "name"]
+        );
+        assert_eq!(labels("SELECT orders.| FROM orders;").unwrap(), vec!["id", "amount"]);
+    }
+
+    #[test]
+    fn test_tables_after_from_and_database() {
+        assert_eq!(labels("SELECT * FROM |").unwrap(), vec!["sales.customers", "sales.orders"]);
+        assert_eq!(labels("SELECT * FROM t JOIN sales.cu|").unwrap(), vec!["customers", "orders"]);
+    }
+
+    #[test]
+    fn test_unknown_context_falls_back() {
+        assert!(labels("SELECT |").is_none());
+        assert!(labels("SELECT x.| FROM orders o").is_none());
+        assert!(labels("SELECT 'unterminated |").is_none());
+    }
+
+    /// Labels offered in context with the cursor at `|`.
+    fn context_labels(text: &str, schema: Option<&Schema>) -> Vec<String> {
+        let (rope, position) = cursor(text);
+        let CompletionResponse::Array(items) = completions(&rope, position, schema, &HqlConfig::default()) else { unreachable!() };
+        items.into_iter().map(|i| i.label).collect()
+    }
+
+    fn variable_labels(text: &str) -> Option<Vec<String>> {
+        let (rope, position) = cursor(text);
+        let namespaces: Vec<String> = ["hivevar", "hiveconf"].iter().map(|ns| ns.to_string()).collect();
+        let variables = HashMap::from([(
+            "hivevar".to_string(),
+            HashMap::from([("db".to_string(), "prod_db".to_string()), ("day".to_string(), "2024-01-01".to_string())]),
+        )]);
+        match variable_completions(&rope, position, &namespaces, &variables)? {
+            CompletionResponse::Array(items) => Some(items.into_iter().map(|i| i.label).collect()),
+            CompletionResponse::List(_) => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_variable_namespaces_after_dollar_brace() {
+        let all = vec!["hivevar:", "hiveconf:", "hivevar:day", "hivevar:db"];
+        assert_eq!(variable_labels("SELECT * FROM ${|").unwrap(), all);
+        assert_eq!(variable_labels("SELECT * FROM ${hiv|").unwrap(), all);
+        assert_eq!(variable_labels("SELECT '${hivevar:d|").unwrap(), vec!["hivevar:day", "hivevar:db"]);
+        assert!(variable_labels("SELECT * FROM ${hiveconf:|").is_none());
+
+        let (rope, position) = cursor("USE ${hiv|");
+        let namespaces = vec!["hivevar".to_string()];
+        let Some(CompletionResponse::Array(items)) = variable_completions(&rope, position, &namespaces, &HashMap::new())
+        else {
+            panic!("expected completions")
+        };
+        let Some(CompletionTextEdit::Edit(edit)) = &items[0].text_edit else { panic!("expected a text edit") };
+        assert_eq!(edit.new_text, "hivevar:${1:name}");
+        assert_eq!(edit.range.start, Position { line: 0, character: 6 });
+    }
+
+    #[test]
+    fn test_no_variable_completions_outside_dollar_brace() {
+        for text in ["SELECT |", "SELECT ${hivevar:db} |", "SELECT $|", "SELECT ${a} + {|", "${x\nSELECT|"] {
+            assert!(variable_labels(text).is_none(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn test_statement_start() {
+        for text in ["|", "SEL|", "SELECT 1;\n|", "-- note\n|"] {
+            let labels = context_labels(text, None);
+            assert!(labels.contains(&"SELECT".to_string()), "{}", text);
+            assert!(labels.contains(&"CREATE TABLE".to_string()), "{}", text);
+            assert!(labels.contains(&"WITH CTE".to_string()), "{}", text);
+            assert!(!labels.contains(&"WHERE".to_string()), "{}", text);
+            assert!(!labels.contains(&"COALESCE".to_string()), "{}", text);
+        }
+    }
+
+    #[test]
+    fn test_select_list() {
+        for text in ["SELECT |", "SELECT DISTINCT co|", "SELECT a, |", "SELECT concat(a, b), |"] {
+            let labels = context_labels(text, None);
+            assert!(labels.contains(&"COALESCE".to_string()), "{}", text);
+            assert!(labels.contains(&"CASE WHEN".to_string()), "{}", text);
+            assert!(!labels.contains(&"CREATE TABLE".to_string()), "{}", text);
+            assert!(!labels.contains(&"WHERE".to_string()), "{}", text);
+        }
+
+        let schema = schema();
+        let labels = context_labels("SELECT | FROM sales.orders o", Some(&schema));
+        assert!(labels.contains(&"amount".to_string()));
+        assert!(!labels.contains(&"name".to_string()));
+    }
+
+    #[test]
+    fn test_ambiguous_context_offers_everything() {
+        let full = get_completions(&HqlConfig::default());
+        let CompletionResponse::Array(full) = full else { unreachable!() };
+        for text in ["SELECT a FROM t WHERE |", "SELECT a FROM |", "SELECT concat(a, |", "SELECT a FROM t GROUP BY a, |"] {
+            assert_eq!(context_labels(text, None).len(), full.len(), "{}", text);
+        }
+        // The schema still answers after FROM
+        let schema = schema();
+        assert_eq!(context_labels("SELECT a FROM |", Some(&schema)), vec!["sales.customers", "sales.orders"]);
+    }
+
+    #[test]
+    fn test_keyword_documentation_and_ranking() {
+        let items = all_items(&HqlConfig::default());
+        let item = |label: &str, kind| items.iter().find(|i| i.label == label && i.kind == Some(kind)).unwrap();
+
+        let group_by = resolve(item("GROUP BY", CompletionItemKind::KEYWORD).clone());
+        let Some(Documentation::MarkupContent(doc)) = &group_by.documentation else { panic!("GROUP BY has no documentation") };
+        assert_eq!(doc.kind, MarkupKind::Markdown);
+        assert!(doc.value.contains("group"));
+        assert_eq!(group_by.filter_text.as_deref(), Some("GROUPBY"));
+
+        // Snippets, then common keywords, then the rest
+        let snippet = item("CASE WHEN", CompletionItemKind::SNIPPET);
+        let rare = resolve(item("CROSS JOIN", CompletionItemKind::KEYWORD).clone());
+        assert!(rare.documentation.is_none());
+        assert!(snippet.sort_text < group_by.sort_text);
+        assert!(group_by.sort_text < rare.sort_text);
+        assert_eq!(item("SELECT", CompletionItemKind::KEYWORD).filter_text, None);
+    }
+
+    #[test]
+    fn test_resolve_adds_documentation() {
+        let items = all_items(&HqlConfig::default());
+        for (label, kind) in [
+            ("WHERE", CompletionItemKind::KEYWORD),
+            ("CASE WHEN", CompletionItemKind::SNIPPET),
+        ] {
+            let item = items.iter().find(|i| i.label == label && i.kind == Some(kind)).unwrap();
+            assert!(item.documentation.is_none() && item.detail.is_none(), "{}", label);
+            let resolved = resolve(item.clone());
+            assert!(resolved.documentation.is_some() && resolved.detail.is_some(), "{}", label);
+        }
+
+        let functions = function_items(&[]);
+        let concat = functions.iter().find(|i| i.label == "CONCAT").unwrap();
+        assert!(concat.documentation.is_none());
+        let resolved = resolve(concat.clone());
+        assert!(resolved.detail.as_deref().is_some_and(|detail| detail.starts_with("CONCAT(")));
+        assert!(resolved.documentation.is_some());
+
+        // Items without lookup data, such as user snippets, come back unchanged
+        let user = create_snippet("mine", "User snippet", None, "SELECT 1");
+        assert_eq!(resolve(user.clone()), user);
     }
 }
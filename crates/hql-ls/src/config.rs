@@ -1,73 +1,392 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tower_lsp::lsp_types::{DiagnosticSeverity, Url};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase", default)]
 pub struct HqlConfig {
+    /// `languageId`s of the documents the server handles; others are ignored when opened
+    pub language_ids: Vec<String>,
     pub linting: LintingConfig,
     pub formatting: FormattingConfig,
+    pub completion: CompletionConfig,
 }
 
+/// Language ids clients commonly assign to `.hql`, `.q` and `.sql` Hive scripts
+const DEFAULT_LANGUAGE_IDS: &[&str] = &["hql", "hiveql", "sql"];
+
 impl Default for HqlConfig {
     fn default() -> Self {
         Self {
+            language_ids: DEFAULT_LANGUAGE_IDS.iter().map(|id| id.to_string()).collect(),
             linting: LintingConfig::default(),
             formatting: FormattingConfig::default(),
+            completion: CompletionConfig::default(),
         }
     }
 }
 
+impl HqlConfig {
+    /// True if documents with `language_id` are handled, ignoring case.
+    pub fn accepts_language(&self, language_id: &str) -> bool {
+        self.language_ids.iter().any(|id| id.eq_ignore_ascii_case(language_id))
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase", default)]
 pub struct LintingConfig {
     pub enabled: bool,
-    pub severity: String, // Error, Warning, Information, Hint
+    /// Least severe diagnostics reported: Error, Warning, Information or Hint (everything)
+    pub severity: String,
+    /// Larger files are linted a few statements at a time
     pub max_file_size: u64,
+    /// Files larger than this aren't linted at all
+    pub max_chunked_file_size: u64,
+    /// Most diagnostics reported per document, the first in document order; 0 reports all
+    pub max_diagnostics: usize,
+    /// Milliseconds to wait after a change before linting, restarted by every further change;
+    /// 0 lints on every change. Opening a document always lints at once
+    pub debounce_ms: u64,
+    /// Push diagnostics on every change; clients that pull them (`textDocument/diagnostic`)
+    /// can turn this off to avoid getting them twice
+    pub push_diagnostics: bool,
+    /// Report warnings and information diagnostics as errors
+    pub treat_warnings_as_errors: bool,
+    /// Report hints as errors as well
+    pub treat_hints_as_errors: bool,
+    /// sqlparser dialect used to tokenize and parse, e.g. `hive`, `generic`, `ansi`, `snowflake`
+    pub dialect: String,
+    /// Source shown with each diagnostic, e.g. to tell them apart from another SQL linter's
+    pub source: String,
     pub rules: LintingRules,
+    /// Namespaces accepted in `${namespace:name}`
+    pub hive_variable_namespaces: Vec<String>,
+    /// Known variables and their values by namespace; other names in a listed namespace
+    /// are reported as unknown
+    pub hive_variables: HashMap<String, HashMap<String, String>>,
+    /// Keywords the bundled list lacks, e.g. from newer Hive versions; they're completed,
+    /// highlighted and cased like the built-in ones
+    pub additional_keywords: Vec<String>,
+    /// Custom UDFs offered by completion alongside the built-in functions
+    pub additional_functions: Vec<String>,
+}
+
+impl LintingConfig {
+    /// The configured `severity` floor, or `None` if it isn't a severity name.
+    pub fn severity_floor(&self) -> Option<DiagnosticSeverity> {
+        parse_severity(&self.severity)
+    }
 }
 
+/// Namespaces Hive itself substitutes
+const DEFAULT_HIVE_VARIABLE_NAMESPACES: &[&str] = &["hiveconf", "hivevar", "env", "system", "define"];
+
 impl Default for LintingConfig {
     fn default() -> Self {
         Self {
             enabled: true,
-            severity: "Warning".to_string(),
+            severity: "Hint".to_string(),
             max_file_size: 1048576,
+            max_chunked_file_size: 64 * 1048576,
+            max_diagnostics: 500,
+            debounce_ms: 200,
+            push_diagnostics: true,
+            treat_warnings_as_errors: false,
+            treat_hints_as_errors: false,
+            dialect: "hive".to_string(),
+            source: "hql-ls".to_string(),
             rules: LintingRules::default(),
+            hive_variable_namespaces: DEFAULT_HIVE_VARIABLE_NAMESPACES.iter().map(|ns| ns.to_string()).collect(),
+            hive_variables: HashMap::new(),
+            additional_keywords: Vec::new(),
+            additional_functions: Vec::new(),
         }
     }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase", default)]
 pub struct LintingRules {
-    pub keyword_casing: bool,
-    pub semicolon: bool,
-    pub string_literal: bool,
-    pub parentheses: bool,
-    pub trailing_whitespace: bool,
-    pub missing_comma: bool,
-    pub hive_variable: bool,
+    pub keyword_casing: RuleConfig,
+    pub semicolon: RuleConfig,
+    pub string_literal: RuleConfig,
+    pub parentheses: RuleConfig,
+    pub trailing_whitespace: RuleConfig,
+    pub missing_comma: RuleConfig,
+    pub hive_variable: RuleConfig,
+    pub scalar_subquery_columns: RuleConfig,
+    pub foreign_operator: RuleConfig,
+    pub limit_value: RuleConfig,
+    pub insert_column_order: RuleConfig,
+    pub mixed_aggregate: RuleConfig,
+    pub misplaced_distinct: RuleConfig,
+    pub empty_table_parens: RuleConfig,
+    pub boolean_literal_casing: RuleConfig,
+    pub statement_length: RuleConfig,
+    pub select_star: RuleConfig,
+    pub reserved_identifier: RuleConfig,
+    pub empty_window: RuleConfig,
+    pub case_branch_types: RuleConfig,
+    pub unused_cte_column: RuleConfig,
+    pub cte_order_by: RuleConfig,
+    pub tuning_set: RuleConfig,
+    pub trailing_comma: RuleConfig,
+    pub indentation: RuleConfig,
+    pub wide_group_by: RuleConfig,
+    pub max_line_length: RuleConfig,
+    pub unsafe_interpolation: RuleConfig,
+    pub cartesian_join: RuleConfig,
+    pub redundant_cast: RuleConfig,
+    pub invalid_regex: RuleConfig,
+    pub duplicate_set_column: RuleConfig,
+    pub cte_missing_as: RuleConfig,
+    pub non_join_on: RuleConfig,
+    pub alias_style_consistency: RuleConfig,
+    pub syntax: RuleConfig,
+    pub null_into_not_null: RuleConfig,
+    pub limit_without_order: RuleConfig,
+    pub insert_select_arity: RuleConfig,
+    pub duplicate_select_column: RuleConfig,
+    pub truncate_external: RuleConfig,
+    pub cluster_order_conflict: RuleConfig,
+    pub implicit_default_db: RuleConfig,
+    pub constant_select_item: RuleConfig,
+    pub no_tabs: RuleConfig,
+    pub comment: RuleConfig,
+    pub too_many_name_parts: RuleConfig,
+    pub comma_join: RuleConfig,
+    pub equality_operator: RuleConfig,
+    pub between_bounds: RuleConfig,
+    pub union_dedup: RuleConfig,
+    pub distinct_with_group_by: RuleConfig,
+    pub ambiguous_column: RuleConfig,
+    pub order_by_ordinal: RuleConfig,
+    pub null_comparison: RuleConfig,
+    pub semicolon_in_parens: RuleConfig,
+    pub keyword_alias: RuleConfig,
+    pub group_by_consistency: RuleConfig,
+    pub missing_over: RuleConfig,
+    pub missing_stored_as: RuleConfig,
+    pub duplicate_alias: RuleConfig,
+    pub deprecated_function: RuleConfig,
+    pub constant_predicate: RuleConfig,
+    pub type_casing: RuleConfig,
+    pub type_mismatch: RuleConfig,
+    pub select_distinct_star: RuleConfig,
+    pub inconsistent_quoting: RuleConfig,
+    pub missing_partition: RuleConfig,
+    pub like_without_wildcard: RuleConfig,
+    pub multi_column_count_distinct: RuleConfig,
+    pub batch_separator: RuleConfig,
+    pub union_column_mismatch: RuleConfig,
+    pub unbalanced_case: RuleConfig,
+    pub subquery_depth: RuleConfig,
+    pub set_property: RuleConfig,
 }
 
 impl Default for LintingRules {
     fn default() -> Self {
         Self {
-            keyword_casing: false,
-            semicolon: true,
-            string_literal: true,
-            parentheses: true,
-            trailing_whitespace: true,
-            missing_comma: false,
-            hive_variable: true,
+            keyword_casing: false.into(),
+            semicolon: true.into(),
+            string_literal: true.into(),
+            parentheses: true.into(),
+            trailing_whitespace: true.into(),
+            missing_comma: false.into(),
+            hive_variable: true.into(),
+            scalar_subquery_columns: true.into(),
+            foreign_operator: true.into(),
+            limit_value: true.into(),
+            insert_column_order: false.into(),
+            mixed_aggregate: true.into(),
+            misplaced_distinct: true.into(),
+            empty_table_parens: false.into(),
+            boolean_literal_casing: false.into(),
+            statement_length: false.into(),
+            select_star: false.into(),
+            reserved_identifier: true.into(),
+            empty_window: false.into(),
+            case_branch_types: false.into(),
+            unused_cte_column: false.into(),
+            cte_order_by: true.into(),
+            tuning_set: false.into(),
+            trailing_comma: true.into(),
+            indentation: true.into(),
+            wide_group_by: false.into(),
+            max_line_length: true.into(),
+            unsafe_interpolation: false.into(),
+            cartesian_join: true.into(),
+            redundant_cast: true.into(),
+            invalid_regex: true.into(),
+            duplicate_set_column: true.into(),
+            cte_missing_as: true.into(),
+            non_join_on: false.into(),
+            alias_style_consistency: true.into(),
+            syntax: false.into(),
+            null_into_not_null: true.into(),
+            limit_without_order: false.into(),
+            insert_select_arity: true.into(),
+            duplicate_select_column: true.into(),
+            truncate_external: true.into(),
+            cluster_order_conflict: true.into(),
+            implicit_default_db: false.into(),
+            constant_select_item: false.into(),
+            no_tabs: false.into(),
+            comment: true.into(),
+            too_many_name_parts: true.into(),
+            comma_join: false.into(),
+            equality_operator: true.into(),
+            between_bounds: true.into(),
+            union_dedup: false.into(),
+            distinct_with_group_by: true.into(),
+            ambiguous_column: true.into(),
+            order_by_ordinal: true.into(),
+            null_comparison: true.into(),
+            semicolon_in_parens: true.into(),
+            keyword_alias: true.into(),
+            group_by_consistency: false.into(),
+            missing_over: true.into(),
+            missing_stored_as: true.into(),
+            duplicate_alias: true.into(),
+            deprecated_function: true.into(),
+            constant_predicate: false.into(),
+            type_casing: false.into(),
+            type_mismatch: false.into(),
+            select_distinct_star: true.into(),
+            inconsistent_quoting: false.into(),
+            missing_partition: false.into(),
+            like_without_wildcard: true.into(),
+            multi_column_count_distinct: false.into(),
+            batch_separator: true.into(),
+            union_column_mismatch: true.into(),
+            unbalanced_case: true.into(),
+            subquery_depth: true.into(),
+            set_property: true.into(),
+        }
+    }
+}
+
+/// A single rule's settings. Accepts either a bare bool or
+/// `{ "enabled": true, "severity": "error", "allow": ["name"], ...rule options }`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "RuleConfigRepr")]
+pub struct RuleConfig {
+    pub enabled: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub severity: Option<String>,
+    /// Names the rule should not flag, for rules that support an allowlist
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub allow: Vec<String>,
+    /// Rule-specific options, e.g. `booleanCase` for `booleanLiteralCasing`
+    #[serde(flatten)]
+    pub options: serde_json::Map<String, Value>,
+}
+
+impl RuleConfig {
+    /// The configured severity, or `None` if unset or unrecognized.
+    pub fn severity(&self) -> Option<DiagnosticSeverity> {
+        self.severity.as_deref().and_then(parse_severity)
+    }
+
+    /// The configured severity, falling back to the rule's built-in `default`.
+    pub fn severity_or(&self, default: DiagnosticSeverity) -> DiagnosticSeverity {
+        self.severity().unwrap_or(default)
+    }
+
+    /// True if `name` is on the rule's allowlist, ignoring case.
+    pub fn allows(&self, name: &str) -> bool {
+        self.allow.iter().any(|allowed| allowed.eq_ignore_ascii_case(name))
+    }
+
+    pub fn option_str(&self, key: &str) -> Option<&str> {
+        self.options.get(key).and_then(Value::as_str)
+    }
+
+    pub fn option_u64(&self, key: &str) -> Option<u64> {
+        self.options.get(key).and_then(Value::as_u64)
+    }
+
+    /// A table option of string values, e.g. `{ old_udf = "new_udf" }`; other values are skipped.
+    pub fn option_str_map(&self, key: &str) -> Option<Vec<(&str, &str)>> {
+        let map = self.options.get(key)?.as_object()?;
+        Some(map.iter().filter_map(|(k, v)| Some((k.as_str(), v.as_str()?))).collect())
+    }
+
+    /// A list option; non-string entries are skipped.
+    pub fn option_str_list(&self, key: &str) -> Option<Vec<&str>> {
+        let list = self.options.get(key)?.as_array()?;
+        Some(list.iter().filter_map(Value::as_str).collect())
+    }
+}
+
+impl From<bool> for RuleConfig {
+    fn from(enabled: bool) -> Self {
+        Self { enabled, severity: None, allow: Vec::new(), options: serde_json::Map::new() }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RuleConfigRepr {
+    Enabled(bool),
+    Detailed {
+        #[serde(default = "enabled_by_default")]
+        enabled: bool,
+        #[serde(default)]
+        severity: Option<String>,
+        #[serde(default)]
+        allow: Vec<String>,
+        #[serde(flatten)]
+        options: serde_json::Map<String, Value>,
+    },
+}
+
+impl From<RuleConfigRepr> for RuleConfig {
+    fn from(repr: RuleConfigRepr) -> Self {
+        match repr {
+            RuleConfigRepr::Enabled(enabled) => enabled.into(),
+            RuleConfigRepr::Detailed { enabled, severity, allow, options } => Self { enabled, severity, allow, options },
         }
     }
 }
 
+fn enabled_by_default() -> bool {
+    true
+}
+
+/// Parses a severity name ("error", "warning", "information", "hint"), ignoring case.
+pub fn parse_severity(value: &str) -> Option<DiagnosticSeverity> {
+    match value.to_lowercase().as_str() {
+        "error" => Some(DiagnosticSeverity::ERROR),
+        "warning" => Some(DiagnosticSeverity::WARNING),
+        "information" | "info" => Some(DiagnosticSeverity::INFORMATION),
+        "hint" => Some(DiagnosticSeverity::HINT),
+        _ => None,
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase", default)]
 pub struct FormattingConfig {
     pub enabled: bool,
     pub keyword_case: String, // upper, lower, preserve
+    /// Line breaks between top-level statements: 1 puts each on its own line, 2 leaves a blank
+    /// line between them and 0 keeps them on one line
     pub lines_between_queries: u8,
+    /// Line up `=` in consecutive SET lines and types in multi-line column lists
+    pub align_assignments: bool,
+    /// Keep `--` comments that trail code on the line they were written on
+    pub preserve_comments: bool,
+    /// `trailing` (`a,`) or `leading` (`, b` on the next line) commas in column lists
+    pub comma_style: String,
+    /// Wrap formatted lines longer than this many characters, if set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_line_width: Option<u32>,
+    /// Format each statement on its own, keeping comments and blank lines between statements as written
+    pub per_statement: bool,
 }
 
 impl Default for FormattingConfig {
@@ -76,6 +395,287 @@ impl Default for FormattingConfig {
             enabled: true,
             keyword_case: "upper".to_string(),
             lines_between_queries: 1,
+            align_assignments: false,
+            preserve_comments: true,
+            comma_style: "trailing".to_string(),
+            max_line_width: None,
+            per_statement: false,
         }
     }
 }
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CompletionConfig {
+    /// Offered alongside the built-in snippets; one with a built-in's label replaces it
+    pub snippets: Vec<SnippetConfig>,
+}
+
+/// A user snippet, e.g. `{ label = "SELECT COUNT", body = "SELECT COUNT(*) FROM ${1:table};" }`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnippetConfig {
+    pub label: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    /// LSP snippet syntax: `$1`, `${1:placeholder}`, `${1|a,b|}` and `$0`
+    pub body: String,
+}
+
+/// Workspace config file, looked up in the workspace root.
+pub const CONFIG_FILE_NAME: &str = ".hqlint.toml";
+
+/// The raw settings layers that make up the effective `HqlConfig`.
+///
+/// Precedence, lowest to highest: built-in defaults, `.hqlint.toml` in the workspace root,
+/// then settings pushed by the client via `didChangeConfiguration`. Layers are merged key by
+/// key, so a client only overrides the settings it actually sends.
+#[derive(Debug, Default)]
+pub struct ConfigSources {
+    pub file_path: Option<PathBuf>,
+    pub file: Option<Value>,
+    pub client: Option<Value>,
+}
+
+impl ConfigSources {
+    pub fn new(workspace_root: Option<&Path>) -> Self {
+        Self {
+            file_path: workspace_root.map(|root| root.join(CONFIG_FILE_NAME)),
+            ..Default::default()
+        }
+    }
+
+    /// Sources for a file outside any workspace: the nearest `.hqlint.toml` in `path`'s
+    /// directory or one of its ancestors.
+    pub fn for_file(path: &Path) -> Self {
+        Self {
+            file_path: path
+                .parent()
+                .into_iter()
+                .flat_map(Path::ancestors)
+                .map(|dir| dir.join(CONFIG_FILE_NAME))
+                .find(|candidate| candidate.exists()),
+            ..Default::default()
+        }
+    }
+
+    /// (Re)reads the workspace config file. A missing file clears the file layer.
+    pub fn reload_file(&mut self) -> anyhow::Result<()> {
+        self.file = None;
+        let Some(path) = &self.file_path else { return Ok(()) };
+        if !path.exists() {
+            return Ok(());
+        }
+        let contents = std::fs::read_to_string(path)?;
+        self.file = Some(toml::from_str(&contents)?);
+        Ok(())
+    }
+
+    /// Merges the layers into the effective configuration.
+    pub fn resolve(&self) -> Result<HqlConfig, serde_json::Error> {
+        let mut merged = serde_json::to_value(HqlConfig::default())?;
+        for layer in [&self.file, &self.client].into_iter().flatten() {
+            merge_settings(&mut merged, layer);
+        }
+        serde_json::from_value(merged)
+    }
+}
+
+/// The effective config of each workspace folder, so the projects of a multi-root workspace
+/// can each have their own settings. Documents outside every folder use the global config.
+#[derive(Debug, Default)]
+pub struct FolderConfigs {
+    folders: Vec<FolderConfig>,
+}
+
+#[derive(Debug)]
+pub struct FolderConfig {
+    pub uri: Url,
+    /// The folder's `.hqlint.toml` and the client settings scoped to the folder
+    pub sources: ConfigSources,
+    pub config: HqlConfig,
+}
+
+impl FolderConfigs {
+    /// Adds folder `uri`, or replaces its config if it's already known.
+    pub fn insert(&mut self, uri: Url, sources: ConfigSources, config: HqlConfig) {
+        self.remove(&uri);
+        self.folders.push(FolderConfig { uri, sources, config });
+    }
+
+    pub fn remove(&mut self, uri: &Url) {
+        self.folders.retain(|folder| folder_prefix(&folder.uri) != folder_prefix(uri));
+    }
+
+    pub fn uris(&self) -> Vec<Url> {
+        self.folders.iter().map(|folder| folder.uri.clone()).collect()
+    }
+
+    /// Folders whose `.hqlint.toml` is one of `paths`.
+    pub fn watching(&self, paths: &[PathBuf]) -> Vec<Url> {
+        self.folders
+            .iter()
+            .filter(|folder| folder.sources.file_path.as_ref().is_some_and(|path| paths.contains(path)))
+            .map(|folder| folder.uri.clone())
+            .collect()
+    }
+
+    /// The config of the innermost folder containing `document`, if any does.
+    pub fn config_for(&self, document: &str) -> Option<&HqlConfig> {
+        self.folders
+            .iter()
+            .filter(|folder| document.starts_with(&folder_prefix(&folder.uri)))
+            .max_by_key(|folder| folder.uri.as_str().len())
+            .map(|folder| &folder.config)
+    }
+}
+
+/// `uri` with a trailing slash, so `file:///a/b` doesn't contain `file:///a/bc/x.hql`.
+fn folder_prefix(uri: &Url) -> String {
+    let uri = uri.as_str();
+    if uri.ends_with('/') { uri.to_string() } else { format!("{}/", uri) }
+}
+
+/// Recursively overlays `overlay` onto `base`; objects merge, everything else replaces.
+fn merge_settings(base: &mut Value, overlay: &Value) {
+    match (base, overlay) {
+        (Value::Object(base), Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(key) {
+                    Some(existing) => merge_settings(existing, value),
+                    None => {
+                        base.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rule_config_accepts_bool_or_object() {
+        let rules: LintingRules = serde_json::from_value(serde_json::json!({
+            "keywordCasing": true,
+            "semicolon": true,
+            "stringLiteral": true,
+            "parentheses": true,
+            "trailingWhitespace": { "severity": "hint" },
+            "missingComma": { "enabled": true, "severity": "error" },
+            "hiveVariable": false,
+            "scalarSubqueryColumns": true,
+            "foreignOperator": true,
+            "limitValue": true,
+            "insertColumnOrder": false,
+            "mixedAggregate": true
+        }))
+        .unwrap();
+
+        assert!(rules.keyword_casing.enabled);
+        assert!(!rules.hive_variable.enabled);
+        assert!(rules.trailing_whitespace.enabled);
+        assert_eq!(rules.trailing_whitespace.severity(), Some(DiagnosticSeverity::HINT));
+        assert_eq!(rules.missing_comma.severity_or(DiagnosticSeverity::WARNING), DiagnosticSeverity::ERROR);
+        assert_eq!(rules.semicolon.severity_or(DiagnosticSeverity::INFORMATION), DiagnosticSeverity::INFORMATION);
+    }
+
+    #[test]
+    fn test_parse_severity() {
+        assert_eq!(parse_severity("Error"), Some(DiagnosticSeverity::ERROR));
+        assert_eq!(parse_severity("information"), Some(DiagnosticSeverity::INFORMATION));
+        assert_eq!(parse_severity("loud"), None);
+    }
+
+    #[test]
+    fn test_client_settings_override_config_file() {
+        let file: Value = toml::from_str(
+            r#"
+            [linting]
+            maxFileSize = 2048
+
+            [linting.rules]
+            keywordCasing = true
+            missingComma = { severity = "error" }
+
+            [formatting]
+            keywordCase = "lower"
+            "#,
+        )
+        .unwrap();
+        let sources = ConfigSources {
+            file: Some(file),
+            client: Some(serde_json::json!({ "formatting": { "keywordCase": "upper" } })),
+            ..Default::default()
+        };
+
+        let config = sources.resolve().unwrap();
+        assert_eq!(config.linting.max_file_size, 2048);
+        assert!(config.linting.rules.keyword_casing.enabled);
+        assert_eq!(config.linting.rules.missing_comma.severity(), Some(DiagnosticSeverity::ERROR));
+        assert_eq!(config.formatting.keyword_case, "upper");
+        assert_eq!(config.formatting.lines_between_queries, 1);
+    }
+
+    #[test]
+    fn test_hive_variable_settings() {
+        let file: Value = toml::from_str(
+            r#"
+            [linting]
+            hiveVariableNamespaces = ["hivevar", "custom"]
+
+            [linting.hiveVariables.hivevar]
+            db = "prod_db"
+            "#,
+        )
+        .unwrap();
+        let sources = ConfigSources { file: Some(file), ..Default::default() };
+
+        let config = sources.resolve().unwrap();
+        assert_eq!(config.linting.hive_variable_namespaces, vec!["hivevar", "custom"]);
+        assert_eq!(config.linting.hive_variables["hivevar"]["db"], "prod_db");
+        assert_eq!(LintingConfig::default().hive_variable_namespaces.len(), 5);
+    }
+
+    #[test]
+    fn test_language_ids() {
+        let config = HqlConfig::default();
+        assert!(config.accepts_language("hql"));
+        assert!(config.accepts_language("HiveQL"));
+        assert!(config.accepts_language("sql"));
+        assert!(!config.accepts_language("python"));
+
+        let sources = ConfigSources { client: Some(serde_json::json!({ "languageIds": ["hql"] })), ..Default::default() };
+        let config = sources.resolve().unwrap();
+        assert!(config.accepts_language("hql"));
+        assert!(!config.accepts_language("sql"));
+    }
+
+    #[test]
+    fn test_folder_configs() {
+        let mut folders = FolderConfigs::default();
+        let folder_config = |dialect: &str| {
+            let sources = ConfigSources { client: Some(serde_json::json!({ "linting": { "dialect": dialect } })), ..Default::default() };
+            let config = sources.resolve().unwrap();
+            (sources, config)
+        };
+        let (sources, config) = folder_config("generic");
+        folders.insert(Url::parse("file:///repo/etl").unwrap(), sources, config);
+        let (sources, config) = folder_config("ansi");
+        folders.insert(Url::parse("file:///repo/etl/legacy/").unwrap(), sources, config);
+
+        let dialect = |folders: &FolderConfigs, document: &str| folders.config_for(document).map(|config| config.linting.dialect.clone());
+        assert_eq!(dialect(&folders, "file:///repo/etl/daily.hql").as_deref(), Some("generic"));
+        // The innermost folder wins
+        assert_eq!(dialect(&folders, "file:///repo/etl/legacy/old.hql").as_deref(), Some("ansi"));
+        assert_eq!(dialect(&folders, "file:///repo/etl2/x.hql"), None);
+        assert_eq!(dialect(&folders, "file:///tmp/x.hql"), None);
+
+        folders.remove(&Url::parse("file:///repo/etl/legacy").unwrap());
+        assert_eq!(dialect(&folders, "file:///repo/etl/legacy/old.hql").as_deref(), Some("generic"));
+        assert_eq!(folders.uris().len(), 1);
+    }
+}
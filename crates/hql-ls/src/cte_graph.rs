@@ -0,0 +1,129 @@
+use serde::Serialize;
+use tower_lsp::lsp_types::{Position, Range};
+use crate::references;
+use crate::syntax::{self, span_to_range};
+
+/// Result of the `hql/cteGraph` request for the statement at a position.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CteGraph {
+    /// The statement's CTEs in declaration order
+    pub ctes: Vec<CteNode>,
+    /// Each cycle as the CTEs along it, starting from the one declared first, e.g. `[a, b]`
+    /// for `a -> b -> a`. A CTE that reads itself is a cycle of one
+    pub cycles: Vec<Vec<String>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CteNode {
+    pub name: String,
+    /// The CTE's name where it is defined
+    pub range: Range,
+    /// CTEs of the same statement read in this one's FROM or JOIN clauses, as declared and
+    /// in order of first use
+    pub depends_on: Vec<String>,
+}
+
+/// The CTEs of the statement at `position` and which of them each one reads. `None` if
+/// the text doesn't tokenize or `position` isn't in a statement.
+pub fn cte_graph(text: &str, position: Position) -> Option<CteGraph> {
+    let tokens = syntax::tokenize(text)?;
+    let statement = syntax::statement_at(&tokens, position)?;
+    let ctes = syntax::collect_ctes(&tokens, &statement);
+    let references = references::collect_table_references(&tokens);
+
+    let mut edges: Vec<Vec<usize>> = Vec::new();
+    for cte in &ctes {
+        let after_name = span_to_range(&tokens[cte.name_idx].span).end;
+        let body_end = span_to_range(&tokens[cte.close_idx].span).end;
+        let mut targets = Vec::new();
+        for reference in &references {
+            if reference.database.is_some() || reference.range.start < after_name || reference.range.end > body_end {
+                continue;
+            }
+            if let Some(target) = ctes.iter().position(|c| c.name.eq_ignore_ascii_case(&reference.name))
+                && !targets.contains(&target)
+            {
+                targets.push(target);
+            }
+        }
+        edges.push(targets);
+    }
+
+    let cycles = find_cycles(&edges)
+        .into_iter()
+        .map(|cycle| cycle.into_iter().map(|i| ctes[i].name.clone()).collect())
+        .collect();
+    let ctes = ctes
+        .iter()
+        .zip(&edges)
+        .map(|(cte, targets)| CteNode {
+            name: cte.name.clone(),
+            range: span_to_range(&tokens[cte.name_idx].span),
+            depends_on: targets.iter().map(|&i| ctes[i].name.clone()).collect(),
+        })
+        .collect();
+    Some(CteGraph { ctes, cycles })
+}
+
+/// The cycles a depth-first search closes in `edges`, one per edge back to a node still on
+/// the search path. Every cyclic node is on at least one of them.
+fn find_cycles(edges: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    fn visit(node: usize, edges: &[Vec<usize>], done: &mut [bool], path: &mut Vec<usize>, cycles: &mut Vec<Vec<usize>>) {
+        path.push(node);
+        for &next in &edges[node] {
+            if let Some(start) = path.iter().position(|&n| n == next) {
+                let mut cycle = path[start..].to_vec();
+                let first = (0..cycle.len()).min_by_key(|&i| cycle[i]).unwrap_or(0);
+                cycle.rotate_left(first);
+                cycles.push(cycle);
+            } else if !done[next] {
+                visit(next, edges, done, path, cycles);
+            }
+        }
+        path.pop();
+        done[node] = true;
+    }
+
+    let mut done = vec![false; edges.len()];
+    let mut cycles = Vec::new();
+    for node in 0..edges.len() {
+        if !done[node] {
+            visit(node, edges, &mut done, &mut Vec::new(), &mut cycles);
+        }
+    }
+    cycles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dependencies(graph: &CteGraph) -> Vec<(&str, Vec<&str>)> {
+        graph.ctes.iter().map(|cte| (cte.name.as_str(), cte.depends_on.iter().map(String::as_str).collect())).collect()
+    }
+
+    #[test]
+    fn test_cte_dependencies() {
+        let sql = "WITH a AS (SELECT 1 AS x),\n\
+                   b AS (SELECT x FROM a),\n\
+                   c AS (SELECT * FROM b JOIN A ON b.x = A.x JOIN db.a d ON d.x = b.x)\n\
+                   SELECT * FROM c;\n\
+                   SELECT 2;";
+        let graph = cte_graph(sql, Position { line: 3, character: 3 }).unwrap();
+        assert_eq!(dependencies(&graph), vec![("a", vec![]), ("b", vec!["a"]), ("c", vec!["b", "a"])]);
+        assert_eq!(graph.ctes[1].range, Range { start: Position { line: 1, character: 0 }, end: Position { line: 1, character: 1 } });
+        assert!(graph.cycles.is_empty());
+
+        let other = cte_graph(sql, Position { line: 4, character: 3 }).unwrap();
+        assert!(other.ctes.is_empty());
+    }
+
+    #[test]
+    fn test_cte_cycles() {
+        let sql = "WITH a AS (SELECT * FROM c), b AS (SELECT * FROM a), c AS (SELECT * FROM b), d AS (SELECT * FROM d) SELECT * FROM a;";
+        let graph = cte_graph(sql, Position { line: 0, character: 0 }).unwrap();
+        assert_eq!(graph.cycles, vec![vec!["a", "c", "b"], vec!["d"]]);
+    }
+}
@@ -0,0 +1,78 @@
+use ropey::Rope;
+use tower_lsp::lsp_types::{Position, TextDocumentContentChangeEvent};
+
+/// Applies one `didChange` event to `rope`. Events without a range replace the whole text.
+pub fn apply_change(rope: &mut Rope, change: &TextDocumentContentChangeEvent) {
+    match change.range {
+        Some(range) => {
+            let start = position_to_char(rope, range.start);
+            let end = position_to_char(rope, range.end).max(start);
+            rope.remove(start..end);
+            rope.insert(start, &change.text);
+        }
+        None => *rope = Rope::from_str(&change.text),
+    }
+}
+
+/// Converts an LSP position (UTF-16 code units) into a char index, clamped to the document.
+pub fn position_to_char(rope: &Rope, position: Position) -> usize {
+    let line = position.line as usize;
+    if line >= rope.len_lines() {
+        return rope.len_chars();
+    }
+
+    let line_start = rope.line_to_char(line);
+    let line_end = if line + 1 < rope.len_lines() {
+        rope.line_to_char(line + 1)
+    } else {
+        rope.len_chars()
+    };
+    let utf16_offset = rope.char_to_utf16_cu(line_start) + position.character as usize;
+    let utf16_offset = utf16_offset.min(rope.char_to_utf16_cu(line_end));
+    rope.utf16_cu_to_char(utf16_offset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tower_lsp::lsp_types::Range;
+
+    fn change(start: (u32, u32), end: (u32, u32), text: &str) -> TextDocumentContentChangeEvent {
+        TextDocumentContentChangeEvent {
+            range: Some(Range {
+                start: Position { line: start.0, character: start.1 },
+                end: Position { line: end.0, character: end.1 },
+            }),
+            range_length: None,
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_apply_incremental_changes() {
+        let mut rope = Rope::from_str("SELECT a\nFROM t;");
+        apply_change(&mut rope, &change((0, 7), (0, 8), "id, name"));
+        apply_change(&mut rope, &change((1, 5), (1, 6), "users"));
+        assert_eq!(rope.to_string(), "SELECT id, name\nFROM users;");
+    }
+
+    #[test]
+    fn test_apply_change_after_utf16_surrogate_pair() {
+        // '😀' is two UTF-16 code units but a single char
+        let mut rope = Rope::from_str("-- 😀 x\nSELECT 1;");
+        apply_change(&mut rope, &change((0, 6), (0, 7), "y"));
+        assert_eq!(rope.to_string(), "-- 😀 y\nSELECT 1;");
+    }
+
+    #[test]
+    fn test_apply_full_replacement() {
+        let mut rope = Rope::from_str("SELECT 1;");
+        let full = TextDocumentContentChangeEvent {
+            range: None,
+            range_length: None,
+            text: "SELECT 2;".to_string(),
+        };
+        apply_change(&mut rope, &full);
+        assert_eq!(rope.to_string(), "SELECT 2;");
+    }
+}
@@ -0,0 +1,77 @@
+use sqlparser::tokenizer::{Token, TokenWithSpan};
+use tower_lsp::lsp_types::FoldingRange;
+use crate::syntax::{self, span_to_range};
+
+/// Folds each multi-line statement, CTE definition and parenthesized block.
+pub fn folding_ranges(tokens: &[TokenWithSpan]) -> Vec<FoldingRange> {
+    let mut folds: Vec<(u32, u32)> = Vec::new();
+
+    for statement in syntax::split_statements(tokens) {
+        push_fold(&mut folds, &tokens[statement.start], &tokens[statement.end - 1]);
+        for cte in syntax::collect_ctes(tokens, &statement) {
+            push_fold(&mut folds, &tokens[cte.name_idx], &tokens[cte.close_idx]);
+        }
+    }
+
+    let mut open_parens = Vec::new();
+    for (i, token_with_span) in tokens.iter().enumerate() {
+        match token_with_span.token {
+            Token::LParen => open_parens.push(i),
+            Token::RParen => {
+                if let Some(open_idx) = open_parens.pop() {
+                    push_fold(&mut folds, &tokens[open_idx], token_with_span);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // A CTE usually folds the same lines as its parentheses
+    folds.sort();
+    folds.dedup();
+    folds
+        .into_iter()
+        .map(|(start_line, end_line)| FoldingRange {
+            start_line,
+            end_line,
+            ..Default::default()
+        })
+        .collect()
+}
+
+fn push_fold(folds: &mut Vec<(u32, u32)>, first: &TokenWithSpan, last: &TokenWithSpan) {
+    let start_line = span_to_range(&first.span).start.line;
+    let end_line = span_to_range(&last.span).end.line;
+    if end_line > start_line {
+        folds.push((start_line, end_line));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(text: &str) -> Vec<(u32, u32)> {
+        folding_ranges(&syntax::tokenize(text).unwrap()).iter().map(|f| (f.start_line, f.end_line)).collect()
+    }
+
+    #[test]
+    fn test_single_line_statement_has_no_fold() {
+        assert!(lines("SELECT a FROM (SELECT 1) t;").is_empty());
+    }
+
+    #[test]
+    fn test_nested_parentheses_fold() {
+        let text = "CREATE TABLE t (\n  id INT,\n  tags STRUCT<a: INT>\n)\nPARTITIONED BY (\n  dt STRING\n);";
+        assert_eq!(lines(text), vec![(0, 3), (0, 6), (4, 6)]);
+
+        let text = "SELECT COALESCE(\n  a,\n  (b +\n   c)\n) FROM t;";
+        assert_eq!(lines(text), vec![(0, 4), (2, 3)]);
+    }
+
+    #[test]
+    fn test_cte_folds() {
+        let text = "WITH a AS\n(\n  SELECT 1\n),\nb AS (SELECT 2)\nSELECT * FROM a, b;";
+        assert_eq!(lines(text), vec![(0, 3), (0, 5), (1, 3)]);
+    }
+}
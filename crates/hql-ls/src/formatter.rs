@@ -1,8 +1,191 @@
-use tower_lsp::lsp_types::{TextEdit, Range, Position, FormattingOptions};
+use std::collections::HashMap;
+use tower_lsp::lsp_types::{TextEdit, Range, Position, FormattingOptions, Url, WorkspaceEdit};
 use sqlformat::{format, FormatOptions, QueryParams, Indent};
+use sqlparser::dialect::Dialect;
+use sqlparser::tokenizer::{Tokenizer, Token, Whitespace};
+use ropey::Rope;
+use regex::Regex;
+use serde::Deserialize;
+use std::sync::OnceLock;
 use crate::config::FormattingConfig;
+use crate::syntax;
 
-pub fn format_text(text: &str, options: FormattingOptions, config: &FormattingConfig) -> Vec<TextEdit> {
+/// Parameters of the `hql/formatString` request.
+#[derive(Debug, Deserialize)]
+pub struct FormatStringParams {
+    pub text: String,
+    /// Indentation to use; two spaces if omitted
+    #[serde(default)]
+    pub options: Option<FormattingOptions>,
+}
+
+/// The options used when a caller gives none: two-space indents.
+pub fn default_options() -> FormattingOptions {
+    FormattingOptions { tab_size: 2, insert_spaces: true, ..Default::default() }
+}
+
+/// Command that formats every open document as one workspace edit.
+pub const FORMAT_WORKSPACE: &str = "hql.formatWorkspace";
+
+/// The edits of `hql.formatWorkspace`, collected one document at a time so the server can
+/// report progress in between.
+#[derive(Debug, Default)]
+pub struct WorkspaceFormat {
+    changes: HashMap<Url, Vec<TextEdit>>,
+    /// Documents left alone because formatting them failed
+    pub failed: Vec<Url>,
+    /// Statements left as written because formatting would have changed their quoted text
+    pub kept: usize,
+}
+
+impl WorkspaceFormat {
+    /// Formats `text` unless formatting is disabled for it or wouldn't change it. A panic in
+    /// the formatter only skips this document.
+    pub fn add(&mut self, uri: Url, text: &str, options: &FormattingOptions, config: &FormattingConfig) {
+        if !config.enabled {
+            return;
+        }
+        let formatted = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| format_text(text, options.clone(), config)));
+        match formatted {
+            Ok((edits, kept)) => {
+                self.kept += kept;
+                if edits.iter().any(|edit| edit.new_text != text) {
+                    self.changes.insert(uri, edits);
+                }
+            }
+            Err(_) => self.failed.push(uri),
+        }
+    }
+
+    pub fn into_edit(self) -> WorkspaceEdit {
+        WorkspaceEdit { changes: Some(self.changes), ..Default::default() }
+    }
+}
+
+/// Formats the whole document, returning one edit that replaces all of it and the number of
+/// statements left as written because formatting would have changed their quoted text.
+pub fn format_text(text: &str, options: FormattingOptions, config: &FormattingConfig) -> (Vec<TextEdit>, usize) {
+    let (formatted, kept) = format_checked(text, &options, config);
+    let edit = TextEdit {
+        range: Range { start: Position { line: 0, character: 0 }, end: document_end(text) },
+        new_text: formatted,
+    };
+    (vec![edit], kept)
+}
+
+/// The position just past the last character of `text`, in UTF-16 code units.
+pub fn document_end(text: &str) -> Position {
+    let (line, last_line) = text.split('\n').enumerate().last().unwrap_or((0, ""));
+    Position { line: line as u32, character: last_line.encode_utf16().count() as u32 }
+}
+
+/// Formats only the statements touched by `range`.
+///
+/// sqlformat needs complete statements, so the selection is widened to the
+/// surrounding top-level semicolons and the returned edit covers that span.
+pub fn format_range(
+    rope: &Rope,
+    range: Range,
+    options: FormattingOptions,
+    config: &FormattingConfig,
+    dialect: &dyn Dialect,
+) -> Option<TextEdit> {
+    let (start, end) = statement_bounds(rope, range, dialect)?;
+    format_span(rope, start, end, &options, config)
+}
+
+/// Formats the statement terminated by the semicolon just typed before `position`.
+pub fn format_on_type(
+    rope: &Rope,
+    position: Position,
+    options: FormattingOptions,
+    config: &FormattingConfig,
+    dialect: &dyn Dialect,
+) -> Option<TextEdit> {
+    let line = position.line as usize;
+    if line >= rope.len_lines() {
+        return None;
+    }
+    let semicolon = (rope.line_to_char(line) + position.character as usize).checked_sub(1)?;
+    if semicolon >= rope.len_chars() || rope.char(semicolon) != ';' {
+        return None;
+    }
+
+    let (start, end) = statement_span(rope, semicolon, semicolon + 1, dialect)?;
+    // A `;` typed inside a string or comment doesn't terminate anything
+    if end != semicolon + 1 {
+        return None;
+    }
+    format_span(rope, start, end, &options, config)
+}
+
+fn format_span(rope: &Rope, start: usize, end: usize, options: &FormattingOptions, config: &FormattingConfig) -> Option<TextEdit> {
+    let slice = rope.slice(start..end).to_string();
+    let formatted = format_string(&slice, options, config);
+
+    Some(TextEdit {
+        range: Range {
+            start: char_to_position(rope, start),
+            end: char_to_position(rope, end),
+        },
+        new_text: formatted,
+    })
+}
+
+pub fn format_string(text: &str, options: &FormattingOptions, config: &FormattingConfig) -> String {
+    format_checked(text, options, config).0
+}
+
+/// Formats `text`, also returning how many statements were left as written because formatting
+/// them would have changed their quoted text.
+fn format_checked(text: &str, options: &FormattingOptions, config: &FormattingConfig) -> (String, usize) {
+    let format = |statement: &str| format_query(statement, options, config);
+    let formatted = if config.per_statement {
+        format_statements(text, config.lines_between_queries, format)
+    } else {
+        space_statements(&format(text), config.lines_between_queries)
+    };
+    keep_quoted_text(text, formatted, config.lines_between_queries, format)
+}
+
+/// sqlformat may normalize whitespace inside string literals, silently changing data. If the
+/// quoted tokens of `formatted` differ from those of `text`, formats statement by statement
+/// instead and leaves the statements it would change as written, counting them.
+fn keep_quoted_text(text: &str, formatted: String, lines_between_queries: u8, format: impl Fn(&str) -> String) -> (String, usize) {
+    if quoted_tokens(text) == quoted_tokens(&formatted) {
+        return (formatted, 0);
+    }
+    let kept = std::cell::Cell::new(0);
+    let formatted = format_statements(text, lines_between_queries, |statement| {
+        let formatted = format(statement);
+        if quoted_tokens(statement) == quoted_tokens(&formatted) {
+            formatted
+        } else {
+            kept.set(kept.get() + 1);
+            statement.to_string()
+        }
+    });
+    (formatted, kept.get())
+}
+
+/// String literals and quoted identifiers as written, quotes included.
+fn quoted_tokens(text: &str) -> Option<Vec<String>> {
+    let tokens = syntax::tokenize(text)?;
+    Some(
+        tokens
+            .iter()
+            .filter(|t| match &t.token {
+                Token::Word(w) => w.quote_style.is_some(),
+                Token::SingleQuotedString(_) | Token::DoubleQuotedString(_) | Token::NationalStringLiteral(_)
+                | Token::HexStringLiteral(_) => true,
+                _ => false,
+            })
+            .map(|t| t.token.to_string())
+            .collect(),
+    )
+}
+
+fn format_query(text: &str, options: &FormattingOptions, config: &FormattingConfig) -> String {
     let indent = if options.insert_spaces {
         Indent::Spaces(options.tab_size as u8)
     } else {
@@ -15,27 +198,708 @@ pub fn format_text(text: &str, options: FormattingOptions, config: &FormattingCo
         _ => None, // preserve
     };
 
+    // Statements are spaced afterwards, between top-level statements only
     let format_opts = FormatOptions {
         indent,
         uppercase,
-        lines_between_queries: config.lines_between_queries,
+        lines_between_queries: 1,
         ..Default::default()
     };
 
-    let formatted = format(text, &QueryParams::None, &format_opts);
+    let mut formatted = format(text, &QueryParams::None, &format_opts);
+    if config.preserve_comments {
+        formatted = restore_trailing_comments(text, &formatted);
+    }
+    if config.align_assignments {
+        formatted = align_assignments(&formatted);
+    }
+    if let Some(max_width) = config.max_line_width {
+        let indent = if options.insert_spaces { " ".repeat(options.tab_size as usize) } else { "\t".to_string() };
+        formatted = wrap_long_lines(&formatted, max_width as usize, &indent);
+    }
+    if config.comma_style.eq_ignore_ascii_case("leading") {
+        formatted = leading_commas(&formatted);
+    }
+    formatted
+}
 
-    // Replace the entire document with the formatted text
-    let line_count = text.lines().count() as u32;
-    let last_line_len = text.lines().last().map(|l| l.len()).unwrap_or(0) as u32;
+/// Formats each top-level statement with `format` and rejoins them. Text between statements
+/// is only whitespace and comments: where it has comments its lines are kept as written, blank
+/// lines included, and a comment trailing a `;` stays on that line. Elsewhere statements are
+/// separated by `lines_between_queries` line breaks. Text that doesn't tokenize is formatted whole.
+fn format_statements(text: &str, lines_between_queries: u8, format: impl Fn(&str) -> String) -> String {
+    let Some(statements) = statement_spans(text) else { return format(text) };
 
-    vec![TextEdit {
-        range: Range {
-            start: Position { line: 0, character: 0 },
-            end: Position { 
-                line: std::cmp::max(line_count, 1) - 1, 
-                character: last_line_len + 1000 
-            },
-        },
-        new_text: formatted,
-    }]
+    let mut output = String::new();
+    let mut gap_start = 0;
+    for (n, &(start, end)) in statements.iter().enumerate() {
+        push_gap(&mut output, &text[gap_start..start], n > 0, lines_between_queries);
+        output.push_str(format(&text[start..end]).trim());
+        gap_start = end;
+    }
+    if statements.is_empty() {
+        return text.to_string();
+    }
+    // Only comments after the last statement
+    let trailing = &text[gap_start..];
+    let (same_line, rest) = trailing.split_once('\n').unwrap_or((trailing, ""));
+    if !same_line.trim().is_empty() {
+        output.push(' ');
+        output.push_str(same_line.trim());
+    }
+    let rest = rest.trim_end();
+    if !rest.trim().is_empty() {
+        output.push('\n');
+        output.push_str(&rest.lines().map(str::trim_end).collect::<Vec<_>>().join("\n"));
+    }
+    output
+}
+
+/// Separates top-level statements with `lines_between_queries` line breaks, or a space if it's
+/// 0, leaving statements with comments between them as they are. Line breaks within a statement,
+/// e.g. in a subquery, aren't touched.
+fn space_statements(text: &str, lines_between_queries: u8) -> String {
+    let Some(statements) = statement_spans(text) else { return text.to_string() };
+    let mut output = String::with_capacity(text.len());
+    let mut gap_start = 0;
+    for (n, &(start, end)) in statements.iter().enumerate() {
+        let gap = &text[gap_start..start];
+        if n > 0 && gap.trim().is_empty() {
+            output.push_str(&statement_separator(lines_between_queries));
+        } else {
+            output.push_str(gap);
+        }
+        output.push_str(&text[start..end]);
+        gap_start = end;
+    }
+    output.push_str(&text[gap_start..]);
+    output
+}
+
+/// `lines_between_queries` line breaks, or a space for 0 so the statements share a line.
+fn statement_separator(lines_between_queries: u8) -> String {
+    match lines_between_queries {
+        0 => " ".to_string(),
+        lines => "\n".repeat(lines as usize),
+    }
+}
+
+/// Byte ranges of the top-level statements of `text`, from their first token through the `;`.
+fn statement_spans(text: &str) -> Option<Vec<(usize, usize)>> {
+    let tokens = syntax::tokenize(text)?;
+    let line_starts: Vec<usize> = std::iter::once(0).chain(text.match_indices('\n').map(|(i, _)| i + 1)).collect();
+    let offset = |location: sqlparser::tokenizer::Location| {
+        let line_start = line_starts[(location.line - 1) as usize];
+        text[line_start..]
+            .char_indices()
+            .nth((location.column - 1) as usize)
+            .map_or(text.len(), |(i, _)| line_start + i)
+    };
+    Some(
+        syntax::split_statements(&tokens)
+            .into_iter()
+            .map(|statement| (offset(tokens[statement.start].span.start), offset(tokens[statement.end - 1].span.end)))
+            .collect(),
+    )
+}
+
+/// Appends the whitespace and comments between two statements (`after_statement`) or before the first.
+fn push_gap(output: &mut String, gap: &str, after_statement: bool, lines_between_queries: u8) {
+    let mut lines: Vec<&str> = gap.split('\n').map(str::trim_end).collect();
+    if after_statement {
+        let same_line = lines.remove(0).trim();
+        if !same_line.is_empty() {
+            output.push(' ');
+            output.push_str(same_line);
+        }
+    }
+    // The last line holds the next statement's indentation
+    if lines.last().is_some_and(|line| line.trim().is_empty()) {
+        lines.pop();
+    }
+
+    if lines.iter().all(|line| line.trim().is_empty()) {
+        if after_statement {
+            output.push_str(&statement_separator(lines_between_queries));
+        }
+        return;
+    }
+    if after_statement {
+        output.push('\n');
+    }
+    for line in lines {
+        output.push_str(line);
+        output.push('\n');
+    }
+}
+
+/// Breaks lines longer than `max_width` after commas and before AND/OR, indenting the
+/// continuation lines one level deeper. Lines with nowhere to break are left long.
+fn wrap_long_lines(text: &str, max_width: usize, indent: &str) -> String {
+    let mut lines = Vec::new();
+    for line in text.split('\n') {
+        if line.chars().count() <= max_width {
+            lines.push(line.to_string());
+            continue;
+        }
+        let Some(tokens) = syntax::tokenize(line) else {
+            lines.push(line.to_string());
+            continue;
+        };
+
+        // Char offsets a continuation line may start at
+        let mut breaks = Vec::new();
+        for (i, token_with_span) in tokens.iter().enumerate() {
+            let column = (token_with_span.span.start.column - 1) as usize;
+            match &token_with_span.token {
+                Token::Comma => breaks.push(column + 1),
+                token if i > 0 && (syntax::is_keyword(token, "AND") || syntax::is_keyword(token, "OR")) => breaks.push(column),
+                _ => {}
+            }
+        }
+
+        let chars: Vec<char> = line.chars().collect();
+        let lead: String = chars.iter().take_while(|c| c.is_whitespace()).collect();
+        let continuation = format!("{}{}", lead, indent);
+        let mut prefix = String::new();
+        let mut start = 0;
+        loop {
+            let segment = |end: usize| chars[start..end].iter().collect::<String>().trim_end().to_string();
+            let width = |end: usize| prefix.chars().count() + segment(end).chars().count();
+            if width(chars.len()) <= max_width {
+                break;
+            }
+            let candidates: Vec<usize> = breaks.iter().copied().filter(|&b| b > start && b < chars.len()).collect();
+            let Some(end) = candidates.iter().copied().rfind(|&b| width(b) <= max_width).or(candidates.first().copied())
+            else {
+                break;
+            };
+            lines.push(format!("{}{}", prefix, segment(end)));
+            start = end;
+            while chars.get(start).is_some_and(|c| c.is_whitespace()) {
+                start += 1;
+            }
+            prefix.clone_from(&continuation);
+        }
+        let rest: String = chars[start..].iter().collect();
+        lines.push(format!("{}{}", prefix, rest));
+    }
+    lines.join("\n")
+}
+
+/// Moves each comma that ends a line in a SELECT, GROUP BY or ORDER BY list to the start
+/// of the next item's line. Commas inside function calls and column definitions stay put.
+fn leading_commas(text: &str) -> String {
+    let Some(tokens) = syntax::tokenize(text) else { return text.to_string() };
+    // One entry per open paren: true for subqueries, whose lists are rewritten too
+    let mut groups: Vec<bool> = Vec::new();
+    let mut moves = Vec::new();
+
+    for (i, token_with_span) in tokens.iter().enumerate() {
+        match token_with_span.token {
+            Token::LParen => {
+                let next = tokens[i + 1..].iter().find(|t| syntax::is_significant(&t.token));
+                groups.push(next.is_some_and(|t| syntax::is_keyword(&t.token, "SELECT")));
+            }
+            Token::RParen => {
+                groups.pop();
+            }
+            Token::Comma if groups.last() != Some(&false) => {
+                let comma = token_with_span.span.start;
+                // Only a comma that is the last code on its line
+                let Some(next) = tokens[i + 1..].iter().find(|t| {
+                    !matches!(t.token, Token::Whitespace(_)) || t.span.start.line > comma.line + 1
+                }) else { continue };
+                let line_break = tokens[i + 1..].iter().any(|t| {
+                    t.span.start.line == comma.line
+                        && matches!(t.token, Token::Whitespace(Whitespace::Newline | Whitespace::SingleLineComment { .. }))
+                });
+                if line_break && next.span.start.line == comma.line + 1 && !matches!(next.token, Token::EOF) {
+                    moves.push((comma, next.span.start));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut lines: Vec<String> = text.split('\n').map(str::to_string).collect();
+    for (comma, next) in moves.into_iter().rev() {
+        let next_line = &mut lines[(next.line - 1) as usize];
+        if let Some((at, _)) = next_line.char_indices().nth((next.column - 1) as usize) {
+            next_line.insert_str(at, ", ");
+        }
+        let comma_line = &mut lines[(comma.line - 1) as usize];
+        if let Some((at, _)) = comma_line.char_indices().nth((comma.column - 1) as usize) {
+            comma_line.remove(at);
+            *comma_line = comma_line.trim_end().to_string();
+        }
+    }
+    lines.join("\n")
+}
+
+/// sqlformat keeps `--` comments but may push one that trailed code onto its own line, e.g.
+/// after a `;`. Moves such comments back to the end of the preceding line, and trims the
+/// gap before trailing comments to one space.
+fn restore_trailing_comments(original: &str, formatted: &str) -> String {
+    let (Some(before), Some(after)) = (line_comments(original), line_comments(formatted)) else {
+        return formatted.to_string();
+    };
+    // Comments are matched up by order, so give up if any were added or lost
+    if before.len() != after.len() {
+        return formatted.to_string();
+    }
+
+    let mut lines: Vec<String> = formatted.split('\n').map(str::to_string).collect();
+    for (&(_, _, trailing), &(line, column, _)) in before.iter().zip(&after).rev() {
+        let (line, column) = (line as usize, column as usize);
+        let Some(start) = lines[line].char_indices().nth(column).map(|(i, _)| i) else { continue };
+        let code = lines[line][..start].trim_end().to_string();
+        let comment = lines[line][start..].to_string();
+
+        if !code.is_empty() {
+            lines[line] = format!("{} {}", code, comment);
+        } else if trailing && line > 0 && !lines[line - 1].trim().is_empty() && !ends_with_line_comment(&lines[line - 1]) {
+            lines[line - 1] = format!("{} {}", lines[line - 1].trim_end(), comment);
+            lines.remove(line);
+        }
+    }
+    lines.join("\n")
+}
+
+/// The `(line, column, trailing)` of each `--` comment, where `trailing` means code precedes
+/// it on its line. `None` if the text doesn't tokenize.
+fn line_comments(text: &str) -> Option<Vec<(u64, u64, bool)>> {
+    let tokens = syntax::tokenize(text)?;
+    let mut comments = Vec::new();
+    let mut code_on_line = false;
+    let mut line = 0;
+    for token_with_span in &tokens {
+        let start = token_with_span.span.start;
+        if start.line != line {
+            line = start.line;
+            code_on_line = false;
+        }
+        match &token_with_span.token {
+            Token::Whitespace(Whitespace::SingleLineComment { prefix, .. }) if prefix == "--" => {
+                comments.push((start.line - 1, start.column - 1, code_on_line));
+            }
+            Token::Whitespace(_) | Token::EOF => {}
+            _ => code_on_line = true,
+        }
+        // Multi-line tokens end on a later line than they start
+        if token_with_span.span.end.line != line {
+            line = token_with_span.span.end.line;
+            code_on_line = !matches!(token_with_span.token, Token::Whitespace(_));
+        }
+    }
+    Some(comments)
+}
+
+fn ends_with_line_comment(line: &str) -> bool {
+    line_comments(line).is_some_and(|comments| !comments.is_empty())
+}
+
+/// Pads consecutive `SET key = value` lines so their `=` line up, and consecutive column
+/// definitions in a multi-line `CREATE TABLE`/`PARTITIONED BY` list so their types line up.
+/// A `SET` statement sqlformat split after its keyword is put back on one line first.
+fn align_assignments(text: &str) -> String {
+    static SET_LINE: OnceLock<Regex> = OnceLock::new();
+    static COLUMN_LINE: OnceLock<Regex> = OnceLock::new();
+    static COLUMN_LIST_OPEN: OnceLock<Regex> = OnceLock::new();
+    let set_line = SET_LINE.get_or_init(|| Regex::new(r"(?i)^(\s*SET\s+[^=\s]+)\s*=\s*(.*)$").unwrap());
+    let column_line = COLUMN_LINE.get_or_init(|| Regex::new(r"^(\s*(?:`[^`]+`|\w+))\s+(\S.*)$").unwrap());
+    let column_list_open = COLUMN_LIST_OPEN
+        .get_or_init(|| Regex::new(r"(?i)^\s*(?:CREATE\b.*\bTABLE\b.*|PARTITIONED\s+BY\s*)\($").unwrap());
+
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.split('\n') {
+        match lines.last_mut() {
+            Some(last) if last.trim().eq_ignore_ascii_case("SET") && line.contains('=') && line.trim_end().ends_with(';') => {
+                last.truncate(last.trim_end().len());
+                last.push(' ');
+                last.push_str(line.trim_start());
+            }
+            _ => lines.push(line.to_string()),
+        }
+    }
+    let mut in_column_list = false;
+    let mut i = 0;
+    while i < lines.len() {
+        let (re, separator) = if in_column_list { (column_line, " ") } else { (set_line, " = ") };
+        let block_end = (i..lines.len()).find(|&j| !re.is_match(&lines[j])).unwrap_or(lines.len());
+        if block_end > i + 1 {
+            align_block(&mut lines[i..block_end], re, separator);
+            i = block_end;
+            continue;
+        }
+
+        if column_list_open.is_match(&lines[i]) {
+            in_column_list = true;
+        } else if lines[i].trim_start().starts_with(')') {
+            in_column_list = false;
+        }
+        i += 1;
+    }
+    lines.join("\n")
+}
+
+/// Pads the first capture group of every line to the widest one in the block.
+fn align_block(lines: &mut [String], re: &Regex, separator: &str) {
+    let parts: Vec<(String, String)> = lines
+        .iter()
+        .filter_map(|line| re.captures(line).map(|cap| (cap[1].to_string(), cap[2].to_string())))
+        .collect();
+    let width = parts.iter().map(|(left, _)| left.chars().count()).max().unwrap_or(0);
+    for (line, (left, right)) in lines.iter_mut().zip(parts) {
+        *line = format!("{:width$}{}{}", left, separator, right, width = width);
+    }
+}
+
+/// Returns the char span of the statements overlapping the lines of `range`.
+fn statement_bounds(rope: &Rope, range: Range, dialect: &dyn Dialect) -> Option<(usize, usize)> {
+    let line_start = |line: usize| rope.line_to_char(line.min(rope.len_lines().saturating_sub(1)));
+    let selection_start = line_start(range.start.line as usize);
+    let selection_end = if (range.end.line as usize) + 1 < rope.len_lines() {
+        rope.line_to_char(range.end.line as usize + 1)
+    } else {
+        rope.len_chars()
+    };
+    statement_span(rope, selection_start, selection_end, dialect)
+}
+
+/// Returns the char span `[start, end)` of the statements overlapping `[selection_start, selection_end)`,
+/// starting at the first token of the first statement and ending after its terminating semicolon
+/// (or at EOF for an unterminated final statement).
+fn statement_span(rope: &Rope, selection_start: usize, selection_end: usize, dialect: &dyn Dialect) -> Option<(usize, usize)> {
+    let text = rope.to_string();
+    let tokens = Tokenizer::new(dialect, &text).tokenize_with_location().ok()?;
+
+    let offsets: Vec<usize> = tokens
+        .iter()
+        .map(|t| rope.line_to_char((t.span.start.line - 1) as usize) + (t.span.start.column - 1) as usize)
+        .collect();
+
+    let last_selected = tokens
+        .iter()
+        .zip(&offsets)
+        .filter(|(t, offset)| !matches!(t.token, Token::Whitespace(_)) && **offset < selection_end)
+        .map(|(_, offset)| *offset)
+        .next_back()?;
+
+    let mut start = 0;
+    let mut end = rope.len_chars();
+    let mut depth = 0;
+    for (token_with_span, &offset) in tokens.iter().zip(&offsets) {
+        match token_with_span.token {
+            Token::LParen => depth += 1,
+            Token::RParen if depth > 0 => depth -= 1,
+            Token::SemiColon if depth == 0 => {
+                if offset < selection_start {
+                    start = offset + 1;
+                } else if offset >= last_selected {
+                    end = offset + 1;
+                    break;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Leave whitespace and comments between statements untouched
+    let start = tokens
+        .iter()
+        .zip(&offsets)
+        .find(|(t, offset)| !matches!(t.token, Token::Whitespace(_)) && **offset >= start)
+        .map(|(_, offset)| *offset)?;
+
+    if start >= end {
+        return None;
+    }
+    Some((start, end))
+}
+
+fn char_to_position(rope: &Rope, char_idx: usize) -> Position {
+    let line = rope.char_to_line(char_idx);
+    Position {
+        line: line as u32,
+        character: (char_idx - rope.line_to_char(line)) as u32,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlparser::dialect::HiveDialect;
+
+    fn lines(start: u32, end: u32) -> Range {
+        Range {
+            start: Position { line: start, character: 0 },
+            end: Position { line: end, character: 0 },
+        }
+    }
+
+    #[test]
+    fn test_statement_bounds_expands_to_semicolons() {
+        let rope = Rope::from_str("SELECT 1;\nSELECT a\nFROM t\nWHERE b = 1;\nSELECT 2;");
+        let (start, end) = statement_bounds(&rope, lines(2, 2), &HiveDialect {}).unwrap();
+        assert_eq!(rope.slice(start..end).to_string(), "SELECT a\nFROM t\nWHERE b = 1;");
+    }
+
+    #[test]
+    fn test_statement_bounds_unterminated_last_statement() {
+        let rope = Rope::from_str("SELECT 1;\nSELECT a\nFROM t");
+        let (start, end) = statement_bounds(&rope, lines(1, 1), &HiveDialect {}).unwrap();
+        assert_eq!(rope.slice(start..end).to_string(), "SELECT a\nFROM t");
+    }
+
+    #[test]
+    fn test_statement_bounds_ignores_semicolon_in_string() {
+        let rope = Rope::from_str("SELECT 'a;b'\nFROM t;\nSELECT 2;");
+        let (start, end) = statement_bounds(&rope, lines(0, 0), &HiveDialect {}).unwrap();
+        assert_eq!(rope.slice(start..end).to_string(), "SELECT 'a;b'\nFROM t;");
+    }
+
+    #[test]
+    fn test_statement_span_for_typed_semicolon() {
+        let rope = Rope::from_str("SELECT 1; SELECT a FROM t;");
+        let semicolon = rope.len_chars() - 1;
+        let (start, end) = statement_span(&rope, semicolon, semicolon + 1, &HiveDialect {}).unwrap();
+        assert_eq!(rope.slice(start..end).to_string(), "SELECT a FROM t;");
+    }
+
+    #[test]
+    fn test_format_text_covers_the_document() {
+        let options = FormattingOptions { tab_size: 2, insert_spaces: true, ..Default::default() };
+        for (text, end) in [
+            ("select 1", Position { line: 0, character: 8 }),
+            ("select 1;\nselect 'héllo 😀'", Position { line: 1, character: 17 }),
+            ("select 1;\r\n", Position { line: 1, character: 0 }),
+            ("", Position { line: 0, character: 0 }),
+        ] {
+            let (edits, _) = format_text(text, options.clone(), &FormattingConfig::default());
+            assert_eq!(edits[0].range, Range { start: Position { line: 0, character: 0 }, end }, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn test_format_string_matches_format_text() {
+        let text = "select a, b from t where c = 1;\nselect 2";
+        let config = FormattingConfig::default();
+        let formatted = format_string(text, &default_options(), &config);
+        assert_eq!(format_text(text, default_options(), &config).0[0].new_text, formatted);
+        assert!(formatted.contains("SELECT"));
+        assert!(!formatted.contains("select"));
+
+        let params: FormatStringParams = serde_json::from_value(serde_json::json!({ "text": "select 1" })).unwrap();
+        assert!(params.options.is_none());
+    }
+
+    #[test]
+    fn test_string_literal_whitespace_is_kept() {
+        let text = "select 'a  b\tc' from t;";
+        let options = FormattingOptions { tab_size: 2, insert_spaces: true, ..Default::default() };
+        assert!(format_string(text, &options, &FormattingConfig::default()).contains("'a  b\tc'"));
+
+        // A formatter that collapses whitespace everywhere leaves that statement alone
+        let collapse = |s: &str| s.split_whitespace().collect::<Vec<_>>().join(" ");
+        let text = "select  'a  b'  from t;\nselect  1;";
+        let formatted = collapse(text);
+        assert_eq!(keep_quoted_text(text, formatted, 1, collapse), ("select  'a  b'  from t;\nselect 1;".to_string(), 1));
+        assert_eq!(keep_quoted_text("select  1;", "select 1;".to_string(), 1, collapse), ("select 1;".to_string(), 0));
+    }
+
+    #[test]
+    fn test_format_on_type_ignores_semicolon_in_string() {
+        let rope = Rope::from_str("SELECT 'a;b' FROM t");
+        let position = Position { line: 0, character: 10 };
+        let options = FormattingOptions { tab_size: 2, insert_spaces: true, ..Default::default() };
+        assert!(format_on_type(&rope, position, options, &FormattingConfig::default(), &HiveDialect {}).is_none());
+    }
+
+    #[test]
+    fn test_align_set_block() {
+        let text = "SET hive.exec.parallel=true;\nSET mapreduce.job.reduces = 64;\n\nSET a=1;\nSELECT a = 1 FROM t;";
+        assert_eq!(
+            align_assignments(text),
+            "SET hive.exec.parallel    = true;\nSET mapreduce.job.reduces = 64;\n\nSET a=1;\nSELECT a = 1 FROM t;"
+        );
+    }
+
+    #[test]
+    fn test_align_column_list() {
+        let text = "CREATE TABLE t (\n  id BIGINT,\n  customer_name STRING COMMENT 'x',\n  `desc` STRING\n)\nPARTITIONED BY (\n  dt STRING,\n  region_code STRING\n);";
+        assert_eq!(
+            align_assignments(text),
+            "CREATE TABLE t (\n  id            BIGINT,\n  customer_name STRING COMMENT 'x',\n  `desc`        STRING\n)\nPARTITIONED BY (\n  dt          STRING,\n  region_code STRING\n);"
+        );
+    }
+
+    #[test]
+    fn test_format_string_aligns_assignments() {
+        let text = "set hive.exec.parallel=true;\nset mapreduce.job.reduces=64;\n\
+                    create table t (\n  id bigint,\n  customer_name string comment 'the name',\n  amount decimal(10, 2)\n);";
+        let config = FormattingConfig { align_assignments: true, ..FormattingConfig::default() };
+        assert_eq!(
+            format_string(text, &default_options(), &config),
+            "SET hive.exec.parallel    = TRUE;\nSET mapreduce.job.reduces = 64;\n\
+             CREATE TABLE t (\n  id            bigint,\n  customer_name STRING COMMENT 'the name',\n  amount        decimal(10, 2)\n);"
+        );
+        assert!(format_string(text, &default_options(), &FormattingConfig::default()).starts_with("SET\n  hive.exec.parallel = TRUE;"));
+    }
+
+    #[test]
+    fn test_align_assignments_disabled_by_default() {
+        assert!(!FormattingConfig::default().align_assignments);
+        // A lone line isn't a block
+        assert_eq!(align_assignments("SET a=1;\nSELECT 1;"), "SET a=1;\nSELECT 1;");
+    }
+
+    #[test]
+    fn test_trailing_comments_stay_on_their_line() {
+        // Test against a fixed formatter output so sqlformat upgrades don't change the case
+        let original = "SELECT a, -- note\n  b -- other\nFROM t; -- end\n-- next query\nSELECT 1;";
+        let formatted = "SELECT\n  a,  -- note\n  b -- other\nFROM\n  t;\n-- end\n-- next query\nSELECT\n  1;";
+        assert_eq!(
+            restore_trailing_comments(original, formatted),
+            "SELECT\n  a, -- note\n  b -- other\nFROM\n  t; -- end\n-- next query\nSELECT\n  1;"
+        );
+
+        // Comments are matched by order; a mismatch leaves the output alone
+        assert_eq!(restore_trailing_comments("SELECT 1; -- a", "SELECT\n  1;"), "SELECT\n  1;");
+
+        let options = FormattingOptions { tab_size: 2, insert_spaces: true, ..Default::default() };
+        assert_eq!(
+            format_string(original, &options, &FormattingConfig::default()),
+            "SELECT\n  a, -- note\n  b -- other\nFROM\n  t; -- end\n-- next query\nSELECT\n  1;"
+        );
+        let config = FormattingConfig { preserve_comments: false, ..FormattingConfig::default() };
+        assert!(format_string(original, &options, &config).contains("  t;\n-- end"));
+    }
+
+    #[test]
+    fn test_comma_styles() {
+        let formatted = "SELECT\n  id,\n  concat(fname, lname) AS name,\n  amount\nFROM\n  t\nORDER BY\n  id,\n  name;";
+        assert_eq!(
+            leading_commas(formatted),
+            "SELECT\n  id\n  , concat(fname, lname) AS name\n  , amount\nFROM\n  t\nORDER BY\n  id\n  , name;"
+        );
+        assert_eq!(leading_commas("SELECT\n  a, -- note\n  b\nFROM\n  t;"), "SELECT\n  a -- note\n  , b\nFROM\n  t;");
+        // Column definitions keep their commas
+        let ddl = "CREATE TABLE t (\n  id INT,\n  name STRING\n);";
+        assert_eq!(leading_commas(ddl), ddl);
+
+        let options = FormattingOptions { tab_size: 2, insert_spaces: true, ..Default::default() };
+        let text = "select id, concat(fname, lname) as name, amount from t order by id, name;";
+        assert_eq!(format_string(text, &options, &FormattingConfig::default()), formatted);
+        let config = FormattingConfig { comma_style: "trailing".to_string(), ..FormattingConfig::default() };
+        assert_eq!(format_string(text, &options, &config), formatted);
+        let config = FormattingConfig { comma_style: "leading".to_string(), ..FormattingConfig::default() };
+        assert_eq!(
+            format_string(text, &options, &config),
+            "SELECT\n  id\n  , concat(fname, lname) AS name\n  , amount\nFROM\n  t\nORDER BY\n  id\n  , name;"
+        );
+        assert_eq!(format_string("SELECT a, -- note\n b FROM t;", &options, &config), "SELECT\n  a -- note\n  , b\nFROM\n  t;");
+    }
+
+    #[test]
+    fn test_format_statements_keeps_comments_between_statements() {
+        let text = "-- Loads\nselect 1;   -- first\n\n\n-- Section: loads\n--   nightly\n\n  select 2;\nselect 3;\n-- end\n";
+        assert_eq!(
+            format_statements(text, 1, str::to_uppercase),
+            "-- Loads\nSELECT 1; -- first\n\n\n-- Section: loads\n--   nightly\n\nSELECT 2;\nSELECT 3;\n-- end"
+        );
+        assert_eq!(format_statements("select 1;\n\n\nselect 2", 2, str::to_uppercase), "SELECT 1;\n\nSELECT 2");
+        assert!(!FormattingConfig::default().per_statement);
+    }
+
+    #[test]
+    fn test_format_string_per_statement() {
+        let text = "-- Loads\nselect a,b from t;   -- first\n\n-- Section\nselect 2;";
+        let config = FormattingConfig { per_statement: true, ..FormattingConfig::default() };
+        assert_eq!(
+            format_string(text, &default_options(), &config),
+            "-- Loads\nSELECT\n  a,\n  b\nFROM\n  t; -- first\n\n-- Section\nSELECT\n  2;"
+        );
+        // Formatted whole, the blank line before the comment is lost
+        assert_eq!(
+            format_string(text, &default_options(), &FormattingConfig::default()),
+            "-- Loads\nSELECT\n  a,\n  b\nFROM\n  t; -- first\n-- Section\nSELECT\n  2;"
+        );
+    }
+
+    #[test]
+    fn test_lines_between_queries() {
+        let text = "select a from (select 1 as a) t;\n\n\nselect 2;";
+        let spaced = |lines_between_queries| {
+            let config = FormattingConfig { lines_between_queries, ..Default::default() };
+            let formatted = format_string(text, &default_options(), &config);
+            // Only the gap between the two statements
+            let end = formatted.find(';').unwrap() + 1;
+            let start = formatted.rfind("SELECT").unwrap();
+            (formatted[end..start].to_string(), formatted[..end].matches("\n\n").count())
+        };
+        // One line, no blank line, one and two
+        assert_eq!(spaced(0), (" ".to_string(), 0));
+        assert_eq!(spaced(1), ("\n".to_string(), 0));
+        assert_eq!(spaced(2), ("\n\n".to_string(), 0));
+        assert_eq!(spaced(3), ("\n\n\n".to_string(), 0));
+
+        assert_eq!(format_statements("select 1;\nselect 2;", 0, str::to_uppercase), "SELECT 1; SELECT 2;");
+        assert_eq!(space_statements("SELECT 1; -- one\nSELECT 2;", 2), "SELECT 1; -- one\nSELECT 2;");
+    }
+
+    #[test]
+    fn test_wrap_long_lines() {
+        let line = "  concat(customer_first_name, ' ', customer_last_name, ' ', customer_suffix) AS full_name,";
+        assert_eq!(
+            wrap_long_lines(line, 40, "  "),
+            "  concat(customer_first_name, ' ',\n    customer_last_name, ' ',\n    customer_suffix) AS full_name,"
+        );
+
+        let text = "WHERE\n  status = 'active' AND region = 'emea' OR priority > 3\nLIMIT 1;";
+        assert_eq!(
+            wrap_long_lines(text, 30, "\t"),
+            "WHERE\n  status = 'active'\n  \tAND region = 'emea'\n  \tOR priority > 3\nLIMIT 1;"
+        );
+
+        // Nothing to break at
+        let long_name = "  a_very_long_column_name_without_any_break";
+        assert_eq!(wrap_long_lines(long_name, 10, "  "), long_name);
+    }
+
+    #[test]
+    fn test_format_string_wraps_at_max_line_width() {
+        let text = "select coalesce(first_name, nickname, 'n/a') as display_name_for_customer from t;";
+        let config = FormattingConfig { max_line_width: Some(40), ..FormattingConfig::default() };
+        assert_eq!(
+            format_string(text, &default_options(), &config),
+            "SELECT\n  coalesce(first_name, nickname,\n    'n/a') AS display_name_for_customer\nFROM\n  t;"
+        );
+        assert!(format_string(text, &default_options(), &FormattingConfig::default())
+            .contains("\n  coalesce(first_name, nickname, 'n/a') AS display_name_for_customer\n"));
+    }
+
+    #[test]
+    fn test_format_workspace() {
+        let first = Url::parse("file:///a.hql").unwrap();
+        let second = Url::parse("file:///b.hql").unwrap();
+        let config = FormattingConfig::default();
+        let mut batch = WorkspaceFormat::default();
+        batch.add(first.clone(), "select 1;", &default_options(), &config);
+        batch.add(second.clone(), "select a from t;", &default_options(), &config);
+        // Already formatted, or formatting turned off for it
+        batch.add(Url::parse("file:///c.hql").unwrap(), "SELECT\n  1;", &default_options(), &config);
+        batch.add(
+            Url::parse("file:///d.hql").unwrap(),
+            "select 2;",
+            &default_options(),
+            &FormattingConfig { enabled: false, ..Default::default() },
+        );
+        assert!(batch.failed.is_empty());
+
+        let changes = batch.into_edit().changes.unwrap();
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[&first], format_text("select 1;", default_options(), &config).0);
+        assert!(changes[&second][0].new_text.contains("SELECT"));
+    }
 }
@@ -0,0 +1,57 @@
+use sqlparser::tokenizer::Token;
+use tower_lsp::lsp_types::{DocumentHighlight, DocumentHighlightKind, Position};
+use crate::syntax::{self, range_contains, span_to_range};
+
+/// Highlights every occurrence, within the current statement, of the word under `position`.
+///
+/// Unquoted words match case-insensitively, as Hive resolves them; quoted identifiers
+/// match exactly.
+pub fn document_highlights(text: &str, position: Position) -> Vec<DocumentHighlight> {
+    let Some(tokens) = syntax::tokenize(text) else { return vec![] };
+    let Some(statement) = syntax::statement_at(&tokens, position) else { return vec![] };
+    let Some(target) = tokens[statement.clone()].iter().find_map(|t| match &t.token {
+        Token::Word(w) if range_contains(&span_to_range(&t.span), position) => Some(w),
+        _ => None,
+    }) else {
+        return vec![];
+    };
+
+    tokens[statement]
+        .iter()
+        .filter(|t| match &t.token {
+            Token::Word(w) if w.quote_style.is_some() || target.quote_style.is_some() => w.value == target.value,
+            Token::Word(w) => w.value.eq_ignore_ascii_case(&target.value),
+            _ => false,
+        })
+        .map(|t| DocumentHighlight {
+            range: span_to_range(&t.span),
+            kind: Some(DocumentHighlightKind::TEXT),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn highlighted(text: &str, line: u32, character: u32) -> Vec<(u32, u32)> {
+        document_highlights(text, Position { line, character })
+            .iter()
+            .map(|h| (h.range.start.line, h.range.start.character))
+            .collect()
+    }
+
+    #[test]
+    fn test_highlights_within_statement() {
+        let text = "SELECT id, u.ID FROM users u WHERE id > 0;\nSELECT id FROM orders;";
+        assert_eq!(highlighted(text, 0, 8), vec![(0, 7), (0, 13), (0, 35)]);
+        assert_eq!(highlighted(text, 1, 7), vec![(1, 7)]);
+    }
+
+    #[test]
+    fn test_quoted_identifiers_match_exactly() {
+        let text = "SELECT `Id`, `id`, Id FROM t;";
+        assert_eq!(highlighted(text, 0, 8), vec![(0, 7), (0, 19)]);
+        assert_eq!(highlighted(text, 0, 14), vec![(0, 13)]);
+    }
+}
@@ -0,0 +1,65 @@
+use tower_lsp::lsp_types::{Diagnostic, Hover, HoverContents, MarkupContent, MarkupKind, NumberOrString, Position};
+use crate::report::severity_name;
+use crate::rules;
+
+/// Explains the diagnostics under `position`, one section per diagnostic, or `None` if
+/// no lint diagnostic covers it.
+pub fn hover(diagnostics: &[Diagnostic], position: Position) -> Option<Hover> {
+    let sections: Vec<String> = diagnostics
+        .iter()
+        .filter(|d| d.range.start <= position && position <= d.range.end)
+        .filter_map(explain)
+        .collect();
+    if sections.is_empty() {
+        return None;
+    }
+    Some(Hover {
+        contents: HoverContents::Markup(MarkupContent { kind: MarkupKind::Markdown, value: sections.join("\n\n---\n\n") }),
+        range: None,
+    })
+}
+
+/// Markdown for one diagnostic, from its rule's entry in `RULES`: what the rule checks, why
+/// it fired here and how to turn it off. `None` for diagnostics no rule reports.
+pub fn explain(diagnostic: &Diagnostic) -> Option<String> {
+    let Some(NumberOrString::String(code)) = &diagnostic.code else { return None };
+    let rule = rules::find(code)?;
+    let setting = match rule.kind {
+        rules::Kind::Rule => format!("set `{} = false` under `[linting.rules]`", rule.config_key),
+        rules::Kind::Note => format!("change `{}` under `[linting]`", rule.config_key),
+    };
+    Some(format!(
+        "**{}** ({}): {}\n\n{}\n\nTo silence it, put `-- hqlint-disable-next-line {}` on the line above, \
+         `-- hqlint-disable {}` at the top of the file, or {}.",
+        rule.code,
+        severity_name(diagnostic.severity),
+        rule.description,
+        diagnostic.message,
+        rule.code,
+        rule.code,
+        setting,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::LintingConfig;
+    use crate::linter;
+
+    #[test]
+    fn test_keyword_casing_explanation() {
+        let mut config = LintingConfig::default();
+        config.rules.keyword_casing = true.into();
+        let diagnostics = linter::lint("select 1;", &config);
+
+        let Some(Hover { contents: HoverContents::Markup(markup), .. }) = hover(&diagnostics, Position { line: 0, character: 3 }) else {
+            panic!("no hover over 'select'")
+        };
+        assert!(markup.value.starts_with("**keyword-casing** (warning): Keywords that aren't uppercase"));
+        assert!(markup.value.contains("-- hqlint-disable-next-line keyword-casing"));
+        assert!(markup.value.contains("keywordCasing = false"));
+
+        assert!(hover(&diagnostics, Position { line: 0, character: 7 }).is_none());
+    }
+}
@@ -0,0 +1,165 @@
+use std::ops::RangeInclusive;
+use tower_lsp::lsp_types::{Diagnostic, NumberOrString, Range, TextDocumentContentChangeEvent, Url};
+use crate::linter;
+
+/// The diagnostics published before a batch of ranged edits, moved to where they now are,
+/// and the lines whose line-rule diagnostics must be checked again.
+///
+/// The merge keeps every diagnostic from outside the edited lines, shifted by the lines each
+/// edit added or removed, drops the line rules' diagnostics on the edited lines and takes
+/// fresh ones for those lines from `linter::lint_lines`. Token rules' diagnostics on the
+/// edited lines are kept as they were, possibly out of date, until the debounced full lint
+/// replaces the whole set.
+#[derive(Debug)]
+pub struct CarriedOver {
+    pub diagnostics: Vec<Diagnostic>,
+    /// Lines of the edited text that any change touched
+    pub dirty: RangeInclusive<u32>,
+}
+
+impl CarriedOver {
+    /// Adds the line rules' fresh diagnostics for `dirty`.
+    pub fn merge(mut self, fresh: Vec<Diagnostic>) -> Vec<Diagnostic> {
+        self.diagnostics.extend(fresh);
+        self.diagnostics
+    }
+}
+
+/// Carries `previous`, published for the text before `changes`, over to the text after them.
+/// Related information is moved too where it points into the document `uri`. Returns `None`
+/// if there are no changes or one replaces the whole text.
+pub fn carry_over(mut previous: Vec<Diagnostic>, uri: &Url, changes: &[TextDocumentContentChangeEvent]) -> Option<CarriedOver> {
+    let mut dirty: Option<RangeInclusive<u32>> = None;
+    for change in changes {
+        let range = change.range?;
+        let start = range.start.line;
+        let end = range.end.line.max(start);
+        let inserted_end = start + change.text.matches('\n').count() as u32;
+        // Where a line of the text before this change ends up after it
+        let map = |line: u32| {
+            if line > end {
+                line - end + inserted_end
+            } else if line >= start {
+                line.min(inserted_end)
+            } else {
+                line
+            }
+        };
+
+        previous.retain(|d| !(is_line_rule(d) && (start..=end).contains(&d.range.start.line)));
+        for diagnostic in &mut previous {
+            for range in ranges_mut(diagnostic, uri) {
+                range.start.line = map(range.start.line);
+                range.end.line = map(range.end.line);
+            }
+        }
+        dirty = Some(match dirty {
+            Some(lines) => map(*lines.start()).min(start)..=map(*lines.end()).max(inserted_end),
+            None => start..=inserted_end,
+        });
+    }
+
+    let dirty = dirty?;
+    previous.retain(|d| !(is_line_rule(d) && dirty.contains(&d.range.start.line)));
+    Some(CarriedOver { diagnostics: previous, dirty })
+}
+
+fn is_line_rule(diagnostic: &Diagnostic) -> bool {
+    matches!(&diagnostic.code, Some(NumberOrString::String(code)) if linter::is_line_rule(code))
+}
+
+/// A diagnostic's own range and those of its related information in `uri`.
+fn ranges_mut<'a>(diagnostic: &'a mut Diagnostic, uri: &'a Url) -> impl Iterator<Item = &'a mut Range> {
+    std::iter::once(&mut diagnostic.range).chain(
+        diagnostic
+            .related_information
+            .iter_mut()
+            .flatten()
+            .filter(move |information| information.location.uri == *uri)
+            .map(|information| &mut information.location.range),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::LintingConfig;
+    use tower_lsp::lsp_types::Position;
+
+    fn change(start: (u32, u32), end: (u32, u32), text: &str) -> TextDocumentContentChangeEvent {
+        TextDocumentContentChangeEvent {
+            range: Some(Range {
+                start: Position { line: start.0, character: start.1 },
+                end: Position { line: end.0, character: end.1 },
+            }),
+            range_length: None,
+            text: text.to_string(),
+        }
+    }
+
+    fn codes_by_line(diagnostics: &[Diagnostic]) -> Vec<(u32, String)> {
+        let mut codes: Vec<(u32, String)> = diagnostics
+            .iter()
+            .map(|d| match &d.code {
+                Some(NumberOrString::String(code)) => (d.range.start.line, code.clone()),
+                _ => (d.range.start.line, String::new()),
+            })
+            .collect();
+        codes.sort();
+        codes
+    }
+
+    #[test]
+    fn test_edit_relints_only_the_edited_line() {
+        let uri = Url::parse("file:///a.hql").unwrap();
+        let config = LintingConfig::default();
+        let before = "SELECT a, \nFROM t  \nWHERE (b = 1;";
+        let previous = linter::lint(before, &config);
+
+        // Removing the trailing whitespace on line 0 leaves line 1's alone
+        let after = "SELECT a,\nFROM t  \nWHERE (b = 1;";
+        let carried = carry_over(previous.clone(), &uri, &[change((0, 9), (0, 10), "")]).unwrap();
+        assert_eq!(carried.dirty, 0..=0);
+        let fresh = linter::lint_lines(after, carried.dirty.clone(), &config).unwrap();
+        assert!(fresh.is_empty());
+        let merged = carried.merge(fresh);
+
+        let expected: Vec<_> = previous.iter().filter(|d| d.range.start.line != 0 || !is_line_rule(d)).cloned().collect();
+        assert_eq!(codes_by_line(&merged), codes_by_line(&expected));
+        // The line rules agree with a full lint of the edited text
+        let line_rules = |diagnostics: &[Diagnostic]| {
+            codes_by_line(&diagnostics.iter().filter(|d| is_line_rule(d)).cloned().collect::<Vec<_>>())
+        };
+        assert_eq!(line_rules(&merged), line_rules(&linter::lint(after, &config)));
+    }
+
+    #[test]
+    fn test_inserted_lines_shift_later_diagnostics() {
+        let uri = Url::parse("file:///a.hql").unwrap();
+        let config = LintingConfig::default();
+        let before = "SELECT a\nFROM t  \nWHERE (b = 1;";
+        let previous = linter::lint(before, &config);
+
+        let after = "SELECT a, \n  b\nFROM t  \nWHERE (b = 1;";
+        let carried = carry_over(previous, &uri, &[change((0, 8), (0, 8), ", \n  b")]).unwrap();
+        assert_eq!(carried.dirty, 0..=1);
+        let fresh = linter::lint_lines(after, carried.dirty.clone(), &config).unwrap();
+        let merged = carried.merge(fresh);
+
+        let trailing: Vec<u32> = codes_by_line(&merged)
+            .into_iter()
+            .filter(|(_, code)| code == "trailing-whitespace")
+            .map(|(line, _)| line)
+            .collect();
+        assert_eq!(trailing, vec![0, 2]);
+        assert!(codes_by_line(&merged).contains(&(3, "unbalanced-parentheses".to_string())));
+    }
+
+    #[test]
+    fn test_full_replacement_is_not_carried_over() {
+        let uri = Url::parse("file:///a.hql").unwrap();
+        let full = TextDocumentContentChangeEvent { range: None, range_length: None, text: "SELECT 1;".to_string() };
+        assert!(carry_over(Vec::new(), &uri, &[full]).is_none());
+        assert!(carry_over(Vec::new(), &uri, &[]).is_none());
+    }
+}
@@ -0,0 +1,50 @@
+use std::collections::HashMap;
+use tower_lsp::lsp_types::{InlayHint, InlayHintLabel, Position, Range};
+use crate::linter::hive_variable_regex;
+
+/// Hints the configured value after each `${namespace:name}` in `range`. Variables
+/// missing from `variables` get no hint.
+pub fn variable_hints(text: &str, range: Range, variables: &HashMap<String, HashMap<String, String>>) -> Vec<InlayHint> {
+    let mut hints = Vec::new();
+
+    for (line_idx, line) in text.lines().enumerate() {
+        let line_idx = line_idx as u32;
+        if line_idx < range.start.line || line_idx > range.end.line {
+            continue;
+        }
+        for cap in hive_variable_regex().captures_iter(line) {
+            let Some((namespace, name)) = cap[1].split_once(':') else { continue };
+            let Some(value) = variables.get(namespace).and_then(|names| names.get(name)) else { continue };
+            let end = cap.get(0).map_or(0, |m| m.end());
+            hints.push(InlayHint {
+                position: Position { line: line_idx, character: line[..end].encode_utf16().count() as u32 },
+                label: InlayHintLabel::String(format!("= \"{}\"", value)),
+                kind: None,
+                text_edits: None,
+                tooltip: None,
+                padding_left: Some(true),
+                padding_right: None,
+                data: None,
+            });
+        }
+    }
+
+    hints
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_variable_hints() {
+        let variables = HashMap::from([("hivevar".to_string(), HashMap::from([("db".to_string(), "prod_db".to_string())]))]);
+        let text = "SELECT 'é' FROM ${hivevar:db}.t\nWHERE dt = '${hivevar:dt}' AND x = ${hiveconf:db};\nSELECT ${hivevar:db};";
+        let range = Range { start: Position { line: 0, character: 0 }, end: Position { line: 1, character: 0 } };
+
+        let hints = variable_hints(text, range, &variables);
+        assert_eq!(hints.len(), 1);
+        assert_eq!(hints[0].position, Position { line: 0, character: 29 });
+        assert!(matches!(&hints[0].label, InlayHintLabel::String(label) if label == "= \"prod_db\""));
+    }
+}
@@ -0,0 +1,184 @@
+use sqlparser::tokenizer::{Token, TokenWithSpan, Word};
+use crate::syntax::is_significant;
+
+/// Words that are keywords wherever they appear unquoted.
+pub const KEYWORDS: &[&str] = &[
+    "ALTER", "AND", "AS", "BETWEEN", "BY", "CASE", "CREATE", "CROSS", "DELETE", "DISTINCT", "DROP", "ELSE", "END",
+    "EXISTS", "FROM", "GROUP", "HAVING", "IN", "INNER", "INSERT", "INTO", "IS", "JOIN", "LEFT", "LIKE", "LIMIT",
+    "NOT", "ON", "OR", "ORDER", "OUTER", "RIGHT", "SELECT", "TABLE", "THEN", "UNION", "UPDATE", "VALUES", "WHEN",
+    "WHERE", "WITH",
+];
+
+/// Where a contextual keyword has to appear to be one.
+enum Context {
+    /// Directly before this word
+    Before(&'static str),
+    /// Directly after this word
+    After(&'static str),
+    /// Directly before a string literal: `LOCATION '...'`
+    BeforeString,
+    /// Directly before `(`: `TBLPROPERTIES (...)`
+    BeforeParen,
+    /// Directly after a number: `INTO 4 BUCKETS`
+    AfterNumber,
+}
+
+/// Hive words that are keywords only in these positions; elsewhere, such as a column
+/// named `location` or `cluster`, they're ordinary identifiers.
+const CONTEXTUAL_KEYWORDS: &[(&str, Context)] = &[
+    ("BUCKETS", Context::AfterNumber),
+    ("CLUSTER", Context::Before("BY")),
+    ("CLUSTERED", Context::Before("BY")),
+    ("COMMENT", Context::BeforeString),
+    ("DELIMITED", Context::After("FORMAT")),
+    ("DISTRIBUTE", Context::Before("BY")),
+    ("EXTERNAL", Context::Before("TABLE")),
+    ("FIELDS", Context::Before("TERMINATED")),
+    ("FORMAT", Context::After("ROW")),
+    ("LATERAL", Context::Before("VIEW")),
+    ("LINES", Context::Before("TERMINATED")),
+    ("LOCATION", Context::BeforeString),
+    ("OVER", Context::BeforeParen),
+    ("OVERWRITE", Context::After("INSERT")),
+    ("PARTITION", Context::BeforeParen),
+    ("PARTITION", Context::Before("BY")),
+    ("PARTITIONED", Context::Before("BY")),
+    ("ROW", Context::Before("FORMAT")),
+    ("SERDE", Context::BeforeString),
+    ("SERDEPROPERTIES", Context::BeforeParen),
+    ("SORT", Context::Before("BY")),
+    ("SORTED", Context::Before("BY")),
+    ("STORED", Context::Before("AS")),
+    ("STORED", Context::Before("BY")),
+    ("TBLPROPERTIES", Context::BeforeParen),
+    ("TEMPORARY", Context::Before("TABLE")),
+    ("TEMPORARY", Context::Before("FUNCTION")),
+    ("TERMINATED", Context::Before("BY")),
+    ("VIEW", Context::After("LATERAL")),
+    ("VIEW", Context::After("CREATE")),
+    ("VIEW", Context::After("DROP")),
+];
+
+/// Multi-word keywords offered by completion.
+pub const KEYWORD_PHRASES: &[&str] = &[
+    "GROUP BY", "ORDER BY", "INNER JOIN", "LEFT JOIN", "RIGHT JOIN", "FULL OUTER JOIN", "CROSS JOIN", "UNION ALL",
+    "INSERT INTO", "INSERT OVERWRITE", "CREATE TABLE", "DROP TABLE", "ALTER TABLE", "PARTITIONED BY", "STORED AS",
+    "CLUSTER BY", "DISTRIBUTE BY", "SORT BY", "LATERAL VIEW", "ROW FORMAT DELIMITED",
+];
+
+/// Analytic functions, which are only valid with an `OVER (...)` window.
+pub const ANALYTIC_FUNCTIONS: &[&str] = &[
+    "ROW_NUMBER", "RANK", "DENSE_RANK", "PERCENT_RANK", "CUME_DIST", "NTILE", "LAG", "LEAD", "FIRST_VALUE", "LAST_VALUE",
+];
+
+/// Hive column types, including the words of multi-word types such as `DOUBLE PRECISION`.
+pub const DATA_TYPES: &[&str] = &[
+    "TINYINT", "SMALLINT", "INT", "INTEGER", "BIGINT", "FLOAT", "DOUBLE", "PRECISION", "DECIMAL", "NUMERIC", "BOOLEAN",
+    "STRING", "VARCHAR", "CHAR", "BINARY", "TIMESTAMP", "DATE", "INTERVAL", "ARRAY", "MAP", "STRUCT", "UNIONTYPE",
+];
+
+/// Literal words, cased by their own rule rather than as keywords.
+pub const LITERAL_KEYWORDS: &[&str] = &["TRUE", "FALSE", "NULL"];
+
+/// Operators and clauses offered by completion that the casing rule leaves alone.
+pub const OTHER_COMPLETION_KEYWORDS: &[&str] = &["OFFSET", "RLIKE", "REGEXP"];
+
+/// True for an unquoted word in `KEYWORDS`, whatever its context.
+pub fn is_keyword(word: &Word) -> bool {
+    word.quote_style.is_none() && KEYWORDS.iter().any(|kw| word.value.eq_ignore_ascii_case(kw))
+}
+
+/// True if the word at `idx` is used as a keyword: a `KEYWORDS` entry, or a contextual
+/// keyword in one of its positions.
+pub fn is_keyword_usage(tokens: &[TokenWithSpan], idx: usize) -> bool {
+    let Token::Word(word) = &tokens[idx].token else { return false };
+    if word.quote_style.is_some() {
+        return false;
+    }
+    let previous = (0..idx).rev().find(|&i| is_significant(&tokens[i].token)).map(|i| &tokens[i].token);
+    let next = (idx + 1..tokens.len()).find(|&i| is_significant(&tokens[i].token)).map(|i| &tokens[i].token);
+    // `t.end` and `end.x` are names
+    if matches!(previous, Some(Token::Period)) || matches!(next, Some(Token::Period)) {
+        return false;
+    }
+    if is_keyword(word) {
+        return true;
+    }
+
+    let is_word = |token: Option<&Token>, expected: &str| {
+        matches!(token, Some(Token::Word(w)) if w.quote_style.is_none() && w.value.eq_ignore_ascii_case(expected))
+    };
+    CONTEXTUAL_KEYWORDS
+        .iter()
+        .filter(|(keyword, _)| word.value.eq_ignore_ascii_case(keyword))
+        .any(|(_, context)| match context {
+            Context::Before(expected) => is_word(next, expected),
+            Context::After(expected) => is_word(previous, expected),
+            Context::BeforeString => matches!(next, Some(Token::SingleQuotedString(_) | Token::DoubleQuotedString(_))),
+            Context::BeforeParen => matches!(next, Some(Token::LParen)),
+            Context::AfterNumber => matches!(previous, Some(Token::Number(_, _))),
+        })
+}
+
+/// True if the unquoted word at `idx` is one of the user's `additional` keywords, which may be
+/// phrases such as `DISTRIBUTE BY`. Like built-in keywords, `t.word` and `word.x` are names.
+pub fn is_additional_keyword(tokens: &[TokenWithSpan], idx: usize, additional: &[String]) -> bool {
+    let Token::Word(word) = &tokens[idx].token else { return false };
+    if word.quote_style.is_some() {
+        return false;
+    }
+    let previous = (0..idx).rev().find(|&i| is_significant(&tokens[i].token)).map(|i| &tokens[i].token);
+    let next = (idx + 1..tokens.len()).find(|&i| is_significant(&tokens[i].token)).map(|i| &tokens[i].token);
+    if matches!(previous, Some(Token::Period)) || matches!(next, Some(Token::Period)) {
+        return false;
+    }
+    additional
+        .iter()
+        .flat_map(|keyword| keyword.split_whitespace())
+        .any(|keyword| word.value.eq_ignore_ascii_case(keyword))
+}
+
+/// Every keyword completion offers, phrases first.
+pub fn completion_keywords() -> Vec<&'static str> {
+    let mut keywords: Vec<&str> = KEYWORD_PHRASES.to_vec();
+    keywords.extend(KEYWORDS);
+    for (keyword, _) in CONTEXTUAL_KEYWORDS {
+        if !keywords.contains(keyword) {
+            keywords.push(keyword);
+        }
+    }
+    keywords.extend(OTHER_COMPLETION_KEYWORDS);
+    keywords.extend(LITERAL_KEYWORDS);
+    keywords
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::syntax::tokenize;
+
+    fn keyword_usages(text: &str) -> Vec<String> {
+        let tokens = tokenize(text).unwrap();
+        (0..tokens.len())
+            .filter(|&i| is_keyword_usage(&tokens, i))
+            .map(|i| tokens[i].token.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_contextual_keywords() {
+        let text = "create external table t (location string, `select` int) partitioned by (dt string)\n\
+                    row format delimited fields terminated by ',' stored as orc location '/x' tblproperties ('a'='b');";
+        assert_eq!(keyword_usages(text), vec![
+            "create", "external", "table", "partitioned", "by", "row", "format", "delimited", "fields",
+            "terminated", "by", "stored", "as", "location", "tblproperties",
+        ]);
+    }
+
+    #[test]
+    fn test_keywords_used_as_names() {
+        assert_eq!(keyword_usages("SELECT cluster, t.end, comment FROM t CLUSTER BY cluster"), vec![
+            "SELECT", "FROM", "CLUSTER", "BY",
+        ]);
+    }
+}
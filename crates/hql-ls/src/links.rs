@@ -0,0 +1,79 @@
+use sqlparser::tokenizer::{Token, TokenWithSpan};
+use tower_lsp::lsp_types::{DocumentLink, Position, Range, Url};
+use crate::syntax::{is_keyword, is_significant, span_to_range};
+
+/// Links the path literals after `LOCATION` and `INPATH`, e.g. `LOCATION '/data/sales'` or
+/// `LOAD DATA INPATH 'hdfs://nn/staging'`. Relative paths and paths built from Hive
+/// variables can't be resolved and are skipped.
+pub fn document_links(tokens: &[TokenWithSpan]) -> Vec<DocumentLink> {
+    let mut links = Vec::new();
+
+    for (i, token_with_span) in tokens.iter().enumerate() {
+        if !is_keyword(&token_with_span.token, "LOCATION") && !is_keyword(&token_with_span.token, "INPATH") {
+            continue;
+        }
+        let Some(literal) = tokens[i + 1..].iter().find(|t| is_significant(&t.token)) else { continue };
+        // Hive reads "..." as a string too
+        let path = match &literal.token {
+            Token::SingleQuotedString(path) => path,
+            Token::Word(w) if w.quote_style == Some('"') => &w.value,
+            _ => continue,
+        };
+        let Some(target) = link_target(path) else { continue };
+
+        // Link the path, not its quotes
+        let range = span_to_range(&literal.span);
+        links.push(DocumentLink {
+            range: Range {
+                start: Position { line: range.start.line, character: range.start.character + 1 },
+                end: Position { line: range.end.line, character: range.end.character.saturating_sub(1) },
+            },
+            target: Some(target),
+            tooltip: None,
+            data: None,
+        });
+    }
+
+    links
+}
+
+/// `file://` for absolute local paths; URLs such as `hdfs://nn/x` or `s3a://bucket/x` as written.
+fn link_target(path: &str) -> Option<Url> {
+    if path.contains("${") {
+        return None;
+    }
+    if path.starts_with('/') {
+        return Url::parse(&format!("file://{}", path)).ok();
+    }
+    Url::parse(path).ok().filter(|url| url.has_host() || url.scheme() == "file")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::syntax;
+
+    fn targets(text: &str) -> Vec<String> {
+        document_links(&syntax::tokenize(text).unwrap())
+            .into_iter()
+            .map(|link| link.target.unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_location_and_inpath_links() {
+        let text = "CREATE EXTERNAL TABLE t (id INT)\nLOCATION '/data/sales';\nLOAD DATA INPATH 'hdfs://nn:8020/staging/x' INTO TABLE t;";
+        assert_eq!(targets(text), vec!["file:///data/sales", "hdfs://nn:8020/staging/x"]);
+
+        let links = document_links(&syntax::tokenize(text).unwrap());
+        assert_eq!(links[0].range.start, Position { line: 1, character: 10 });
+        assert_eq!(links[0].range.end, Position { line: 1, character: 21 });
+    }
+
+    #[test]
+    fn test_unresolvable_paths_are_skipped() {
+        assert!(targets("ALTER TABLE t SET LOCATION '${hivevar:dir}/x';").is_empty());
+        assert!(targets("LOAD DATA LOCAL INPATH 'data/x.csv' INTO TABLE t;").is_empty());
+        assert!(targets("SELECT 'LOCATION', '/data/x';").is_empty());
+    }
+}
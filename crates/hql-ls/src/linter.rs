@@ -1,95 +1,587 @@
-use tower_lsp::lsp_types::{Diagnostic, DiagnosticSeverity, Position, Range, NumberOrString};
-use sqlparser::dialect::HiveDialect;
-use sqlparser::tokenizer::{Tokenizer, Token, TokenWithSpan};
+use tower_lsp::lsp_types::{
+    self as lsp, Diagnostic, DiagnosticRelatedInformation, DiagnosticSeverity, DiagnosticTag, NumberOrString, Position, Range, Url,
+};
+use sqlparser::dialect::Dialect;
+use sqlparser::parser::{Parser, ParserError};
+use sqlparser::tokenizer::{Location, Tokenizer, Token, TokenWithSpan, Span, TokenizerError, Whitespace};
 use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::ops::RangeInclusive;
 use std::sync::OnceLock;
-use crate::config::LintingConfig;
+use std::time::Instant;
+use crate::config::{LintingConfig, RuleConfig};
+use crate::rules::{FnRule, Input, LintContext, Rule};
+use crate::keywords::{self, is_keyword};
+use crate::metrics::RuleMetrics;
+use crate::schema::Schema;
+use crate::rename;
+use crate::suppression;
+use crate::syntax::{self, is_keyword as is_word, matching_paren, span_to_range};
+use crate::token_cache::Tokenized;
+
+/// Line limit for `statementLength` when `maxStatementLines` isn't set
+const DEFAULT_MAX_STATEMENT_LINES: u64 = 100;
+
+/// Character limit for `maxLineLength` when `maxLength` isn't set
+const DEFAULT_MAX_LINE_LENGTH: u64 = 120;
+
+/// Key limit for `wideGroupBy` when `maxGroupByKeys` isn't set
+const DEFAULT_MAX_GROUP_BY_KEYS: u64 = 10;
+
+/// Nesting limit for `subqueryDepth` when `maxDepth` isn't set
+const DEFAULT_MAX_SUBQUERY_DEPTH: u64 = 4;
+
+/// Spaces per tab in `noTabs` fixes when `tabWidth` isn't set
+const DEFAULT_TAB_WIDTH: u64 = 4;
+
+/// Dotted parts allowed in a table name when `maxParts` isn't set
+const DEFAULT_MAX_NAME_PARTS: u64 = 3;
+
+/// Keywords that open a clause; a list never ends right before one of them.
+const CLAUSE_KEYWORDS: &[&str] = &[
+    "FROM", "WHERE", "GROUP", "ORDER", "HAVING", "LIMIT", "OFFSET", "UNION", "LATERAL",
+    "JOIN", "INNER", "LEFT", "RIGHT", "FULL", "CROSS", "ON", "USING", "WINDOW",
+];
+
+/// Keywords that continue an expression or window specification.
+const EXPRESSION_KEYWORDS: &[&str] = &[
+    "DISTINCT", "AS", "AND", "OR", "XOR", "CASE", "WHEN", "THEN", "ELSE", "END", "IS", "NOT", "IN", "BETWEEN", "LIKE", "RLIKE", "REGEXP",
+    "OVER", "PARTITION", "BY", "ROWS", "RANGE", "UNBOUNDED", "PRECEDING", "FOLLOWING", "CURRENT", "ROW",
+];
 
 pub fn lint(text: &str, config: &LintingConfig) -> Vec<Diagnostic> {
-    if !config.enabled {
-        return vec![];
+    lint_with_schema(text, config, None)
+}
+
+/// Like `lint`, additionally running the rules that need the workspace schema.
+pub fn lint_with_schema(text: &str, config: &LintingConfig, schema: Option<&Schema>) -> Vec<Diagnostic> {
+    match lint_mode(text, config) {
+        LintMode::Skip => return vec![],
+        LintMode::Chunked => return lint_cancellable(text, &Ok(Vec::new()), config, schema, None, &|| false).unwrap_or_default(),
+        LintMode::Whole => {}
+    }
+    let dialect = syntax::dialect(&config.dialect);
+    let tokenized = Tokenizer::new(&*dialect, text).tokenize_with_location();
+    lint_tokenized(text, &tokenized, config, schema)
+}
+
+/// Like `lint_with_schema`, reusing tokens the caller already has for `text`.
+pub fn lint_tokenized(text: &str, tokenized: &Tokenized, config: &LintingConfig, schema: Option<&Schema>) -> Vec<Diagnostic> {
+    lint_cancellable(text, tokenized, config, schema, None, &|| false).unwrap_or_default()
+}
+
+/// Like `lint_tokenized`, but gives up between rules once `cancelled` returns true, e.g.
+/// because a newer version of the document arrived. Returns `None` if it gave up. Each
+/// rule's run time is added to `metrics` if given.
+///
+/// Files over `maxFileSize` are linted a few statements at a time (see `lint_in_chunks`),
+/// ignoring `tokenized`.
+pub fn lint_cancellable(
+    text: &str,
+    tokenized: &Tokenized,
+    config: &LintingConfig,
+    schema: Option<&Schema>,
+    metrics: Option<&RuleMetrics>,
+    cancelled: &dyn Fn() -> bool,
+) -> Option<Vec<Diagnostic>> {
+    let diagnostics = match lint_mode(text, config) {
+        LintMode::Skip => return Some(vec![]),
+        LintMode::Chunked => lint_in_chunks(text, config, schema, metrics, cancelled)?,
+        LintMode::Whole => {
+            let (tokens, tokenizer_error) = match tokenized {
+                Ok(tokens) => (tokens.as_slice(), None),
+                Err(e) => (&[][..], Some(e)),
+            };
+            run_rules(&LintContext { text, tokens, tokenizer_error, config, schema }, metrics, cancelled)?
+        }
+    };
+
+    let mut diagnostics = suppression::filter_suppressed(text, diagnostics);
+    // Filter on the rules' own severities, before any are escalated
+    if let Some(floor) = config.severity_floor() {
+        diagnostics.retain(|d| d.severity.is_none_or(|severity| severity <= floor));
+    }
+    to_utf16_ranges(text, &mut diagnostics);
+    escalate_severities(config, &mut diagnostics);
+    cap_diagnostics(text, config.max_diagnostics, &mut diagnostics);
+    set_source(&config.source, &mut diagnostics);
+    Some(diagnostics)
+}
+
+/// Rules report `hql-ls` as the source; a configured `source` replaces it.
+fn set_source(source: &str, diagnostics: &mut [Diagnostic]) {
+    for diagnostic in diagnostics {
+        diagnostic.source = Some(source.to_string());
+    }
+}
+
+/// Keeps the first `max` diagnostics in document order, so the same ones survive every run,
+/// and adds a note at the top of the file saying how many were dropped, unless `text`
+/// suppresses `max-diagnostics`.
+fn cap_diagnostics(text: &str, max: usize, diagnostics: &mut Vec<Diagnostic>) {
+    if max == 0 || diagnostics.len() <= max {
+        return;
     }
+    diagnostics.sort_by_key(|d| (d.range.start.line, d.range.start.character));
+    let dropped = diagnostics.len() - max;
+    diagnostics.truncate(max);
+    let note = Diagnostic {
+        range: Range::default(),
+        severity: Some(DiagnosticSeverity::INFORMATION),
+        code: Some(NumberOrString::String("max-diagnostics".to_string())),
+        source: Some("hql-ls".to_string()),
+        message: format!("{} more diagnostics not shown; only the first {} (maxDiagnostics) are reported", dropped, max),
+        ..Default::default()
+    };
+    diagnostics.extend(suppression::filter_suppressed(text, vec![note]));
+}
 
-    // Check file size
-    if text.len() as u64 > config.max_file_size {
-        return vec![];
+/// The line rules' (`Input::Lines`) diagnostics for `lines` of `text` only, ready to publish
+/// alongside the other diagnostics carried over from before an edit. Returns `None` if
+/// `text` isn't linted as a whole, where those couldn't be carried over.
+pub fn lint_lines(text: &str, lines: RangeInclusive<u32>, config: &LintingConfig) -> Option<Vec<Diagnostic>> {
+    if !matches!(lint_mode(text, config), LintMode::Whole) {
+        return None;
     }
+    let first = *lines.start();
+    let count = lines.end().saturating_sub(first) as usize + 1;
+    let excerpt = syntax::lines(text).skip(first as usize).take(count).collect::<Vec<_>>().join("\n");
 
+    let ctx = LintContext { text: &excerpt, tokens: &[], tokenizer_error: None, config, schema: None };
     let mut diagnostics = Vec::new();
+    for rule in registry().iter().filter(|rule| rule.input() == Input::Lines) {
+        if rule.settings(&config.rules).enabled {
+            diagnostics.extend(rule.check(&ctx));
+        }
+    }
+    to_utf16_ranges(&excerpt, &mut diagnostics);
+    for diagnostic in &mut diagnostics {
+        diagnostic.range.start.line += first;
+        diagnostic.range.end.line += first;
+    }
 
-    // 1. Text-based checks
-    if config.rules.trailing_whitespace {
-        diagnostics.extend(check_trailing_whitespace(text));
+    let mut diagnostics = suppression::filter_suppressed(text, diagnostics);
+    if let Some(floor) = config.severity_floor() {
+        diagnostics.retain(|d| d.severity.is_none_or(|severity| severity <= floor));
     }
-    if config.rules.hive_variable {
-        diagnostics.extend(check_hive_variables(text));
+    escalate_severities(config, &mut diagnostics);
+    set_source(&config.source, &mut diagnostics);
+    Some(diagnostics)
+}
+
+/// True if `code` is reported by a line rule, whose diagnostics `lint_lines` can redo.
+pub fn is_line_rule(code: &str) -> bool {
+    registry().iter().any(|rule| rule.input() == Input::Lines && rule.id() == code)
+}
+
+/// Every enabled rule's diagnostics for `ctx`, in char columns and before suppression.
+fn run_rules(ctx: &LintContext, metrics: Option<&RuleMetrics>, cancelled: &dyn Fn() -> bool) -> Option<Vec<Diagnostic>> {
+    let mut diagnostics = Vec::new();
+    for rule in registry() {
+        if cancelled() {
+            return None;
+        }
+        if !rule.settings(&ctx.config.rules).enabled {
+            continue;
+        }
+        let start = Instant::now();
+        diagnostics.extend(rule.check(ctx));
+        if let Some(metrics) = metrics {
+            metrics.record(rule.id(), start.elapsed());
+        }
     }
+    Some(diagnostics)
+}
 
-    // 2. Tokenization
-    let dialect = HiveDialect {};
-    let tokens_result = Tokenizer::new(&dialect, text).tokenize_with_location();
+/// Lints a file too large to tokenize at once by running the rules over runs of whole
+/// statements of up to `maxFileSize` bytes, so only one run's tokens are held at a time.
+/// Checks that look across statements only see the statements in the same run, and a single
+/// statement over the limit is skipped; a warning at the top of the file says so.
+fn lint_in_chunks(
+    text: &str,
+    config: &LintingConfig,
+    schema: Option<&Schema>,
+    metrics: Option<&RuleMetrics>,
+    cancelled: &dyn Fn() -> bool,
+) -> Option<Vec<Diagnostic>> {
+    let mut diagnostics = vec![Diagnostic {
+        range: Range::default(),
+        severity: Some(DiagnosticSeverity::WARNING),
+        code: Some(NumberOrString::String("max-file-size".to_string())),
+        source: Some("hql-ls".to_string()),
+        message: format!(
+            "File is larger than maxFileSize ({} bytes), so it is linted a few statements at a time; checks across statements may be incomplete",
+            config.max_file_size
+        ),
+        ..Default::default()
+    }];
 
-    match tokens_result {
-        Ok(tokens) => {
-            // 3. Token-based checks
-            if config.rules.keyword_casing {
-                diagnostics.extend(check_keyword_casing(&tokens));
-            }
-            if config.rules.semicolon {
-                diagnostics.extend(check_semicolons(&tokens));
+    let dialect = syntax::dialect(&config.dialect);
+    // Where each chunk starts, counted incrementally from the previous one
+    let (mut line, mut column, mut counted) = (0u32, 0u32, 0usize);
+    for chunk in statement_chunks(text, config.max_file_size as usize, dialect.supports_string_literal_backslash_escape()) {
+        for c in text[counted..chunk.start].chars() {
+            if c == '\n' {
+                line += 1;
+                column = 0;
+            } else {
+                column += 1;
             }
-            if config.rules.parentheses {
-                diagnostics.extend(check_parentheses(&tokens));
-            }
-            if config.rules.missing_comma {
-                diagnostics.extend(check_missing_comma(&tokens, text));
+        }
+        counted = chunk.start;
+
+        let chunk_text = &text[chunk];
+        if chunk_text.len() as u64 > config.max_file_size {
+            continue;
+        }
+        let tokenized = Tokenizer::new(&*dialect, chunk_text).tokenize_with_location();
+        let (tokens, tokenizer_error) = match &tokenized {
+            Ok(tokens) => (tokens.as_slice(), None),
+            Err(e) => (&[][..], Some(e)),
+        };
+        let ctx = LintContext { text: chunk_text, tokens, tokenizer_error, config, schema };
+        for mut diagnostic in run_rules(&ctx, metrics, cancelled)? {
+            for range in ranges_mut(&mut diagnostic) {
+                for position in [&mut range.start, &mut range.end] {
+                    if position.line == 0 {
+                        position.character += column;
+                    }
+                    position.line += line;
+                }
             }
+            diagnostics.push(diagnostic);
         }
-        Err(e) => {
-            // Tokenizer error (e.g. unclosed string)
-            if config.rules.string_literal {
-                let msg = e.to_string();
-                diagnostics.push(Diagnostic {
-                    range: Range::default(), 
-                    severity: Some(DiagnosticSeverity::ERROR),
+    }
+
+    Some(diagnostics)
+}
+
+/// Splits `text` after top-level semicolons (outside strings, quoted names and comments)
+/// and groups consecutive statements into byte ranges of at most `max_len`, unless a single
+/// statement is longer. `backslash_escape` is whether the dialect reads `\'` inside a string
+/// as an escaped quote.
+fn statement_chunks(text: &str, max_len: usize, backslash_escape: bool) -> Vec<std::ops::Range<usize>> {
+    let mut statement_ends = Vec::new();
+    let bytes = text.as_bytes();
+    let mut quote: Option<u8> = None;
+    let mut i = 0;
+    while i < bytes.len() {
+        let b = bytes[i];
+        match quote {
+            Some(q) if backslash_escape && b == b'\\' && q != b'`' => i += 1,
+            Some(q) if b == q => quote = None,
+            Some(_) => {}
+            None => match b {
+                b'\'' | b'"' | b'`' => quote = Some(b),
+                b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                    i = text[i..].find('\n').map_or(bytes.len(), |end| i + end);
+                }
+                b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                    i = text[i + 2..].find("*/").map_or(bytes.len(), |end| i + 2 + end + 1);
+                }
+                b';' => statement_ends.push(i + 1),
+                _ => {}
+            },
+        }
+        i += 1;
+    }
+    if statement_ends.last() != Some(&text.len()) {
+        statement_ends.push(text.len());
+    }
+
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    let mut end = 0;
+    for statement_end in statement_ends {
+        if statement_end - start > max_len && end > start {
+            chunks.push(start..end);
+            start = end;
+        }
+        end = statement_end;
+    }
+    if end > start {
+        chunks.push(start..end);
+    }
+    chunks
+}
+
+/// Every built-in rule, in the order they run: text rules, then token rules, then the
+/// tokenizer error rules.
+pub fn registry() -> &'static [Box<dyn Rule>] {
+    static REGISTRY: OnceLock<Vec<Box<dyn Rule>>> = OnceLock::new();
+    REGISTRY.get_or_init(|| {
+        vec![
+            FnRule::boxed("trailing-whitespace", Input::Lines, |rules| &rules.trailing_whitespace, |ctx, _, severity| check_trailing_whitespace(ctx.text, severity)),
+            FnRule::boxed("hive-variable", Input::Text, |rules| &rules.hive_variable, |ctx, _, severity| {
+                check_hive_variables(ctx.text, &ctx.config.hive_variable_namespaces, &ctx.config.hive_variables, severity)
+            }),
+            FnRule::boxed("mixed-indentation", Input::Lines, |rules| &rules.indentation, |ctx, rule, severity| {
+                let style = match rule.option_str("style") {
+                    Some(s) if s.eq_ignore_ascii_case("tabs") => Some('\t'),
+                    Some(s) if s.eq_ignore_ascii_case("spaces") => Some(' '),
+                    _ => None,
+                };
+                check_indentation(ctx.text, style, severity)
+            }),
+            FnRule::boxed("max-line-length", Input::Lines, |rules| &rules.max_line_length, |ctx, rule, severity| {
+                let max_length = rule.option_u64("maxLength").unwrap_or(DEFAULT_MAX_LINE_LENGTH);
+                check_max_line_length(ctx.text, max_length as usize, severity)
+            }),
+            FnRule::boxed("keyword-casing", Input::Tokens, |rules| &rules.keyword_casing, |ctx, _, severity| check_keyword_casing(ctx.tokens, &ctx.config.additional_keywords, severity)),
+            FnRule::boxed("missing-semicolon", Input::Tokens, |rules| &rules.semicolon, |ctx, _, severity| check_semicolons(ctx.tokens, severity)),
+            FnRule::boxed("unbalanced-parentheses", Input::Tokens, |rules| &rules.parentheses, |ctx, _, severity| check_parentheses(ctx.tokens, severity)),
+            FnRule::boxed("missing-comma", Input::Tokens, |rules| &rules.missing_comma, |ctx, _, severity| check_missing_comma(ctx.tokens, ctx.text, severity)),
+            FnRule::boxed("scalar-subquery-columns", Input::Tokens, |rules| &rules.scalar_subquery_columns, |ctx, _, severity| check_scalar_subquery_columns(ctx.tokens, severity)),
+            // Hive (2.2+) treats `||` as string concatenation
+            FnRule::boxed("foreign-operator", Input::Tokens, |rules| &rules.foreign_operator, |ctx, _, severity| check_foreign_operators(ctx.tokens, syntax::pipe_is_concat(&ctx.config.dialect), severity)),
+            FnRule::boxed("limit-value", Input::Tokens, |rules| &rules.limit_value, |ctx, rule, _| check_limit_value(ctx.tokens, rule.severity())),
+            FnRule::boxed("insert-column-order", Input::Tokens, |rules| &rules.insert_column_order, |ctx, _, severity| check_insert_column_order(ctx.tokens, severity)),
+            FnRule::boxed("mixed-aggregate", Input::Tokens, |rules| &rules.mixed_aggregate, |ctx, _, severity| check_mixed_aggregate(ctx.tokens, severity)),
+            FnRule::boxed("misplaced-distinct", Input::Tokens, |rules| &rules.misplaced_distinct, |ctx, _, severity| check_misplaced_distinct(ctx.tokens, severity)),
+            FnRule::boxed("empty-table-parens", Input::Tokens, |rules| &rules.empty_table_parens, |ctx, rule, severity| check_empty_table_parens(ctx.tokens, rule, severity)),
+            FnRule::boxed("boolean-literal-casing", Input::Tokens, |rules| &rules.boolean_literal_casing, |ctx, rule, severity| {
+                let upper = !rule.option_str("booleanCase").is_some_and(|case| case.eq_ignore_ascii_case("lower"));
+                check_boolean_literal_casing(ctx.tokens, upper, severity)
+            }),
+            FnRule::boxed("statement-length", Input::Tokens, |rules| &rules.statement_length, |ctx, rule, severity| {
+                let max_lines = rule.option_u64("maxStatementLines").unwrap_or(DEFAULT_MAX_STATEMENT_LINES);
+                check_statement_length(ctx.tokens, max_lines, severity)
+            }),
+            FnRule::boxed("select-star", Input::Tokens, |rules| &rules.select_star, |ctx, _, severity| check_select_star(ctx.tokens, severity)),
+            FnRule::boxed("reserved-identifier", Input::Tokens, |rules| &rules.reserved_identifier, |ctx, _, severity| {
+                // keyword-alias reports the aliases after AS when it's on
+                check_reserved_identifiers(ctx.tokens, !ctx.config.rules.keyword_alias.enabled, severity)
+            }),
+            FnRule::boxed("empty-window", Input::Tokens, |rules| &rules.empty_window, |ctx, _, severity| check_empty_window(ctx.tokens, severity)),
+            FnRule::boxed("case-branch-types", Input::Tokens, |rules| &rules.case_branch_types, |ctx, _, severity| check_case_branch_types(ctx.tokens, severity)),
+            FnRule::boxed("unused-cte-column", Input::Tokens, |rules| &rules.unused_cte_column, |ctx, _, severity| check_unused_cte_columns(ctx.tokens, severity)),
+            FnRule::boxed("cte-order-by", Input::Tokens, |rules| &rules.cte_order_by, |ctx, _, severity| check_cte_order_by(ctx.tokens, severity)),
+            FnRule::boxed("tuning-set", Input::Tokens, |rules| &rules.tuning_set, |ctx, rule, severity| {
+                let keys = rule.option_str_list("keys").unwrap_or_else(|| DEFAULT_TUNING_KEYS.to_vec());
+                check_tuning_set(ctx.tokens, &keys, severity)
+            }),
+            FnRule::boxed("trailing-comma", Input::Tokens, |rules| &rules.trailing_comma, |ctx, _, severity| check_trailing_comma(ctx.tokens, severity)),
+            FnRule::boxed("wide-group-by", Input::Tokens, |rules| &rules.wide_group_by, |ctx, rule, severity| {
+                let max_keys = rule.option_u64("maxGroupByKeys").unwrap_or(DEFAULT_MAX_GROUP_BY_KEYS);
+                check_wide_group_by(ctx.tokens, max_keys, severity)
+            }),
+            FnRule::boxed("unsafe-interpolation", Input::Tokens, |rules| &rules.unsafe_interpolation, |ctx, _, severity| check_unsafe_interpolation(ctx.tokens, ctx.text, severity)),
+            FnRule::boxed("cartesian-join", Input::Tokens, |rules| &rules.cartesian_join, |ctx, _, severity| check_cartesian_join(ctx.tokens, severity)),
+            FnRule::boxed("redundant-cast", Input::Tokens, |rules| &rules.redundant_cast, |ctx, _, severity| match ctx.schema {
+                Some(schema) => check_redundant_cast(ctx.tokens, schema, severity),
+                None => Vec::new(),
+            }),
+            FnRule::boxed("null-into-not-null", Input::Tokens, |rules| &rules.null_into_not_null, |ctx, _, severity| match ctx.schema {
+                Some(schema) => check_null_into_not_null(ctx.tokens, schema, severity),
+                None => Vec::new(),
+            }),
+            FnRule::boxed("truncate-external", Input::Tokens, |rules| &rules.truncate_external, |ctx, _, severity| match ctx.schema {
+                Some(schema) => check_truncate_external(ctx.tokens, schema, severity),
+                None => Vec::new(),
+            }),
+            FnRule::boxed("invalid-regex", Input::Tokens, |rules| &rules.invalid_regex, |ctx, _, severity| check_invalid_regex(ctx.tokens, severity)),
+            FnRule::boxed("duplicate-set-column", Input::Tokens, |rules| &rules.duplicate_set_column, |ctx, _, severity| check_duplicate_set_columns(ctx.tokens, severity)),
+            FnRule::boxed("cte-missing-as", Input::Tokens, |rules| &rules.cte_missing_as, |ctx, _, severity| check_cte_missing_as(ctx.tokens, severity)),
+            FnRule::boxed("non-join-on", Input::Tokens, |rules| &rules.non_join_on, |ctx, _, severity| check_non_join_on(ctx.tokens, severity)),
+            FnRule::boxed("alias-style-consistency", Input::Tokens, |rules| &rules.alias_style_consistency, |ctx, _, severity| check_alias_style_consistency(ctx.tokens, severity)),
+            FnRule::boxed("syntax", Input::Tokens, |rules| &rules.syntax, |ctx, _, severity| check_syntax(ctx.tokens, &*syntax::dialect(&ctx.config.dialect), severity)),
+            FnRule::boxed("limit-without-order", Input::Tokens, |rules| &rules.limit_without_order, |ctx, _, severity| check_limit_without_order(ctx.tokens, severity)),
+            FnRule::boxed("insert-select-arity", Input::Tokens, |rules| &rules.insert_select_arity, |ctx, _, severity| check_insert_select_arity(ctx.tokens, severity)),
+            FnRule::boxed("duplicate-column", Input::Tokens, |rules| &rules.duplicate_select_column, |ctx, _, severity| check_duplicate_select_columns(ctx.tokens, severity)),
+            FnRule::boxed("cluster-order-conflict", Input::Tokens, |rules| &rules.cluster_order_conflict, |ctx, _, severity| check_cluster_order_conflict(ctx.tokens, severity)),
+            FnRule::boxed("implicit-default-db", Input::Tokens, |rules| &rules.implicit_default_db, |ctx, _, severity| check_implicit_default_db(ctx.tokens, severity)),
+            FnRule::boxed("constant-select-item", Input::Tokens, |rules| &rules.constant_select_item, |ctx, _, severity| check_constant_select_items(ctx.tokens, severity)),
+            FnRule::boxed("no-tabs", Input::Tokens, |rules| &rules.no_tabs, |ctx, rule, severity| {
+                check_no_tabs(ctx.tokens, rule.option_u64("tabWidth").unwrap_or(DEFAULT_TAB_WIDTH) as usize, severity)
+            }),
+            FnRule::boxed("too-many-name-parts", Input::Tokens, |rules| &rules.too_many_name_parts, |ctx, rule, severity| {
+                check_too_many_name_parts(ctx.tokens, rule.option_u64("maxParts").unwrap_or(DEFAULT_MAX_NAME_PARTS) as usize, severity)
+            }),
+            FnRule::boxed("comma-join", Input::Tokens, |rules| &rules.comma_join, |ctx, _, severity| check_comma_join(ctx.tokens, severity)),
+            FnRule::boxed("equality-operator", Input::Tokens, |rules| &rules.equality_operator, |ctx, _, severity| check_equality_operator(ctx.tokens, severity)),
+            FnRule::boxed("between-bounds", Input::Tokens, |rules| &rules.between_bounds, |ctx, rule, _| check_between_bounds(ctx.tokens, rule.severity())),
+            FnRule::boxed("union-dedup", Input::Tokens, |rules| &rules.union_dedup, |ctx, _, severity| check_union_dedup(ctx.tokens, severity)),
+            FnRule::boxed("distinct-with-group-by", Input::Tokens, |rules| &rules.distinct_with_group_by, |ctx, _, severity| check_distinct_with_group_by(ctx.tokens, severity)),
+            FnRule::boxed("ambiguous-column", Input::Tokens, |rules| &rules.ambiguous_column, |ctx, _, severity| match ctx.schema {
+                Some(schema) => check_ambiguous_columns(ctx.tokens, schema, severity),
+                None => Vec::new(),
+            }),
+            FnRule::boxed("positional-reference", Input::Tokens, |rules| &rules.order_by_ordinal, |ctx, _, severity| check_positional_references(ctx.tokens, severity)),
+            FnRule::boxed("null-comparison", Input::Tokens, |rules| &rules.null_comparison, |ctx, _, severity| check_null_comparison(ctx.tokens, severity)),
+            FnRule::boxed("semicolon-in-parentheses", Input::Tokens, |rules| &rules.semicolon_in_parens, |ctx, _, severity| check_semicolon_in_parens(ctx.tokens, severity)),
+            FnRule::boxed("keyword-alias", Input::Tokens, |rules| &rules.keyword_alias, |ctx, _, severity| check_keyword_aliases(ctx.tokens, severity)),
+            FnRule::boxed("group-by-consistency", Input::Tokens, |rules| &rules.group_by_consistency, |ctx, _, severity| check_group_by_consistency(ctx.tokens, severity)),
+            FnRule::boxed("missing-over", Input::Tokens, |rules| &rules.missing_over, |ctx, _, severity| check_missing_over(ctx.tokens, severity)),
+            FnRule::boxed("missing-stored-as", Input::Tokens, |rules| &rules.missing_stored_as, |ctx, _, severity| check_missing_stored_as(ctx.tokens, severity)),
+            FnRule::boxed("duplicate-alias", Input::Tokens, |rules| &rules.duplicate_alias, |ctx, _, severity| check_duplicate_aliases(ctx.tokens, severity)),
+            FnRule::boxed("deprecated-function", Input::Tokens, |rules| &rules.deprecated_function, |ctx, rule, severity| {
+                let mut deprecated = DEFAULT_DEPRECATED_FUNCTIONS.to_vec();
+                deprecated.extend(rule.option_str_map("functions").unwrap_or_default());
+                check_deprecated_functions(ctx.tokens, &deprecated, rule, severity)
+            }),
+            FnRule::boxed("constant-predicate", Input::Tokens, |rules| &rules.constant_predicate, |ctx, _, severity| check_constant_predicates(ctx.tokens, severity)),
+            FnRule::boxed("type-casing", Input::Tokens, |rules| &rules.type_casing, |ctx, _, severity| check_type_casing(ctx.tokens, severity)),
+            FnRule::boxed("type-mismatch", Input::Tokens, |rules| &rules.type_mismatch, |ctx, _, severity| match ctx.schema {
+                Some(schema) => check_type_mismatch(ctx.tokens, schema, severity),
+                None => Vec::new(),
+            }),
+            FnRule::boxed("select-distinct-star", Input::Tokens, |rules| &rules.select_distinct_star, |ctx, _, severity| check_select_distinct_star(ctx.tokens, severity)),
+            FnRule::boxed("inconsistent-quoting", Input::Tokens, |rules| &rules.inconsistent_quoting, |ctx, _, severity| check_inconsistent_quoting(ctx.tokens, severity)),
+            FnRule::boxed("missing-partition", Input::Tokens, |rules| &rules.missing_partition, |ctx, _, severity| match ctx.schema {
+                Some(schema) => check_missing_partition(ctx.tokens, schema, severity),
+                None => Vec::new(),
+            }),
+            FnRule::boxed("like-without-wildcard", Input::Tokens, |rules| &rules.like_without_wildcard, |ctx, _, severity| check_like_without_wildcard(ctx.tokens, severity)),
+            FnRule::boxed("multi-column-count-distinct", Input::Tokens, |rules| &rules.multi_column_count_distinct, |ctx, _, severity| {
+                // Other dialects have their own rules for COUNT(DISTINCT ...)
+                if !syntax::is_hive(&ctx.config.dialect) {
+                    return Vec::new();
+                }
+                check_multi_column_count_distinct(ctx.tokens, severity)
+            }),
+            FnRule::boxed("batch-separator", Input::Tokens, |rules| &rules.batch_separator, |ctx, _, severity| check_batch_separator(ctx.tokens, severity)),
+            FnRule::boxed("union-column-mismatch", Input::Tokens, |rules| &rules.union_column_mismatch, |ctx, _, severity| check_union_column_mismatch(ctx.tokens, severity)),
+            FnRule::boxed("unbalanced-case", Input::Tokens, |rules| &rules.unbalanced_case, |ctx, _, severity| check_unbalanced_case(ctx.tokens, severity)),
+            FnRule::boxed("subquery-depth", Input::Tokens, |rules| &rules.subquery_depth, |ctx, rule, severity| {
+                let max_depth = rule.option_u64("maxDepth").unwrap_or(DEFAULT_MAX_SUBQUERY_DEPTH);
+                check_subquery_depth(ctx.tokens, max_depth, severity)
+            }),
+            FnRule::boxed("set-property", Input::Tokens, |rules| &rules.set_property, |ctx, rule, _| {
+                let mut prefixes = DEFAULT_PROPERTY_PREFIXES.to_vec();
+                prefixes.extend(rule.option_str_list("prefixes").unwrap_or_default());
+                let mut deprecated = DEFAULT_DEPRECATED_PROPERTIES.to_vec();
+                deprecated.extend(rule.option_str_map("deprecated").unwrap_or_default());
+                check_set_property(ctx.tokens, &prefixes, &deprecated, rule.severity())
+            }),
+            // An unclosed comment surfaces as an EOF error; point at its opening instead
+            FnRule::boxed("unterminated-comment", Input::TokenizerError, |rules| &rules.comment, |ctx, _, severity| {
+                let backslash_escape = syntax::dialect(&ctx.config.dialect).supports_string_literal_backslash_escape();
+                check_unterminated_comment(ctx.text, backslash_escape, severity).into_iter().collect()
+            }),
+            FnRule::boxed("tokenizer-error", Input::TokenizerError, |rules| &rules.string_literal, |ctx, _, severity| {
+                let Some(error) = ctx.tokenizer_error else { return Vec::new() };
+                // Already reported as an unterminated comment
+                let backslash_escape = syntax::dialect(&ctx.config.dialect).supports_string_literal_backslash_escape();
+                if ctx.config.rules.comment.enabled && check_unterminated_comment(ctx.text, backslash_escape, severity).is_some() {
+                    return Vec::new();
+                }
+                // Tokenizer error (e.g. unclosed string)
+                vec![Diagnostic {
+                    range: tokenizer_error_range(ctx.text, error),
+                    severity: Some(severity),
+                    code: Some(NumberOrString::String("tokenizer-error".to_string())),
                     source: Some("hql-ls".to_string()),
-                    message: msg,
+                    message: error.to_string(),
                     ..Default::default()
-                });
-            }
+                }]
+            }),
+        ]
+    })
+}
+
+/// Reports warnings and information (and optionally hints) as errors, so CI fails on them.
+fn escalate_severities(config: &LintingConfig, diagnostics: &mut [Diagnostic]) {
+    for diagnostic in diagnostics {
+        let escalate = match diagnostic.severity {
+            Some(DiagnosticSeverity::WARNING | DiagnosticSeverity::INFORMATION) => config.treat_warnings_as_errors,
+            Some(DiagnosticSeverity::HINT) => config.treat_hints_as_errors,
+            _ => false,
+        };
+        if escalate {
+            diagnostic.severity = Some(DiagnosticSeverity::ERROR);
         }
     }
+}
 
-    // Filter/Map severity based on config.severity if needed
-    // For now, we stick to rule-defined severities but could override.
+enum LintMode {
+    Skip,
+    Whole,
+    /// Over `maxFileSize` but within `maxChunkedFileSize`
+    Chunked,
+}
 
-    diagnostics
+fn lint_mode(text: &str, config: &LintingConfig) -> LintMode {
+    let len = text.len() as u64;
+    if !config.enabled || len > config.max_chunked_file_size {
+        LintMode::Skip
+    } else if len > config.max_file_size {
+        LintMode::Chunked
+    } else {
+        LintMode::Whole
+    }
+}
+
+/// Stands in for the linted document in related information, since rules don't know its URI;
+/// see `resolve_related`.
+const LINTED_DOCUMENT: &str = "hql-ls:linted-document";
+
+fn related(range: Range, message: &str) -> DiagnosticRelatedInformation {
+    DiagnosticRelatedInformation {
+        location: lsp::Location { uri: Url::parse(LINTED_DOCUMENT).unwrap(), range },
+        message: message.to_string(),
+    }
+}
+
+/// Points related information that refers to the linted text at `uri`, the document's URI.
+pub fn resolve_related(uri: &Url, diagnostics: &mut [Diagnostic]) {
+    for information in diagnostics.iter_mut().flat_map(|d| d.related_information.iter_mut().flatten()) {
+        if information.location.uri.as_str() == LINTED_DOCUMENT {
+            information.location.uri = uri.clone();
+        }
+    }
+}
+
+/// Every range a diagnostic reports in the linted text: its own and its related locations'.
+fn ranges_mut(diagnostic: &mut Diagnostic) -> impl Iterator<Item = &mut Range> {
+    std::iter::once(&mut diagnostic.range)
+        .chain(diagnostic.related_information.iter_mut().flatten().map(|information| &mut information.location.range))
+}
+
+/// Rules report columns in chars, as the tokenizer does; LSP wants UTF-16 code units.
+fn to_utf16_ranges(text: &str, diagnostics: &mut [Diagnostic]) {
+    if text.is_ascii() {
+        return;
+    }
+    let lines: Vec<&str> = syntax::lines(text).collect();
+    let convert = |position: &mut Position| {
+        if let Some(line) = lines.get(position.line as usize) {
+            position.character = syntax::utf16_column(line, position.character);
+        }
+    };
+    for range in diagnostics.iter_mut().flat_map(ranges_mut) {
+        convert(&mut range.start);
+        convert(&mut range.end);
+    }
 }
 
 // --- Text Based Rules ---
 
-fn check_trailing_whitespace(text: &str) -> Vec<Diagnostic> {
+/// Spaces and tabs at the end of a line; a line of nothing but whitespace is reported whole.
+/// Columns are in chars here and converted to UTF-16 with every other range.
+fn check_trailing_whitespace(text: &str, severity: DiagnosticSeverity) -> Vec<Diagnostic> {
     let mut diagnostics = Vec::new();
-    for (i, line) in text.lines().enumerate() {
+    for (i, line) in syntax::lines(text).enumerate() {
         if line.ends_with(' ') || line.ends_with('\t') {
-            let trimmed = line.trim_end();
+            let trimmed = line.trim_end_matches([' ', '\t']);
+            let message = if trimmed.is_empty() { "Whitespace-only line" } else { "Trailing whitespace" };
             let range = Range {
                 start: Position {
                     line: i as u32,
-                    character: trimmed.len() as u32,
+                    character: trimmed.chars().count() as u32,
                 },
                 end: Position {
                     line: i as u32,
-                    character: line.len() as u32,
+                    character: line.chars().count() as u32,
                 },
             };
             
             diagnostics.push(Diagnostic {
                 range,
-                severity: Some(DiagnosticSeverity::HINT),
+                severity: Some(severity),
                 code: Some(NumberOrString::String("trailing-whitespace".to_string())),
                 source: Some("hql-ls".to_string()),
-                message: "Trailing whitespace".to_string(),
+                message: message.to_string(),
+                tags: Some(vec![DiagnosticTag::UNNECESSARY]),
                 ..Default::default()
             });
         }
@@ -97,21 +589,27 @@ fn check_trailing_whitespace(text: &str) -> Vec<Diagnostic> {
     diagnostics
 }
 
-fn check_hive_variables(text: &str) -> Vec<Diagnostic> {
-    let mut diagnostics = Vec::new();
-    // Regex to find ${...}
+/// Matches `${...}`, capturing the contents.
+pub fn hive_variable_regex() -> &'static Regex {
     static RE: OnceLock<Regex> = OnceLock::new();
-    let re = RE.get_or_init(|| Regex::new(r"\$\{([^}]*)\}").unwrap());
-    
-    // Valid namespaces: hiveconf, hivevar, env, system, define
-    let valid_namespaces = ["hiveconf", "hivevar", "env", "system", "define"];
+    RE.get_or_init(|| Regex::new(r"\$\{([^}]*)\}").unwrap())
+}
+
+fn check_hive_variables(
+    text: &str,
+    valid_namespaces: &[String],
+    known_variables: &HashMap<String, HashMap<String, String>>,
+    severity: DiagnosticSeverity,
+) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let re = hive_variable_regex();
 
-    for (i, line) in text.lines().enumerate() {
+    for (i, line) in syntax::lines(text).enumerate() {
         for cap in re.captures_iter(line) {
             if let Some(full_match) = cap.get(0) {
                 let inner_content = &cap[1]; // content inside ${...}
-                let start_col = full_match.start();
-                let end_col = full_match.end();
+                let start_col = line[..full_match.start()].chars().count();
+                let end_col = start_col + full_match.as_str().chars().count();
                 
                 let range = Range {
                     start: Position { line: i as u32, character: start_col as u32 },
@@ -121,7 +619,8 @@ fn check_hive_variables(text: &str) -> Vec<Diagnostic> {
                 if inner_content.trim().is_empty() {
                      diagnostics.push(Diagnostic {
                         range,
-                        severity: Some(DiagnosticSeverity::WARNING),
+                        severity: Some(severity),
+                        code: Some(NumberOrString::String("hive-variable".to_string())),
                         source: Some("hql-ls".to_string()),
                         message: "Empty Hive variable".to_string(),
                         ..Default::default()
@@ -132,7 +631,8 @@ fn check_hive_variables(text: &str) -> Vec<Diagnostic> {
                 if !inner_content.contains(':') {
                      diagnostics.push(Diagnostic {
                         range,
-                        severity: Some(DiagnosticSeverity::WARNING),
+                        severity: Some(severity),
+                        code: Some(NumberOrString::String("hive-variable".to_string())),
                         source: Some("hql-ls".to_string()),
                         message: "Invalid Hive variable: missing colon (expected ${namespace:name})".to_string(),
                         ..Default::default()
@@ -144,10 +644,11 @@ fn check_hive_variables(text: &str) -> Vec<Diagnostic> {
                 let namespace = parts[0];
                 let varname = parts[1];
 
-                if !valid_namespaces.contains(&namespace) {
+                if !valid_namespaces.iter().any(|ns| ns == namespace) {
                      diagnostics.push(Diagnostic {
                         range,
-                        severity: Some(DiagnosticSeverity::WARNING),
+                        severity: Some(severity),
+                        code: Some(NumberOrString::String("hive-variable".to_string())),
                         source: Some("hql-ls".to_string()),
                         message: format!("Invalid namespace '{}'. Expected: {:?}", namespace, valid_namespaces),
                         ..Default::default()
@@ -155,11 +656,23 @@ fn check_hive_variables(text: &str) -> Vec<Diagnostic> {
                 } else if varname.trim().is_empty() {
                      diagnostics.push(Diagnostic {
                         range,
-                        severity: Some(DiagnosticSeverity::WARNING),
+                        severity: Some(severity),
+                        code: Some(NumberOrString::String("hive-variable".to_string())),
                         source: Some("hql-ls".to_string()),
                         message: "Variable name is empty".to_string(),
                         ..Default::default()
                     });
+                } else if let Some(known) = known_variables.get(namespace)
+                    && !known.contains_key(varname)
+                {
+                     diagnostics.push(Diagnostic {
+                        range,
+                        severity: Some(severity),
+                        code: Some(NumberOrString::String("hive-variable".to_string())),
+                        source: Some("hql-ls".to_string()),
+                        message: format!("Unknown variable '{}' in namespace '{}'", varname, namespace),
+                        ..Default::default()
+                    });
                 }
             }
         }
@@ -167,34 +680,112 @@ fn check_hive_variables(text: &str) -> Vec<Diagnostic> {
     diagnostics
 }
 
+/// Flags indents mixing tabs and spaces, and, when `style` names the expected indent
+/// character, indents using the other one.
+fn check_indentation(text: &str, style: Option<char>, severity: DiagnosticSeverity) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    for (i, line) in syntax::lines(text).enumerate() {
+        let indent = &line[..line.len() - line.trim_start_matches([' ', '\t']).len()];
+        let has_tabs = indent.contains('\t');
+        let has_spaces = indent.contains(' ');
+
+        let message = match style {
+            _ if has_tabs && has_spaces => "Indentation mixes tabs and spaces",
+            Some('\t') if has_spaces => "Indentation should use tabs",
+            Some(' ') if has_tabs => "Indentation should use spaces",
+            _ => continue,
+        };
+
+        diagnostics.push(Diagnostic {
+            range: Range {
+                start: Position { line: i as u32, character: 0 },
+                end: Position { line: i as u32, character: indent.len() as u32 },
+            },
+            severity: Some(severity),
+            code: Some(NumberOrString::String("mixed-indentation".to_string())),
+            source: Some("hql-ls".to_string()),
+            message: message.to_string(),
+            ..Default::default()
+        });
+    }
+    diagnostics
+}
+
+fn check_max_line_length(text: &str, max_length: usize, severity: DiagnosticSeverity) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    for (i, line) in syntax::lines(text).enumerate() {
+        let length = line.chars().count();
+        if length <= max_length {
+            continue;
+        }
+
+        diagnostics.push(Diagnostic {
+            range: Range {
+                start: Position { line: i as u32, character: max_length as u32 },
+                end: Position { line: i as u32, character: length as u32 },
+            },
+            severity: Some(severity),
+            code: Some(NumberOrString::String("max-line-length".to_string())),
+            source: Some("hql-ls".to_string()),
+            message: format!("Line is {} characters long (limit {})", length, max_length),
+            ..Default::default()
+        });
+    }
+    diagnostics
+}
+
 // --- Token Based Rules ---
 
-fn check_keyword_casing(tokens: &[TokenWithSpan]) -> Vec<Diagnostic> {
+fn check_keyword_casing(tokens: &[TokenWithSpan], additional: &[String], severity: DiagnosticSeverity) -> Vec<Diagnostic> {
     let mut diagnostics = Vec::new();
-    for token_with_span in tokens {
-        if let Token::Word(word) = &token_with_span.token {
-             if is_keyword(word) && word.value != word.value.to_uppercase() {
-                let loc = &token_with_span.span;
-                let range = Range {
-                    start: Position { line: (loc.start.line - 1) as u32, character: (loc.start.column - 1) as u32 },
-                    end: Position { line: (loc.end.line - 1) as u32, character: (loc.end.column - 1) as u32 },
-                };
-                
-                diagnostics.push(Diagnostic {
-                    range,
-                    severity: Some(DiagnosticSeverity::WARNING),
-                    code: Some(NumberOrString::String("keyword-casing".to_string())),
-                    source: Some("hql-ls".to_string()),
-                    message: format!("Keyword '{}' should be uppercase", word.value),
-                    ..Default::default()
-                });
-             }
+    for (i, token_with_span) in tokens.iter().enumerate() {
+        if let Token::Word(word) = &token_with_span.token
+            && (keywords::is_keyword_usage(tokens, i) || keywords::is_additional_keyword(tokens, i, additional))
+            && word.value != word.value.to_uppercase()
+        {
+            let loc = &token_with_span.span;
+            let range = Range {
+                start: Position { line: (loc.start.line - 1) as u32, character: (loc.start.column - 1) as u32 },
+                end: Position { line: (loc.end.line - 1) as u32, character: (loc.end.column - 1) as u32 },
+            };
+
+            diagnostics.push(Diagnostic {
+                range,
+                severity: Some(severity),
+                code: Some(NumberOrString::String("keyword-casing".to_string())),
+                source: Some("hql-ls".to_string()),
+                message: format!("Keyword '{}' should be uppercase", word.value),
+                ..Default::default()
+            });
         }
     }
     diagnostics
 }
 
-fn check_semicolons(tokens: &[TokenWithSpan]) -> Vec<Diagnostic> {
+/// Statement keywords that can appear inside a statement begun by another, e.g. the SELECT of
+/// `CREATE TABLE ... AS SELECT`, the SET of `UPDATE ... SET` or the INSERTs of a multi-insert
+/// `FROM src INSERT ... INSERT ...`. `EXPLAIN` is followed by the statement it explains.
+const STATEMENT_CONTINUATIONS: &[(&str, &[&str])] = &[
+    ("WITH", &["SELECT", "INSERT"]),
+    ("INSERT", &["SELECT", "WITH"]),
+    ("CREATE", &["SELECT", "WITH"]),
+    ("ALTER", &["SELECT", "WITH", "SET", "DROP"]),
+    ("UPDATE", &["SET"]),
+    ("MERGE", &["UPDATE", "SET", "DELETE", "INSERT"]),
+    ("SHOW", &["CREATE"]),
+    ("FROM", &["INSERT", "SELECT"]),
+    ("GRANT", &["SELECT", "INSERT", "UPDATE", "DELETE", "CREATE", "DROP", "ALTER"]),
+    ("REVOKE", &["SELECT", "INSERT", "UPDATE", "DELETE", "CREATE", "DROP", "ALTER"]),
+    ("EXPLAIN", &["SELECT", "INSERT", "UPDATE", "DELETE", "CREATE", "DROP", "ALTER", "WITH", "MERGE"]),
+];
+
+/// Keywords that start a statement only as its first word, e.g. `FROM src INSERT ...`.
+const LEADING_STATEMENT_KEYWORDS: &[&str] = &["FROM", "GRANT", "REVOKE"];
+
+/// Words after which a SELECT continues a compound query.
+const SET_OPERATORS: &[&str] = &["UNION", "ALL", "DISTINCT", "INTERSECT", "EXCEPT", "MINUS"];
+
+fn check_semicolons(tokens: &[TokenWithSpan], severity: DiagnosticSeverity) -> Vec<Diagnostic> {
     let mut diagnostics = Vec::new();
     let statement_starters = [
         "SELECT", "INSERT", "UPDATE", "DELETE", "CREATE", "DROP", "ALTER", 
@@ -210,26 +801,30 @@ fn check_semicolons(tokens: &[TokenWithSpan]) -> Vec<Diagnostic> {
         
         match token {
             Token::LParen => paren_balance += 1,
-            Token::RParen => if paren_balance > 0 { paren_balance -= 1 },
-            Token::SemiColon | Token::Char(';') => {
-                if paren_balance == 0 {
-                    current_statement_keyword = None;
-                }
+            Token::RParen if paren_balance > 0 => paren_balance -= 1,
+            Token::SemiColon | Token::Char(';') if paren_balance == 0 => {
+                current_statement_keyword = None;
             },
             Token::Word(w) => {
                 let upper = w.value.to_uppercase();
-                
-                if paren_balance == 0 && statement_starters.contains(&upper.as_str()) {
+                let previous = last_significant_token_idx.map(|idx| &tokens[idx].token);
+                let at_statement_start = previous.is_none_or(|t| matches!(t, Token::SemiColon | Token::Char(';')));
+                let leading = at_statement_start && LEADING_STATEMENT_KEYWORDS.contains(&upper.as_str());
+
+                if paren_balance == 0 && (statement_starters.contains(&upper.as_str()) || leading) {
                     let mut is_continuation = false;
                     
                     if let Some(current) = &current_statement_keyword {
                         // Check if this starter is a valid continuation of the current statement
-                        if upper == "SELECT" {
-                            if matches!(current.as_str(), "WITH" | "INSERT" | "CREATE" | "EXPLAIN") {
-                                is_continuation = true;
-                            }
+                        is_continuation = STATEMENT_CONTINUATIONS
+                            .iter()
+                            .any(|(statement, continuations)| statement == current && continuations.contains(&upper.as_str()))
+                            || (upper == "SELECT"
+                                && previous.is_some_and(|t| SET_OPERATORS.iter().any(|op| is_word(t, op))));
+                        if is_continuation && current == "EXPLAIN" {
+                            // What follows is the statement being explained
+                            current_statement_keyword = Some(upper.clone());
                         }
-                        // Add other continuations if needed
                     }
 
                     if !is_continuation {
@@ -254,7 +849,7 @@ fn check_semicolons(tokens: &[TokenWithSpan]) -> Vec<Diagnostic> {
 
                                 diagnostics.push(Diagnostic {
                                     range,
-                                    severity: Some(DiagnosticSeverity::INFORMATION),
+                                    severity: Some(severity),
                                     code: Some(NumberOrString::String("missing-semicolon".to_string())),
                                     source: Some("hql-ls".to_string()),
                                     message: "Missing semicolon at end of statement".to_string(),
@@ -277,54 +872,66 @@ fn check_semicolons(tokens: &[TokenWithSpan]) -> Vec<Diagnostic> {
     }
     
     // Check for missing semicolon at EOF
-    if paren_balance == 0 && current_statement_keyword.is_some() {
-        if let Some(last_idx) = last_significant_token_idx {
-             let last_token = &tokens[last_idx].token;
-             let is_semicolon = matches!(last_token, Token::SemiColon) || 
-                              matches!(last_token, Token::Char(';'));
-             
-             if !is_semicolon {
-                 let last_span = &tokens[last_idx].span;
-                 let range = Range {
-                    start: Position { 
-                        line: (last_span.end.line - 1) as u32, 
-                        character: (last_span.end.column - 1) as u32 
-                    },
-                    end: Position { 
-                        line: (last_span.end.line - 1) as u32, 
-                        character: (last_span.end.column - 1) as u32 
-                    },
-                };
+    if paren_balance == 0
+        && current_statement_keyword.is_some()
+        && let Some(last_idx) = last_significant_token_idx
+    {
+        let last_token = &tokens[last_idx].token;
+        let is_semicolon = matches!(last_token, Token::SemiColon) || 
+                         matches!(last_token, Token::Char(';'));
+        
+        if !is_semicolon {
+            let last_span = &tokens[last_idx].span;
+            let range = Range {
+                start: Position { 
+                    line: (last_span.end.line - 1) as u32, 
+                    character: (last_span.end.column - 1) as u32 
+                },
+                end: Position { 
+                    line: (last_span.end.line - 1) as u32, 
+                    character: (last_span.end.column - 1) as u32 
+                },
+            };
 
-                diagnostics.push(Diagnostic {
-                    range,
-                    severity: Some(DiagnosticSeverity::INFORMATION),
-                    code: Some(NumberOrString::String("missing-semicolon".to_string())),
-                    source: Some("hql-ls".to_string()),
-                    message: "Missing semicolon at end of file".to_string(),
-                    ..Default::default()
-                });
-             }
+            diagnostics.push(Diagnostic {
+                range,
+                severity: Some(severity),
+                code: Some(NumberOrString::String("missing-semicolon".to_string())),
+                source: Some("hql-ls".to_string()),
+                message: "Missing semicolon at end of file".to_string(),
+                ..Default::default()
+            });
         }
     }
     
     diagnostics
 }
 
-fn check_missing_comma(tokens: &[TokenWithSpan], text: &str) -> Vec<Diagnostic> {
+fn check_missing_comma(tokens: &[TokenWithSpan], text: &str, severity: DiagnosticSeverity) -> Vec<Diagnostic> {
     let mut diagnostics = Vec::new();
     
     // Keywords that are valid starts of a new clause/expression/operator, so they don't need a preceding comma
-    let clause_starters = [
-        "FROM", "WHERE", "GROUP", "ORDER", "HAVING", "LIMIT", "OFFSET", "UNION", "LATERAL", "DISTINCT",
-        "JOIN", "INNER", "LEFT", "RIGHT", "FULL", "CROSS", "ON", "AS", "USING", "WINDOW",
-        "AND", "OR", "XOR", "CASE", "WHEN", "THEN", "ELSE", "END", "IS", "NOT", "IN", "BETWEEN", "LIKE", "RLIKE", "REGEXP",
-        "OVER", "PARTITION", "BY", "ROWS", "RANGE", "UNBOUNDED", "PRECEDING", "FOLLOWING", "CURRENT", "ROW"
-    ];
+    let is_clause_starter = |word: &str| CLAUSE_KEYWORDS.contains(&word) || EXPRESSION_KEYWORDS.contains(&word);
+    // One entry per open paren: true for subqueries, whose SELECT lists are still checked
+    let mut groups: Vec<bool> = Vec::new();
+
+    for (i, t1) in tokens.iter().enumerate() {
+        match t1.token {
+            Token::LParen => {
+                groups.push(next_significant(tokens, i).is_some_and(|j| is_word(&tokens[j].token, "SELECT")));
+                continue;
+            }
+            Token::RParen => {
+                groups.pop();
+                continue;
+            }
+            _ => {}
+        }
+        // Words wrapped inside a function call or other expression group aren't list items
+        if groups.last() == Some(&false) {
+            continue;
+        }
 
-    for i in 0..tokens.len() {
-        let t1 = &tokens[i];
-        
         if let Token::Word(w1) = &t1.token {
             // Look ahead for next non-whitespace token
             let mut j = i + 1;
@@ -348,7 +955,7 @@ fn check_missing_comma(tokens: &[TokenWithSpan], text: &str) -> Vec<Diagnostic>
                 if t1.span.end.line < t2.span.start.line {
                     
                     // Extract text slice corresponding to t1 and the potential comma position
-                    let t1_end_line_text = text.lines().nth((t1.span.end.line - 1) as usize).unwrap_or("");
+                    let t1_end_line_text = syntax::lines(text).nth((t1.span.end.line - 1) as usize).unwrap_or("");
                     let mut has_comma_after_t1 = false;
                     // Check from t1.span.end.column to end of line
                     let slice_start = (t1.span.end.column - 1) as usize;
@@ -370,13 +977,17 @@ fn check_missing_comma(tokens: &[TokenWithSpan], text: &str) -> Vec<Diagnostic>
                     let u2 = w2.value.to_uppercase();
                     
                     // If second word is a known keyword that starts a clause, skip (e.g. `col FROM`)
-                    if clause_starters.contains(&u2.as_str()) {
+                    if is_clause_starter(&u2) {
                         continue;
                     }
                     // If first word is a known keyword or `SELECT` (which starts a list), skip
                     // e.g., `SELECT col` then `FROM` on next line is fine.
                     // This is to avoid flagging `SELECT col FROM`
-                    if clause_starters.contains(&u1.as_str()) || u1 == "SELECT" {
+                    if is_clause_starter(&u1) || u1 == "SELECT" {
+                        continue;
+                    }
+                    // `id\n COALESCE(...)` is a function call continuing the expression
+                    if next_significant(tokens, j).is_some_and(|k| matches!(tokens[k].token, Token::LParen)) {
                         continue;
                     }
                     
@@ -394,7 +1005,7 @@ fn check_missing_comma(tokens: &[TokenWithSpan], text: &str) -> Vec<Diagnostic>
 
                     diagnostics.push(Diagnostic {
                         range,
-                        severity: Some(DiagnosticSeverity::WARNING),
+                        severity: Some(severity),
                         code: Some(NumberOrString::String("missing-comma".to_string())),
                         source: Some("hql-ls".to_string()),
                         message: "Possible missing comma between columns in SELECT list".to_string(),
@@ -408,221 +1019,6257 @@ fn check_missing_comma(tokens: &[TokenWithSpan], text: &str) -> Vec<Diagnostic>
     diagnostics
 }
 
-fn check_parentheses(tokens: &[TokenWithSpan]) -> Vec<Diagnostic> {
+fn check_parentheses(tokens: &[TokenWithSpan], severity: DiagnosticSeverity) -> Vec<Diagnostic> {
     let mut diagnostics = Vec::new();
-    let mut balance = 0;
-    let mut first_negative_idx = None;
+    // Indices of the `(` tokens not yet closed
+    let mut open_parens = Vec::new();
+    // The `(` of the pair closed most recently, which is often where a ')' went missing
+    let mut last_matched = None;
 
     for (i, token_with_span) in tokens.iter().enumerate() {
         match &token_with_span.token {
-            Token::LParen => balance += 1,
-            Token::RParen => {
-                balance -= 1;
-                if balance < 0 && first_negative_idx.is_none() {
-                    first_negative_idx = Some(i);
-                }
-            }
+            Token::LParen => open_parens.push(i),
+            Token::RParen => match open_parens.pop() {
+                Some(open_idx) => last_matched = Some(open_idx),
+                None => diagnostics.push(Diagnostic {
+                    range: span_to_range(&token_with_span.span),
+                    severity: Some(severity),
+                    code: Some(NumberOrString::String("unbalanced-parentheses".to_string())),
+                    source: Some("hql-ls".to_string()),
+                    message: "Unbalanced parentheses: extra ')'".to_string(),
+                    related_information: last_matched.map(|open_idx: usize| {
+                        vec![related(span_to_range(&tokens[open_idx].span), "Last '(' closed before it")]
+                    }),
+                    ..Default::default()
+                }),
+            },
             _ => {} // Ignore other tokens
         }
     }
 
-    if balance != 0 {
-        if balance > 0 {
-            // Unclosed (
+    // Unclosed (
+    for idx in open_parens {
+        diagnostics.push(Diagnostic {
+            range: span_to_range(&tokens[idx].span),
+            severity: Some(severity),
+            code: Some(NumberOrString::String("unbalanced-parentheses".to_string())),
+            source: Some("hql-ls".to_string()),
+            message: "Unbalanced parentheses: unclosed '('".to_string(),
+            ..Default::default()
+        });
+    }
+
+    diagnostics
+}
+
+fn check_scalar_subquery_columns(tokens: &[TokenWithSpan], severity: DiagnosticSeverity) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for (i, token_with_span) in tokens.iter().enumerate() {
+        if !is_comparison_operator(&token_with_span.token) {
+            continue;
+        }
+
+        // Only `<op> (SELECT ...)` is a scalar subquery; `IN (SELECT ...)` may return rows of any width
+        let Some(open_idx) = next_significant(tokens, i) else { continue };
+        if !matches!(tokens[open_idx].token, Token::LParen) {
+            continue;
+        }
+        let Some(select_idx) = next_significant(tokens, open_idx) else { continue };
+        if !is_word(&tokens[select_idx].token, "SELECT") {
+            continue;
+        }
+
+        // Count columns in the subquery's own SELECT list, ignoring commas in nested calls
+        let mut depth = 0;
+        let mut columns = 1;
+        let mut close_idx = None;
+        for (j, inner) in tokens.iter().enumerate().skip(select_idx + 1) {
+            match &inner.token {
+                Token::LParen => depth += 1,
+                Token::RParen if depth == 0 => {
+                    close_idx = Some(j);
+                    break;
+                }
+                Token::RParen => depth -= 1,
+                Token::Comma if depth == 0 => columns += 1,
+                Token::Word(w) if depth == 0 && w.value.eq_ignore_ascii_case("FROM") => break,
+                _ => {}
+            }
+        }
+
+        if columns > 1 {
+            let start = &tokens[open_idx].span;
+            let end = close_idx.map(|idx| &tokens[idx].span).unwrap_or(&tokens[select_idx].span);
             diagnostics.push(Diagnostic {
-                range: Range::default(), // TODO: Better location (last open paren)
-                severity: Some(DiagnosticSeverity::ERROR),
-                message: format!("Unbalanced parentheses: {} unclosed '(", balance),
+                range: Range {
+                    start: span_to_range(start).start,
+                    end: span_to_range(end).end,
+                },
+                severity: Some(severity),
+                code: Some(NumberOrString::String("scalar-subquery-columns".to_string())),
+                source: Some("hql-ls".to_string()),
+                message: format!("Scalar subquery must return exactly one column, but it selects {}", columns),
                 ..Default::default()
             });
-        } else {
-            // Extra )
-            if let Some(idx) = first_negative_idx {
-                let span = &tokens[idx].span;
-                 let range = Range {
-                    start: Position { line: (span.start.line - 1) as u32, character: (span.start.column - 1) as u32 },
-                    end: Position { line: (span.end.line - 1) as u32, character: (span.end.column - 1) as u32 },
-                };
-                diagnostics.push(Diagnostic {
-                    range,
-                    severity: Some(DiagnosticSeverity::ERROR),
-                    message: "Unbalanced parentheses: extra ')'".to_string(),
-                    ..Default::default()
-                });
-            }
         }
     }
 
     diagnostics
 }
 
-// Helper
-fn is_keyword(word: &sqlparser::tokenizer::Word) -> bool {
-    if word.quote_style.is_some() {
-        return false;
-    }
-    let upper = word.value.to_uppercase();
-    matches!(upper.as_str(), 
-        "SELECT" | "FROM" | "WHERE" | "GROUP" | "BY" | "HAVING" | "ORDER" | "LIMIT" | 
-        "JOIN" | "LEFT" | "RIGHT" | "INNER" | "OUTER" | "CROSS" | "ON" | "AS" | 
-        "AND" | "OR" | "NOT" | "IN" | "EXISTS" | "BETWEEN" | "LIKE" | "CASE" | "WHEN" | "THEN" | "ELSE" | "END" |
-        "INSERT" | "INTO" | "VALUES" | "UPDATE" | "DELETE" | "CREATE" | "TABLE" | "DROP" | "ALTER"
-    )
-}
+fn check_foreign_operators(tokens: &[TokenWithSpan], pipe_is_concat: bool, severity: DiagnosticSeverity) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut i = 0;
 
-fn is_significant(token: &Token) -> bool {
-    !matches!(token, Token::Whitespace(_))
-}
+    while i < tokens.len() {
+        let token_with_span = &tokens[i];
+        let mut span = token_with_span.span;
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::config::{LintingConfig, LintingRules};
+        let found = match &token_with_span.token {
+            Token::Assignment => Some((":=", "use '=' for comparison")),
+            Token::Overlap => Some(("&&", "use AND")),
+            Token::StringConcat if !pipe_is_concat => Some(("||", "use OR, or CONCAT() for strings")),
+            Token::RArrow => Some(("=>", "HiveQL has no named arguments; pass arguments by position")),
+            Token::Arrow => Some(("->", "HiveQL has no lambda expressions")),
+            // Depending on the dialect these may also arrive as two adjacent tokens
+            Token::Eq | Token::Minus => match tokens.get(i + 1) {
+                Some(next) if matches!(next.token, Token::Gt) && next.span.start == span.end => {
+                    span.end = next.span.end;
+                    i += 1;
+                    if matches!(token_with_span.token, Token::Eq) {
+                        Some(("=>", "HiveQL has no named arguments; pass arguments by position"))
+                    } else {
+                        Some(("->", "HiveQL has no lambda expressions"))
+                    }
+                }
+                _ => None,
+            },
+            // `===` tokenizes as `==` immediately followed by `=`
+            Token::DoubleEq => match tokens.get(i + 1) {
+                Some(next) if matches!(next.token, Token::Eq) && next.span.start == span.end => {
+                    span.end = next.span.end;
+                    i += 1;
+                    Some(("===", "use '='"))
+                }
+                _ => None,
+            },
+            _ => None,
+        };
 
-    fn get_messages(diagnostics: &[Diagnostic]) -> Vec<String> {
-        diagnostics.iter().map(|d| d.message.clone()).collect()
+        if let Some((operator, suggestion)) = found {
+            diagnostics.push(Diagnostic {
+                range: span_to_range(&span),
+                severity: Some(severity),
+                code: Some(NumberOrString::String("foreign-operator".to_string())),
+                source: Some("hql-ls".to_string()),
+                message: format!("Operator '{}' is not valid HQL; {}", operator, suggestion),
+                ..Default::default()
+            });
+        }
+
+        i += 1;
     }
 
-    fn default_config() -> LintingConfig {
-        let mut rules = LintingRules::default();
-        // Enable all rules for testing
-        rules.keyword_casing = true;
-        rules.semicolon = true;
-        rules.string_literal = true;
-        rules.parentheses = true;
-        rules.trailing_whitespace = true;
-        rules.missing_comma = true;
-        rules.hive_variable = true;
-        
-        LintingConfig {
-            enabled: true,
-            severity: "Warning".to_string(),
-            max_file_size: 1048576,
-            rules,
+    diagnostics
+}
+
+/// A configured `severity` applies to both cases; otherwise negative limits are errors and `LIMIT 0` a hint.
+fn check_limit_value(tokens: &[TokenWithSpan], severity: Option<DiagnosticSeverity>) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for (i, token_with_span) in tokens.iter().enumerate() {
+        if !is_word(&token_with_span.token, "LIMIT") {
+            continue;
         }
-    }
 
-    #[test]
-    fn test_keyword_casing() {
-        let sql = "select * from users";
-        let diags = lint(sql, &default_config());
-        let msgs = get_messages(&diags);
-        assert!(msgs.iter().any(|m| m.contains("Keyword 'select' should be uppercase")));
-        assert!(msgs.iter().any(|m| m.contains("Keyword 'from' should be uppercase")));
-    }
+        let Some((mut value, mut span, last_idx)) = read_signed_number(tokens, i) else { continue };
 
-    #[test]
-    fn test_keyword_casing_valid() {
-        let sql = "SELECT * FROM users;";
-        let diags = lint(sql, &default_config());
-        let msgs = get_messages(&diags);
-        assert!(msgs.is_empty());
+        // Hive also accepts `LIMIT offset, count`; the row count is the second literal
+        if let Some(comma_idx) = next_significant(tokens, last_idx)
+            && matches!(tokens[comma_idx].token, Token::Comma)
+        {
+            match read_signed_number(tokens, comma_idx) {
+                Some((count, count_span, _)) => {
+                    value = count;
+                    span = count_span;
+                }
+                None => continue,
+            }
+        }
+
+        let (severity, message) = if value < 0.0 {
+            (severity.unwrap_or(DiagnosticSeverity::ERROR), format!("LIMIT must not be negative (found {})", value))
+        } else if value == 0.0 {
+            (severity.unwrap_or(DiagnosticSeverity::HINT), "LIMIT 0 returns no rows".to_string())
+        } else {
+            continue;
+        };
+
+        diagnostics.push(Diagnostic {
+            range: span_to_range(&span),
+            severity: Some(severity),
+            code: Some(NumberOrString::String("limit-value".to_string())),
+            source: Some("hql-ls".to_string()),
+            message,
+            ..Default::default()
+        });
+    }
+
+    diagnostics
+}
+
+fn check_insert_column_order(tokens: &[TokenWithSpan], severity: DiagnosticSeverity) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for (i, token_with_span) in tokens.iter().enumerate() {
+        if !is_word(&token_with_span.token, "INSERT") {
+            continue;
+        }
+
+        let insert = read_insert(tokens, i);
+        let (Some(target_columns), Some(select_idx)) = (insert.columns, insert.select_idx) else { continue };
+        let items = select_list_items(tokens, select_idx);
+        let names: Vec<Option<String>> = items.iter().map(|item| select_item_name(tokens, item)).collect();
+        if names.len() != target_columns.len() || names.iter().any(|n| n.is_none()) {
+            continue;
+        }
+        let names: Vec<String> = names.into_iter().flatten().map(|n| n.to_lowercase()).collect();
+        let targets: Vec<String> = target_columns.iter().map(|c| c.to_lowercase()).collect();
+
+        let mut sorted_names = names.clone();
+        let mut sorted_targets = targets.clone();
+        sorted_names.sort();
+        sorted_targets.sort();
+        if sorted_names != sorted_targets || names == targets {
+            continue;
+        }
+
+        if let Some(pos) = names.iter().zip(&targets).position(|(n, t)| n != t) {
+            let item = &items[pos];
+            let first = (item.start..item.end).find(|&k| is_significant(&tokens[k].token));
+            let last = (item.start..item.end).rev().find(|&k| is_significant(&tokens[k].token));
+            if let (Some(first), Some(last)) = (first, last) {
+                diagnostics.push(Diagnostic {
+                    range: Range {
+                        start: span_to_range(&tokens[first].span).start,
+                        end: span_to_range(&tokens[last].span).end,
+                    },
+                    severity: Some(severity),
+                    code: Some(NumberOrString::String("insert-column-order".to_string())),
+                    source: Some("hql-ls".to_string()),
+                    message: format!(
+                        "SELECT column '{}' is inserted into target column '{}'; column order may not match the target column list",
+                        names[pos], targets[pos]
+                    ),
+                    ..Default::default()
+                });
+            }
+        }
+    }
+
+    diagnostics
+}
+
+fn check_mixed_aggregate(tokens: &[TokenWithSpan], severity: DiagnosticSeverity) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for (i, token_with_span) in tokens.iter().enumerate() {
+        if !is_word(&token_with_span.token, "SELECT") {
+            continue;
+        }
+
+        let items = select_list_items(tokens, i);
+        let Some(list_end) = items.last().map(|item| item.end) else { continue };
+        if has_group_by(tokens, list_end) {
+            continue;
+        }
+
+        // Nested SELECTs are checked on their own iteration
+        let items: Vec<_> = items
+            .into_iter()
+            .filter(|item| !tokens[item.clone()].iter().any(|t| is_word(&t.token, "SELECT")))
+            .collect();
+        if !items.iter().any(|item| is_aggregate_item(tokens, item)) {
+            continue;
+        }
+
+        for item in &items {
+            let Some(column) = bare_column(tokens, item) else { continue };
+            let first = item.clone().find(|&k| is_significant(&tokens[k].token)).unwrap_or(item.start);
+            let last = item.clone().rev().find(|&k| is_significant(&tokens[k].token)).unwrap_or(first);
+            diagnostics.push(Diagnostic {
+                range: Range {
+                    start: span_to_range(&tokens[first].span).start,
+                    end: span_to_range(&tokens[last].span).end,
+                },
+                severity: Some(severity),
+                code: Some(NumberOrString::String("mixed-aggregate".to_string())),
+                source: Some("hql-ls".to_string()),
+                message: format!(
+                    "Column '{}' is selected alongside an aggregate without GROUP BY; add it to a GROUP BY or aggregate it",
+                    column
+                ),
+                ..Default::default()
+            });
+        }
+    }
+
+    diagnostics
+}
+
+fn check_misplaced_distinct(tokens: &[TokenWithSpan], severity: DiagnosticSeverity) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for (i, token_with_span) in tokens.iter().enumerate() {
+        if !is_word(&token_with_span.token, "SELECT") {
+            continue;
+        }
+
+        // A leading DISTINCT is skipped by select_list_items, so any DISTINCT left
+        // at the list's own depth follows another column. `COUNT(DISTINCT x)` sits deeper.
+        for item in select_list_items(tokens, i) {
+            let mut depth = 0;
+            for j in item.clone() {
+                let inner = &tokens[j];
+                match &inner.token {
+                    Token::LParen => depth += 1,
+                    Token::RParen => depth -= 1,
+                    token if depth == 0 && is_word(token, "DISTINCT") && !is_distinct_from(tokens, item.start, j) => {
+                        diagnostics.push(Diagnostic {
+                            range: span_to_range(&inner.span),
+                            severity: Some(severity),
+                            code: Some(NumberOrString::String("misplaced-distinct".to_string())),
+                            source: Some("hql-ls".to_string()),
+                            message: "DISTINCT must immediately follow SELECT".to_string(),
+                            ..Default::default()
+                        });
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    diagnostics
+}
+
+/// Whether the DISTINCT at `idx` belongs to `IS [NOT] DISTINCT FROM`, looking back no
+/// further than `start`.
+fn is_distinct_from(tokens: &[TokenWithSpan], start: usize, idx: usize) -> bool {
+    let mut previous = (start..idx).rev().filter(|&j| is_significant(&tokens[j].token));
+    match previous.next() {
+        Some(j) if is_word(&tokens[j].token, "IS") => true,
+        Some(j) if is_word(&tokens[j].token, "NOT") => previous.next().is_some_and(|k| is_word(&tokens[k].token, "IS")),
+        _ => false,
+    }
+}
+
+/// Flags `FROM t()` / `JOIN t()`; table functions on the rule's allowlist are skipped.
+fn check_empty_table_parens(tokens: &[TokenWithSpan], rule: &RuleConfig, severity: DiagnosticSeverity) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for (i, token_with_span) in tokens.iter().enumerate() {
+        if !is_word(&token_with_span.token, "FROM") && !is_word(&token_with_span.token, "JOIN") {
+            continue;
+        }
+
+        // Read a possibly qualified name: `t` or `db.t`
+        let Some(name_start) = next_significant(tokens, i) else { continue };
+        let Token::Word(first) = &tokens[name_start].token else { continue };
+        let mut name = first.value.clone();
+        let mut name_end = name_start;
+        while let Some(dot) = next_significant(tokens, name_end).filter(|&j| matches!(tokens[j].token, Token::Period)) {
+            let Some(part) = next_significant(tokens, dot) else { break };
+            let Token::Word(w) = &tokens[part].token else { break };
+            name.push('.');
+            name.push_str(&w.value);
+            name_end = part;
+        }
+
+        let Some(open_idx) = next_significant(tokens, name_end) else { continue };
+        if !matches!(tokens[open_idx].token, Token::LParen) {
+            continue;
+        }
+        let Some(close_idx) = next_significant(tokens, open_idx) else { continue };
+        if !matches!(tokens[close_idx].token, Token::RParen) || rule.allows(&name) {
+            continue;
+        }
+
+        diagnostics.push(Diagnostic {
+            range: Range {
+                start: span_to_range(&tokens[name_start].span).start,
+                end: span_to_range(&tokens[close_idx].span).end,
+            },
+            severity: Some(severity),
+            code: Some(NumberOrString::String("empty-table-parens".to_string())),
+            source: Some("hql-ls".to_string()),
+            message: format!("Empty parentheses after table '{}'", name),
+            ..Default::default()
+        });
+    }
+
+    diagnostics
+}
+
+fn check_boolean_literal_casing(tokens: &[TokenWithSpan], upper: bool, severity: DiagnosticSeverity) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for token_with_span in tokens {
+        let Token::Word(w) = &token_with_span.token else { continue };
+        // Quoted `"true"` / `` `null` `` are identifiers, not literals
+        if w.quote_style.is_some() || !matches!(w.value.to_uppercase().as_str(), "TRUE" | "FALSE" | "NULL") {
+            continue;
+        }
+
+        let expected = if upper { w.value.to_uppercase() } else { w.value.to_lowercase() };
+        if w.value != expected {
+            diagnostics.push(Diagnostic {
+                range: span_to_range(&token_with_span.span),
+                severity: Some(severity),
+                code: Some(NumberOrString::String("boolean-literal-casing".to_string())),
+                source: Some("hql-ls".to_string()),
+                message: format!("Literal '{}' should be written as '{}'", w.value, expected),
+                // The replacement text, for the quick fix
+                data: Some(serde_json::Value::String(expected)),
+                ..Default::default()
+            });
+        }
+    }
+
+    diagnostics
+}
+
+fn check_statement_length(tokens: &[TokenWithSpan], max_lines: u64, severity: DiagnosticSeverity) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for statement in syntax::split_statements(tokens) {
+        let first = &tokens[statement.start];
+        let last = &tokens[statement.end - 1];
+        let lines = last.span.end.line - first.span.start.line + 1;
+        if lines <= max_lines {
+            continue;
+        }
+
+        diagnostics.push(Diagnostic {
+            range: span_to_range(&first.span),
+            severity: Some(severity),
+            code: Some(NumberOrString::String("statement-length".to_string())),
+            source: Some("hql-ls".to_string()),
+            message: format!(
+                "Statement spans {} lines (limit {}); consider splitting it into CTEs",
+                lines, max_lines
+            ),
+            ..Default::default()
+        });
+    }
+
+    diagnostics
+}
+
+fn check_select_star(tokens: &[TokenWithSpan], severity: DiagnosticSeverity) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut paren_balance = 0;
+
+    for (i, token_with_span) in tokens.iter().enumerate() {
+        match &token_with_span.token {
+            Token::LParen => paren_balance += 1,
+            Token::RParen if paren_balance > 0 => paren_balance -= 1,
+            token if paren_balance == 0 && is_word(token, "SELECT") => {
+                let Some(mut star_idx) = next_significant(tokens, i) else { continue };
+                if is_word(&tokens[star_idx].token, "DISTINCT") || is_word(&tokens[star_idx].token, "ALL") {
+                    let Some(next) = next_significant(tokens, star_idx) else { continue };
+                    star_idx = next;
+                }
+                if !matches!(tokens[star_idx].token, Token::Mul) {
+                    continue;
+                }
+
+                diagnostics.push(Diagnostic {
+                    range: span_to_range(&tokens[star_idx].span),
+                    severity: Some(severity),
+                    code: Some(NumberOrString::String("select-star".to_string())),
+                    source: Some("hql-ls".to_string()),
+                    message: "Avoid SELECT *; list the columns explicitly".to_string(),
+                    tags: Some(vec![DiagnosticTag::UNNECESSARY]),
+                    ..Default::default()
+                });
+            }
+            _ => {}
+        }
+    }
+
+    diagnostics
+}
+
+/// Reserved words used as names: in column definitions and lists, and as aliases after AS
+/// if `aliases`.
+fn check_reserved_identifiers(tokens: &[TokenWithSpan], aliases: bool, severity: DiagnosticSeverity) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut flag = |idx: usize| {
+        let Token::Word(w) = &tokens[idx].token else { return };
+        if w.quote_style.is_some() || !is_reserved_word(&w.value) {
+            return;
+        }
+        diagnostics.push(Diagnostic {
+            range: span_to_range(&tokens[idx].span),
+            severity: Some(severity),
+            code: Some(NumberOrString::String("reserved-identifier".to_string())),
+            source: Some("hql-ls".to_string()),
+            message: format!("'{}' is a reserved word in Hive; quote it as `{}`", w.value, w.value),
+            ..Default::default()
+        });
+    };
+
+    if aliases {
+        for alias in as_aliases(tokens) {
+            flag(alias);
+        }
+    }
+
+    // Column names in CREATE TABLE / PARTITIONED BY definitions and INSERT column lists
+    for open_idx in column_list_opens(tokens) {
+        let Some(close_idx) = matching_paren(tokens, open_idx) else { continue };
+        let mut depth = 0;
+        let mut expect_name = true;
+        for (j, inner) in tokens.iter().enumerate().take(close_idx).skip(open_idx + 1) {
+            match &inner.token {
+                Token::LParen | Token::Lt => depth += 1,
+                Token::RParen | Token::Gt => depth -= 1,
+                Token::Comma if depth == 0 => expect_name = true,
+                token if expect_name && is_significant(token) => {
+                    expect_name = false;
+                    // Table constraints aren't column names
+                    if !["PRIMARY", "CONSTRAINT", "FOREIGN", "UNIQUE"].iter().any(|kw| is_word(token, kw)) {
+                        flag(j);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    diagnostics
+}
+
+fn check_empty_window(tokens: &[TokenWithSpan], severity: DiagnosticSeverity) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for (i, token_with_span) in tokens.iter().enumerate() {
+        if !is_word(&token_with_span.token, "OVER") {
+            continue;
+        }
+        let Some(open_idx) = next_significant(tokens, i) else { continue };
+        if !matches!(tokens[open_idx].token, Token::LParen) {
+            continue;
+        }
+        let Some(close_idx) = next_significant(tokens, open_idx) else { continue };
+        if !matches!(tokens[close_idx].token, Token::RParen) {
+            continue;
+        }
+
+        diagnostics.push(Diagnostic {
+            range: Range {
+                start: span_to_range(&token_with_span.span).start,
+                end: span_to_range(&tokens[close_idx].span).end,
+            },
+            severity: Some(severity),
+            code: Some(NumberOrString::String("empty-window".to_string())),
+            source: Some("hql-ls".to_string()),
+            message: "Empty OVER () window computes over the whole result; add PARTITION BY/ORDER BY if that isn't intended".to_string(),
+            ..Default::default()
+        });
+    }
+
+    diagnostics
+}
+
+fn check_case_branch_types(tokens: &[TokenWithSpan], severity: DiagnosticSeverity) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for (i, token_with_span) in tokens.iter().enumerate() {
+        let results = if is_word(&token_with_span.token, "CASE") {
+            case_results(tokens, i)
+        } else if is_word(&token_with_span.token, "IF")
+            && let Some(open_idx) = next_significant(tokens, i).filter(|&j| matches!(tokens[j].token, Token::LParen))
+        {
+            // IF(condition, then, else)
+            call_arguments(tokens, open_idx).into_iter().skip(1).collect()
+        } else {
+            continue;
+        };
+
+        // Only literal results are compared; NULL fits any type
+        let typed: Vec<(&'static str, &std::ops::Range<usize>)> = results
+            .iter()
+            .filter_map(|result| literal_type(tokens, result).map(|t| (t, result)))
+            .collect();
+        let Some(&(first_type, _)) = typed.first() else { continue };
+        let Some(&(other_type, other)) = typed.iter().find(|(t, _)| *t != first_type) else { continue };
+
+        let start = other.clone().find(|&k| is_significant(&tokens[k].token)).unwrap_or(other.start);
+        let end = other.clone().rev().find(|&k| is_significant(&tokens[k].token)).unwrap_or(start);
+        diagnostics.push(Diagnostic {
+            range: Range {
+                start: span_to_range(&tokens[start].span).start,
+                end: span_to_range(&tokens[end].span).end,
+            },
+            severity: Some(severity),
+            code: Some(NumberOrString::String("case-branch-types".to_string())),
+            source: Some("hql-ls".to_string()),
+            message: format!(
+                "{} branches return different types ({} and {}); Hive will coerce them to a common type",
+                if is_word(&token_with_span.token, "CASE") { "CASE" } else { "IF" },
+                first_type,
+                other_type
+            ),
+            ..Default::default()
+        });
+    }
+
+    diagnostics
+}
+
+fn check_unused_cte_columns(tokens: &[TokenWithSpan], severity: DiagnosticSeverity) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for statement in syntax::split_statements(tokens) {
+        let ctes = syntax::collect_ctes(tokens, &statement);
+        for cte in &ctes {
+            let downstream = cte.close_idx + 1..statement.end;
+            let words: Vec<&sqlparser::tokenizer::Word> = tokens[downstream.clone()]
+                .iter()
+                .filter_map(|t| match &t.token {
+                    Token::Word(w) => Some(w),
+                    _ => None,
+                })
+                .collect();
+
+            // Only judge CTEs that are read downstream, and never through a `*`
+            if !words.iter().any(|w| w.value.eq_ignore_ascii_case(&cte.name)) || has_star_projection(tokens, downstream) {
+                continue;
+            }
+
+            let Some(select_idx) = (cte.name_idx..cte.close_idx).find(|&j| is_word(&tokens[j].token, "SELECT")) else { continue };
+            for item in select_list_items(tokens, select_idx) {
+                let Some(name) = select_item_name(tokens, &item) else { continue };
+                if words.iter().any(|w| w.value.eq_ignore_ascii_case(&name)) {
+                    continue;
+                }
+                let Some(name_idx) = item.clone().rev().find(|&k| is_significant(&tokens[k].token)) else { continue };
+                diagnostics.push(Diagnostic {
+                    range: span_to_range(&tokens[name_idx].span),
+                    severity: Some(severity),
+                    code: Some(NumberOrString::String("unused-cte-column".to_string())),
+                    source: Some("hql-ls".to_string()),
+                    message: format!("Column '{}' of CTE '{}' is never used", name, cte.name),
+                    tags: Some(vec![DiagnosticTag::UNNECESSARY]),
+                    ..Default::default()
+                });
+            }
+        }
+    }
+
+    diagnostics
+}
+
+fn check_cte_order_by(tokens: &[TokenWithSpan], severity: DiagnosticSeverity) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for statement in syntax::split_statements(tokens) {
+        for cte in syntax::collect_ctes(tokens, &statement) {
+            let Some(open_idx) = (cte.name_idx..cte.close_idx).find(|&j| matches!(tokens[j].token, Token::LParen)) else { continue };
+
+            // Only the CTE's own query level; window and subquery ORDER BYs sit deeper
+            let mut depth = 0;
+            let mut order_by = None;
+            let mut has_limit = false;
+            for j in open_idx + 1..cte.close_idx {
+                match &tokens[j].token {
+                    Token::LParen => depth += 1,
+                    Token::RParen => depth -= 1,
+                    token if depth == 0 && is_word(token, "ORDER") => {
+                        if let Some(by_idx) = next_significant(tokens, j)
+                            && is_word(&tokens[by_idx].token, "BY")
+                        {
+                            order_by = Some((j, by_idx));
+                        }
+                    }
+                    // ORDER BY ... LIMIT picks the top rows, so the ordering matters there
+                    token if depth == 0 && is_word(token, "LIMIT") => has_limit = true,
+                    _ => {}
+                }
+            }
+
+            if let Some((order_idx, by_idx)) = order_by
+                && !has_limit
+            {
+                diagnostics.push(Diagnostic {
+                    range: Range {
+                        start: span_to_range(&tokens[order_idx].span).start,
+                        end: span_to_range(&tokens[by_idx].span).end,
+                    },
+                    severity: Some(severity),
+                    code: Some(NumberOrString::String("cte-order-by".to_string())),
+                    source: Some("hql-ls".to_string()),
+                    message: format!("ORDER BY in CTE '{}' does not order the final result", cte.name),
+                    ..Default::default()
+                });
+            }
+        }
+    }
+
+    diagnostics
+}
+
+/// Points at the tokenizer error's location, or at the end of the document if it has none.
+fn tokenizer_error_range(text: &str, error: &TokenizerError) -> Range {
+    let location = error.location;
+    if location.line > 0 && location.column > 0 {
+        let start = Position { line: (location.line - 1) as u32, character: (location.column - 1) as u32 };
+        return Range { start, end: Position { character: start.character + 1, ..start } };
+    }
+
+    let last_line = syntax::lines(text).last().unwrap_or("");
+    let end = Position {
+        line: text.matches('\n').count() as u32,
+        character: last_line.chars().count() as u32,
+    };
+    Range { start: Position { character: end.character.saturating_sub(1), ..end }, end }
+}
+
+fn check_tuning_set(tokens: &[TokenWithSpan], keys: &[&str], severity: DiagnosticSeverity) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for (set_idx, key, key_range) in set_statements(tokens) {
+        if !keys.iter().any(|k| k.eq_ignore_ascii_case(&key)) {
+            continue;
+        }
+
+        let justified = tokens[..set_idx]
+            .iter()
+            .rev()
+            .find(|t| !matches!(t.token, Token::Whitespace(Whitespace::Space | Whitespace::Tab | Whitespace::Newline)))
+            .is_some_and(|t| matches!(t.token, Token::Whitespace(Whitespace::SingleLineComment { .. } | Whitespace::MultiLineComment(_))));
+        if justified {
+            continue;
+        }
+
+        diagnostics.push(Diagnostic {
+            range: Range { start: span_to_range(&tokens[set_idx].span).start, end: key_range.end },
+            severity: Some(severity),
+            code: Some(NumberOrString::String("tuning-set".to_string())),
+            source: Some("hql-ls".to_string()),
+            message: format!("Setting '{}' changes cluster resource usage; add a comment explaining why", key),
+            ..Default::default()
+        });
+    }
+
+    diagnostics
+}
+
+fn check_trailing_comma(tokens: &[TokenWithSpan], severity: DiagnosticSeverity) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for (i, token_with_span) in tokens.iter().enumerate() {
+        if !matches!(token_with_span.token, Token::Comma) {
+            continue;
+        }
+        let Some(next_idx) = next_significant(tokens, i) else { continue };
+        let next = match &tokens[next_idx].token {
+            Token::RParen => ")".to_string(),
+            Token::Word(w) if w.quote_style.is_none() && CLAUSE_KEYWORDS.iter().any(|kw| w.value.eq_ignore_ascii_case(kw)) => {
+                w.value.to_uppercase()
+            }
+            _ => continue,
+        };
+
+        diagnostics.push(Diagnostic {
+            range: span_to_range(&token_with_span.span),
+            severity: Some(severity),
+            code: Some(NumberOrString::String("trailing-comma".to_string())),
+            source: Some("hql-ls".to_string()),
+            message: format!("Trailing comma before '{}'", next),
+            ..Default::default()
+        });
+    }
+
+    diagnostics
+}
+
+fn check_wide_group_by(tokens: &[TokenWithSpan], max_keys: u64, severity: DiagnosticSeverity) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for (i, token_with_span) in tokens.iter().enumerate() {
+        if !is_word(&token_with_span.token, "GROUP") {
+            continue;
+        }
+        let Some(by_idx) = next_significant(tokens, i) else { continue };
+        if !is_word(&tokens[by_idx].token, "BY") {
+            continue;
+        }
+
+        // Keys are the comma-separated items at the GROUP BY's own depth
+        let mut depth = 0;
+        let mut keys = 0;
+        let mut in_key = false;
+        for t in &tokens[by_idx + 1..] {
+            match &t.token {
+                Token::LParen => depth += 1,
+                Token::RParen if depth == 0 => break,
+                Token::RParen => depth -= 1,
+                Token::SemiColon if depth == 0 => break,
+                Token::Comma if depth == 0 => {
+                    in_key = false;
+                    continue;
+                }
+                Token::Word(w) if depth == 0 && w.quote_style.is_none() && GROUP_BY_TERMINATORS.iter().any(|kw| w.value.eq_ignore_ascii_case(kw)) => break,
+                token if !is_significant(token) => continue,
+                _ => {}
+            }
+            if !in_key {
+                keys += 1;
+                in_key = true;
+            }
+        }
+
+        if keys as u64 > max_keys {
+            diagnostics.push(Diagnostic {
+                range: Range {
+                    start: span_to_range(&token_with_span.span).start,
+                    end: span_to_range(&tokens[by_idx].span).end,
+                },
+                severity: Some(severity),
+                code: Some(NumberOrString::String("wide-group-by".to_string())),
+                source: Some("hql-ls".to_string()),
+                message: format!(
+                    "GROUP BY has {} keys (limit {}); if it only deduplicates rows, SELECT DISTINCT is clearer",
+                    keys, max_keys
+                ),
+                ..Default::default()
+            });
+        }
+    }
+
+    diagnostics
+}
+
+fn check_unsafe_interpolation(tokens: &[TokenWithSpan], text: &str, severity: DiagnosticSeverity) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let lines: Vec<&str> = syntax::lines(text).collect();
+
+    for (i, token_with_span) in tokens.iter().enumerate() {
+        // Quoted variables tokenize as part of a string literal, bare ones as a word starting with `${`
+        let Token::Word(word) = &token_with_span.token else { continue };
+        if word.quote_style.is_some() || !word.value.starts_with("${") {
+            continue;
+        }
+        let start = token_with_span.span.start;
+        let Some(line) = lines.get((start.line - 1) as usize) else { continue };
+        let Some((byte_start, _)) = line.char_indices().nth((start.column - 1) as usize) else { continue };
+        let Some(variable) = hive_variable_regex().find_at(line, byte_start).filter(|m| m.start() == byte_start) else { continue };
+
+        if !in_value_position(tokens, i) {
+            continue;
+        }
+
+        let range = span_to_range(&token_with_span.span);
+        diagnostics.push(Diagnostic {
+            range: Range {
+                start: range.start,
+                end: Position { character: range.start.character + variable.as_str().chars().count() as u32, ..range.start },
+            },
+            severity: Some(severity),
+            code: Some(NumberOrString::String("unsafe-interpolation".to_string())),
+            source: Some("hql-ls".to_string()),
+            message: format!(
+                "Variable '{}' is substituted unquoted; wrap it in quotes so its value stays a string literal",
+                variable.as_str()
+            ),
+            ..Default::default()
+        });
+    }
+
+    diagnostics
+}
+
+fn check_cartesian_join(tokens: &[TokenWithSpan], severity: DiagnosticSeverity) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for (i, token_with_span) in tokens.iter().enumerate() {
+        if !is_word(&token_with_span.token, "FROM") {
+            continue;
+        }
+
+        // Scan the FROM clause at its own depth; subqueries are checked when their FROM comes up
+        let mut depth = 0;
+        let mut comma_idx = None;
+        let mut joined = false;
+        for (j, t) in tokens.iter().enumerate().skip(i + 1) {
+            match &t.token {
+                Token::LParen => depth += 1,
+                Token::RParen if depth == 0 => break,
+                Token::RParen => depth -= 1,
+                Token::SemiColon if depth == 0 => break,
+                Token::Comma if depth == 0 => {
+                    comma_idx = Some(j);
+                    joined = false;
+                }
+                // An ON or USING after a comma belongs to a later explicit JOIN, never to the
+                // comma-joined table, so only WHERE can supply that table's condition
+                token if depth == 0 && is_word(token, "WHERE") => {
+                    joined = true;
+                    break;
+                }
+                Token::Word(w) if depth == 0 && w.quote_style.is_none() && FROM_CLAUSE_TERMINATORS.iter().any(|kw| w.value.eq_ignore_ascii_case(kw)) => break,
+                _ => {}
+            }
+        }
+
+        if let Some(comma_idx) = comma_idx
+            && !joined
+        {
+            diagnostics.push(Diagnostic {
+                range: span_to_range(&tokens[comma_idx].span),
+                severity: Some(severity),
+                code: Some(NumberOrString::String("cartesian-join".to_string())),
+                source: Some("hql-ls".to_string()),
+                message: "Comma-separated tables with no join condition form a cross join".to_string(),
+                ..Default::default()
+            });
+        }
+    }
+
+    diagnostics
+}
+
+fn check_redundant_cast(tokens: &[TokenWithSpan], schema: &Schema, severity: DiagnosticSeverity) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for statement in syntax::split_statements(tokens) {
+        let aliases = syntax::table_aliases(tokens, statement.clone());
+        let significant: Vec<usize> = statement.filter(|&i| is_significant(&tokens[i].token)).collect();
+
+        for (pos, &cast_idx) in significant.iter().enumerate() {
+            if !is_word(&tokens[cast_idx].token, "CAST")
+                || !significant.get(pos + 1).is_some_and(|&i| matches!(tokens[i].token, Token::LParen))
+            {
+                continue;
+            }
+            let open_idx = significant[pos + 1];
+            let Some(close_idx) = syntax::matching_paren(tokens, open_idx) else { continue };
+
+            // Only a plain `col` or `alias.col` operand; anything else has a type we can't know
+            let operand: Vec<&Token> = significant[pos + 2..].iter().map(|&i| &tokens[i].token).take_while(|t| !is_word(t, "AS")).collect();
+            let (qualifier, column) = match operand.as_slice() {
+                [Token::Word(column)] => (None, column),
+                [Token::Word(qualifier), Token::Period, Token::Word(column)] => (Some(qualifier), column),
+                _ => continue,
+            };
+            let target: String = significant[pos + 4 + 2 * usize::from(qualifier.is_some())..]
+                .iter()
+                .take_while(|&&i| i < close_idx)
+                .map(|&i| tokens[i].token.to_string())
+                .collect();
+
+            let Some(declared) = column_type(schema, &aliases, qualifier.map(|q| q.value.as_str()), &column.value) else { continue };
+            if normalize_type(declared) != normalize_type(&target) {
+                continue;
+            }
+
+            let operand_text = match qualifier {
+                Some(q) => format!("{}.{}", q, column),
+                None => column.to_string(),
+            };
+            diagnostics.push(Diagnostic {
+                range: Range {
+                    start: span_to_range(&tokens[cast_idx].span).start,
+                    end: span_to_range(&tokens[close_idx].span).end,
+                },
+                severity: Some(severity),
+                code: Some(NumberOrString::String("redundant-cast".to_string())),
+                source: Some("hql-ls".to_string()),
+                message: format!("'{}' is already {}; the cast is redundant", operand_text, declared.to_uppercase()),
+                tags: Some(vec![DiagnosticTag::UNNECESSARY]),
+                data: Some(serde_json::Value::String(operand_text)),
+                ..Default::default()
+            });
+        }
+    }
+
+    diagnostics
+}
+
+fn check_invalid_regex(tokens: &[TokenWithSpan], severity: DiagnosticSeverity) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for (i, token_with_span) in tokens.iter().enumerate() {
+        if !is_word(&token_with_span.token, "RLIKE") && !is_word(&token_with_span.token, "REGEXP") {
+            continue;
+        }
+        // Only literal patterns can be checked
+        let Some(pattern_idx) = next_significant(tokens, i) else { continue };
+        let (Token::SingleQuotedString(raw) | Token::DoubleQuotedString(raw)) = &tokens[pattern_idx].token else { continue };
+
+        let Err(error) = Regex::new(&unescape_hive_string(raw)) else { continue };
+        // Java regex supports look-around and backreferences; the regex crate just doesn't
+        let message = error.to_string();
+        if message.contains("not supported") {
+            continue;
+        }
+        let detail = message.lines().last().unwrap_or_default().trim_start_matches("error: ");
+
+        diagnostics.push(Diagnostic {
+            range: span_to_range(&tokens[pattern_idx].span),
+            severity: Some(severity),
+            code: Some(NumberOrString::String("invalid-regex".to_string())),
+            source: Some("hql-ls".to_string()),
+            message: format!("Invalid regular expression: {}", detail),
+            ..Default::default()
+        });
+    }
+
+    diagnostics
+}
+
+fn check_duplicate_set_columns(tokens: &[TokenWithSpan], severity: DiagnosticSeverity) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for (i, token_with_span) in tokens.iter().enumerate() {
+        if !is_word(&token_with_span.token, "UPDATE") {
+            continue;
+        }
+        // Find the SET of this UPDATE, stopping at the end of the statement
+        let mut set_idx = None;
+        let mut depth = 0;
+        for (j, t) in tokens.iter().enumerate().skip(i + 1) {
+            match &t.token {
+                Token::LParen => depth += 1,
+                Token::RParen => depth -= 1,
+                Token::SemiColon => break,
+                token if depth == 0 && is_word(token, "SET") => {
+                    set_idx = Some(j);
+                    break;
+                }
+                _ => {}
+            }
+        }
+        let Some(set_idx) = set_idx else { continue };
+
+        // Each assignment's target is the last word before its `=`, so `t.a` counts as `a`
+        let mut seen: HashSet<String> = HashSet::new();
+        let mut target: Option<&TokenWithSpan> = None;
+        let mut in_value = false;
+        let mut depth = 0;
+        for t in &tokens[set_idx + 1..] {
+            match &t.token {
+                Token::LParen => depth += 1,
+                Token::RParen if depth == 0 => break,
+                Token::RParen => depth -= 1,
+                Token::SemiColon => break,
+                // WHEN ends a MERGE ... UPDATE SET branch
+                Token::Word(w) if depth == 0 && w.quote_style.is_none()
+                    && (w.value.eq_ignore_ascii_case("WHEN") || CLAUSE_KEYWORDS.iter().any(|kw| w.value.eq_ignore_ascii_case(kw))) => break,
+                Token::Comma if depth == 0 => {
+                    in_value = false;
+                    target = None;
+                }
+                Token::Eq if depth == 0 && !in_value => {
+                    in_value = true;
+                    let Some(target) = target else { continue };
+                    let Token::Word(w) = &target.token else { continue };
+                    let name = if w.quote_style.is_some() { w.value.clone() } else { w.value.to_lowercase() };
+                    if !seen.insert(name) {
+                        diagnostics.push(Diagnostic {
+                            range: span_to_range(&target.span),
+                            severity: Some(severity),
+                            code: Some(NumberOrString::String("duplicate-set-column".to_string())),
+                            source: Some("hql-ls".to_string()),
+                            message: format!("Column '{}' is assigned more than once; only one assignment takes effect", w.value),
+                            ..Default::default()
+                        });
+                    }
+                }
+                Token::Word(_) if depth == 0 && !in_value => target = Some(t),
+                _ => {}
+            }
+        }
+    }
+
+    diagnostics
+}
+
+fn check_cte_missing_as(tokens: &[TokenWithSpan], severity: DiagnosticSeverity) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for statement in syntax::split_statements(tokens) {
+        for cte in syntax::collect_ctes(tokens, &statement) {
+            if cte.as_idx.is_some() {
+                continue;
+            }
+            // `name (col, ...) AS (...)` is a column list, not a body missing its AS
+            if next_significant(tokens, cte.close_idx).is_some_and(|j| is_word(&tokens[j].token, "AS")) {
+                continue;
+            }
+            diagnostics.push(Diagnostic {
+                range: span_to_range(&tokens[cte.name_idx].span),
+                severity: Some(severity),
+                code: Some(NumberOrString::String("cte-missing-as".to_string())),
+                source: Some("hql-ls".to_string()),
+                message: format!("Missing AS between CTE '{}' and its query", cte.name),
+                ..Default::default()
+            });
+        }
+    }
+
+    diagnostics
+}
+
+fn check_non_join_on(tokens: &[TokenWithSpan], severity: DiagnosticSeverity) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for (i, token_with_span) in tokens.iter().enumerate() {
+        if !is_word(&token_with_span.token, "JOIN") {
+            continue;
+        }
+        let Some((joined, on_idx)) = joined_table_alias(tokens, i) else { continue };
+
+        // Scan the ON predicate, noting the qualifiers it uses
+        let mut qualifiers: Vec<String> = Vec::new();
+        let mut has_unqualified = false;
+        let mut depth = 0;
+        let mut end = on_idx;
+        for (j, t) in tokens.iter().enumerate().skip(on_idx + 1) {
+            match &t.token {
+                Token::LParen => depth += 1,
+                Token::RParen if depth == 0 => break,
+                Token::RParen => depth -= 1,
+                Token::SemiColon => break,
+                Token::Word(w) if depth == 0 && w.quote_style.is_none()
+                    && (JOIN_KEYWORDS.iter().any(|kw| w.value.eq_ignore_ascii_case(kw))
+                        || FROM_CLAUSE_TERMINATORS.iter().any(|kw| w.value.eq_ignore_ascii_case(kw))
+                        || w.value.eq_ignore_ascii_case("WHERE")) => break,
+                Token::Word(w) => {
+                    let next = next_significant(tokens, j).map(|k| &tokens[k].token);
+                    let previous = (0..j).rev().find(|&k| is_significant(&tokens[k].token)).map(|k| &tokens[k].token);
+                    if matches!(next, Some(Token::Period)) {
+                        // `db.t.col` is qualified by `t`, the word right before the column
+                        let after = next_significant(tokens, j).and_then(|k| next_significant(tokens, k));
+                        if after.and_then(|k| next_significant(tokens, k)).is_none_or(|k| !matches!(tokens[k].token, Token::Period)) {
+                            qualifiers.push(w.value.to_lowercase());
+                        }
+                    } else if !matches!(previous, Some(Token::Period))
+                        && !matches!(next, Some(Token::LParen))
+                        && (w.quote_style.is_some() || !PREDICATE_WORDS.iter().any(|kw| w.value.eq_ignore_ascii_case(kw)))
+                    {
+                        has_unqualified = true;
+                    }
+                }
+                _ => {}
+            }
+            if is_significant(&t.token) {
+                end = j;
+            }
+        }
+
+        // Unqualified columns could belong to either table
+        if has_unqualified || qualifiers.is_empty() {
+            continue;
+        }
+        let references_joined = qualifiers.contains(&joined);
+        let references_other = qualifiers.iter().any(|q| *q != joined);
+        if references_joined && references_other {
+            continue;
+        }
+
+        let only = if references_joined { joined } else { qualifiers[0].clone() };
+        diagnostics.push(Diagnostic {
+            range: Range {
+                start: span_to_range(&tokens[on_idx].span).start,
+                end: span_to_range(&tokens[end].span).end,
+            },
+            severity: Some(severity),
+            code: Some(NumberOrString::String("non-join-on".to_string())),
+            source: Some("hql-ls".to_string()),
+            message: format!("ON condition only references '{}'; did you mean to filter in WHERE?", only),
+            ..Default::default()
+        });
+    }
+
+    diagnostics
+}
+
+fn check_alias_style_consistency(tokens: &[TokenWithSpan], severity: DiagnosticSeverity) -> Vec<Diagnostic> {
+    // (alias token, written with AS) for each aliased FROM/JOIN table or subquery
+    let mut aliases: Vec<(usize, bool)> = Vec::new();
+
+    for (i, token_with_span) in tokens.iter().enumerate() {
+        if !is_word(&token_with_span.token, "FROM") && !is_word(&token_with_span.token, "JOIN") {
+            continue;
+        }
+
+        // Skip `db.table` or `( ... )` to what may be the alias
+        let Some(mut idx) = next_significant(tokens, i) else { continue };
+        if matches!(tokens[idx].token, Token::LParen) {
+            let Some(close_idx) = matching_paren(tokens, idx) else { continue };
+            idx = close_idx;
+        } else {
+            while let Some(period) = next_significant(tokens, idx).filter(|&j| matches!(tokens[j].token, Token::Period)) {
+                let Some(part) = next_significant(tokens, period) else { break };
+                idx = part;
+            }
+            if !matches!(tokens[idx].token, Token::Word(_)) {
+                continue;
+            }
+        }
+
+        let Some(mut alias_idx) = next_significant(tokens, idx) else { continue };
+        let explicit = is_word(&tokens[alias_idx].token, "AS");
+        if explicit {
+            let Some(next) = next_significant(tokens, alias_idx) else { continue };
+            alias_idx = next;
+        }
+        if let Token::Word(alias) = &tokens[alias_idx].token
+            && (alias.quote_style.is_some() || syntax::can_be_table_alias(&alias.value))
+        {
+            aliases.push((alias_idx, explicit));
+        }
+    }
+
+    let explicit_count = aliases.iter().filter(|(_, explicit)| *explicit).count();
+    let implicit_count = aliases.len() - explicit_count;
+    if explicit_count == 0 || implicit_count == 0 {
+        return Vec::new();
+    }
+
+    // Flag the minority style; on a tie, prefer the explicit AS
+    let flag_explicit = explicit_count < implicit_count;
+    aliases
+        .into_iter()
+        .filter(|&(_, explicit)| explicit == flag_explicit)
+        .map(|(alias_idx, _)| {
+            let message = if flag_explicit {
+                format!("Alias '{}' uses AS, but most table aliases in this file omit it", tokens[alias_idx].token)
+            } else {
+                format!("Alias '{}' omits AS, but most table aliases in this file use it", tokens[alias_idx].token)
+            };
+            Diagnostic {
+                range: span_to_range(&tokens[alias_idx].span),
+                severity: Some(severity),
+                code: Some(NumberOrString::String("alias-style-consistency".to_string())),
+                source: Some("hql-ls".to_string()),
+                message,
+                ..Default::default()
+            }
+        })
+        .collect()
+}
+
+fn check_syntax(tokens: &[TokenWithSpan], dialect: &dyn Dialect, severity: DiagnosticSeverity) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let location_regex = syntax_error_location_regex();
+
+    // Parse statement by statement so one error doesn't hide the rest of the file
+    for statement in syntax::split_statements(tokens) {
+        // The parser can't see through `${var}` substitution
+        if tokens[statement.clone()].iter().any(|t| matches!(&t.token, Token::Word(w) if w.value.starts_with("${"))) {
+            continue;
+        }
+        let mut parser = Parser::new(dialect).with_tokens_with_locations(tokens[statement.clone()].to_vec());
+        let message = match parser.parse_statements() {
+            Ok(_) => continue,
+            Err(ParserError::ParserError(message) | ParserError::TokenizerError(message)) => message,
+            Err(ParserError::RecursionLimitExceeded) => "Statement is nested too deeply to parse".to_string(),
+        };
+
+        // Errors end with ` at Line: l, Column: c`, the start of the offending token
+        let location = location_regex.captures(&message).and_then(|caps| {
+            let line: u64 = caps[1].parse().ok()?;
+            let column: u64 = caps[2].parse().ok()?;
+            Some(Location::new(line, column))
+        });
+        let range = location
+            .and_then(|location| tokens[statement.clone()].iter().find(|t| t.span.start == location))
+            .map(|t| span_to_range(&t.span))
+            .unwrap_or_else(|| Range {
+                start: span_to_range(&tokens[statement.start].span).start,
+                end: span_to_range(&tokens[statement.end - 1].span).end,
+            });
+
+        diagnostics.push(Diagnostic {
+            range,
+            severity: Some(severity),
+            code: Some(NumberOrString::String("syntax".to_string())),
+            source: Some("hql-ls".to_string()),
+            message: format!("Syntax error: {}", location_regex.replace(&message, "")),
+            ..Default::default()
+        });
+    }
+
+    diagnostics
+}
+
+fn check_null_into_not_null(tokens: &[TokenWithSpan], schema: &Schema, severity: DiagnosticSeverity) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for (i, token_with_span) in tokens.iter().enumerate() {
+        if !is_word(&token_with_span.token, "INSERT") {
+            continue;
+        }
+        let insert = read_insert(tokens, i);
+        let (Some(table_name), Some(columns), Some(values_idx)) = (insert.table, insert.columns, insert.values_idx) else { continue };
+        let Some(table) = schema.table(&table_name) else { continue };
+
+        // Each `( ... )` row after VALUES, up to the end of the statement
+        let mut j = values_idx;
+        while let Some(open_idx) = next_significant(tokens, j).filter(|&k| matches!(tokens[k].token, Token::LParen)) {
+            let Some(close_idx) = matching_paren(tokens, open_idx) else { break };
+            for (value, column) in call_arguments(tokens, open_idx).iter().zip(&columns) {
+                let mut significant = value.clone().filter(|&k| is_significant(&tokens[k].token));
+                let (Some(null_idx), None) = (significant.next(), significant.next()) else { continue };
+                if !is_word(&tokens[null_idx].token, "NULL") || !table.column(column).is_some_and(|c| c.not_null) {
+                    continue;
+                }
+                diagnostics.push(Diagnostic {
+                    range: span_to_range(&tokens[null_idx].span),
+                    severity: Some(severity),
+                    code: Some(NumberOrString::String("null-into-not-null".to_string())),
+                    source: Some("hql-ls".to_string()),
+                    message: format!("Column '{}' of '{}' is NOT NULL", column, table.name),
+                    ..Default::default()
+                });
+            }
+
+            match next_significant(tokens, close_idx) {
+                Some(comma) if matches!(tokens[comma].token, Token::Comma) => j = comma,
+                _ => break,
+            }
+        }
+    }
+
+    diagnostics
+}
+
+fn check_limit_without_order(tokens: &[TokenWithSpan], severity: DiagnosticSeverity) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for statement in syntax::split_statements(tokens) {
+        // Whether each open paren level, innermost last, has had an ORDER BY
+        let mut ordered = vec![false];
+        for i in statement {
+            match &tokens[i].token {
+                Token::LParen => ordered.push(false),
+                Token::RParen if ordered.len() > 1 => {
+                    ordered.pop();
+                }
+                token if is_word(token, "ORDER")
+                    && next_significant(tokens, i).is_some_and(|j| is_word(&tokens[j].token, "BY")) =>
+                {
+                    if let Some(level) = ordered.last_mut() {
+                        *level = true;
+                    }
+                }
+                token if is_word(token, "LIMIT") && ordered.last() == Some(&false) => {
+                    diagnostics.push(Diagnostic {
+                        range: span_to_range(&tokens[i].span),
+                        severity: Some(severity),
+                        code: Some(NumberOrString::String("limit-without-order".to_string())),
+                        source: Some("hql-ls".to_string()),
+                        message: "LIMIT without ORDER BY returns an arbitrary set of rows; add an ORDER BY".to_string(),
+                        ..Default::default()
+                    });
+                }
+                _ => {}
+            }
+        }
+    }
+
+    diagnostics
+}
+
+fn check_insert_select_arity(tokens: &[TokenWithSpan], severity: DiagnosticSeverity) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for (i, token_with_span) in tokens.iter().enumerate() {
+        if !is_word(&token_with_span.token, "INSERT") {
+            continue;
+        }
+        let insert = read_insert(tokens, i);
+        let (Some(columns), Some(select_idx)) = (insert.columns, insert.select_idx) else { continue };
+        let items = select_list_items(tokens, select_idx);
+
+        // `*` and `t.*` expand to an unknown number of columns
+        let is_star = |item: &std::ops::Range<usize>| {
+            item.clone().rev().find(|&k| is_significant(&tokens[k].token)).is_some_and(|k| matches!(tokens[k].token, Token::Mul))
+        };
+        if items.iter().any(is_star) || items.len() == columns.len() {
+            continue;
+        }
+
+        diagnostics.push(Diagnostic {
+            range: span_to_range(&tokens[select_idx].span),
+            severity: Some(severity),
+            code: Some(NumberOrString::String("insert-select-arity".to_string())),
+            source: Some("hql-ls".to_string()),
+            message: format!(
+                "INSERT lists {} target column(s) but SELECT returns {}",
+                columns.len(),
+                items.len()
+            ),
+            ..Default::default()
+        });
+    }
+
+    diagnostics
+}
+
+fn check_duplicate_select_columns(tokens: &[TokenWithSpan], severity: DiagnosticSeverity) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for (i, token_with_span) in tokens.iter().enumerate() {
+        if !is_word(&token_with_span.token, "SELECT") {
+            continue;
+        }
+
+        let mut seen: HashSet<String> = HashSet::new();
+        for item in select_list_items(tokens, i) {
+            let significant: Vec<usize> = item.filter(|&k| is_significant(&tokens[k].token)).collect();
+            let (Some(&first), Some(&last)) = (significant.first(), significant.last()) else { continue };
+            if matches!(tokens[last].token, Token::Mul) {
+                continue;
+            }
+            // Items are told apart by their alias, else by their expression
+            let key = match select_item_alias(tokens, &significant) {
+                Some(alias) => alias,
+                None => significant
+                    .iter()
+                    .map(|&k| match &tokens[k].token {
+                        Token::Word(w) if w.quote_style.is_none() => w.value.to_lowercase(),
+                        Token::Word(w) => w.value.clone(),
+                        token => token.to_string(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            };
+            if seen.insert(key) {
+                continue;
+            }
+
+            let range = Range {
+                start: span_to_range(&tokens[first].span).start,
+                end: span_to_range(&tokens[last].span).end,
+            };
+            let text: String = tokens[first..=last].iter().map(|t| t.token.to_string()).collect();
+            diagnostics.push(Diagnostic {
+                range,
+                severity: Some(severity),
+                code: Some(NumberOrString::String("duplicate-column".to_string())),
+                source: Some("hql-ls".to_string()),
+                message: format!("'{}' duplicates an earlier column in the SELECT list", text),
+                ..Default::default()
+            });
+        }
+    }
+
+    diagnostics
+}
+
+fn check_truncate_external(tokens: &[TokenWithSpan], schema: &Schema, severity: DiagnosticSeverity) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for (i, token_with_span) in tokens.iter().enumerate() {
+        if !is_word(&token_with_span.token, "TRUNCATE") {
+            continue;
+        }
+        let Some(mut idx) = next_significant(tokens, i) else { continue };
+        if is_word(&tokens[idx].token, "TABLE") {
+            let Some(next) = next_significant(tokens, idx) else { continue };
+            idx = next;
+        }
+
+        // `db.table`
+        let Token::Word(first) = &tokens[idx].token else { continue };
+        let mut name = first.value.clone();
+        let mut end = idx;
+        while let Some(period) = next_significant(tokens, end).filter(|&p| matches!(tokens[p].token, Token::Period)) {
+            let Some(part) = next_significant(tokens, period) else { break };
+            let Token::Word(part_word) = &tokens[part].token else { break };
+            name = format!("{}.{}", name, part_word.value);
+            end = part;
+        }
+
+        let Some(table) = schema.table(&name).filter(|t| t.external) else { continue };
+        diagnostics.push(Diagnostic {
+            range: Range {
+                start: span_to_range(&tokens[idx].span).start,
+                end: span_to_range(&tokens[end].span).end,
+            },
+            severity: Some(severity),
+            code: Some(NumberOrString::String("truncate-external".to_string())),
+            source: Some("hql-ls".to_string()),
+            message: format!("'{}' is an external table; Hive can't TRUNCATE it", table.name),
+            ..Default::default()
+        });
+    }
+
+    diagnostics
+}
+
+fn check_cluster_order_conflict(tokens: &[TokenWithSpan], severity: DiagnosticSeverity) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for statement in syntax::split_statements(tokens) {
+        // Per open paren level, innermost last: the first CLUSTER/DISTRIBUTE BY and ORDER BY
+        // seen, and whether the level was already reported
+        let mut levels: Vec<(Option<usize>, Option<usize>, bool)> = vec![(None, None, false)];
+        for i in statement {
+            let token = &tokens[i].token;
+            match token {
+                Token::LParen => levels.push((None, None, false)),
+                Token::RParen if levels.len() > 1 => {
+                    levels.pop();
+                }
+                _ if (is_word(token, "CLUSTER") || is_word(token, "DISTRIBUTE") || is_word(token, "ORDER"))
+                    && next_significant(tokens, i).is_some_and(|j| is_word(&tokens[j].token, "BY")) =>
+                {
+                    let Some((distribution, order, reported)) = levels.last_mut() else { continue };
+                    if is_word(token, "ORDER") {
+                        order.get_or_insert(i);
+                    } else {
+                        distribution.get_or_insert(i);
+                    }
+                    let (Some(distribution), Some(_)) = (*distribution, *order) else { continue };
+                    if *reported {
+                        continue;
+                    }
+                    *reported = true;
+
+                    diagnostics.push(Diagnostic {
+                        range: span_to_range(&tokens[i].span),
+                        severity: Some(severity),
+                        code: Some(NumberOrString::String("cluster-order-conflict".to_string())),
+                        source: Some("hql-ls".to_string()),
+                        message: format!(
+                            "{} BY conflicts with ORDER BY in the same query; ORDER BY already sorts all rows in a single reducer",
+                            tokens[distribution].token.to_string().to_uppercase(),
+                        ),
+                        ..Default::default()
+                    });
+                }
+                _ => {}
+            }
+        }
+    }
+
+    diagnostics
+}
+
+fn check_implicit_default_db(tokens: &[TokenWithSpan], severity: DiagnosticSeverity) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut database_selected = false;
+
+    for statement in syntax::split_statements(tokens) {
+        let significant: Vec<usize> = statement.filter(|&i| is_significant(&tokens[i].token)).collect();
+        let Some(&first) = significant.first() else { continue };
+        if is_word(&tokens[first].token, "USE") {
+            database_selected = true;
+            continue;
+        }
+        if database_selected
+            || !["INSERT", "CREATE", "DROP"].iter().any(|kw| is_word(&tokens[first].token, kw))
+        {
+            continue;
+        }
+
+        // The target follows the statement's leading keywords; it must be a table or view
+        let mut names_table = false;
+        let mut target = None;
+        for &i in &significant[1..] {
+            match &tokens[i].token {
+                Token::Word(w) if w.quote_style.is_none() && matches!(w.value.to_uppercase().as_str(), "TABLE" | "VIEW" | "INTO") => {
+                    names_table = true;
+                }
+                // Temporary tables live in the session, not in a database
+                Token::Word(w) if w.quote_style.is_none() && w.value.eq_ignore_ascii_case("TEMPORARY") => break,
+                Token::Word(w) if w.quote_style.is_none()
+                    && matches!(w.value.to_uppercase().as_str(), "OVERWRITE" | "EXTERNAL" | "MATERIALIZED" | "TRANSACTIONAL" | "IF" | "NOT" | "EXISTS") => {}
+                Token::Word(_) => {
+                    target = Some(i);
+                    break;
+                }
+                _ => break,
+            }
+        }
+        let (true, Some(target)) = (names_table, target) else { continue };
+        if next_significant(tokens, target).is_some_and(|j| matches!(tokens[j].token, Token::Period)) {
+            continue;
+        }
+
+        diagnostics.push(Diagnostic {
+            range: span_to_range(&tokens[target].span),
+            severity: Some(severity),
+            code: Some(NumberOrString::String("implicit-default-db".to_string())),
+            source: Some("hql-ls".to_string()),
+            message: format!("'{}' isn't qualified with a database and no USE precedes it, so it resolves to 'default'", tokens[target].token),
+            ..Default::default()
+        });
+    }
+
+    diagnostics
+}
+
+fn check_constant_select_items(tokens: &[TokenWithSpan], severity: DiagnosticSeverity) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for (i, token_with_span) in tokens.iter().enumerate() {
+        if !is_word(&token_with_span.token, "SELECT") {
+            continue;
+        }
+
+        let items = select_list_items(tokens, i);
+        let item_count = items.len();
+        let constants: Vec<(usize, usize)> = items
+            .into_iter()
+            .filter_map(|item| {
+                let mut significant: Vec<usize> = item.filter(|&k| is_significant(&tokens[k].token)).collect();
+                let (&first, &last) = (significant.first()?, significant.last()?);
+                // Only the expression matters, not its alias
+                if select_item_alias(tokens, &significant).is_some() {
+                    significant.pop();
+                    if significant.last().is_some_and(|&k| is_word(&tokens[k].token, "AS")) {
+                        significant.pop();
+                    }
+                }
+                let is_constant = !significant.is_empty() && significant.iter().all(|&k| match &tokens[k].token {
+                    Token::Word(w) => w.quote_style.is_none() && keywords::LITERAL_KEYWORDS.iter().any(|kw| w.value.eq_ignore_ascii_case(kw)),
+                    Token::Mul => false,
+                    _ => true,
+                });
+                Some((first, last)).filter(|_| is_constant)
+            })
+            .collect();
+        // `SELECT 1` and the like have nothing to repeat per row
+        if constants.len() == item_count {
+            continue;
+        }
+
+        for (first, last) in constants {
+            diagnostics.push(Diagnostic {
+                range: Range {
+                    start: span_to_range(&tokens[first].span).start,
+                    end: span_to_range(&tokens[last].span).end,
+                },
+                severity: Some(severity),
+                code: Some(NumberOrString::String("constant-select-item".to_string())),
+                source: Some("hql-ls".to_string()),
+                message: "Constant select item is the same for every row".to_string(),
+                ..Default::default()
+            });
+        }
+    }
+
+    diagnostics
+}
+
+/// Flags tab characters outside string literals and comments. Each diagnostic carries the
+/// spaces to replace the tab with in `data`.
+fn check_no_tabs(tokens: &[TokenWithSpan], tab_width: usize, severity: DiagnosticSeverity) -> Vec<Diagnostic> {
+    tokens
+        .iter()
+        .filter(|t| matches!(t.token, Token::Whitespace(Whitespace::Tab)))
+        .map(|t| Diagnostic {
+            range: syntax::span_to_range(&t.span),
+            severity: Some(severity),
+            code: Some(NumberOrString::String("no-tabs".to_string())),
+            source: Some("hql-ls".to_string()),
+            message: "Tab character; use spaces instead".to_string(),
+            data: Some(" ".repeat(tab_width).into()),
+            ..Default::default()
+        })
+        .collect()
+}
+
+/// Flags a `/*` with no closing `*/`. The tokenizer only reports hitting the end of the file,
+/// so the opening is found by scanning the text, skipping quoted strings and line comments.
+/// Backslashes escape quotes only where the dialect's tokenizer reads them that way.
+fn check_unterminated_comment(text: &str, backslash_escape: bool, severity: DiagnosticSeverity) -> Option<Diagnostic> {
+    let mut chars = text.chars().peekable();
+    let (mut line, mut column) = (0u32, 0u32);
+    let mut quote: Option<char> = None;
+    let mut open: Option<Position> = None;
+    let mut line_comment = false;
+
+    while let Some(c) = chars.next() {
+        let position = Position { line, character: column };
+        if c == '\n' {
+            line += 1;
+            column = 0;
+            line_comment = false;
+            continue;
+        }
+        column += 1;
+
+        let next = chars.peek().copied();
+        let mut consume_next = || {
+            chars.next();
+            column += 1;
+        };
+        if open.is_some() {
+            if c == '*' && next == Some('/') {
+                consume_next();
+                open = None;
+            }
+        } else if let Some(q) = quote {
+            if backslash_escape && c == '\\' && next.is_some_and(|n| n != '\n') {
+                consume_next();
+            } else if c == q {
+                quote = None;
+            }
+        } else if line_comment {
+            continue;
+        } else if matches!(c, '\'' | '"' | '`') {
+            quote = Some(c);
+        } else if c == '-' && next == Some('-') {
+            line_comment = true;
+        } else if c == '/' && next == Some('*') {
+            consume_next();
+            open = Some(position);
+        }
+    }
+
+    let start = open?;
+    Some(Diagnostic {
+        range: Range { start, end: Position { character: start.character + 2, ..start } },
+        severity: Some(severity),
+        code: Some(NumberOrString::String("unterminated-comment".to_string())),
+        source: Some("hql-ls".to_string()),
+        message: "Unterminated block comment; it runs to the end of the file".to_string(),
+        ..Default::default()
+    })
+}
+
+/// Flags table references with more than `max_parts` dotted parts. Column references can
+/// continue into struct fields (`t.address.city`), so only table positions are checked.
+fn check_too_many_name_parts(tokens: &[TokenWithSpan], max_parts: usize, severity: DiagnosticSeverity) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for (i, token_with_span) in tokens.iter().enumerate() {
+        if !["FROM", "JOIN", "INTO", "TABLE", "UPDATE"].iter().any(|kw| is_word(&token_with_span.token, kw)) {
+            continue;
+        }
+        let Some(first) = next_significant(tokens, i) else { continue };
+        if !matches!(tokens[first].token, Token::Word(_)) {
+            continue;
+        }
+
+        // `a.b.c`: words joined by periods with nothing in between
+        let mut last = first;
+        let mut parts = 1;
+        while matches!(tokens.get(last + 1).map(|t| &t.token), Some(Token::Period))
+            && matches!(tokens.get(last + 2).map(|t| &t.token), Some(Token::Word(_)))
+        {
+            last += 2;
+            parts += 1;
+        }
+        if parts <= max_parts {
+            continue;
+        }
+
+        diagnostics.push(Diagnostic {
+            range: Range {
+                start: span_to_range(&tokens[first].span).start,
+                end: span_to_range(&tokens[last].span).end,
+            },
+            severity: Some(severity),
+            code: Some(NumberOrString::String("too-many-name-parts".to_string())),
+            source: Some("hql-ls".to_string()),
+            message: format!("Table name has {} parts; at most {} are allowed", parts, max_parts),
+            ..Default::default()
+        });
+    }
+
+    diagnostics
+}
+
+/// Flags every comma between tables in a FROM clause, whether or not WHERE joins them.
+fn check_comma_join(tokens: &[TokenWithSpan], severity: DiagnosticSeverity) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for (i, token_with_span) in tokens.iter().enumerate() {
+        if !is_word(&token_with_span.token, "FROM") {
+            continue;
+        }
+
+        // Same clause scan as the cartesian-join rule; subqueries are checked at their own FROM
+        let mut depth = 0;
+        for t in &tokens[i + 1..] {
+            match &t.token {
+                Token::LParen => depth += 1,
+                Token::RParen if depth == 0 => break,
+                Token::RParen => depth -= 1,
+                Token::SemiColon if depth == 0 => break,
+                Token::Comma if depth == 0 => diagnostics.push(Diagnostic {
+                    range: span_to_range(&t.span),
+                    severity: Some(severity),
+                    code: Some(NumberOrString::String("comma-join".to_string())),
+                    source: Some("hql-ls".to_string()),
+                    message: "Implicit comma join; use explicit JOIN ... ON syntax".to_string(),
+                    ..Default::default()
+                }),
+                token if depth == 0 && is_word(token, "WHERE") => break,
+                Token::Word(w) if depth == 0 && w.quote_style.is_none() && FROM_CLAUSE_TERMINATORS.iter().any(|kw| w.value.eq_ignore_ascii_case(kw)) => break,
+                _ => {}
+            }
+        }
+    }
+
+    diagnostics
+}
+
+/// `==` (or `= =`) where HQL expects a single `=`. `===` is left to the foreign-operator rule.
+fn check_equality_operator(tokens: &[TokenWithSpan], severity: DiagnosticSeverity) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut i = 0;
+
+    while i < tokens.len() {
+        let mut span = tokens[i].span;
+        let found = match &tokens[i].token {
+            Token::DoubleEq => !tokens
+                .get(i + 1)
+                .is_some_and(|next| matches!(next.token, Token::Eq) && next.span.start == span.end),
+            Token::Eq => match next_significant(tokens, i) {
+                Some(j) if matches!(tokens[j].token, Token::Eq) => {
+                    span.end = tokens[j].span.end;
+                    i = j;
+                    true
+                }
+                _ => false,
+            },
+            _ => false,
+        };
+
+        if found {
+            diagnostics.push(Diagnostic {
+                range: span_to_range(&span),
+                severity: Some(severity),
+                code: Some(NumberOrString::String("equality-operator".to_string())),
+                source: Some("hql-ls".to_string()),
+                message: "Use a single '=' for equality".to_string(),
+                ..Default::default()
+            });
+        }
+
+        i += 1;
+    }
+
+    diagnostics
+}
+
+/// A configured `severity` applies to both cases; otherwise reversed numeric bounds (an empty
+/// range, or under `NOT` one that excludes nothing) are warnings and equal bounds, which read
+/// better as `=` or `<>`, a hint.
+fn check_between_bounds(tokens: &[TokenWithSpan], severity: Option<DiagnosticSeverity>) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for (i, token_with_span) in tokens.iter().enumerate() {
+        if !is_word(&token_with_span.token, "BETWEEN") {
+            continue;
+        }
+
+        // Only `BETWEEN <number> AND <number>`; bounds that are columns or expressions are skipped
+        let Some((lower, _, lower_idx)) = read_signed_number(tokens, i) else { continue };
+        let Some(and_idx) = next_significant(tokens, lower_idx) else { continue };
+        if !is_word(&tokens[and_idx].token, "AND") {
+            continue;
+        }
+        let Some((upper, _, upper_idx)) = read_signed_number(tokens, and_idx) else { continue };
+        if next_significant(tokens, upper_idx).is_some_and(|j| {
+            matches!(tokens[j].token, Token::Plus | Token::Minus | Token::Mul | Token::Div | Token::Mod)
+        }) {
+            continue;
+        }
+
+        // `NOT BETWEEN` inverts the range: reversed bounds exclude nothing
+        let negated = (0..i).rev().find(|&j| is_significant(&tokens[j].token)).is_some_and(|j| is_word(&tokens[j].token, "NOT"));
+        let (severity, message) = if lower > upper {
+            let effect = if negated { "NOT BETWEEN matches every non-NULL value" } else { "the range matches nothing" };
+            (
+                severity.unwrap_or(DiagnosticSeverity::WARNING),
+                format!("BETWEEN bounds are reversed ({} > {}); {}", lower, upper, effect),
+            )
+        } else if lower == upper {
+            let equivalent = if negated { "<>" } else { "=" };
+            (
+                severity.unwrap_or(DiagnosticSeverity::HINT),
+                format!("BETWEEN with equal bounds is the same as '{} {}'", equivalent, lower),
+            )
+        } else {
+            continue;
+        };
+
+        diagnostics.push(Diagnostic {
+            range: span_to_range(&token_with_span.span),
+            severity: Some(severity),
+            code: Some(NumberOrString::String("between-bounds".to_string())),
+            source: Some("hql-ls".to_string()),
+            message,
+            ..Default::default()
+        });
+    }
+
+    diagnostics
+}
+
+/// Plain `UNION`, which deduplicates the combined rows; `UNION ALL` is cheaper when duplicates
+/// can't occur or don't matter. An explicit `UNION DISTINCT` is left alone.
+fn check_union_dedup(tokens: &[TokenWithSpan], severity: DiagnosticSeverity) -> Vec<Diagnostic> {
+    tokens
+        .iter()
+        .enumerate()
+        .filter(|(i, t)| {
+            is_word(&t.token, "UNION")
+                && !next_significant(tokens, *i).is_some_and(|j| is_word(&tokens[j].token, "ALL") || is_word(&tokens[j].token, "DISTINCT"))
+        })
+        .map(|(_, t)| Diagnostic {
+            range: span_to_range(&t.span),
+            severity: Some(severity),
+            code: Some(NumberOrString::String("union-dedup".to_string())),
+            source: Some("hql-ls".to_string()),
+            message: "UNION removes duplicate rows, which needs an extra reduce step; use UNION ALL if deduplication isn't required".to_string(),
+            ..Default::default()
+        })
+        .collect()
+}
+
+/// `SELECT DISTINCT ... GROUP BY` in one query block: the grouping already makes rows unique
+/// unless the select list drops some keys, so the DISTINCT is usually redundant.
+fn check_distinct_with_group_by(tokens: &[TokenWithSpan], severity: DiagnosticSeverity) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for (i, token_with_span) in tokens.iter().enumerate() {
+        if !is_word(&token_with_span.token, "SELECT") {
+            continue;
+        }
+        let Some(distinct_idx) = next_significant(tokens, i) else { continue };
+        if !is_word(&tokens[distinct_idx].token, "DISTINCT") {
+            continue;
+        }
+        // has_group_by stops at this query's closing paren, so a subquery's GROUP BY doesn't count
+        let Some(list_end) = select_list_items(tokens, i).last().map(|item| item.end) else { continue };
+        if !has_group_by(tokens, list_end) {
+            continue;
+        }
+
+        diagnostics.push(Diagnostic {
+            range: span_to_range(&tokens[distinct_idx].span),
+            severity: Some(severity),
+            code: Some(NumberOrString::String("distinct-with-group-by".to_string())),
+            source: Some("hql-ls".to_string()),
+            message: "DISTINCT is likely redundant with GROUP BY in the same query".to_string(),
+            ..Default::default()
+        });
+    }
+
+    diagnostics
+}
+
+fn check_ambiguous_columns(tokens: &[TokenWithSpan], schema: &Schema, severity: DiagnosticSeverity) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for statement in syntax::split_statements(tokens) {
+        let aliases = syntax::table_aliases(tokens, statement.clone());
+        let mut tables: Vec<&str> = aliases.values().map(String::as_str).filter(|name| schema.table(name).is_some()).collect();
+        tables.sort_unstable();
+        tables.dedup();
+        if tables.len() < 2 {
+            continue;
+        }
+
+        // Start at the query; an INSERT column list names the target's columns
+        let significant: Vec<usize> = statement.filter(|&i| is_significant(&tokens[i].token)).collect();
+        let Some(query_start) = significant.iter().position(|&i| is_word(&tokens[i].token, "SELECT")) else { continue };
+
+        for pos in query_start..significant.len() {
+            let idx = significant[pos];
+            let Token::Word(word) = &tokens[idx].token else { continue };
+            let previous = pos.checked_sub(1).map(|p| &tokens[significant[p]].token);
+            let next = significant.get(pos + 1).map(|&i| &tokens[i].token);
+            // Skip qualified columns, function calls, output aliases and table names or aliases
+            if word.quote_style == Some('"')
+                || matches!(previous, Some(Token::Period))
+                || matches!(next, Some(Token::Period | Token::LParen))
+                || previous.is_some_and(|t| is_word(t, "AS"))
+                || aliases.contains_key(&word.value.to_lowercase())
+                || keywords::is_keyword_usage(tokens, idx)
+            {
+                continue;
+            }
+
+            let owners: Vec<&str> = tables
+                .iter()
+                .copied()
+                .filter(|name| schema.table(name).is_some_and(|t| t.column(&word.value).is_some()))
+                .collect();
+            if owners.len() < 2 {
+                continue;
+            }
+
+            diagnostics.push(Diagnostic {
+                range: span_to_range(&tokens[idx].span),
+                severity: Some(severity),
+                code: Some(NumberOrString::String("ambiguous-column".to_string())),
+                source: Some("hql-ls".to_string()),
+                message: format!(
+                    "Column '{}' exists in {}; qualify it with a table name or alias",
+                    word.value,
+                    owners.join(" and ")
+                ),
+                ..Default::default()
+            });
+        }
+    }
+
+    diagnostics
+}
+
+fn check_positional_references(tokens: &[TokenWithSpan], severity: DiagnosticSeverity) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for (i, token_with_span) in tokens.iter().enumerate() {
+        let clause = if is_word(&token_with_span.token, "ORDER") {
+            "ORDER BY"
+        } else if is_word(&token_with_span.token, "GROUP") {
+            "GROUP BY"
+        } else {
+            continue;
+        };
+        let Some(by_idx) = next_significant(tokens, i) else { continue };
+        if !is_word(&tokens[by_idx].token, "BY") {
+            continue;
+        }
+
+        // `1`, `2 DESC` or `3 NULLS LAST`, but not an expression such as `a + 1`
+        for item in by_clause_items(tokens, by_idx) {
+            let Some((&first, rest)) = item.split_first() else { continue };
+            let Token::Number(position, _) = &tokens[first].token else { continue };
+            if !rest.iter().all(|&j| ["ASC", "DESC", "NULLS", "FIRST", "LAST"].iter().any(|kw| is_word(&tokens[j].token, kw))) {
+                continue;
+            }
+            diagnostics.push(Diagnostic {
+                range: span_to_range(&tokens[first].span),
+                severity: Some(severity),
+                code: Some(NumberOrString::String("positional-reference".to_string())),
+                source: Some("hql-ls".to_string()),
+                message: format!("{} {} refers to a column by position; name the column instead", clause, position),
+                ..Default::default()
+            });
+        }
+    }
+
+    diagnostics
+}
+
+fn check_null_comparison(tokens: &[TokenWithSpan], severity: DiagnosticSeverity) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for statement in syntax::split_statements(tokens) {
+        let significant: Vec<usize> = statement.filter(|&i| is_significant(&tokens[i].token)).collect();
+        // `UPDATE t SET a = NULL` assigns rather than compares
+        let mut in_set = false;
+
+        for (pos, &idx) in significant.iter().enumerate() {
+            let token = &tokens[idx].token;
+            if is_word(token, "SET") {
+                in_set = true;
+            } else if is_word(token, "WHERE") {
+                in_set = false;
+            }
+            let replacement = match token {
+                Token::Eq | Token::DoubleEq if !in_set => "IS NULL",
+                Token::Neq => "IS NOT NULL",
+                _ => continue,
+            };
+            let is_null = |p: Option<usize>| p.and_then(|p| significant.get(p)).is_some_and(|&i| is_word(&tokens[i].token, "NULL"));
+            if !is_null(pos.checked_sub(1)) && !is_null(Some(pos + 1)) {
+                continue;
+            }
+
+            diagnostics.push(Diagnostic {
+                range: span_to_range(&tokens[idx].span),
+                severity: Some(severity),
+                code: Some(NumberOrString::String("null-comparison".to_string())),
+                source: Some("hql-ls".to_string()),
+                message: format!("Comparing with NULL using '{}' is never true; use {}", token, replacement),
+                ..Default::default()
+            });
+        }
+    }
+
+    diagnostics
+}
+
+/// A `;` inside parentheses, e.g. `IN (SELECT x FROM t;)`, is usually pasted in with a subquery.
+fn check_semicolon_in_parens(tokens: &[TokenWithSpan], severity: DiagnosticSeverity) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut paren_balance = 0;
+
+    for token_with_span in tokens {
+        match token_with_span.token {
+            Token::LParen => paren_balance += 1,
+            Token::RParen if paren_balance > 0 => paren_balance -= 1,
+            Token::SemiColon if paren_balance > 0 => {
+                diagnostics.push(Diagnostic {
+                    range: span_to_range(&token_with_span.span),
+                    severity: Some(severity),
+                    code: Some(NumberOrString::String("semicolon-in-parentheses".to_string())),
+                    source: Some("hql-ls".to_string()),
+                    message: "Semicolon inside parentheses ends the statement early; remove it".to_string(),
+                    ..Default::default()
+                });
+                // The statement ends here all the same, so its open parens don't carry into the next
+                paren_balance = 0;
+            }
+            _ => {}
+        }
+    }
+
+    diagnostics
+}
+
+/// Aliases after AS that are reserved words or aggregate names, e.g. `SUM(x) AS count`.
+/// Contextual keywords such as `location` or `comment` are ordinary names there.
+fn check_keyword_aliases(tokens: &[TokenWithSpan], severity: DiagnosticSeverity) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for alias in as_aliases(tokens) {
+        let Token::Word(w) = &tokens[alias].token else { continue };
+        let clashes = is_reserved_word(&w.value) || AGGREGATE_FUNCTIONS.contains(&w.value.to_uppercase().as_str());
+        if w.quote_style.is_some() || !clashes {
+            continue;
+        }
+        diagnostics.push(Diagnostic {
+            range: span_to_range(&tokens[alias].span),
+            severity: Some(severity),
+            code: Some(NumberOrString::String("keyword-alias".to_string())),
+            source: Some("hql-ls".to_string()),
+            message: format!("Alias '{}' is a Hive keyword; quote it as `{}`", w.value, w.value),
+            ..Default::default()
+        });
+    }
+
+    diagnostics
+}
+
+/// Plain columns selected in a grouped query that the GROUP BY doesn't mention. Without a
+/// schema this is a heuristic: a column counts as grouped if its name appears anywhere in
+/// the GROUP BY, its alias does, or its position is listed, and GROUPING SETS are skipped.
+fn check_group_by_consistency(tokens: &[TokenWithSpan], severity: DiagnosticSeverity) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for (i, token_with_span) in tokens.iter().enumerate() {
+        if !is_word(&token_with_span.token, "SELECT") {
+            continue;
+        }
+        let items = select_list_items(tokens, i);
+        let Some(list_end) = items.last().map(|item| item.end) else { continue };
+        let Some(by_idx) = group_by_index(tokens, list_end)
+            .and_then(|group| next_significant(tokens, group))
+            .filter(|&by| is_word(&tokens[by].token, "BY"))
+        else {
+            continue;
+        };
+        let keys = by_clause_items(tokens, by_idx);
+        let clause_end = keys.iter().flatten().max().copied().unwrap_or(by_idx);
+        if next_significant(tokens, clause_end).is_some_and(|k| is_word(&tokens[k].token, "GROUPING")) {
+            continue;
+        }
+
+        let mut names = HashSet::new();
+        let mut positions = HashSet::new();
+        for key in &keys {
+            if let [only] = key.as_slice()
+                && let Token::Number(position, _) = &tokens[*only].token
+            {
+                positions.insert(position.clone());
+            }
+            for &k in key {
+                if let Token::Word(w) = &tokens[k].token {
+                    names.insert(w.value.to_lowercase());
+                }
+            }
+        }
+
+        for (n, item) in items.iter().enumerate() {
+            // Nested SELECTs are checked on their own iteration
+            if tokens[item.clone()].iter().any(|t| is_word(&t.token, "SELECT")) || is_aggregate_item(tokens, item) {
+                continue;
+            }
+            let Some(column) = bare_column(tokens, item) else { continue };
+            if positions.contains(&(n + 1).to_string())
+                || names.contains(&column.to_lowercase())
+                || select_item_name(tokens, item).is_some_and(|name| names.contains(&name.to_lowercase()))
+            {
+                continue;
+            }
+            let first = item.clone().find(|&k| is_significant(&tokens[k].token)).unwrap_or(item.start);
+            let last = item.clone().rev().find(|&k| is_significant(&tokens[k].token)).unwrap_or(first);
+            diagnostics.push(Diagnostic {
+                range: Range {
+                    start: span_to_range(&tokens[first].span).start,
+                    end: span_to_range(&tokens[last].span).end,
+                },
+                severity: Some(severity),
+                code: Some(NumberOrString::String("group-by-consistency".to_string())),
+                source: Some("hql-ls".to_string()),
+                message: format!("Column '{}' is selected but not in the GROUP BY; add it to the GROUP BY or aggregate it", column),
+                ..Default::default()
+            });
+        }
+    }
+
+    diagnostics
+}
+
+/// Analytic function calls, e.g. `ROW_NUMBER()`, with no `OVER (...)` after them.
+fn check_missing_over(tokens: &[TokenWithSpan], severity: DiagnosticSeverity) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for (i, token_with_span) in tokens.iter().enumerate() {
+        let Token::Word(w) = &token_with_span.token else { continue };
+        if w.quote_style.is_some() || !keywords::ANALYTIC_FUNCTIONS.contains(&w.value.to_uppercase().as_str()) {
+            continue;
+        }
+        // A qualified name is some other function
+        if (0..i).rev().find(|&j| is_significant(&tokens[j].token)).is_some_and(|p| matches!(tokens[p].token, Token::Period)) {
+            continue;
+        }
+        let Some(open) = next_significant(tokens, i).filter(|&j| matches!(tokens[j].token, Token::LParen)) else { continue };
+        let Some(close) = matching_paren(tokens, open) else { continue };
+        if next_significant(tokens, close).is_some_and(|j| is_word(&tokens[j].token, "OVER")) {
+            continue;
+        }
+        diagnostics.push(Diagnostic {
+            range: span_to_range(&token_with_span.span),
+            severity: Some(severity),
+            code: Some(NumberOrString::String("missing-over".to_string())),
+            source: Some("hql-ls".to_string()),
+            message: format!("{} is an analytic function and needs an OVER (...) clause", w.value.to_uppercase()),
+            ..Default::default()
+        });
+    }
+
+    diagnostics
+}
+
+/// `CREATE TABLE` statements that leave the storage format to the cluster default. CTAS and
+/// `CREATE TABLE ... LIKE` are skipped; `STORED BY` handlers and `USING` count as a format.
+fn check_missing_stored_as(tokens: &[TokenWithSpan], severity: DiagnosticSeverity) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for statement in syntax::split_statements(tokens) {
+        let create = statement.start;
+        if !is_word(&tokens[create].token, "CREATE") {
+            continue;
+        }
+        let mut table = create;
+        while let Some(next) = next_significant(tokens, table).filter(|&j| j < statement.end) {
+            table = next;
+            if !["TEMPORARY", "EXTERNAL", "TRANSACTIONAL"].iter().any(|kw| is_word(&tokens[next].token, kw)) {
+                break;
+            }
+        }
+        if !is_word(&tokens[table].token, "TABLE") {
+            continue;
+        }
+
+        // Column definitions sit inside parentheses, the clauses at depth 0
+        let mut depth = 0;
+        let mut has_format = false;
+        for t in &tokens[table + 1..statement.end] {
+            match &t.token {
+                Token::LParen => depth += 1,
+                Token::RParen => depth -= 1,
+                token if depth == 0 && ["STORED", "USING", "AS", "LIKE"].iter().any(|kw| is_word(token, kw)) => {
+                    has_format = true;
+                    break;
+                }
+                _ => {}
+            }
+        }
+        if has_format {
+            continue;
+        }
+
+        diagnostics.push(Diagnostic {
+            range: Range {
+                start: span_to_range(&tokens[create].span).start,
+                end: span_to_range(&tokens[table].span).end,
+            },
+            severity: Some(severity),
+            code: Some(NumberOrString::String("missing-stored-as".to_string())),
+            source: Some("hql-ls".to_string()),
+            message: "CREATE TABLE without STORED AS uses the cluster's default format; name one, e.g. STORED AS ORC"
+                .to_string(),
+            ..Default::default()
+        });
+    }
+
+    diagnostics
+}
+
+/// Table and subquery aliases defined twice in the same query block, e.g. `JOIN b t ... JOIN c t`.
+/// Reusing an alias in a subquery or another UNION branch is a separate scope and fine.
+fn check_duplicate_aliases(tokens: &[TokenWithSpan], severity: DiagnosticSeverity) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for statement in syntax::split_statements(tokens) {
+        let definitions: HashSet<usize> = rename::alias_definitions(tokens, &statement).into_iter().collect();
+        // Per open paren, innermost last: the paren and how many set operators have passed in it
+        let mut scopes: Vec<(Option<usize>, usize)> = vec![(None, 0)];
+        let mut first_definitions: HashMap<(Option<usize>, usize, String), usize> = HashMap::new();
+
+        for i in statement {
+            let token = &tokens[i].token;
+            match token {
+                Token::LParen => scopes.push((Some(i), 0)),
+                Token::RParen if scopes.len() > 1 => {
+                    scopes.pop();
+                }
+                _ if ["UNION", "INTERSECT", "EXCEPT", "MINUS"].iter().any(|kw| is_word(token, kw)) => {
+                    if let Some(scope) = scopes.last_mut() {
+                        scope.1 += 1;
+                    }
+                }
+                _ => {}
+            }
+
+            let Token::Word(alias) = token else { continue };
+            if !definitions.contains(&i) {
+                continue;
+            }
+            let Some(&(paren, branch)) = scopes.last() else { continue };
+            let key = (paren, branch, alias.value.to_lowercase());
+            let Some(&first) = first_definitions.get(&key) else {
+                first_definitions.insert(key, i);
+                continue;
+            };
+            diagnostics.push(Diagnostic {
+                range: span_to_range(&tokens[i].span),
+                severity: Some(severity),
+                code: Some(NumberOrString::String("duplicate-alias".to_string())),
+                source: Some("hql-ls".to_string()),
+                message: format!("Alias '{}' is already defined in this query; give this table its own alias", alias.value),
+                related_information: Some(vec![related(span_to_range(&tokens[first].span), "First defined here")]),
+                ..Default::default()
+            });
+        }
+    }
+
+    diagnostics
+}
+
+/// Calls to deprecated functions, given as (name, replacement) pairs, and `UNIX_TIMESTAMP()`
+/// without arguments unless `UNIX_TIMESTAMP` is allowed.
+fn check_deprecated_functions(
+    tokens: &[TokenWithSpan],
+    deprecated: &[(&str, &str)],
+    rule: &RuleConfig,
+    severity: DiagnosticSeverity,
+) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for (i, token_with_span) in tokens.iter().enumerate() {
+        let Token::Word(w) = &token_with_span.token else { continue };
+        if w.quote_style.is_some() || rule.allows(&w.value) {
+            continue;
+        }
+        let Some(open) = next_significant(tokens, i).filter(|&j| matches!(tokens[j].token, Token::LParen)) else { continue };
+        let replacement = match deprecated.iter().find(|(name, _)| name.eq_ignore_ascii_case(&w.value)) {
+            Some((_, replacement)) => replacement.to_string(),
+            None if w.value.eq_ignore_ascii_case("UNIX_TIMESTAMP")
+                && next_significant(tokens, open).is_some_and(|j| matches!(tokens[j].token, Token::RParen)) =>
+            {
+                "CURRENT_TIMESTAMP".to_string()
+            }
+            None => continue,
+        };
+        diagnostics.push(Diagnostic {
+            range: span_to_range(&token_with_span.span),
+            severity: Some(severity),
+            code: Some(NumberOrString::String("deprecated-function".to_string())),
+            source: Some("hql-ls".to_string()),
+            message: format!("'{}' is deprecated; use {} instead", w.value, replacement),
+            tags: Some(vec![DiagnosticTag::DEPRECATED]),
+            ..Default::default()
+        });
+    }
+
+    diagnostics
+}
+
+/// `WHERE 1 = 1` and `WHERE 1 = 0` style predicates: a number compared with a number as a
+/// whole top-level condition of a WHERE clause, i.e. between WHERE, AND, OR and the clause end.
+fn check_constant_predicates(tokens: &[TokenWithSpan], severity: DiagnosticSeverity) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let clause_end = ["GROUP", "ORDER", "HAVING", "LIMIT", "UNION", "CLUSTER", "DISTRIBUTE", "SORT", "WINDOW"];
+
+    for (i, token_with_span) in tokens.iter().enumerate() {
+        if !is_word(&token_with_span.token, "WHERE") {
+            continue;
+        }
+        // Significant tokens of the clause at its own depth, `None` standing in for nested ones
+        let mut items: Vec<Option<usize>> = Vec::new();
+        let mut depth = 0;
+        for (j, t) in tokens.iter().enumerate().skip(i + 1) {
+            let token = &t.token;
+            match token {
+                Token::LParen => depth += 1,
+                Token::RParen if depth == 0 => break,
+                Token::RParen => depth -= 1,
+                Token::SemiColon if depth == 0 => break,
+                _ if depth == 0 && clause_end.iter().any(|kw| is_word(token, kw)) => break,
+                _ => {}
+            }
+            if is_significant(token) {
+                items.push((depth == 0 && !matches!(token, Token::RParen)).then_some(j));
+            }
+        }
+
+        let is_boundary = |item: Option<&Option<usize>>| match item {
+            None => true,
+            Some(Some(k)) => is_word(&tokens[*k].token, "AND") || is_word(&tokens[*k].token, "OR"),
+            Some(None) => false,
+        };
+        for (n, window) in items.windows(3).enumerate() {
+            let [Some(left), Some(op), Some(right)] = *window else { continue };
+            let (Token::Number(a, _), Token::Eq, Token::Number(b, _)) = (&tokens[left].token, &tokens[op].token, &tokens[right].token)
+            else {
+                continue;
+            };
+            if !(n == 0 || is_boundary(items.get(n - 1))) || !is_boundary(items.get(n + 3)) {
+                continue;
+            }
+            let equal = match (a.parse::<f64>(), b.parse::<f64>()) {
+                (Ok(a), Ok(b)) => a == b,
+                _ => a == b,
+            };
+            let message = if equal {
+                format!("{} = {} is always true and filters nothing", a, b)
+            } else {
+                format!("{} = {} is always false, so no rows match", a, b)
+            };
+            diagnostics.push(Diagnostic {
+                range: Range {
+                    start: span_to_range(&tokens[left].span).start,
+                    end: span_to_range(&tokens[right].span).end,
+                },
+                severity: Some(severity),
+                code: Some(NumberOrString::String("constant-predicate".to_string())),
+                source: Some("hql-ls".to_string()),
+                message,
+                ..Default::default()
+            });
+        }
+    }
+
+    diagnostics
+}
+
+/// Lowercase or mixed-case types in the column lists of `CREATE TABLE`, e.g. `id int`. The
+/// first word of each column is its name and a word before `:` a struct field, so neither
+/// counts even when it is a type name.
+fn check_type_casing(tokens: &[TokenWithSpan], severity: DiagnosticSeverity) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    let statements = syntax::split_statements(tokens);
+    for open in column_list_opens(tokens) {
+        // `INSERT INTO t (...)` lists names only
+        if !statements.iter().any(|s| s.contains(&open) && is_word(&tokens[s.start].token, "CREATE")) {
+            continue;
+        }
+        let Some(close) = matching_paren(tokens, open) else { continue };
+
+        let mut entry_start = true;
+        let mut depth = 0;
+        for i in open + 1..close {
+            match &tokens[i].token {
+                Token::LParen => depth += 1,
+                Token::RParen => depth -= 1,
+                Token::Comma if depth == 0 => entry_start = true,
+                Token::Word(word) if word.quote_style.is_none() => {
+                    if std::mem::take(&mut entry_start) {
+                        continue;
+                    }
+                    let is_field = next_significant(tokens, i).is_some_and(|k| matches!(tokens[k].token, Token::Colon));
+                    if is_field
+                        || !keywords::DATA_TYPES.iter().any(|t| word.value.eq_ignore_ascii_case(t))
+                        || word.value == word.value.to_uppercase()
+                    {
+                        continue;
+                    }
+                    diagnostics.push(Diagnostic {
+                        range: span_to_range(&tokens[i].span),
+                        severity: Some(severity),
+                        code: Some(NumberOrString::String("type-casing".to_string())),
+                        source: Some("hql-ls".to_string()),
+                        message: format!("Type '{}' should be uppercase", word.value),
+                        ..Default::default()
+                    });
+                }
+                token if is_significant(token) => entry_start = false,
+                _ => {}
+            }
+        }
+    }
+
+    diagnostics
+}
+
+/// A column compared with a literal of the other kind: a numeric column with a quoted string,
+/// as in `WHERE id = '5'`, or a string column with a number. Hive converts both sides to
+/// DOUBLE, so `'5.0'` matches 5 and `'007'` compares as 7. Column types come from the schema.
+fn check_type_mismatch(tokens: &[TokenWithSpan], schema: &Schema, severity: DiagnosticSeverity) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for statement in syntax::split_statements(tokens) {
+        let aliases = syntax::table_aliases(tokens, statement.clone());
+        let significant: Vec<usize> = statement.filter(|&i| is_significant(&tokens[i].token)).collect();
+        let token_at = |pos: usize| significant.get(pos).map(|&i| &tokens[i].token);
+        let word_at = |pos: usize| match token_at(pos) {
+            Some(Token::Word(w)) if w.quote_style != Some('"') => Some(w),
+            _ => None,
+        };
+        let is_period = |pos: usize| matches!(token_at(pos), Some(Token::Period));
+        let arithmetic = |pos: usize| {
+            matches!(token_at(pos), Some(Token::Plus | Token::Minus | Token::Mul | Token::Div | Token::Mod | Token::StringConcat))
+        };
+
+        for (pos, &op) in significant.iter().enumerate() {
+            if !matches!(tokens[op].token, Token::Eq | Token::DoubleEq | Token::Neq | Token::Lt | Token::Gt | Token::LtEq | Token::GtEq) {
+                continue;
+            }
+            let Some(left) = pos.checked_sub(1) else { continue };
+            let right = pos + 1;
+            // Positions of a `col` or `q.col` operand on either side
+            let left_column = if left >= 2 && is_period(left - 1) && word_at(left - 2).is_some() { left - 2..left + 1 } else { left..left + 1 };
+            let right_column = if is_period(right + 1) && word_at(right + 2).is_some() { right..right + 3 } else { right..right + 1 };
+
+            for (column, literal) in [(left_column, right), (right_column, left)] {
+                let Some(name) = word_at(column.end - 1) else { continue };
+                let qualifier = if column.len() == 3 { word_at(column.start) } else { None };
+                let (first, last) = (column.start.min(literal), (column.end - 1).max(literal));
+                // Either side being part of a larger expression, e.g. `id + 1 = '5'` or `f(x) = 1`
+                if first.checked_sub(1).is_some_and(|p| arithmetic(p) || is_period(p))
+                    || arithmetic(last + 1)
+                    || matches!(token_at(last + 1), Some(Token::Period | Token::LParen))
+                    || keywords::is_keyword_usage(tokens, significant[column.end - 1])
+                {
+                    continue;
+                }
+
+                let Some(declared) = column_type(schema, &aliases, qualifier.map(|q| q.value.as_str()), &name.value) else { continue };
+                let normalized = normalize_type(declared);
+                let base = normalized.split(['(', '<']).next().unwrap_or_default();
+                let numeric = ["TINYINT", "SMALLINT", "INT", "BIGINT", "FLOAT", "DOUBLE", "DECIMAL", "NUMERIC"].contains(&base);
+                let string = ["STRING", "VARCHAR", "CHAR"].contains(&base);
+                let message = match token_at(literal) {
+                    Some(Token::SingleQuotedString(value)) if numeric => format!(
+                        "'{}' is {} but is compared with the string '{}'; Hive converts both to DOUBLE",
+                        name.value, declared.to_uppercase(), value
+                    ),
+                    Some(Token::Number(value, _)) if string => format!(
+                        "'{}' is {} but is compared with the number {}; Hive converts both to DOUBLE, so quote it as '{}'",
+                        name.value, declared.to_uppercase(), value, value
+                    ),
+                    _ => continue,
+                };
+                diagnostics.push(Diagnostic {
+                    range: Range {
+                        start: span_to_range(&tokens[significant[first]].span).start,
+                        end: span_to_range(&tokens[significant[last]].span).end,
+                    },
+                    severity: Some(severity),
+                    code: Some(NumberOrString::String("type-mismatch".to_string())),
+                    source: Some("hql-ls".to_string()),
+                    message,
+                    ..Default::default()
+                });
+            }
+        }
+    }
+
+    diagnostics
+}
+
+/// `SELECT DISTINCT *`, which deduplicates on every column; the `select-star` rule reports the
+/// `*` on its own.
+fn check_select_distinct_star(tokens: &[TokenWithSpan], severity: DiagnosticSeverity) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut paren_balance = 0;
+
+    for (i, token_with_span) in tokens.iter().enumerate() {
+        match &token_with_span.token {
+            Token::LParen => paren_balance += 1,
+            Token::RParen if paren_balance > 0 => paren_balance -= 1,
+            token if paren_balance == 0 && is_word(token, "SELECT") => {
+                let Some(distinct_idx) = next_significant(tokens, i).filter(|&j| is_word(&tokens[j].token, "DISTINCT")) else { continue };
+                let Some(star_idx) = next_significant(tokens, distinct_idx).filter(|&j| matches!(tokens[j].token, Token::Mul)) else { continue };
+
+                diagnostics.push(Diagnostic {
+                    range: Range {
+                        start: span_to_range(&tokens[distinct_idx].span).start,
+                        end: span_to_range(&tokens[star_idx].span).end,
+                    },
+                    severity: Some(severity),
+                    code: Some(NumberOrString::String("select-distinct-star".to_string())),
+                    source: Some("hql-ls".to_string()),
+                    message: "DISTINCT * compares every column of every row; list the columns that make a row unique".to_string(),
+                    ..Default::default()
+                });
+            }
+            _ => {}
+        }
+    }
+
+    diagnostics
+}
+
+/// The same identifier written both with and without backticks in one statement, e.g.
+/// `` `id` `` and `id`. Occurrences in the other style from the first are reported.
+fn check_inconsistent_quoting(tokens: &[TokenWithSpan], severity: DiagnosticSeverity) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for statement in syntax::split_statements(tokens) {
+        // Index of each name's first occurrence, keyed by lowercased name
+        let mut first_use: HashMap<String, usize> = HashMap::new();
+        for i in statement {
+            let Token::Word(word) = &tokens[i].token else { continue };
+            let quoted = match word.quote_style {
+                Some('`') => true,
+                None if !keywords::is_keyword_usage(tokens, i) => false,
+                _ => continue,
+            };
+            let first = *first_use.entry(word.value.to_lowercase()).or_insert(i);
+            let Token::Word(first_word) = &tokens[first].token else { continue };
+            if first_word.quote_style.is_some() == quoted {
+                continue;
+            }
+
+            let message = if quoted {
+                format!("'{}' is quoted here but not where it is first used", word.value)
+            } else {
+                format!("'{}' is unquoted here but quoted with backticks where it is first used", word.value)
+            };
+            diagnostics.push(Diagnostic {
+                range: span_to_range(&tokens[i].span),
+                severity: Some(severity),
+                code: Some(NumberOrString::String("inconsistent-quoting".to_string())),
+                source: Some("hql-ls".to_string()),
+                message,
+                related_information: Some(vec![related(span_to_range(&tokens[first].span), "First used here")]),
+                ..Default::default()
+            });
+        }
+    }
+
+    diagnostics
+}
+
+/// `INSERT OVERWRITE TABLE t SELECT ...` into a table the schema lists partition columns for,
+/// without a `PARTITION (...)` clause saying which partitions are meant to be replaced.
+fn check_missing_partition(tokens: &[TokenWithSpan], schema: &Schema, severity: DiagnosticSeverity) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for (i, token_with_span) in tokens.iter().enumerate() {
+        if !is_word(&token_with_span.token, "OVERWRITE")
+            || !(0..i).rev().find(|&j| is_significant(&tokens[j].token)).is_some_and(|j| is_word(&tokens[j].token, "INSERT"))
+        {
+            continue;
+        }
+        // `INSERT OVERWRITE [LOCAL] DIRECTORY` writes files, not a table
+        let Some(mut idx) = next_significant(tokens, i) else { continue };
+        if is_word(&tokens[idx].token, "TABLE") {
+            let Some(next) = next_significant(tokens, idx) else { continue };
+            idx = next;
+        }
+
+        // `db.table`
+        let Token::Word(first) = &tokens[idx].token else { continue };
+        let mut name = first.value.clone();
+        let mut end = idx;
+        while let Some(period) = next_significant(tokens, end).filter(|&p| matches!(tokens[p].token, Token::Period)) {
+            let Some(part) = next_significant(tokens, period) else { break };
+            let Token::Word(part_word) = &tokens[part].token else { break };
+            name = format!("{}.{}", name, part_word.value);
+            end = part;
+        }
+        if next_significant(tokens, end).is_some_and(|j| is_word(&tokens[j].token, "PARTITION")) {
+            continue;
+        }
+
+        let Some(table) = schema.table(&name).filter(|t| !t.partitioned_by.is_empty()) else { continue };
+        diagnostics.push(Diagnostic {
+            range: Range {
+                start: span_to_range(&tokens[idx].span).start,
+                end: span_to_range(&tokens[end].span).end,
+            },
+            severity: Some(severity),
+            code: Some(NumberOrString::String("missing-partition".to_string())),
+            source: Some("hql-ls".to_string()),
+            message: format!(
+                "'{}' is partitioned by {}; add PARTITION (...) to say which partitions INSERT OVERWRITE replaces",
+                table.name,
+                table.partitioned_by.join(", ")
+            ),
+            ..Default::default()
+        });
+    }
+
+    diagnostics
+}
+
+/// `LIKE 'abc'`: a pattern without an unescaped `%` or `_` only matches the string itself,
+/// so the comparison is `=` (or `<>` after NOT) written as a pattern match.
+fn check_like_without_wildcard(tokens: &[TokenWithSpan], severity: DiagnosticSeverity) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    // `SHOW TABLES LIKE 'sales*'` patterns use * and | instead
+    let show_statements: Vec<_> = syntax::split_statements(tokens)
+        .into_iter()
+        .filter(|statement| is_word(&tokens[statement.start].token, "SHOW"))
+        .collect();
+
+    for (i, token_with_span) in tokens.iter().enumerate() {
+        if !is_word(&token_with_span.token, "LIKE") || show_statements.iter().any(|statement| statement.contains(&i)) {
+            continue;
+        }
+        let Some(pattern_idx) = next_significant(tokens, i) else { continue };
+        // Hive reads "..." as a string too
+        let raw = match &tokens[pattern_idx].token {
+            Token::SingleQuotedString(raw) | Token::DoubleQuotedString(raw) => raw,
+            Token::Word(w) if w.quote_style == Some('"') => &w.value,
+            _ => continue,
+        };
+        // A variable may expand to a wildcard
+        if has_like_wildcard(raw) || raw.contains("${") {
+            continue;
+        }
+        let negated = (0..i).rev().find(|&j| is_significant(&tokens[j].token)).is_some_and(|j| is_word(&tokens[j].token, "NOT"));
+        let operator = if negated { "<>" } else { "=" };
+
+        diagnostics.push(Diagnostic {
+            range: span_to_range(&tokens[pattern_idx].span),
+            severity: Some(severity),
+            code: Some(NumberOrString::String("like-without-wildcard".to_string())),
+            source: Some("hql-ls".to_string()),
+            message: format!("Pattern has no % or _ wildcard, so it only matches itself; use {} instead of {}LIKE", operator, if negated { "NOT " } else { "" }),
+            ..Default::default()
+        });
+    }
+
+    diagnostics
+}
+
+/// True if a raw LIKE pattern has a `%` or `_` that isn't escaped with a backslash.
+fn has_like_wildcard(raw: &str) -> bool {
+    let mut chars = raw.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '%' | '_' => return true,
+            _ => {}
+        }
+    }
+    false
+}
+
+/// `COUNT(DISTINCT a, b)`: Hive accepts several columns here, but not everywhere, e.g. not
+/// with `hive.groupby.skewindata` or next to another multi-column COUNT(DISTINCT).
+fn check_multi_column_count_distinct(tokens: &[TokenWithSpan], severity: DiagnosticSeverity) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for (i, token_with_span) in tokens.iter().enumerate() {
+        if !is_word(&token_with_span.token, "COUNT") {
+            continue;
+        }
+        let Some(open_idx) = next_significant(tokens, i) else { continue };
+        let Some(close_idx) = matching_paren(tokens, open_idx) else { continue };
+        if !next_significant(tokens, open_idx).is_some_and(|j| is_word(&tokens[j].token, "DISTINCT")) {
+            continue;
+        }
+        let mut depth = 0;
+        let mut columns = 1;
+        for token in &tokens[open_idx + 1..close_idx] {
+            match token.token {
+                Token::LParen => depth += 1,
+                Token::RParen => depth -= 1,
+                Token::Comma if depth == 0 => columns += 1,
+                _ => {}
+            }
+        }
+        if columns < 2 {
+            continue;
+        }
+
+        diagnostics.push(Diagnostic {
+            range: Range {
+                start: span_to_range(&token_with_span.span).start,
+                end: span_to_range(&tokens[close_idx].span).end,
+            },
+            severity: Some(severity),
+            code: Some(NumberOrString::String("multi-column-count-distinct".to_string())),
+            source: Some("hql-ls".to_string()),
+            message: format!(
+                "COUNT(DISTINCT ...) over {} columns isn't supported everywhere in Hive (e.g. with hive.groupby.skewindata); count the distinct rows of a subquery instead",
+                columns
+            ),
+            ..Default::default()
+        });
+    }
+
+    diagnostics
+}
+
+/// Statement keywords that may follow a pasted `GO`.
+const AFTER_BATCH_SEPARATOR: &[&str] = &[
+    "SELECT", "INSERT", "UPDATE", "DELETE", "CREATE", "DROP", "ALTER", "TRUNCATE", "WITH", "MERGE", "SHOW", "DESCRIBE",
+    "EXPLAIN", "SET", "USE", "LOAD",
+];
+
+/// `GO` alone on a line, as SQL Server scripts separate batches. Hive reads it as an
+/// identifier, so a column named `go` on a line of its own is told apart by what surrounds
+/// it: a batch separator ends a statement and is followed by another or by the end of the file.
+fn check_batch_separator(tokens: &[TokenWithSpan], severity: DiagnosticSeverity) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut paren_balance = 0;
+
+    for (i, token_with_span) in tokens.iter().enumerate() {
+        match &token_with_span.token {
+            Token::LParen => paren_balance += 1,
+            Token::RParen if paren_balance > 0 => paren_balance -= 1,
+            token if paren_balance == 0 && is_word(token, "GO") => {
+                let line = token_with_span.span.start.line;
+                let previous = (0..i).rev().find(|&j| is_significant(&tokens[j].token));
+                let next = next_significant(tokens, i);
+                // Comments may share the line
+                let alone = previous.is_none_or(|j| tokens[j].span.end.line < line) && next.is_none_or(|j| tokens[j].span.start.line > line);
+                let ends_statement = previous.is_none_or(|j| {
+                    !matches!(tokens[j].token, Token::Comma) && !is_word(&tokens[j].token, "SELECT") && !is_word(&tokens[j].token, "DISTINCT")
+                });
+                let starts_next = next.is_none_or(|j| {
+                    matches!(tokens[j].token, Token::SemiColon) || AFTER_BATCH_SEPARATOR.iter().any(|kw| is_word(&tokens[j].token, kw))
+                });
+                if !alone || !ends_statement || !starts_next {
+                    continue;
+                }
+
+                diagnostics.push(Diagnostic {
+                    range: span_to_range(&token_with_span.span),
+                    severity: Some(severity),
+                    code: Some(NumberOrString::String("batch-separator".to_string())),
+                    source: Some("hql-ls".to_string()),
+                    message: "GO is a SQL Server batch separator, not HQL; end statements with ; instead".to_string(),
+                    ..Default::default()
+                });
+            }
+            _ => {}
+        }
+    }
+
+    diagnostics
+}
+
+/// Branches of a `UNION` (or another set operation) that select a different number of
+/// columns from the first branch. Branches with `*` or `t.*` are skipped, since their width
+/// is unknown, and so are parenthesized branches.
+fn check_union_column_mismatch(tokens: &[TokenWithSpan], severity: DiagnosticSeverity) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    // Column count of the first branch of the query at each paren depth; `None` for `*`
+    let mut first_branch: HashMap<usize, Option<usize>> = HashMap::new();
+    let mut depth = 0;
+
+    let is_set_operator = |token: &Token| ["UNION", "INTERSECT", "EXCEPT", "MINUS"].iter().any(|op| is_word(token, op));
+    for (i, token_with_span) in tokens.iter().enumerate() {
+        match &token_with_span.token {
+            Token::LParen => depth += 1,
+            Token::RParen => {
+                first_branch.remove(&depth);
+                depth = depth.saturating_sub(1);
+            }
+            Token::SemiColon if depth == 0 => first_branch.clear(),
+            token if is_word(token, "SELECT") => {
+                let items = select_list_items(tokens, i);
+                let has_star = items.iter().any(|item| {
+                    item.clone().rev().find(|&k| is_significant(&tokens[k].token)).is_some_and(|k| matches!(tokens[k].token, Token::Mul))
+                });
+                let columns = (!has_star).then_some(items.len());
+
+                let previous = (0..i).rev().find(|&j| is_significant(&tokens[j].token));
+                let continues = previous.is_some_and(|j| {
+                    let token = &tokens[j].token;
+                    is_set_operator(token)
+                        || ((is_word(token, "ALL") || is_word(token, "DISTINCT"))
+                            && (0..j).rev().find(|&k| is_significant(&tokens[k].token)).is_some_and(|k| is_set_operator(&tokens[k].token)))
+                });
+                if !continues {
+                    first_branch.insert(depth, columns);
+                    continue;
+                }
+                let (Some(Some(expected)), Some(actual)) = (first_branch.get(&depth), columns) else { continue };
+                if *expected == actual {
+                    continue;
+                }
+
+                diagnostics.push(Diagnostic {
+                    range: span_to_range(&token_with_span.span),
+                    severity: Some(severity),
+                    code: Some(NumberOrString::String("union-column-mismatch".to_string())),
+                    source: Some("hql-ls".to_string()),
+                    message: format!("SELECT returns {} column(s) but the first branch returns {}", actual, expected),
+                    ..Default::default()
+                });
+            }
+            _ => {}
+        }
+    }
+
+    diagnostics
+}
+
+/// `CASE` without a matching `END`, and `END` with no open `CASE`, counted per statement.
+/// A CASE must end inside the parentheses it opened in. `BEGIN ... END` blocks are paired
+/// too, so their END isn't mistaken for an extra one.
+fn check_unbalanced_case(tokens: &[TokenWithSpan], severity: DiagnosticSeverity) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let diagnostic = |idx: usize, message: &str| Diagnostic {
+        range: span_to_range(&tokens[idx].span),
+        severity: Some(severity),
+        code: Some(NumberOrString::String("unbalanced-case".to_string())),
+        source: Some("hql-ls".to_string()),
+        message: message.to_string(),
+        ..Default::default()
+    };
+    let unterminated = "CASE without a matching END";
+
+    for statement in syntax::split_statements(tokens) {
+        // Open CASE and BEGIN blocks: token index and paren depth
+        let mut open: Vec<(usize, usize)> = Vec::new();
+        let mut depth: usize = 0;
+        for i in statement {
+            let token = &tokens[i].token;
+            // `t.end` is a column
+            let qualified = (0..i).rev().find(|&j| is_significant(&tokens[j].token)).is_some_and(|j| matches!(tokens[j].token, Token::Period));
+            match token {
+                Token::LParen => depth += 1,
+                Token::RParen => {
+                    depth = depth.saturating_sub(1);
+                    while let Some(&(idx, _)) = open.last().filter(|&&(_, opened)| opened > depth) {
+                        open.pop();
+                        if is_word(&tokens[idx].token, "CASE") {
+                            diagnostics.push(diagnostic(idx, unterminated));
+                        }
+                    }
+                }
+                token if !qualified && (is_word(token, "CASE") || is_word(token, "BEGIN")) => open.push((i, depth)),
+                token if !qualified && is_word(token, "END") => {
+                    if open.last().is_some_and(|&(_, opened)| opened == depth) {
+                        open.pop();
+                    } else {
+                        diagnostics.push(diagnostic(i, "END without a matching CASE"));
+                    }
+                }
+                _ => {}
+            }
+        }
+        for (idx, _) in open {
+            if is_word(&tokens[idx].token, "CASE") {
+                diagnostics.push(diagnostic(idx, unterminated));
+            }
+        }
+    }
+
+    diagnostics
+}
+
+/// Subqueries nested more than `max_depth` levels deep, flagged at the first SELECT past the limit.
+fn check_subquery_depth(tokens: &[TokenWithSpan], max_depth: u64, severity: DiagnosticSeverity) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    // Whether each open parenthesis starts a subquery
+    let mut parens: Vec<bool> = Vec::new();
+
+    for (i, token_with_span) in tokens.iter().enumerate() {
+        match &token_with_span.token {
+            Token::LParen => {
+                let select = next_significant(tokens, i).filter(|&j| is_word(&tokens[j].token, "SELECT"));
+                parens.push(select.is_some());
+                let depth = parens.iter().filter(|&&subquery| subquery).count() as u64;
+                // Only the outermost SELECT past the limit, not every one nested inside it
+                if let Some(j) = select
+                    && depth == max_depth + 1
+                {
+                    diagnostics.push(Diagnostic {
+                        range: span_to_range(&tokens[j].span),
+                        severity: Some(severity),
+                        code: Some(NumberOrString::String("subquery-depth".to_string())),
+                        source: Some("hql-ls".to_string()),
+                        message: format!(
+                            "Subquery is nested {} levels deep (limit {}); consider rewriting the inner queries as CTEs",
+                            depth, max_depth
+                        ),
+                        ..Default::default()
+                    });
+                }
+            }
+            Token::RParen => {
+                parens.pop();
+            }
+            Token::SemiColon => parens.clear(),
+            _ => {}
+        }
+    }
+
+    diagnostics
+}
+
+/// `SET key=value` statements whose key is a known deprecated property, or doesn't start
+/// with any known property prefix and so is probably misspelled. Variables such as
+/// `hivevar:x` and keys built from `${...}` aren't checked.
+fn check_set_property(
+    tokens: &[TokenWithSpan],
+    prefixes: &[&str],
+    deprecated: &[(&str, &str)],
+    severity: Option<DiagnosticSeverity>,
+) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    for (_, key, range) in set_statements(tokens) {
+        if key.contains(':') || key.contains("${") {
+            continue;
+        }
+
+        let lower = key.to_lowercase();
+        let (severity, message, tags) = if let Some((_, replacement)) = deprecated.iter().find(|(name, _)| name.eq_ignore_ascii_case(&key)) {
+            (
+                severity.unwrap_or(DiagnosticSeverity::WARNING),
+                format!("'{}' is deprecated; use {} instead", key, replacement),
+                Some(vec![DiagnosticTag::DEPRECATED]),
+            )
+        } else if !prefixes.iter().any(|prefix| lower.starts_with(&prefix.to_lowercase())) {
+            (
+                severity.unwrap_or(DiagnosticSeverity::HINT),
+                format!("'{}' doesn't start with a known property prefix; is it misspelled?", key),
+                None,
+            )
+        } else {
+            continue;
+        };
+
+        diagnostics.push(Diagnostic {
+            range,
+            severity: Some(severity),
+            code: Some(NumberOrString::String("set-property".to_string())),
+            source: Some("hql-ls".to_string()),
+            message,
+            tags,
+            ..Default::default()
+        });
+    }
+
+    diagnostics
+}
+
+// Helper
+fn is_significant(token: &Token) -> bool {
+    !matches!(token, Token::Whitespace(_))
+}
+
+fn next_significant(tokens: &[TokenWithSpan], idx: usize) -> Option<usize> {
+    (idx + 1..tokens.len()).find(|&j| is_significant(&tokens[j].token))
+}
+
+/// Each statement-start `SET key=value` as the SET token's index, the key as written and the
+/// key's range. The key is everything between SET and `=`, so a bare `SET` or `SET key`, which
+/// only print values, yield nothing.
+fn set_statements(tokens: &[TokenWithSpan]) -> Vec<(usize, String, Range)> {
+    let mut statements = Vec::new();
+    let mut at_statement_start = true;
+
+    for (i, token_with_span) in tokens.iter().enumerate() {
+        let token = &token_with_span.token;
+        if !is_significant(token) {
+            continue;
+        }
+        let starts_statement = std::mem::replace(&mut at_statement_start, matches!(token, Token::SemiColon));
+        if !starts_statement || !is_word(token, "SET") {
+            continue;
+        }
+
+        let Some(eq) = (i + 1..tokens.len())
+            .take_while(|&j| !matches!(tokens[j].token, Token::SemiColon | Token::Whitespace(Whitespace::Newline)))
+            .find(|&j| matches!(tokens[j].token, Token::Eq))
+        else {
+            continue;
+        };
+        let Some(first) = next_significant(tokens, i).filter(|&j| j < eq) else { continue };
+        let last = (first..eq).rev().find(|&j| is_significant(&tokens[j].token)).unwrap_or(first);
+        let key = tokens[first..=last].iter().map(|t| t.token.to_string()).collect();
+        let range = Range { start: span_to_range(&tokens[first].span).start, end: span_to_range(&tokens[last].span).end };
+        statements.push((i, key, range));
+    }
+
+    statements
+}
+
+fn is_comparison_operator(token: &Token) -> bool {
+    matches!(token,
+        Token::Eq | Token::DoubleEq | Token::Neq | Token::Lt | Token::Gt |
+        Token::LtEq | Token::GtEq | Token::Spaceship
+    )
+}
+
+/// Reads an optionally negated numeric literal after `idx`, returning its value,
+/// the span covering sign and digits, and the index of the last token consumed.
+fn read_signed_number(tokens: &[TokenWithSpan], idx: usize) -> Option<(f64, Span, usize)> {
+    let mut j = next_significant(tokens, idx)?;
+    let mut span = tokens[j].span;
+    let negative = matches!(tokens[j].token, Token::Minus);
+    if negative {
+        j = next_significant(tokens, j)?;
+        span.end = tokens[j].span.end;
+    }
+    match &tokens[j].token {
+        Token::Number(n, _) => {
+            let value: f64 = n.parse().ok()?;
+            Some((if negative { -value } else { value }, span, j))
+        }
+        _ => None,
+    }
+}
+
+/// The parts of an `INSERT` statement the insert rules look at.
+struct InsertParts {
+    /// The target table as written, `db.t` or `t`
+    table: Option<String>,
+    /// The explicit target column list
+    columns: Option<Vec<String>>,
+    select_idx: Option<usize>,
+    values_idx: Option<usize>,
+}
+
+/// Reads the target and source of the INSERT at `insert_idx`.
+fn read_insert(tokens: &[TokenWithSpan], insert_idx: usize) -> InsertParts {
+    let mut insert = InsertParts { table: None, columns: None, select_idx: None, values_idx: None };
+    let mut j = insert_idx;
+    while let Some(k) = next_significant(tokens, j) {
+        match &tokens[k].token {
+            Token::Word(w) if w.quote_style.is_none() && w.value.eq_ignore_ascii_case("PARTITION") => {
+                // Skip the partition spec so it isn't mistaken for the column list
+                let Some(open) = next_significant(tokens, k) else { break };
+                let Some(close) = matching_paren(tokens, open) else { break };
+                j = close;
+                continue;
+            }
+            Token::LParen => {
+                let Some(close) = matching_paren(tokens, k) else { break };
+                insert.columns = Some(identifier_list(tokens, k + 1, close));
+                j = close;
+                continue;
+            }
+            Token::Word(w) if w.quote_style.is_none() && w.value.eq_ignore_ascii_case("SELECT") => {
+                insert.select_idx = Some(k);
+                break;
+            }
+            Token::Word(w) if w.quote_style.is_none() && w.value.eq_ignore_ascii_case("VALUES") => {
+                insert.values_idx = Some(k);
+                break;
+            }
+            Token::Word(w) if insert.table.is_none()
+                && (w.quote_style.is_some() || !matches!(w.value.to_uppercase().as_str(), "INTO" | "OVERWRITE" | "TABLE")) =>
+            {
+                // `db.table`
+                let mut name = w.value.clone();
+                let mut end = k;
+                while let Some(period) = next_significant(tokens, end).filter(|&p| matches!(tokens[p].token, Token::Period)) {
+                    let Some(part) = next_significant(tokens, period) else { break };
+                    let Token::Word(part_word) = &tokens[part].token else { break };
+                    name = format!("{}.{}", name, part_word.value);
+                    end = part;
+                }
+                insert.table = Some(name);
+                j = end;
+                continue;
+            }
+            Token::SemiColon => break,
+            _ => {}
+        }
+        j = k;
+    }
+    insert
+}
+
+/// Collects the names in a comma-separated identifier list between `start` and `end` (exclusive).
+fn identifier_list(tokens: &[TokenWithSpan], start: usize, end: usize) -> Vec<String> {
+    tokens[start..end]
+        .iter()
+        .filter_map(|t| match &t.token {
+            Token::Word(w) => Some(w.value.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Splits the SELECT list following `select_idx` into token index ranges, one per item.
+///
+/// Commas inside parentheses are ignored, and the list ends at the first clause keyword,
+/// closing paren or semicolon at the SELECT's own depth. A leading DISTINCT/ALL is skipped.
+fn select_list_items(tokens: &[TokenWithSpan], select_idx: usize) -> Vec<std::ops::Range<usize>> {
+    let list_terminators = [
+        "FROM", "WHERE", "GROUP", "ORDER", "HAVING", "LIMIT", "UNION", "CLUSTER", "DISTRIBUTE",
+        "SORT", "LATERAL", "WINDOW", "INTO",
+    ];
+
+    let mut items = Vec::new();
+    let mut start = select_idx + 1;
+    if let Some(first) = next_significant(tokens, select_idx)
+        && (is_word(&tokens[first].token, "DISTINCT") || is_word(&tokens[first].token, "ALL"))
+    {
+        start = first + 1;
+    }
+
+    let mut depth = 0;
+    let mut j = start;
+    while j < tokens.len() {
+        match &tokens[j].token {
+            Token::LParen => depth += 1,
+            Token::RParen if depth == 0 => break,
+            Token::RParen => depth -= 1,
+            Token::SemiColon if depth == 0 => break,
+            Token::Comma if depth == 0 => {
+                items.push(start..j);
+                start = j + 1;
+            }
+            Token::Word(w) if depth == 0 && w.quote_style.is_none()
+                && list_terminators.contains(&w.value.to_uppercase().as_str()) => break,
+            _ => {}
+        }
+        j += 1;
+    }
+    if (start..j).any(|k| is_significant(&tokens[k].token)) {
+        items.push(start..j);
+    }
+    items
+}
+
+/// Returns the output column name of a SELECT item: its alias, or the last part of a plain column reference.
+fn select_item_name(tokens: &[TokenWithSpan], item: &std::ops::Range<usize>) -> Option<String> {
+    let significant: Vec<&Token> = tokens[item.clone()]
+        .iter()
+        .map(|t| &t.token)
+        .filter(|t| is_significant(t))
+        .collect();
+
+    let Some(Token::Word(last)) = significant.last() else { return None };
+    if last.quote_style.is_none() && is_keyword(last) {
+        return None;
+    }
+    match significant.len() {
+        1 => Some(last.value.clone()),
+        n => match significant[n - 2] {
+            // `t.col` is a column reference, `expr AS alias` / `expr alias` an alias
+            Token::Period => significant[..n - 1]
+                .iter()
+                .all(|t| matches!(t, Token::Word(_) | Token::Period))
+                .then(|| last.value.clone()),
+            Token::Word(w) if w.value.eq_ignore_ascii_case("AS") => Some(last.value.clone()),
+            Token::Word(_) | Token::RParen | Token::Number(_, _) | Token::SingleQuotedString(_) => Some(last.value.clone()),
+            _ => None,
+        },
+    }
+}
+
+const AGGREGATE_FUNCTIONS: [&str; 14] = [
+    "COUNT", "SUM", "AVG", "MIN", "MAX", "COLLECT_SET", "COLLECT_LIST", "STDDEV", "STDDEV_POP",
+    "STDDEV_SAMP", "VARIANCE", "VAR_POP", "VAR_SAMP", "PERCENTILE",
+];
+
+/// True if the query whose SELECT list ends at `list_end` has a GROUP BY at the same depth.
+fn has_group_by(tokens: &[TokenWithSpan], list_end: usize) -> bool {
+    group_by_index(tokens, list_end).is_some()
+}
+
+/// The `GROUP` of the query whose SELECT list ends at `list_end`, at the same depth.
+fn group_by_index(tokens: &[TokenWithSpan], list_end: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (i, token_with_span) in tokens.iter().enumerate().skip(list_end) {
+        match &token_with_span.token {
+            Token::LParen => depth += 1,
+            Token::RParen if depth == 0 => return None,
+            Token::RParen => depth -= 1,
+            Token::SemiColon if depth == 0 => return None,
+            token if depth == 0 && is_word(token, "UNION") => return None,
+            token if depth == 0 && is_word(token, "GROUP") => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// The comma-separated items of the `GROUP BY` or `ORDER BY` whose `BY` is at `by_idx`, as
+/// the indices of their significant tokens at the clause's own depth.
+fn by_clause_items(tokens: &[TokenWithSpan], by_idx: usize) -> Vec<Vec<usize>> {
+    let mut items: Vec<Vec<usize>> = vec![Vec::new()];
+    let mut depth = 0;
+    for (j, t) in tokens.iter().enumerate().skip(by_idx + 1) {
+        match &t.token {
+            Token::LParen => depth += 1,
+            Token::RParen if depth == 0 => break,
+            Token::RParen => depth -= 1,
+            Token::SemiColon if depth == 0 => break,
+            Token::Comma if depth == 0 => {
+                items.push(Vec::new());
+                continue;
+            }
+            Token::Word(w) if depth == 0 && w.quote_style.is_none() && GROUP_BY_TERMINATORS.iter().any(|kw| w.value.eq_ignore_ascii_case(kw)) => break,
+            token if !is_significant(token) => continue,
+            _ => {}
+        }
+        if let Some(item) = items.last_mut() {
+            item.push(j);
+        }
+    }
+    items
+}
+
+/// True if the SELECT item calls an aggregate function that isn't a window function (`... OVER (...)`).
+fn is_aggregate_item(tokens: &[TokenWithSpan], item: &std::ops::Range<usize>) -> bool {
+    item.clone().any(|k| {
+        let Token::Word(w) = &tokens[k].token else { return false };
+        if w.quote_style.is_some() || !AGGREGATE_FUNCTIONS.contains(&w.value.to_uppercase().as_str()) {
+            return false;
+        }
+        let Some(open_idx) = next_significant(tokens, k).filter(|&j| matches!(tokens[j].token, Token::LParen)) else {
+            return false;
+        };
+        let Some(close_idx) = matching_paren(tokens, open_idx) else { return false };
+        !next_significant(tokens, close_idx).is_some_and(|j| is_word(&tokens[j].token, "OVER"))
+    })
+}
+
+/// Returns the column name if the SELECT item is a plain column reference (`col`, `t.col`, optionally aliased).
+fn bare_column(tokens: &[TokenWithSpan], item: &std::ops::Range<usize>) -> Option<String> {
+    let significant: Vec<&Token> = tokens[item.clone()]
+        .iter()
+        .map(|t| &t.token)
+        .filter(|t| is_significant(t))
+        .collect();
+
+    // Drop a trailing alias: `col AS x` or `col x`
+    let reference = match significant.as_slice() {
+        [head @ .., Token::Word(w), Token::Word(_)] if w.quote_style.is_none() && w.value.eq_ignore_ascii_case("AS") => head,
+        [head @ .., Token::Word(_), Token::Word(_)] => &significant[..head.len() + 1],
+        _ => &significant[..],
+    };
+
+    let mut name = None;
+    for (n, token) in reference.iter().enumerate() {
+        match token {
+            Token::Word(w) if n % 2 == 0 && (w.quote_style.is_some() || !is_keyword(w)) => name = Some(w.value.clone()),
+            Token::Period if n % 2 == 1 => {}
+            _ => return None,
+        }
+    }
+    name
+}
+
+/// Hive's reserved keywords (Hive 3). Unlike non-reserved keywords these can't be used
+/// as identifiers without backticks.
+const HIVE_RESERVED_WORDS: &[&str] = &[
+    "ALL", "ALTER", "AND", "ARRAY", "AS", "AUTHORIZATION", "BETWEEN", "BIGINT", "BINARY", "BOOLEAN",
+    "BOTH", "BY", "CACHE", "CASE", "CAST", "CHAR", "COLUMN", "COMMIT", "CONF", "CONSTRAINT",
+    "CREATE", "CROSS", "CUBE", "CURRENT", "CURRENT_DATE", "CURRENT_TIMESTAMP", "CURSOR", "DATABASE", "DATE", "DAYOFWEEK",
+    "DECIMAL", "DELETE", "DESCRIBE", "DISTINCT", "DOUBLE", "DROP", "ELSE", "END", "EXCHANGE", "EXISTS",
+    "EXTENDED", "EXTERNAL", "EXTRACT", "FALSE", "FETCH", "FLOAT", "FLOOR", "FOLLOWING", "FOR", "FOREIGN",
+    "FROM", "FULL", "FUNCTION", "GRANT", "GROUP", "GROUPING", "HAVING", "IF", "IMPORT", "IN",
+    "INNER", "INSERT", "INT", "INTEGER", "INTERSECT", "INTERVAL", "INTO", "IS", "JOIN", "LATERAL",
+    "LEFT", "LESS", "LIKE", "LOCAL", "MACRO", "MAP", "MORE", "NONE", "NOT", "NULL",
+    "NUMERIC", "OF", "ON", "ONLY", "OR", "ORDER", "OUT", "OUTER", "OVER", "PARTIALSCAN",
+    "PARTITION", "PERCENT", "PRECEDING", "PRECISION", "PRESERVE", "PRIMARY", "PROCEDURE", "RANGE", "READS", "REDUCE",
+    "REFERENCES", "REGEXP", "REVOKE", "RIGHT", "RLIKE", "ROLLBACK", "ROLLUP", "ROW", "ROWS", "SELECT",
+    "SET", "SMALLINT", "START", "SYNC", "TABLE", "TABLESAMPLE", "THEN", "TIME", "TIMESTAMP", "TO",
+    "TRANSFORM", "TRIGGER", "TRUE", "TRUNCATE", "UNBOUNDED", "UNION", "UNIQUEJOIN", "UPDATE", "USER", "USING",
+    "UTC_TMESTAMP", "VALUES", "VARCHAR", "VIEWS", "WHEN", "WHERE", "WINDOW", "WITH",
+];
+
+pub fn is_reserved_word(value: &str) -> bool {
+    HIVE_RESERVED_WORDS.contains(&value.to_uppercase().as_str())
+}
+
+/// Indices of the aliases named after `AS` (`expr AS name`), except the type in
+/// `CAST(x AS DATE)` and the query of `CREATE TABLE t AS SELECT`.
+fn as_aliases(tokens: &[TokenWithSpan]) -> Vec<usize> {
+    let mut aliases = Vec::new();
+    let mut cast_parens = Vec::new();
+    let mut previous: Option<usize> = None;
+    for (i, token_with_span) in tokens.iter().enumerate() {
+        match &token_with_span.token {
+            Token::LParen => cast_parens.push(previous.is_some_and(|p| is_word(&tokens[p].token, "CAST"))),
+            Token::RParen => {
+                cast_parens.pop();
+            }
+            token if is_word(token, "AS") && cast_parens.last() != Some(&true) => {
+                if let Some(alias) = next_significant(tokens, i)
+                    && matches!(tokens[alias].token, Token::Word(_))
+                    && !is_word(&tokens[alias].token, "SELECT")
+                    && !is_word(&tokens[alias].token, "WITH")
+                {
+                    aliases.push(alias);
+                }
+            }
+            _ => {}
+        }
+        if is_significant(&token_with_span.token) {
+            previous = Some(i);
+        }
+    }
+    aliases
+}
+
+/// Finds the `(` opening column lists whose entries start with a column name:
+/// `CREATE TABLE t (...)`, `PARTITIONED BY (...)` and `INSERT INTO t (...)`.
+fn column_list_opens(tokens: &[TokenWithSpan]) -> Vec<usize> {
+    let mut opens = Vec::new();
+
+    for (i, token_with_span) in tokens.iter().enumerate() {
+        let token = &token_with_span.token;
+        let name_after = if is_word(token, "TABLE") {
+            // CREATE [EXTERNAL|TEMPORARY] TABLE [IF NOT EXISTS] name (, or INSERT INTO TABLE name (
+            let mut j = i;
+            while let Some(k) = next_significant(tokens, j)
+                && ["IF", "NOT", "EXISTS"].iter().any(|kw| is_word(&tokens[k].token, kw))
+            {
+                j = k;
+            }
+            Some(j)
+        } else if is_word(token, "INTO") {
+            next_significant(tokens, i).filter(|&k| !is_word(&tokens[k].token, "TABLE")).map(|_| i)
+        } else if is_word(token, "PARTITIONED") {
+            let Some(by) = next_significant(tokens, i).filter(|&k| is_word(&tokens[k].token, "BY")) else { continue };
+            if let Some(open) = next_significant(tokens, by).filter(|&k| matches!(tokens[k].token, Token::LParen)) {
+                opens.push(open);
+            }
+            continue;
+        } else {
+            None
+        };
+
+        // Skip a possibly qualified table name, then expect the list
+        let Some(mut j) = name_after else { continue };
+        let Some(name) = next_significant(tokens, j).filter(|&k| matches!(tokens[k].token, Token::Word(_))) else { continue };
+        j = name;
+        while let Some(dot) = next_significant(tokens, j).filter(|&k| matches!(tokens[k].token, Token::Period)) {
+            let Some(part) = next_significant(tokens, dot) else { break };
+            j = part;
+        }
+        if let Some(open) = next_significant(tokens, j).filter(|&k| matches!(tokens[k].token, Token::LParen)) {
+            opens.push(open);
+        }
+    }
+
+    opens
+}
+
+/// Returns the token ranges of each THEN/ELSE result of the CASE at `case_idx`.
+fn case_results(tokens: &[TokenWithSpan], case_idx: usize) -> Vec<std::ops::Range<usize>> {
+    let mut results = Vec::new();
+    let mut result_start: Option<usize> = None;
+    let mut depth = 0;
+    let mut nested_cases = 0;
+
+    for (j, token_with_span) in tokens.iter().enumerate().skip(case_idx + 1) {
+        let token = &token_with_span.token;
+        match token {
+            Token::LParen => depth += 1,
+            Token::RParen if depth == 0 => break,
+            Token::RParen => depth -= 1,
+            Token::SemiColon => break,
+            _ if depth > 0 => {}
+            _ if is_word(token, "CASE") => nested_cases += 1,
+            _ if is_word(token, "END") && nested_cases > 0 => nested_cases -= 1,
+            _ if nested_cases > 0 => {}
+            _ if is_word(token, "WHEN") || is_word(token, "ELSE") || is_word(token, "END") => {
+                if let Some(start) = result_start.take() {
+                    results.push(start..j);
+                }
+                if is_word(token, "ELSE") {
+                    result_start = Some(j + 1);
+                }
+                if is_word(token, "END") {
+                    break;
+                }
+            }
+            _ if is_word(token, "THEN") => result_start = Some(j + 1),
+            _ => {}
+        }
+    }
+    results
+}
+
+/// Splits the arguments of the call whose `(` is at `open_idx` into token ranges.
+fn call_arguments(tokens: &[TokenWithSpan], open_idx: usize) -> Vec<std::ops::Range<usize>> {
+    let Some(close_idx) = matching_paren(tokens, open_idx) else { return vec![] };
+    let mut arguments = Vec::new();
+    let mut start = open_idx + 1;
+    let mut depth = 0;
+    for (j, token_with_span) in tokens.iter().enumerate().take(close_idx).skip(open_idx + 1) {
+        match token_with_span.token {
+            Token::LParen => depth += 1,
+            Token::RParen => depth -= 1,
+            Token::Comma if depth == 0 => {
+                arguments.push(start..j);
+                start = j + 1;
+            }
+            _ => {}
+        }
+    }
+    arguments.push(start..close_idx);
+    arguments
+}
+
+/// Classifies an expression consisting of a single literal. NULL and non-literals return `None`.
+fn literal_type(tokens: &[TokenWithSpan], expr: &std::ops::Range<usize>) -> Option<&'static str> {
+    let significant: Vec<&Token> = tokens[expr.clone()]
+        .iter()
+        .map(|t| &t.token)
+        .filter(|t| is_significant(t))
+        .collect();
+    match significant.as_slice() {
+        [Token::Number(_, _)] | [Token::Minus, Token::Number(_, _)] => Some("numeric"),
+        [Token::SingleQuotedString(_)] | [Token::DoubleQuotedString(_)] => Some("string"),
+        // Hive reads double-quoted text as a string literal
+        [Token::Word(w)] if w.quote_style == Some('"') => Some("string"),
+        [token] if is_word(token, "TRUE") || is_word(token, "FALSE") => Some("boolean"),
+        _ => None,
+    }
+}
+
+/// True if a `*` or `alias.*` projection appears in `range` (`COUNT(*)` doesn't count).
+fn has_star_projection(tokens: &[TokenWithSpan], range: std::ops::Range<usize>) -> bool {
+    let mut previous: Option<&Token> = None;
+    for token_with_span in &tokens[range] {
+        let token = &token_with_span.token;
+        if matches!(token, Token::Mul)
+            && previous.is_some_and(|p| {
+                matches!(p, Token::Comma | Token::Period) || is_word(p, "SELECT") || is_word(p, "DISTINCT")
+            })
+        {
+            return true;
+        }
+        if is_significant(token) {
+            previous = Some(token);
+        }
+    }
+    false
+}
+
+/// Deprecated functions and their replacements; configured `functions` add to these.
+/// `UNIX_TIMESTAMP()` without arguments is also deprecated, in favour of `CURRENT_TIMESTAMP`.
+const DEFAULT_DEPRECATED_FUNCTIONS: &[(&str, &str)] = &[("JAVA_METHOD", "REFLECT")];
+
+/// Property prefixes `set-property` accepts; configured `prefixes` add to these. Hive's own
+/// keys are listed by their second part, so a typo there is caught too; `hive.compat` is a
+/// whole key. Hadoop's are accepted by their first.
+const DEFAULT_PROPERTY_PREFIXES: &[&str] = &[
+    "hive.archive.", "hive.auto.", "hive.blobstore.", "hive.cbo.", "hive.cli.", "hive.compactor.",
+    "hive.compat", "hive.compute.", "hive.conf.", "hive.convert.", "hive.count.", "hive.ddl.",
+    "hive.default.", "hive.display.", "hive.enforce.", "hive.error.", "hive.exec.", "hive.execution.",
+    "hive.explain.", "hive.fetch.", "hive.fileformat.", "hive.groupby.", "hive.hashtable.",
+    "hive.heartbeat.", "hive.ignore.", "hive.input.", "hive.insert.", "hive.intermediate.", "hive.io.",
+    "hive.join.", "hive.limit.", "hive.llap.", "hive.load.", "hive.lock.", "hive.log.", "hive.map.",
+    "hive.mapjoin.", "hive.mapred.", "hive.materializedview.", "hive.merge.", "hive.metastore.",
+    "hive.msck.", "hive.multigroupby.", "hive.new.", "hive.optimize.", "hive.orc.", "hive.output.",
+    "hive.parquet.", "hive.ppd.", "hive.prewarm.", "hive.query.", "hive.repl.", "hive.resultset.",
+    "hive.sample.", "hive.scratchdir", "hive.script.", "hive.security.", "hive.server2.",
+    "hive.session.", "hive.skewjoin.", "hive.smbjoin.", "hive.sort.", "hive.spark.", "hive.stats.",
+    "hive.strict.", "hive.support.", "hive.tez.", "hive.txn.", "hive.typecheck.", "hive.udtf.",
+    "hive.variable.", "hive.vectorized.", "hive.warehouse.", "hive.zookeeper.",
+    "mapreduce.", "mapred.", "tez.", "yarn.", "dfs.", "fs.", "io.", "hadoop.", "parquet.", "orc.", "spark.",
+];
+
+/// Deprecated properties and their replacements; configured `deprecated` add to these.
+const DEFAULT_DEPRECATED_PROPERTIES: &[(&str, &str)] = &[
+    ("mapred.reduce.tasks", "mapreduce.job.reduces"),
+    ("mapred.map.tasks", "mapreduce.job.maps"),
+    ("mapred.job.name", "mapreduce.job.name"),
+    ("mapred.job.queue.name", "mapreduce.job.queuename"),
+    ("mapred.max.split.size", "mapreduce.input.fileinputformat.split.maxsize"),
+    ("mapred.min.split.size", "mapreduce.input.fileinputformat.split.minsize"),
+    ("mapred.output.compress", "mapreduce.output.fileoutputformat.compress"),
+    ("mapred.output.compression.codec", "mapreduce.output.fileoutputformat.compress.codec"),
+];
+
+/// Reducer and parallelism settings `tuning-set` flags by default.
+const DEFAULT_TUNING_KEYS: &[&str] = &[
+    "hive.exec.reducers.max",
+    "hive.exec.reducers.bytes.per.reducer",
+    "mapreduce.job.reduces",
+    "mapred.reduce.tasks",
+    "mapreduce.job.maps",
+    "mapred.map.tasks",
+    "hive.exec.parallel",
+    "hive.exec.parallel.thread.number",
+    "tez.grouping.min-size",
+    "tez.grouping.max-size",
+];
+
+/// Keywords that end a GROUP BY list.
+const GROUP_BY_TERMINATORS: &[&str] = &[
+    "HAVING", "ORDER", "LIMIT", "UNION", "WINDOW", "CLUSTER", "DISTRIBUTE", "SORT", "WITH", "GROUPING", "INSERT", "SELECT",
+];
+
+/// True if the token at `idx` is compared against, matched by LIKE, or listed in an `IN (...)`.
+fn in_value_position(tokens: &[TokenWithSpan], idx: usize) -> bool {
+    let Some(prev_idx) = (0..idx).rev().find(|&j| is_significant(&tokens[j].token)) else { return false };
+    match &tokens[prev_idx].token {
+        Token::Eq | Token::DoubleEq | Token::Neq | Token::Lt | Token::Gt | Token::LtEq | Token::GtEq | Token::Spaceship => true,
+        token if is_word(token, "LIKE") || is_word(token, "RLIKE") || is_word(token, "REGEXP") => true,
+        Token::LParen | Token::Comma => {
+            // Walk back to the enclosing `(` and check it opens an IN list
+            let mut depth = 0;
+            for j in (0..=prev_idx).rev() {
+                match tokens[j].token {
+                    Token::RParen => depth += 1,
+                    Token::LParen if depth == 0 => {
+                        return (0..j).rev().find(|&k| is_significant(&tokens[k].token)).is_some_and(|k| is_word(&tokens[k].token, "IN"));
+                    }
+                    Token::LParen => depth -= 1,
+                    _ => {}
+                }
+            }
+            false
+        }
+        _ => false,
+    }
+}
+
+/// Keywords that end a FROM clause (other than WHERE, which may hold the join condition).
+/// LATERAL is included because `LATERAL VIEW ... AS a, b` lists column aliases, not tables.
+const FROM_CLAUSE_TERMINATORS: &[&str] = &[
+    "LATERAL", "GROUP", "ORDER", "HAVING", "LIMIT", "UNION", "WINDOW", "CLUSTER", "DISTRIBUTE", "SORT", "INSERT", "SELECT",
+];
+
+/// The schema type of `qualifier.column`, or of an unqualified `column` if exactly one table
+/// in `aliases` (from `syntax::table_aliases`) has it.
+fn column_type<'a>(schema: &'a Schema, aliases: &HashMap<String, String>, qualifier: Option<&str>, column: &str) -> Option<&'a str> {
+    let tables: Vec<&str> = match qualifier {
+        Some(q) => aliases.get(&q.to_lowercase()).map(String::as_str).into_iter().collect(),
+        None => {
+            let mut names: Vec<&str> = aliases.values().map(String::as_str).collect();
+            names.sort_unstable();
+            names.dedup();
+            names
+        }
+    };
+    let types: Vec<&str> = tables
+        .iter()
+        .filter_map(|name| schema.table(name)?.column(column)?.data_type.as_deref())
+        .collect();
+    match types.as_slice() {
+        [declared] => Some(declared),
+        _ => None,
+    }
+}
+
+/// Uppercases a type name and drops whitespace, so `decimal(10, 2)` equals `DECIMAL(10,2)`.
+fn normalize_type(data_type: &str) -> String {
+    let normalized: String = data_type.chars().filter(|c| !c.is_whitespace()).collect::<String>().to_uppercase();
+    match normalized.as_str() {
+        "INTEGER" => "INT".to_string(),
+        _ => normalized,
+    }
+}
+
+/// Applies Hive's string literal escapes: `\\`, quotes and control characters are
+/// unescaped, `\%` and `\_` are kept for LIKE, and any other escaped character stands for itself.
+fn unescape_hive_string(raw: &str) -> String {
+    let mut value = String::with_capacity(raw.len());
+    let mut chars = raw.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => value.push('\n'),
+            Some('t') => value.push('\t'),
+            Some('r') => value.push('\r'),
+            Some('0') => value.push('\0'),
+            Some(c @ ('%' | '_')) => {
+                value.push('\\');
+                value.push(c);
+            }
+            Some(c) => value.push(c),
+            None => value.push('\\'),
+        }
+    }
+    value
+}
+
+/// Words that start or continue a join, ending the previous join's ON condition
+const JOIN_KEYWORDS: &[&str] = &["JOIN", "LEFT", "RIGHT", "INNER", "FULL", "CROSS", "OUTER", "SEMI", "ANTI"];
+
+/// Unquoted words in a predicate that aren't column names
+const PREDICATE_WORDS: &[&str] = &[
+    "AND", "OR", "NOT", "IS", "NULL", "TRUE", "FALSE", "IN", "BETWEEN", "LIKE", "RLIKE", "REGEXP",
+    "CASE", "WHEN", "THEN", "ELSE", "END",
+];
+
+/// Reads the table joined by the JOIN at `join_idx`, returning the lowercased name it's
+/// referred to by (its alias, else its name) and the index of its ON.
+fn joined_table_alias(tokens: &[TokenWithSpan], join_idx: usize) -> Option<(String, usize)> {
+    let mut idx = next_significant(tokens, join_idx)?;
+    let mut name = None;
+    if matches!(tokens[idx].token, Token::LParen) {
+        idx = matching_paren(tokens, idx)?;
+    } else {
+        // `name` or `db.name`
+        loop {
+            let Token::Word(w) = &tokens[idx].token else { return None };
+            name = Some(w.value.to_lowercase());
+            let next = next_significant(tokens, idx)?;
+            if !matches!(tokens[next].token, Token::Period) {
+                break;
+            }
+            idx = next_significant(tokens, next)?;
+        }
+    }
+
+    let mut next = next_significant(tokens, idx)?;
+    if is_word(&tokens[next].token, "AS") {
+        next = next_significant(tokens, next)?;
+    }
+    if let Token::Word(w) = &tokens[next].token
+        && !is_word(&tokens[next].token, "ON")
+        && (w.quote_style.is_some() || syntax::can_be_table_alias(&w.value))
+    {
+        name = Some(w.value.to_lowercase());
+        next = next_significant(tokens, next)?;
+    }
+    is_word(&tokens[next].token, "ON").then_some((name?, next))
+}
+
+fn syntax_error_location_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(r" at Line: (\d+), Column: (\d+)$").unwrap())
+}
+
+/// The lowercased alias of a select item given its significant tokens: `expr AS alias`
+/// or `expr alias`.
+fn select_item_alias(tokens: &[TokenWithSpan], significant: &[usize]) -> Option<String> {
+    let [.., before, last] = significant else { return None };
+    let Token::Word(alias) = &tokens[*last].token else { return None };
+    if alias.quote_style.is_none() && is_keyword(alias) {
+        return None;
+    }
+    match &tokens[*before].token {
+        Token::Word(w) if w.quote_style.is_none() && is_keyword(w) && !w.value.eq_ignore_ascii_case("AS") => None,
+        Token::Word(_) | Token::RParen | Token::Number(_, _) | Token::SingleQuotedString(_) => Some(alias.value.to_lowercase()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::LintingRules;
+
+    fn get_messages(diagnostics: &[Diagnostic]) -> Vec<String> {
+        diagnostics.iter().map(|d| d.message.clone()).collect()
+    }
+
+    fn default_config() -> LintingConfig {
+        // Enable all rules for testing
+        let rules = LintingRules {
+            keyword_casing: true.into(),
+            semicolon: true.into(),
+            string_literal: true.into(),
+            parentheses: true.into(),
+            trailing_whitespace: true.into(),
+            missing_comma: true.into(),
+            hive_variable: true.into(),
+            scalar_subquery_columns: true.into(),
+            foreign_operator: true.into(),
+            limit_value: true.into(),
+            insert_column_order: true.into(),
+            mixed_aggregate: true.into(),
+            misplaced_distinct: true.into(),
+            empty_table_parens: true.into(),
+            boolean_literal_casing: true.into(),
+            statement_length: true.into(),
+            // Off here since many fixtures use `SELECT *`
+            select_star: false.into(),
+            reserved_identifier: true.into(),
+            empty_window: true.into(),
+            case_branch_types: true.into(),
+            unused_cte_column: true.into(),
+            cte_order_by: true.into(),
+            tuning_set: true.into(),
+            trailing_comma: true.into(),
+            indentation: true.into(),
+            wide_group_by: true.into(),
+            max_line_length: true.into(),
+            unsafe_interpolation: true.into(),
+            cartesian_join: true.into(),
+            redundant_cast: true.into(),
+            invalid_regex: true.into(),
+            duplicate_set_column: true.into(),
+            cte_missing_as: true.into(),
+            non_join_on: true.into(),
+            alias_style_consistency: true.into(),
+            syntax: false.into(),
+            null_into_not_null: true.into(),
+            limit_without_order: false.into(),
+            insert_select_arity: true.into(),
+            duplicate_select_column: true.into(),
+            truncate_external: true.into(),
+            cluster_order_conflict: true.into(),
+            implicit_default_db: true.into(),
+            constant_select_item: true.into(),
+            no_tabs: false.into(),
+            comment: true.into(),
+            too_many_name_parts: true.into(),
+            comma_join: false.into(),
+            equality_operator: true.into(),
+            between_bounds: true.into(),
+            union_dedup: false.into(),
+            distinct_with_group_by: true.into(),
+            ambiguous_column: true.into(),
+            order_by_ordinal: true.into(),
+            null_comparison: true.into(),
+            semicolon_in_parens: true.into(),
+            keyword_alias: true.into(),
+            group_by_consistency: false.into(),
+            missing_over: true.into(),
+            missing_stored_as: true.into(),
+            duplicate_alias: true.into(),
+            deprecated_function: true.into(),
+            constant_predicate: false.into(),
+            type_casing: false.into(),
+            type_mismatch: false.into(),
+            select_distinct_star: true.into(),
+            inconsistent_quoting: false.into(),
+            missing_partition: false.into(),
+            like_without_wildcard: true.into(),
+            multi_column_count_distinct: false.into(),
+            batch_separator: true.into(),
+            union_column_mismatch: true.into(),
+            unbalanced_case: true.into(),
+            subquery_depth: true.into(),
+            set_property: true.into(),
+        };
+
+        LintingConfig {
+            enabled: true,
+            severity: "Hint".to_string(),
+            max_file_size: 1048576,
+            rules,
+            ..LintingConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_keyword_casing() {
+        let sql = "select * from users";
+        let diags = lint(sql, &default_config());
+        let msgs = get_messages(&diags);
+        assert!(msgs.iter().any(|m| m.contains("Keyword 'select' should be uppercase")));
+        assert!(msgs.iter().any(|m| m.contains("Keyword 'from' should be uppercase")));
+    }
+
+    #[test]
+    fn test_keyword_casing_additional_keywords() {
+        let config = LintingConfig {
+            additional_keywords: vec!["qualify".to_string(), "WITHIN GROUP".to_string()],
+            ..default_config()
+        };
+        let sql = "SELECT a FROM t qualify rank() OVER (ORDER BY a) = 1;\nSELECT percentile_cont(0.5) within GROUP (ORDER BY a), t.qualify FROM t;";
+        let flagged: Vec<_> = lint(sql, &config)
+            .into_iter()
+            .filter(|d| d.code == Some(NumberOrString::String("keyword-casing".to_string())))
+            .map(|d| d.message)
+            .collect();
+        assert_eq!(flagged, vec!["Keyword 'qualify' should be uppercase", "Keyword 'within' should be uppercase"]);
+        assert!(!lint(sql, &default_config()).iter().any(|d| d.message.contains("'qualify'")));
+    }
+
+    #[test]
+    fn test_keyword_casing_ddl() {
+        let sql = "CREATE TABLE t (location STRING, `select` INT)\npartitioned by (dt STRING) stored as ORC location '/data/t';";
+        let diags = lint(sql, &default_config());
+        let casing: Vec<_> = diags.iter()
+            .filter(|d| d.code == Some(NumberOrString::String("keyword-casing".to_string())))
+            .map(|d| d.message.as_str())
+            .collect();
+        assert_eq!(casing, vec![
+            "Keyword 'partitioned' should be uppercase",
+            "Keyword 'by' should be uppercase",
+            "Keyword 'stored' should be uppercase",
+            "Keyword 'as' should be uppercase",
+            "Keyword 'location' should be uppercase",
+        ]);
+    }
+
+    #[test]
+    fn test_keyword_casing_valid() {
+        let sql = "SELECT * FROM users;";
+        let diags = lint(sql, &default_config());
+        let msgs = get_messages(&diags);
+        assert!(msgs.is_empty());
+    }
+
+    #[test]
+    fn test_missing_semicolon_multi_query() {
+        let sql = "SELECT * FROM users WHERE id = 1\n\nSELECT * FROM orders WHERE status = 'active';";
+        let diags = lint(sql, &default_config());
+        let msgs = get_messages(&diags);
+        assert!(msgs.iter().any(|m| m.contains("Missing semicolon")));
+    }
+
+    #[test]
+    fn test_semicolon_valid() {
+        let sql = "SELECT * FROM users; SELECT * FROM orders;";
+        let diags = lint(sql, &default_config());
+        let msgs = get_messages(&diags);
+        assert!(!msgs.iter().any(|m| m.contains("Missing semicolon")));
+    }
+
+    // Ported from SemicolonRule - DDL and Subqueries
+    #[test]
+    fn test_semicolon_subquery_no_flag() {
+        let sql = "SELECT *\nFROM users\nWHERE id IN (\n  SELECT user_id\n  FROM orders\n)\nAND created_date > '2024';";
+        let diags = lint(sql, &default_config());
+        let msgs = get_messages(&diags);
+        assert!(!msgs.iter().any(|m| m.contains("Missing semicolon")));
+    }
+
+    #[test]
+    fn test_semicolon_ddl_no_flag() {
+        let sql = "CREATE TABLE sales (id INT)\nPARTITIONED BY (year INT)\nSTORED AS PARQUET\nLOCATION '/data';";
+        let diags = lint(sql, &default_config());
+        let msgs = get_messages(&diags);
+        assert!(!msgs.iter().any(|m| m.contains("Missing semicolon")));
+    }
+
+    #[test]
+    fn test_semicolon_statement_continuations_no_flag() {
+        for sql in [
+            "CREATE TABLE x AS\nSELECT a FROM t;",
+            "CREATE TABLE x AS\nWITH c AS (SELECT 1) SELECT * FROM c;",
+            "INSERT OVERWRITE TABLE t PARTITION (dt = '2024')\nSELECT a\nFROM s;",
+            "INSERT INTO t VALUES (1), (2);",
+            "WITH c AS (SELECT 1)\nINSERT OVERWRITE TABLE t SELECT * FROM c;",
+            "FROM src\nINSERT OVERWRITE TABLE a SELECT x\nINSERT OVERWRITE TABLE b SELECT y;",
+            "SELECT 1 UNION ALL SELECT 2;",
+            "ALTER TABLE t SET TBLPROPERTIES ('a' = 'b');\nALTER TABLE t DROP PARTITION (dt = 'x');\nALTER VIEW v AS SELECT 1;",
+            "UPDATE t SET a = 1 WHERE b = 2;",
+            "MERGE INTO t USING s ON t.id = s.id\nWHEN MATCHED THEN UPDATE SET a = s.a\nWHEN NOT MATCHED THEN INSERT VALUES (s.id, s.a);",
+            "SHOW CREATE TABLE t;\nEXPLAIN INSERT INTO t SELECT 1;\nGRANT SELECT ON TABLE t TO USER bob;",
+            "SET hive.exec.dynamic.partition=true;\nINSERT OVERWRITE TABLE t PARTITION (dt) SELECT a, dt FROM s;",
+        ] {
+            let diags = lint(sql, &default_config());
+            let msgs = get_messages(&diags);
+            assert!(!msgs.iter().any(|m| m.contains("Missing semicolon")), "{}", sql);
+        }
+    }
+
+    #[test]
+    fn test_semicolon_between_statements_still_flagged() {
+        for sql in [
+            "SET hive.exec.dynamic.partition=true\nSELECT 1;",
+            "INSERT INTO a VALUES (1)\nINSERT INTO b VALUES (2);",
+            "EXPLAIN SELECT 1\nSELECT 2;",
+            "UPDATE t SET a = 1\nDELETE FROM t WHERE a = 2;",
+        ] {
+            let diags = lint(sql, &default_config());
+            let msgs = get_messages(&diags);
+            assert!(msgs.iter().any(|m| m.contains("Missing semicolon")), "{}", sql);
+        }
+    }
+
+    #[test]
+    fn test_unbalanced_parentheses() {
+        let sql = "SELECT * FROM users WHERE (id = 1";
+        let diags = lint(sql, &default_config());
+        let msgs = get_messages(&diags);
+        assert!(msgs.iter().any(|m| m.contains("Unbalanced parentheses")));
+    }
+
+    #[test]
+    fn test_extra_paren_points_at_last_match() {
+        let sql = "SELECT count(a), max(b))\nFROM t;";
+        let mut diags = lint(sql, &default_config());
+        let extra = diags.iter().find(|d| d.message == "Unbalanced parentheses: extra ')'").unwrap();
+        assert_eq!(extra.range.start, Position { line: 0, character: 23 });
+        let related = extra.related_information.as_ref().unwrap();
+        assert_eq!(related.len(), 1);
+        assert_eq!(related[0].location.range.start, Position { line: 0, character: 20 });
+
+        let uri = Url::parse("file:///query.hql").unwrap();
+        resolve_related(&uri, &mut diags);
+        let extra = diags.iter().find(|d| d.message == "Unbalanced parentheses: extra ')'").unwrap();
+        assert_eq!(extra.related_information.as_ref().unwrap()[0].location.uri, uri);
+
+        // Nothing was closed before it
+        let diags = lint("SELECT a) FROM t;", &default_config());
+        let extra = diags.iter().find(|d| d.message == "Unbalanced parentheses: extra ')'").unwrap();
+        assert_eq!(extra.related_information, None);
+    }
+
+    #[test]
+    fn test_balanced_parentheses_complex() {
+        let sql = "SELECT * FROM users WHERE id IN (SELECT user_id FROM orders WHERE (amt > 100))";
+        let diags = lint(sql, &default_config());
+        let msgs = get_messages(&diags);
+        assert!(!msgs.iter().any(|m| m.contains("Unbalanced parentheses")));
+    }
+
+    #[test]
+    fn test_trailing_whitespace() {
+        let sql = "SELECT * FROM users \nWHERE id = 1";
+        let diags = lint(sql, &default_config());
+        let msgs = get_messages(&diags);
+        assert!(msgs.iter().any(|m| m.contains("Trailing whitespace")));
+    }
+
+    #[test]
+    fn test_trailing_whitespace_crlf() {
+        let sql = "SELECT 1 \r\nFROM t;\r\nSELECT 2;\r";
+        let diags = lint(sql, &default_config());
+        let trailing: Vec<_> = diags
+            .iter()
+            .filter(|d| d.code == Some(NumberOrString::String("trailing-whitespace".to_string())))
+            .collect();
+        assert_eq!(trailing.len(), 1);
+        assert_eq!(trailing[0].range.start, Position { line: 0, character: 8 });
+        assert_eq!(trailing[0].range.end, Position { line: 0, character: 9 });
+    }
+
+    #[test]
+    fn test_max_diagnostics() {
+        let sql = "SELECT 1;  \n".repeat(11);
+        let config = LintingConfig { max_diagnostics: 4, ..default_config() };
+        let diags = lint(&sql, &config);
+        assert_eq!(diags.len(), 5);
+        let lines: Vec<u32> = diags[..4].iter().map(|d| d.range.start.line).collect();
+        assert_eq!(lines, vec![0, 1, 2, 3]);
+        assert_eq!(diags[4].range, Range::default());
+        assert_eq!(diags[4].severity, Some(DiagnosticSeverity::INFORMATION));
+        assert_eq!(diags[4].message, "7 more diagnostics not shown; only the first 4 (maxDiagnostics) are reported");
+        assert_eq!(lint(&sql, &config), diags);
+
+        // The note has a code of its own, so it can be silenced
+        assert_eq!(diags[4].code, Some(NumberOrString::String("max-diagnostics".to_string())));
+        let silenced = format!("-- hqlint-disable max-diagnostics\n{}", sql);
+        assert_eq!(lint(&silenced, &config).len(), 4);
+
+        let uncapped = LintingConfig { max_diagnostics: 0, ..default_config() };
+        assert_eq!(lint(&sql, &uncapped).len(), 11);
+    }
+
+    #[test]
+    fn test_whitespace_only_line() {
+        let sql = "SELECT a\n    \nFROM t;  ";
+        let trailing: Vec<_> = lint(sql, &default_config())
+            .into_iter()
+            .filter(|d| d.code == Some(NumberOrString::String("trailing-whitespace".to_string())))
+            .map(|d| (d.range, d.message))
+            .collect();
+        let range = |line, start, end| Range {
+            start: Position { line, character: start },
+            end: Position { line, character: end },
+        };
+        assert_eq!(trailing, vec![
+            (range(1, 0, 4), "Whitespace-only line".to_string()),
+            (range(2, 7, 9), "Trailing whitespace".to_string()),
+        ]);
+
+        // Columns after the emoji count it as two UTF-16 units
+        let sql = "SELECT '😀'\t \n;";
+        let trailing = lint(sql, &default_config())
+            .into_iter()
+            .find(|d| d.code == Some(NumberOrString::String("trailing-whitespace".to_string())))
+            .unwrap();
+        assert_eq!(trailing.range, range(0, 11, 13));
+    }
+
+    #[test]
+    fn test_ranges_are_utf16() {
+        // The emoji is one char but two UTF-16 code units
+        let sql = "SELECT '😀' AS e from t;";
+        let diags = lint(sql, &default_config());
+        let casing = diags
+            .iter()
+            .find(|d| d.code == Some(NumberOrString::String("keyword-casing".to_string())))
+            .unwrap();
+        assert_eq!(casing.range.start, Position { line: 0, character: 17 });
+        assert_eq!(casing.range.end, Position { line: 0, character: 21 });
+    }
+
+    #[test]
+    fn test_dialect_controls_quoting() {
+        // Only some dialects read `\'` as an escaped quote
+        let sql = "SELECT 'it\\'s' FROM t;";
+        let mut config = default_config();
+        assert!(get_messages(&lint(sql, &config)).iter().any(|m| m.contains("Unterminated string literal")));
+
+        config.dialect = "mysql".to_string();
+        assert!(lint(sql, &config).is_empty());
+    }
+
+    #[test]
+    fn test_treat_warnings_as_errors() {
+        let sql = "select 1;";
+        let severity_of = |config: &LintingConfig, code: &str| {
+            lint(sql, config)
+                .into_iter()
+                .find(|d| d.code == Some(NumberOrString::String(code.to_string())))
+                .and_then(|d| d.severity)
+        };
+
+        let mut config = default_config();
+        config.treat_warnings_as_errors = true;
+        assert_eq!(severity_of(&config, "keyword-casing"), Some(DiagnosticSeverity::ERROR));
+
+        let sql = "SELECT 1; \n";
+        let hint = |config: &LintingConfig| lint(sql, config)[0].severity;
+        assert_eq!(hint(&config), Some(DiagnosticSeverity::HINT));
+        config.treat_hints_as_errors = true;
+        assert_eq!(hint(&config), Some(DiagnosticSeverity::ERROR));
+    }
+
+    #[test]
+    fn test_hive_variables() {
+        let sql = "SELECT ${hiveconf:my_var} FROM table";
+        let diags = lint(sql, &default_config());
+        let msgs = get_messages(&diags);
+        assert!(!msgs.iter().any(|m| m.contains("Invalid Hive variable")));
+    }
+
+    #[test]
+    fn test_hive_variables_invalid() {
+        let sql = "SELECT ${invalid:var} FROM table";
+        let diags = lint(sql, &default_config());
+        let msgs = get_messages(&diags);
+        assert!(msgs.iter().any(|m| m.contains("Invalid namespace")));
+    }
+
+    #[test]
+    fn test_hive_variables_configured() {
+        let mut config = default_config();
+        config.hive_variable_namespaces.push("custom".to_string());
+        config.hive_variables.insert("hivevar".to_string(), HashMap::from([("db".to_string(), "prod_db".to_string())]));
+        let sql = "SELECT ${custom:x}, ${hiveconf:anything} FROM ${hivevar:db}.t WHERE ${hivevar:undefined_var};";
+        let diags = lint(sql, &config);
+        let msgs = get_messages(&diags);
+        assert!(!msgs.iter().any(|m| m.contains("Invalid namespace")));
+        let unknown: Vec<_> = msgs.iter().filter(|m| m.contains("Unknown variable")).collect();
+        assert_eq!(unknown, vec!["Unknown variable 'undefined_var' in namespace 'hivevar'"]);
+    }
+
+    #[test]
+    fn test_hive_variables_empty() {
+        let sql = "SELECT ${} FROM table";
+        let diags = lint(sql, &default_config());
+        let msgs = get_messages(&diags);
+        assert!(msgs.iter().any(|m| m.contains("Empty Hive variable")));
+    }
+
+    #[test]
+    fn test_missing_comma() {
+        let sql = "SELECT id name FROM users;";
+        let diags = lint(sql, &default_config());
+        let msgs = get_messages(&diags);
+        // Heuristic only checks multiline to avoid flagging valid aliases 'SELECT col alias'
+        // So this should NOT have a warning.
+        assert!(msgs.is_empty());
+    }
+
+    #[test]
+    fn test_missing_comma_multiline() {
+        let sql = "SELECT\n  id\n  name\nFROM users";
+        let diags = lint(sql, &default_config());
+        let msgs = get_messages(&diags);
+        assert!(msgs.iter().any(|m| m.contains("Possible missing comma")));
+    }
+
+    #[test]
+    fn test_missing_comma_ignores_wrapped_calls() {
+        let sql = "SELECT\n  COALESCE(a\n    b),\n  id\n  CONCAT(x, y)\nFROM users;";
+        let diags = lint(sql, &default_config());
+        assert!(!get_messages(&diags).iter().any(|m| m.contains("Possible missing comma")));
+
+        let sql = "SELECT * FROM (\n  SELECT id\n  name\n  FROM users\n) u;";
+        let diags = lint(sql, &default_config());
+        assert!(get_messages(&diags).iter().any(|m| m.contains("Possible missing comma")));
+    }
+
+    #[test]
+    fn test_missing_comma_with_comma_present() {
+        let sql = "SELECT\n  p.product_id,\n  p.product_name,\n  i.quantity\nFROM products p;";
+        let diags = lint(sql, &default_config());
+        let msgs = get_messages(&diags);
+        assert!(msgs.is_empty());
+    }
+
+    #[test]
+    fn test_scalar_subquery_multiple_columns() {
+        let sql = "SELECT * FROM users WHERE id = (SELECT user_id, name FROM orders);";
+        let diags = lint(sql, &default_config());
+        let msgs = get_messages(&diags);
+        assert!(msgs.iter().any(|m| m.contains("Scalar subquery must return exactly one column")));
+    }
+
+    #[test]
+    fn test_scalar_subquery_single_column() {
+        let sql = "SELECT * FROM users WHERE id = (SELECT MAX(user_id) FROM orders);";
+        let diags = lint(sql, &default_config());
+        let msgs = get_messages(&diags);
+        assert!(!msgs.iter().any(|m| m.contains("Scalar subquery")));
+    }
+
+    #[test]
+    fn test_in_subquery_multiple_columns_not_scalar() {
+        let sql = "SELECT * FROM users WHERE (id, name) IN (SELECT user_id, name FROM orders);";
+        let diags = lint(sql, &default_config());
+        let msgs = get_messages(&diags);
+        assert!(!msgs.iter().any(|m| m.contains("Scalar subquery")));
+    }
+
+    #[test]
+    fn test_foreign_operator_double_ampersand() {
+        let sql = "SELECT * FROM users WHERE active = 1 && age > 18;";
+        let diags = lint(sql, &default_config());
+        let msgs = get_messages(&diags);
+        assert!(msgs.iter().any(|m| m.contains("Operator '&&' is not valid HQL; use AND")));
+    }
+
+    #[test]
+    fn test_foreign_operator_and_keyword_clean() {
+        let sql = "SELECT * FROM users WHERE active = 1 AND age > 18;";
+        let diags = lint(sql, &default_config());
+        let msgs = get_messages(&diags);
+        assert!(!msgs.iter().any(|m| m.contains("not valid HQL")));
+    }
+
+    #[test]
+    fn test_foreign_operator_assignment_and_triple_equals() {
+        let sql = "SELECT * FROM users WHERE id := 1 OR id === 2;";
+        let diags = lint(sql, &default_config());
+        let msgs = get_messages(&diags);
+        assert!(msgs.iter().any(|m| m.contains("Operator ':='")));
+        assert!(msgs.iter().any(|m| m.contains("Operator '==='")));
+    }
+
+    #[test]
+    fn test_foreign_operator_arrows() {
+        let sql = "SELECT f(a => b) FROM t;\nSELECT transform(xs, x -> x + 1), a - b, c >= 1, d = -1 FROM t;";
+        let foreign: Vec<_> = lint(sql, &default_config())
+            .into_iter()
+            .filter(|d| d.code == Some(NumberOrString::String("foreign-operator".to_string())))
+            .collect();
+        assert_eq!(foreign.len(), 2);
+        assert_eq!(foreign[0].severity, Some(DiagnosticSeverity::ERROR));
+        assert_eq!(foreign[0].range.start, Position { line: 0, character: 11 });
+        assert_eq!(foreign[0].range.end, Position { line: 0, character: 13 });
+        assert_eq!(foreign[0].message, "Operator '=>' is not valid HQL; HiveQL has no named arguments; pass arguments by position");
+        assert_eq!(foreign[1].range.start, Position { line: 1, character: 23 });
+        assert_eq!(foreign[1].range.end, Position { line: 1, character: 25 });
+        assert_eq!(foreign[1].message, "Operator '->' is not valid HQL; HiveQL has no lambda expressions");
+    }
+
+    #[test]
+    fn test_foreign_operator_pipe_concat_allowed_in_hive() {
+        let sql = "SELECT first_name || last_name FROM users;";
+        let diags = lint(sql, &default_config());
+        let msgs = get_messages(&diags);
+        assert!(!msgs.iter().any(|m| m.contains("not valid HQL")));
+    }
+
+    #[test]
+    fn test_foreign_operator_pipe_is_or_in_mysql() {
+        let mut config = default_config();
+        config.dialect = "mysql".to_string();
+        let foreign: Vec<_> = lint("SELECT * FROM users WHERE active = 1 || age > 18;", &config)
+            .into_iter()
+            .filter(|d| d.code == Some(NumberOrString::String("foreign-operator".to_string())))
+            .collect();
+        assert_eq!(foreign.len(), 1);
+        assert_eq!(foreign[0].message, "Operator '||' is not valid HQL; use OR, or CONCAT() for strings");
+    }
+
+    #[test]
+    fn test_limit_zero() {
+        let sql = "SELECT * FROM users LIMIT 0;";
+        let diags = lint(sql, &default_config());
+        let diag = diags.iter().find(|d| d.message.contains("LIMIT 0")).unwrap();
+        assert_eq!(diag.severity, Some(DiagnosticSeverity::HINT));
+    }
+
+    #[test]
+    fn test_limit_negative() {
+        let sql = "SELECT * FROM users LIMIT -1;";
+        let diags = lint(sql, &default_config());
+        let diag = diags.iter().find(|d| d.message.contains("LIMIT must not be negative")).unwrap();
+        assert_eq!(diag.severity, Some(DiagnosticSeverity::ERROR));
+        assert_eq!(diag.range.start.character, 26);
+        assert_eq!(diag.range.end.character, 28);
+    }
+
+    #[test]
+    fn test_limit_positive() {
+        let sql = "SELECT * FROM users LIMIT 10;";
+        let diags = lint(sql, &default_config());
+        let msgs = get_messages(&diags);
+        assert!(!msgs.iter().any(|m| m.contains("LIMIT")));
+    }
+
+    #[test]
+    fn test_limit_with_offset_checks_count() {
+        let sql = "SELECT * FROM users LIMIT 0, 10;";
+        let diags = lint(sql, &default_config());
+        let msgs = get_messages(&diags);
+        assert!(!msgs.iter().any(|m| m.contains("LIMIT")));
+    }
+
+    #[test]
+    fn test_insert_column_order_matching() {
+        let sql = "INSERT INTO target (a, b)\nSELECT x AS a, s.b FROM source s;";
+        let diags = lint(sql, &default_config());
+        let msgs = get_messages(&diags);
+        assert!(!msgs.iter().any(|m| m.contains("column order")));
+    }
+
+    #[test]
+    fn test_insert_column_order_reordered() {
+        let sql = "INSERT INTO target (a, b)\nSELECT y AS b, x AS a FROM source;";
+        let diags = lint(sql, &default_config());
+        let msgs = get_messages(&diags);
+        assert!(msgs.iter().any(|m| m.contains("SELECT column 'b' is inserted into target column 'a'")));
+    }
+
+    #[test]
+    fn test_insert_column_order_skips_partition_spec() {
+        let sql = "INSERT INTO target PARTITION (dt = '2024') (a, b)\nSELECT b, a FROM source;";
+        let diags = lint(sql, &default_config());
+        let msgs = get_messages(&diags);
+        assert!(msgs.iter().any(|m| m.contains("column order")));
+    }
+
+    #[test]
+    fn test_mixed_aggregate_without_group_by() {
+        let sql = "SELECT id, COUNT(*) FROM t;";
+        let diagnostics = lint(sql, &default_config());
+        let mixed: Vec<_> = diagnostics.iter()
+            .filter(|d| d.code == Some(NumberOrString::String("mixed-aggregate".to_string())))
+            .collect();
+        assert_eq!(mixed.len(), 1);
+        assert!(mixed[0].message.contains("'id'"));
+        assert_eq!(mixed[0].severity, Some(DiagnosticSeverity::ERROR));
+    }
+
+    #[test]
+    fn test_mixed_aggregate_valid_queries() {
+        let sql = "SELECT COUNT(*), MAX(ts) FROM t;\n\
+                   SELECT id, COUNT(*) FROM t GROUP BY id;\n\
+                   SELECT id, COUNT(*) OVER (PARTITION BY id) FROM t;";
+        let diagnostics = lint(sql, &default_config());
+        assert!(!get_messages(&diagnostics).iter().any(|m| m.contains("aggregate")));
+    }
+
+    #[test]
+    fn test_mixed_aggregate_scoped_to_query() {
+        // The inner query is grouped; the outer one has no aggregates
+        let sql = "SELECT s.id FROM (SELECT id, SUM(x) AS total FROM t GROUP BY id) s;\n\
+                   SELECT a.id, (SELECT MAX(v) FROM u) FROM a;";
+        let diagnostics = lint(sql, &default_config());
+        assert!(!get_messages(&diagnostics).iter().any(|m| m.contains("aggregate")));
+    }
+
+    #[test]
+    fn test_rule_severity_override() {
+        let mut config = default_config();
+        config.rules.missing_comma = RuleConfig { severity: Some("error".to_string()), ..true.into() };
+        let sql = "SELECT\n  id\n  name\nFROM users; \n";
+        let diagnostics = lint(sql, &config);
+
+        let comma = diagnostics.iter()
+            .find(|d| d.code == Some(NumberOrString::String("missing-comma".to_string())))
+            .unwrap();
+        assert_eq!(comma.severity, Some(DiagnosticSeverity::ERROR));
+        let whitespace = diagnostics.iter()
+            .find(|d| d.message.contains("Trailing whitespace"))
+            .unwrap();
+        assert_eq!(whitespace.severity, Some(DiagnosticSeverity::HINT));
+    }
+
+    #[test]
+    fn test_semicolon_inside_string_not_a_terminator() {
+        let sql = "SELECT 'a;b'";
+        let diags = lint(sql, &default_config());
+        let missing: Vec<_> = diags.iter()
+            .filter(|d| d.code == Some(NumberOrString::String("missing-semicolon".to_string())))
+            .collect();
+        assert_eq!(missing.len(), 1);
+        assert_eq!(missing[0].message, "Missing semicolon at end of file");
+        assert_eq!(missing[0].range.start, Position { line: 0, character: 12 });
+    }
+
+    #[test]
+    fn test_semicolon_inside_comments_not_a_terminator() {
+        let sql = "SELECT 1 -- done; SELECT 2\nFROM t /* ; SELECT */\nWHERE x = 'y;'";
+        let diags = lint(sql, &default_config());
+        let missing: Vec<_> = diags.iter()
+            .filter(|d| d.code == Some(NumberOrString::String("missing-semicolon".to_string())))
+            .collect();
+        assert_eq!(missing.len(), 1);
+        assert_eq!(missing[0].range.start, Position { line: 2, character: 14 });
+    }
+
+    #[test]
+    fn test_trailing_comment_is_not_a_terminator() {
+        let missing = |sql| -> Vec<Diagnostic> {
+            lint(sql, &default_config())
+                .into_iter()
+                .filter(|d| d.code == Some(NumberOrString::String("missing-semicolon".to_string())))
+                .collect()
+        };
+
+        let flagged = missing("SELECT 1 -- comment");
+        assert_eq!(flagged.len(), 1);
+        assert_eq!(flagged[0].range.start, Position { line: 0, character: 8 });
+
+        let flagged = missing("SELECT 1 /* comment */
+SELECT 2;");
+        assert_eq!(flagged.len(), 1);
+        assert_eq!(flagged[0].range.start, Position { line: 0, character: 8 });
+
+        assert!(missing("SELECT 1; -- comment").is_empty());
+    }
+
+    #[test]
+    fn test_semicolon_inside_string_with_terminator() {
+        let sql = "SELECT 'a;b', \"c;d\" FROM t;\nSELECT 2;";
+        let diags = lint(sql, &default_config());
+        assert!(!get_messages(&diags).iter().any(|m| m.contains("semicolon")));
+    }
+
+    #[test]
+    fn test_misplaced_distinct() {
+        let diags = lint("SELECT a, DISTINCT b FROM t;", &default_config());
+        let distinct: Vec<_> = diags.iter()
+            .filter(|d| d.code == Some(NumberOrString::String("misplaced-distinct".to_string())))
+            .collect();
+        assert_eq!(distinct.len(), 1);
+        assert_eq!(distinct[0].range.start, Position { line: 0, character: 10 });
+    }
+
+    #[test]
+    fn test_distinct_valid_positions() {
+        let sql = "SELECT DISTINCT a, b FROM t;\nSELECT a, COUNT(DISTINCT x) FROM t GROUP BY a;";
+        let diags = lint(sql, &default_config());
+        assert!(!get_messages(&diags).iter().any(|m| m.contains("DISTINCT")));
+    }
+
+    #[test]
+    fn test_is_distinct_from_is_not_misplaced() {
+        let sql = "SELECT a IS DISTINCT FROM b FROM t;\nSELECT a, b IS NOT DISTINCT FROM c FROM t;";
+        let diags = lint(sql, &default_config());
+        assert!(!diags.iter().any(|d| d.code == Some(NumberOrString::String("misplaced-distinct".to_string()))));
+    }
+
+    #[test]
+    fn test_empty_table_parens() {
+        let diags = lint("SELECT * FROM db.t() JOIN u ON t.id = u.id;", &default_config());
+        let parens: Vec<_> = diags.iter()
+            .filter(|d| d.code == Some(NumberOrString::String("empty-table-parens".to_string())))
+            .collect();
+        assert_eq!(parens.len(), 1);
+        assert!(parens[0].message.contains("'db.t'"));
+    }
+
+    #[test]
+    fn test_empty_table_parens_plain_table_and_allowlist() {
+        let mut config = default_config();
+        config.rules.empty_table_parens.allow = vec!["my_udtf".to_string()];
+        let diags = lint("SELECT * FROM t;\nSELECT * FROM MY_UDTF();", &config);
+        assert!(!get_messages(&diags).iter().any(|m| m.contains("Empty parentheses")));
+    }
+
+    #[test]
+    fn test_unclosed_paren_location() {
+        let sql = "SELECT (a\nFROM t\nWHERE x IN (1, (2);";
+        let diags = lint(sql, &default_config());
+        let unclosed: Vec<_> = diags.iter().filter(|d| d.message.contains("unclosed '('")).collect();
+        assert_eq!(unclosed.len(), 2);
+        assert_eq!(unclosed[0].range.start, Position { line: 0, character: 7 });
+        assert_eq!(unclosed[1].range.start, Position { line: 2, character: 11 });
+    }
+
+    #[test]
+    fn test_every_extra_paren_reported() {
+        let sql = "SELECT a)\nFROM t) WHERE (b = 1;";
+        let diags = lint(sql, &default_config());
+        let extra: Vec<_> = diags.iter().filter(|d| d.message.contains("extra ')'")).collect();
+        assert_eq!(extra.len(), 2);
+        assert_eq!(extra[0].range.start, Position { line: 0, character: 8 });
+        assert_eq!(extra[1].range.start, Position { line: 1, character: 6 });
+        assert!(diags.iter().any(|d| d.message.contains("unclosed '('") && d.range.start == Position { line: 1, character: 14 }));
+    }
+
+    #[test]
+    fn test_boolean_literal_casing_upper() {
+        let sql = "SELECT true, False, NULL, TRUE FROM t WHERE x IS null;";
+        let diags = lint(sql, &default_config());
+        let flagged: Vec<_> = diags.iter()
+            .filter(|d| d.code == Some(NumberOrString::String("boolean-literal-casing".to_string())))
+            .map(|d| d.message.clone())
+            .collect();
+        assert_eq!(flagged, vec![
+            "Literal 'true' should be written as 'TRUE'",
+            "Literal 'False' should be written as 'FALSE'",
+            "Literal 'null' should be written as 'NULL'",
+        ]);
+    }
+
+    #[test]
+    fn test_boolean_literal_casing_lower() {
+        let mut config = default_config();
+        config.rules.boolean_literal_casing.options.insert("booleanCase".to_string(), "lower".into());
+        let sql = "SELECT TRUE, false, Null, `NULL` FROM t;";
+        let diags = lint(sql, &config);
+        let flagged: Vec<_> = diags.iter()
+            .filter(|d| d.code == Some(NumberOrString::String("boolean-literal-casing".to_string())))
+            .map(|d| d.message.clone())
+            .collect();
+        assert_eq!(flagged, vec![
+            "Literal 'TRUE' should be written as 'true'",
+            "Literal 'Null' should be written as 'null'",
+        ]);
+    }
+
+    #[test]
+    fn test_statement_length() {
+        let mut config = default_config();
+        config.rules.statement_length.options.insert("maxStatementLines".to_string(), 3.into());
+        let sql = "SELECT a\nFROM t;\nSELECT a,\n  b\nFROM t\nWHERE a = 1;";
+        let diags = lint(sql, &config);
+        let long: Vec<_> = diags.iter()
+            .filter(|d| d.code == Some(NumberOrString::String("statement-length".to_string())))
+            .collect();
+        assert_eq!(long.len(), 1);
+        assert_eq!(long[0].range.start, Position { line: 2, character: 0 });
+        assert!(long[0].message.contains("4 lines"));
+    }
+
+    #[test]
+    fn test_select_star() {
+        let mut config = default_config();
+        config.rules.select_star.enabled = true;
+        let sql = "SELECT * FROM t;\nSELECT DISTINCT *\nFROM t;";
+        let diags = lint(sql, &config);
+        let stars: Vec<_> = diags.iter()
+            .filter(|d| d.code == Some(NumberOrString::String("select-star".to_string())))
+            .collect();
+        assert_eq!(stars.len(), 2);
+        assert_eq!(stars[0].range.start, Position { line: 0, character: 7 });
+        assert_eq!(stars[1].range.start, Position { line: 1, character: 16 });
+    }
+
+    #[test]
+    fn test_select_star_ignores_count_and_subqueries() {
+        let mut config = default_config();
+        config.rules.select_star.enabled = true;
+        let sql = "SELECT COUNT(*) FROM t;\nSELECT a FROM (SELECT * FROM t) s;";
+        let diags = lint(sql, &config);
+        assert!(!get_messages(&diags).iter().any(|m| m.contains("SELECT *")));
+    }
+
+    #[test]
+    fn test_reserved_identifier_alias() {
+        let mut config = default_config();
+        config.rules.keyword_alias = false.into();
+        let diags = lint("SELECT ts AS timestamp, CAST(d AS DATE) AS d FROM t;", &config);
+        let reserved: Vec<_> = diags.iter()
+            .filter(|d| d.code == Some(NumberOrString::String("reserved-identifier".to_string())))
+            .collect();
+        assert_eq!(reserved.len(), 1);
+        assert!(reserved[0].message.contains("'timestamp'"));
+    }
+
+    #[test]
+    fn test_reserved_identifier_quoted_is_clean() {
+        let sql = "SELECT ts AS `timestamp` FROM t;\n\
+                   CREATE TABLE t AS SELECT 1 AS x;\n\
+                   WITH c AS (SELECT 1 AS n) SELECT n FROM c;";
+        let diags = lint(sql, &default_config());
+        assert!(!get_messages(&diags).iter().any(|m| m.contains("reserved word")));
+    }
+
+    #[test]
+    fn test_reserved_identifier_column_lists() {
+        let sql = "CREATE TABLE IF NOT EXISTS db.events (id INT, date STRING, tags ARRAY<STRING>, `order` INT)\n\
+                   PARTITIONED BY (timestamp STRING);\n\
+                   INSERT INTO events (id, date) VALUES (1, '2024-01-01');";
+        let diags = lint(sql, &default_config());
+        let flagged: Vec<(u32, u32)> = diags.iter()
+            .filter(|d| d.code == Some(NumberOrString::String("reserved-identifier".to_string())))
+            .map(|d| (d.range.start.line, d.range.start.character))
+            .collect();
+        assert_eq!(flagged, vec![(0, 46), (1, 16), (2, 24)]);
+    }
+
+    #[test]
+    fn test_empty_window() {
+        let sql = "SELECT SUM(x) OVER ( ), SUM(x) OVER (PARTITION BY a) FROM t;";
+        let diags = lint(sql, &default_config());
+        let windows: Vec<_> = diags.iter()
+            .filter(|d| d.code == Some(NumberOrString::String("empty-window".to_string())))
+            .collect();
+        assert_eq!(windows.len(), 1);
+        assert_eq!(windows[0].range.start, Position { line: 0, character: 14 });
+        assert_eq!(windows[0].range.end, Position { line: 0, character: 22 });
+    }
+
+    #[test]
+    fn test_case_branch_types_mismatch() {
+        let sql = "SELECT CASE WHEN a > 0 THEN 1 WHEN a < 0 THEN -1 ELSE 'x' END,\n  IF(b, 'y', TRUE) FROM t;";
+        let diags = lint(sql, &default_config());
+        let mismatches: Vec<_> = diags.iter()
+            .filter(|d| d.code == Some(NumberOrString::String("case-branch-types".to_string())))
+            .collect();
+        assert_eq!(mismatches.len(), 2);
+        assert!(mismatches[0].message.contains("numeric and string"));
+        assert_eq!(mismatches[0].range.start, Position { line: 0, character: 54 });
+        assert!(mismatches[1].message.starts_with("IF branches"));
+    }
+
+    #[test]
+    fn test_case_branch_types_consistent() {
+        let sql = "SELECT CASE WHEN a THEN 1 WHEN b THEN 2.5 ELSE NULL END,\n\
+                   CASE x WHEN 1 THEN 'a' ELSE CASE WHEN y THEN 2 ELSE 3 END END,\n\
+                   CASE WHEN a THEN col ELSE 'x' END FROM t;";
+        let diags = lint(sql, &default_config());
+        assert!(!get_messages(&diags).iter().any(|m| m.contains("different types")));
+    }
+
+    #[test]
+    fn test_unused_cte_column() {
+        let sql = "WITH base AS (\n  SELECT id, amount * 2 AS doubled, t.region\n  FROM t\n)\nSELECT id, region FROM base;";
+        let diags = lint(sql, &default_config());
+        let unused: Vec<_> = diags.iter()
+            .filter(|d| d.code == Some(NumberOrString::String("unused-cte-column".to_string())))
+            .collect();
+        assert_eq!(unused.len(), 1);
+        assert_eq!(unused[0].message, "Column 'doubled' of CTE 'base' is never used");
+        assert_eq!(unused[0].range.start, Position { line: 1, character: 27 });
+    }
+
+    #[test]
+    fn test_unused_cte_column_conservative() {
+        // Every column used, or consumed through `*`
+        let sql = "WITH a AS (SELECT id, name FROM t) SELECT a.id, a.name FROM a;\n\
+                   WITH b AS (SELECT id, name FROM t) SELECT * FROM b;\n\
+                   WITH c AS (SELECT id, name FROM t) SELECT c.* FROM c;";
+        let diags = lint(sql, &default_config());
+        assert!(!get_messages(&diags).iter().any(|m| m.contains("never used")));
+    }
+
+    #[test]
+    fn test_cte_order_by() {
+        let sql = "WITH sorted AS (\n  SELECT id FROM t ORDER BY id\n)\nSELECT id FROM sorted;";
+        let diags = lint(sql, &default_config());
+        let order_by: Vec<_> = diags.iter()
+            .filter(|d| d.code == Some(NumberOrString::String("cte-order-by".to_string())))
+            .collect();
+        assert_eq!(order_by.len(), 1);
+        assert_eq!(order_by[0].severity, Some(DiagnosticSeverity::INFORMATION));
+        assert_eq!(order_by[0].range.start, Position { line: 1, character: 19 });
+        assert_eq!(order_by[0].range.end, Position { line: 1, character: 27 });
+    }
+
+    #[test]
+    fn test_cte_without_order_by() {
+        // Top-level ORDER BY, windowed ORDER BY and ORDER BY ... LIMIT are all fine
+        let sql = "WITH a AS (SELECT id, ROW_NUMBER() OVER (ORDER BY id) AS rn FROM t),\n\
+                   b AS (SELECT id FROM t ORDER BY id LIMIT 10)\n\
+                   SELECT id FROM a JOIN b ON a.id = b.id ORDER BY id;";
+        let diags = lint(sql, &default_config());
+        assert!(!diags.iter().any(|d| d.code == Some(NumberOrString::String("cte-order-by".to_string()))));
+    }
+
+    #[test]
+    fn test_unterminated_string_location() {
+        let sql = "SELECT a\nFROM t\nWHERE name = 'abc;\n";
+        let diags = lint(sql, &default_config());
+        let error = diags.iter().find(|d| d.message.contains("Unterminated string literal")).unwrap();
+        assert_eq!(error.severity, Some(DiagnosticSeverity::ERROR));
+        assert_eq!(error.range.start, Position { line: 2, character: 13 });
+        assert_eq!(error.range.end, Position { line: 2, character: 14 });
+    }
+
+    #[test]
+    fn test_tokenizer_error_without_location() {
+        let error = TokenizerError { message: "boom".to_string(), location: sqlparser::tokenizer::Location::empty() };
+        let range = tokenizer_error_range("SELECT 1\nFROM t", &error);
+        assert_eq!(range.start, Position { line: 1, character: 5 });
+        assert_eq!(range.end, Position { line: 1, character: 6 });
+    }
+
+    #[test]
+    fn test_tuning_set() {
+        let sql = "SET hive.exec.reducers.max=200;\n\
+                   -- Skewed join needs more reducers\n\
+                   SET mapreduce.job.reduces = 64;\n\
+                   SET hive.execution.engine=tez;\n\
+                   SET tez.grouping.max-size=1024;";
+        let diags = lint(sql, &default_config());
+        let tuning: Vec<_> = diags.iter()
+            .filter(|d| d.code == Some(NumberOrString::String("tuning-set".to_string())))
+            .collect();
+        assert_eq!(tuning.len(), 2);
+        assert_eq!(tuning[0].severity, Some(DiagnosticSeverity::INFORMATION));
+        assert_eq!(tuning[0].range.start, Position { line: 0, character: 0 });
+        assert_eq!(tuning[0].range.end, Position { line: 0, character: 26 });
+        assert!(tuning[1].message.contains("tez.grouping.max-size"));
+    }
+
+    #[test]
+    fn test_tuning_set_custom_keys() {
+        let mut config = default_config();
+        config.rules.tuning_set = serde_json::from_value(serde_json::json!({ "keys": ["hive.execution.engine"] })).unwrap();
+        let sql = "SET hive.exec.reducers.max=200;\nSET hive.execution.engine=tez;";
+        let diags = lint(sql, &config);
+        let tuning: Vec<_> = diags.iter()
+            .filter(|d| d.code == Some(NumberOrString::String("tuning-set".to_string())))
+            .collect();
+        assert_eq!(tuning.len(), 1);
+        assert_eq!(tuning[0].range.start.line, 1);
+    }
+
+    #[test]
+    fn test_trailing_comma() {
+        let sql = "SELECT a, b,\nFROM t\nWHERE x IN (1, 2, );";
+        let diags = lint(sql, &default_config());
+        let trailing: Vec<_> = diags.iter()
+            .filter(|d| d.code == Some(NumberOrString::String("trailing-comma".to_string())))
+            .collect();
+        assert_eq!(trailing.len(), 2);
+        assert_eq!(trailing[0].severity, Some(DiagnosticSeverity::ERROR));
+        assert_eq!(trailing[0].range.start, Position { line: 0, character: 11 });
+        assert_eq!(trailing[0].message, "Trailing comma before 'FROM'");
+        assert_eq!(trailing[1].message, "Trailing comma before ')'");
+    }
+
+    #[test]
+    fn test_no_trailing_comma() {
+        let sql = "SELECT a, b, CASE WHEN c THEN 1 END AS d\nFROM t\nWHERE x IN (1, 2);";
+        let diags = lint(sql, &default_config());
+        assert!(!diags.iter().any(|d| d.code == Some(NumberOrString::String("trailing-comma".to_string()))));
+    }
+
+    #[test]
+    fn test_mixed_indentation() {
+        let sql = "SELECT a,\n\t b,\n\tc,\n    d\nFROM t;";
+        let diags = lint(sql, &default_config());
+        let mixed: Vec<_> = diags.iter()
+            .filter(|d| d.code == Some(NumberOrString::String("mixed-indentation".to_string())))
+            .collect();
+        assert_eq!(mixed.len(), 1);
+        assert_eq!(mixed[0].message, "Indentation mixes tabs and spaces");
+        assert_eq!(mixed[0].range.start, Position { line: 1, character: 0 });
+        assert_eq!(mixed[0].range.end, Position { line: 1, character: 2 });
+    }
+
+    #[test]
+    fn test_indentation_style() {
+        let mut config = default_config();
+        config.rules.indentation = serde_json::from_value(serde_json::json!({ "style": "spaces" })).unwrap();
+        let sql = "SELECT a,\n\tb,\n    c\nFROM t;";
+        let diags = lint(sql, &config);
+        let mixed: Vec<_> = diags.iter()
+            .filter(|d| d.code == Some(NumberOrString::String("mixed-indentation".to_string())))
+            .collect();
+        assert_eq!(mixed.len(), 1);
+        assert_eq!(mixed[0].message, "Indentation should use spaces");
+        assert_eq!(mixed[0].range.start.line, 1);
+    }
+
+    #[test]
+    fn test_wide_group_by() {
+        let mut config = default_config();
+        config.rules.wide_group_by = serde_json::from_value(serde_json::json!({ "maxGroupByKeys": 3 })).unwrap();
+        let sql = "SELECT a, b, c, d, COUNT(*) FROM t\nGROUP BY a, b, concat(c, 'x'), d\nHAVING COUNT(*) > 1;";
+        let diags = lint(sql, &config);
+        let wide: Vec<_> = diags.iter()
+            .filter(|d| d.code == Some(NumberOrString::String("wide-group-by".to_string())))
+            .collect();
+        assert_eq!(wide.len(), 1);
+        assert_eq!(wide[0].severity, Some(DiagnosticSeverity::INFORMATION));
+        assert_eq!(wide[0].range.start, Position { line: 1, character: 0 });
+        assert!(wide[0].message.starts_with("GROUP BY has 4 keys (limit 3)"));
+    }
+
+    #[test]
+    fn test_narrow_group_by() {
+        let mut config = default_config();
+        config.rules.wide_group_by = serde_json::from_value(serde_json::json!({ "maxGroupByKeys": 3 })).unwrap();
+        let sql = "SELECT * FROM (SELECT a, b, COUNT(*) FROM t GROUP BY a, b) x\nGROUP BY a, b, c WITH ROLLUP;";
+        let diags = lint(sql, &config);
+        assert!(!diags.iter().any(|d| d.code == Some(NumberOrString::String("wide-group-by".to_string()))));
+    }
+
+    #[test]
+    fn test_max_line_length() {
+        let mut config = default_config();
+        config.rules.max_line_length.options.insert("maxLength".to_string(), 12.into());
+        // Emoji are one character but two UTF-16 code units
+        let sql = "SELECT a, b\nSELECT '😀😀', abc FROM t;";
+        let diags = lint(sql, &config);
+        let long: Vec<_> = diags.iter()
+            .filter(|d| d.code == Some(NumberOrString::String("max-line-length".to_string())))
+            .collect();
+        assert_eq!(long.len(), 1);
+        assert_eq!(long[0].severity, Some(DiagnosticSeverity::HINT));
+        assert_eq!(long[0].message, "Line is 24 characters long (limit 12)");
+        assert_eq!(long[0].range.start, Position { line: 1, character: 14 });
+        assert_eq!(long[0].range.end, Position { line: 1, character: 26 });
+    }
+
+    #[test]
+    fn test_unsafe_interpolation() {
+        let sql = "SELECT * FROM t\nWHERE name = ${hivevar:n}\n  AND region IN ('eu', ${hivevar:region})\n  AND dt = '${hivevar:dt}';";
+        let diags = lint(sql, &default_config());
+        let unsafe_vars: Vec<_> = diags.iter()
+            .filter(|d| d.code == Some(NumberOrString::String("unsafe-interpolation".to_string())))
+            .collect();
+        assert_eq!(unsafe_vars.len(), 2);
+        assert_eq!(unsafe_vars[0].severity, Some(DiagnosticSeverity::WARNING));
+        assert_eq!(unsafe_vars[0].range.start, Position { line: 1, character: 13 });
+        assert_eq!(unsafe_vars[0].range.end, Position { line: 1, character: 25 });
+        assert_eq!(unsafe_vars[1].range.start.line, 2);
+    }
+
+    #[test]
+    fn test_unsafe_interpolation_ignores_non_values() {
+        // Table names and LIMIT counts can't be quoted anyway
+        let sql = "SELECT * FROM ${hivevar:db}.t\nWHERE name = '${hivevar:n}'\nLIMIT ${hivevar:n};";
+        let diags = lint(sql, &default_config());
+        assert!(!diags.iter().any(|d| d.code == Some(NumberOrString::String("unsafe-interpolation".to_string()))));
+    }
+
+    #[test]
+    fn test_cartesian_join() {
+        let sql = "SELECT * FROM a, b;\n\
+                   SELECT * FROM a JOIN b ON a.id = b.id, c GROUP BY x;\n\
+                   SELECT * FROM a, b JOIN c ON b.id = c.id;";
+        let diags = lint(sql, &default_config());
+        let cartesian: Vec<_> = diags.iter()
+            .filter(|d| d.code == Some(NumberOrString::String("cartesian-join".to_string())))
+            .collect();
+        assert_eq!(cartesian.len(), 3);
+        assert_eq!(cartesian[0].severity, Some(DiagnosticSeverity::WARNING));
+        assert_eq!(cartesian[0].range.start, Position { line: 0, character: 15 });
+        assert_eq!(cartesian[1].range.start.line, 1);
+        assert_eq!(cartesian[2].range.start, Position { line: 2, character: 15 });
+    }
+
+    #[test]
+    fn test_no_cartesian_join() {
+        let sql = "SELECT * FROM a JOIN b ON a.id = b.id;\n\
+                   SELECT * FROM a CROSS JOIN b;\n\
+                   SELECT * FROM a, b WHERE a.id = b.id;\n\
+                   SELECT * FROM a, b JOIN c ON b.id = c.id WHERE a.id = b.id;\n\
+                   SELECT * FROM (SELECT x, y FROM a WHERE x > 1) s WHERE s.y = 2;\n\
+                   SELECT p, v FROM a LATERAL VIEW posexplode(arr) e AS p, v;";
+        let diags = lint(sql, &default_config());
+        assert!(!diags.iter().any(|d| d.code == Some(NumberOrString::String("cartesian-join".to_string()))));
+    }
+
+    fn test_schema() -> Schema {
+        serde_json::from_value(serde_json::json!({ "tables": [
+            { "name": "sales.orders", "columns": [ { "name": "id", "type": "int" }, { "name": "amount", "type": "decimal(10, 2)" } ] },
+            { "name": "customers", "columns": [ { "name": "id", "type": "bigint" } ] }
+        ] }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_redundant_cast() {
+        let sql = "SELECT CAST(o.id AS INTEGER), CAST(amount AS DECIMAL(10,2))\nFROM sales.orders o;";
+        let diags = lint_with_schema(sql, &default_config(), Some(&test_schema()));
+        let redundant: Vec<_> = diags.iter()
+            .filter(|d| d.code == Some(NumberOrString::String("redundant-cast".to_string())))
+            .collect();
+        assert_eq!(redundant.len(), 2);
+        assert_eq!(redundant[0].severity, Some(DiagnosticSeverity::HINT));
+        assert_eq!(redundant[0].range.start, Position { line: 0, character: 7 });
+        assert_eq!(redundant[0].range.end, Position { line: 0, character: 28 });
+        assert_eq!(redundant[0].data, Some(serde_json::Value::String("o.id".to_string())));
+        assert_eq!(redundant[1].data, Some(serde_json::Value::String("amount".to_string())));
+    }
+
+    #[test]
+    fn test_meaningful_cast() {
+        // Different type, ambiguous column, expression operand, or no schema at all
+        let sql = "SELECT CAST(o.id AS STRING), CAST(id AS INT), CAST(o.id + 1 AS INT)\nFROM sales.orders o JOIN customers c ON o.id = c.id;";
+        let diags = lint_with_schema(sql, &default_config(), Some(&test_schema()));
+        assert!(!diags.iter().any(|d| d.code == Some(NumberOrString::String("redundant-cast".to_string()))));
+        let diags = lint("SELECT CAST(id AS INT) FROM sales.orders;", &default_config());
+        assert!(!diags.iter().any(|d| d.code == Some(NumberOrString::String("redundant-cast".to_string()))));
+    }
+
+    #[test]
+    fn test_invalid_regex() {
+        let sql = "SELECT * FROM t\nWHERE a RLIKE '(' OR b NOT REGEXP '[a-';";
+        let diags = lint(sql, &default_config());
+        let invalid: Vec<_> = diags.iter()
+            .filter(|d| d.code == Some(NumberOrString::String("invalid-regex".to_string())))
+            .collect();
+        assert_eq!(invalid.len(), 2);
+        assert_eq!(invalid[0].severity, Some(DiagnosticSeverity::WARNING));
+        assert_eq!(invalid[0].range.start, Position { line: 1, character: 14 });
+        assert_eq!(invalid[0].range.end, Position { line: 1, character: 17 });
+        assert!(invalid[0].message.starts_with("Invalid regular expression: "));
+    }
+
+    #[test]
+    fn test_valid_regex() {
+        // Escaped backslashes, Java-only look-ahead, and non-literal patterns are all left alone
+        let sql = "SELECT * FROM t\nWHERE a RLIKE '^a.*' AND b RLIKE '\\\\d+\\\\.\\\\d+' AND c RLIKE '(?=x)y' AND d RLIKE e;";
+        let diags = lint(sql, &default_config());
+        assert!(!diags.iter().any(|d| d.code == Some(NumberOrString::String("invalid-regex".to_string()))));
+    }
+
+    #[test]
+    fn test_duplicate_set_column() {
+        let sql = "UPDATE t SET a = 1, b = f(a, 2), T.A = 3 WHERE id = 1;";
+        let diags = lint(sql, &default_config());
+        let duplicates: Vec<_> = diags.iter()
+            .filter(|d| d.code == Some(NumberOrString::String("duplicate-set-column".to_string())))
+            .collect();
+        assert_eq!(duplicates.len(), 1);
+        assert_eq!(duplicates[0].severity, Some(DiagnosticSeverity::WARNING));
+        assert_eq!(duplicates[0].range.start, Position { line: 0, character: 35 });
+        assert_eq!(duplicates[0].message, "Column 'A' is assigned more than once; only one assignment takes effect");
+    }
+
+    #[test]
+    fn test_distinct_set_columns() {
+        let sql = "UPDATE t SET a = 1, b = a WHERE a = 2 AND b = 3;\nSET hive.exec.parallel=true;\nSET hive.exec.parallel=true;";
+        let diags = lint(sql, &default_config());
+        assert!(!diags.iter().any(|d| d.code == Some(NumberOrString::String("duplicate-set-column".to_string()))));
+    }
+
+    #[test]
+    fn test_cte_missing_as() {
+        let sql = "WITH a AS (SELECT 1 AS x),\n  b (SELECT x FROM a)\nSELECT * FROM b;";
+        let diags = lint(sql, &default_config());
+        let missing: Vec<_> = diags.iter()
+            .filter(|d| d.code == Some(NumberOrString::String("cte-missing-as".to_string())))
+            .collect();
+        assert_eq!(missing.len(), 1);
+        assert_eq!(missing[0].severity, Some(DiagnosticSeverity::ERROR));
+        assert_eq!(missing[0].range.start, Position { line: 1, character: 2 });
+        assert_eq!(missing[0].message, "Missing AS between CTE 'b' and its query");
+    }
+
+    #[test]
+    fn test_first_cte_missing_as() {
+        let sql = "WITH a (SELECT 1 AS x)\nSELECT * FROM a;";
+        let missing: Vec<_> = lint(sql, &default_config())
+            .into_iter()
+            .filter(|d| d.code == Some(NumberOrString::String("cte-missing-as".to_string())))
+            .collect();
+        assert_eq!(missing.len(), 1);
+        assert_eq!(missing[0].range.start, Position { line: 0, character: 5 });
+        assert_eq!(missing[0].message, "Missing AS between CTE 'a' and its query");
+    }
+
+    #[test]
+    fn test_cte_with_as() {
+        let sql = "WITH a AS (SELECT 1 AS x), b AS (SELECT x FROM a)\nSELECT * FROM b;";
+        let diags = lint(sql, &default_config());
+        assert!(!diags.iter().any(|d| d.code == Some(NumberOrString::String("cte-missing-as".to_string()))));
+    }
+
+    #[test]
+    fn test_non_join_on() {
+        let sql = "SELECT * FROM a JOIN b ON a.x = 5 WHERE a.y = 1;\n\
+                   SELECT * FROM a JOIN db.b ON b.flag AND b.x > 2;";
+        let diags = lint(sql, &default_config());
+        let non_join: Vec<_> = diags.iter()
+            .filter(|d| d.code == Some(NumberOrString::String("non-join-on".to_string())))
+            .collect();
+        assert_eq!(non_join.len(), 2);
+        assert_eq!(non_join[0].severity, Some(DiagnosticSeverity::HINT));
+        assert_eq!(non_join[0].range.start, Position { line: 0, character: 23 });
+        assert_eq!(non_join[0].range.end, Position { line: 0, character: 33 });
+        assert_eq!(non_join[0].message, "ON condition only references 'a'; did you mean to filter in WHERE?");
+        assert_eq!(non_join[1].message, "ON condition only references 'b'; did you mean to filter in WHERE?");
+    }
+
+    #[test]
+    fn test_join_on_both_tables() {
+        let sql = "SELECT * FROM a JOIN b ON a.x = b.y;\n\
+                   SELECT * FROM orders o LEFT JOIN (SELECT id FROM u) AS s ON s.id = o.uid AND o.x = 5 JOIN c ON upper(c.k) = o.k;\n\
+                   SELECT * FROM a JOIN b ON id = b.id;";
+        let diags = lint(sql, &default_config());
+        assert!(!diags.iter().any(|d| d.code == Some(NumberOrString::String("non-join-on".to_string()))));
+    }
+
+    #[test]
+    fn test_alias_style_consistency() {
+        let sql = "SELECT * FROM orders AS o JOIN db.users AS u ON o.uid = u.id;\n\
+                   SELECT * FROM (SELECT 1 AS x) s JOIN items AS i ON s.x = i.x;";
+        let diags = lint(sql, &default_config());
+        let inconsistent: Vec<_> = diags.iter()
+            .filter(|d| d.code == Some(NumberOrString::String("alias-style-consistency".to_string())))
+            .collect();
+        assert_eq!(inconsistent.len(), 1);
+        assert_eq!(inconsistent[0].severity, Some(DiagnosticSeverity::HINT));
+        assert_eq!(inconsistent[0].range.start, Position { line: 1, character: 30 });
+        assert_eq!(inconsistent[0].message, "Alias 's' omits AS, but most table aliases in this file use it");
+    }
+
+    #[test]
+    fn test_consistent_alias_style() {
+        let sql = "SELECT * FROM orders AS o JOIN users AS u ON o.uid = u.id;\n\
+                   SELECT * FROM (SELECT 1 AS x) AS s WHERE s.x = 1;\n\
+                   SELECT * FROM t WHERE x = 1;\n\
+                   CREATE TABLE c AS SELECT * FROM t;";
+        let diags = lint(sql, &default_config());
+        assert!(!diags.iter().any(|d| d.code == Some(NumberOrString::String("alias-style-consistency".to_string()))));
+    }
+
+    #[test]
+    fn test_syntax_error() {
+        let mut config = default_config();
+        config.rules.syntax = true.into();
+        let sql = "SELECT a FROM t;\nSELECT FROM WHERE;\nSELECT b FROM (SELECT 1;";
+        let diags = lint(sql, &config);
+        let errors: Vec<_> = diags.iter()
+            .filter(|d| d.code == Some(NumberOrString::String("syntax".to_string())))
+            .collect();
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].severity, Some(DiagnosticSeverity::ERROR));
+        assert_eq!(errors[0].range.start, Position { line: 1, character: 12 });
+        assert!(errors[0].message.starts_with("Syntax error: Expected"), "{}", errors[0].message);
+        assert!(!errors[0].message.contains("Line:"));
+        assert_eq!(errors[1].range.start.line, 2);
+    }
+
+    #[test]
+    fn test_valid_syntax() {
+        let mut config = default_config();
+        config.rules.syntax = true.into();
+        let sql = "WITH c AS (SELECT a, count(*) AS n FROM db.t GROUP BY a)\nSELECT * FROM c WHERE n > 1;\n\
+                   SELECT * FROM t WHERE dt = '${hivevar:dt}';\n\
+                   SELECT * FROM ${hivevar:table};";
+        let diags = lint(sql, &config);
+        assert!(!diags.iter().any(|d| d.code == Some(NumberOrString::String("syntax".to_string()))));
+    }
+
+    #[test]
+    fn test_null_into_not_null() {
+        let schema: Schema = serde_json::from_str(r#"{ "tables": [ { "name": "db.users", "columns": [
+            { "name": "id", "type": "bigint", "notNull": true }, { "name": "email", "type": "string" } ] } ] }"#).unwrap();
+        let sql = "INSERT INTO db.users (email, id) VALUES ('a@b.c', 1), (NULL, NULL), (concat(NULL), 2);\n\
+                   INSERT INTO TABLE users (id) VALUES (null);\n\
+                   INSERT INTO other (id) VALUES (NULL);";
+        let diags = lint_with_schema(sql, &default_config(), Some(&schema));
+        let nulls: Vec<_> = diags.iter()
+            .filter(|d| d.code == Some(NumberOrString::String("null-into-not-null".to_string())))
+            .collect();
+        assert_eq!(nulls.len(), 2);
+        assert_eq!(nulls[0].severity, Some(DiagnosticSeverity::ERROR));
+        assert_eq!(nulls[0].range.start, Position { line: 0, character: 61 });
+        assert_eq!(nulls[0].message, "Column 'id' of 'db.users' is NOT NULL");
+        assert_eq!(nulls[1].range.start.line, 1);
+    }
+
+    #[test]
+    fn test_null_into_not_null_needs_schema() {
+        let sql = "INSERT INTO users (id) VALUES (NULL);";
+        let diags = lint(sql, &default_config());
+        assert!(!diags.iter().any(|d| d.code == Some(NumberOrString::String("null-into-not-null".to_string()))));
+    }
+
+    #[test]
+    fn test_limit_without_order() {
+        let mut config = default_config();
+        config.rules.limit_without_order = true.into();
+        let sql = "SELECT * FROM t LIMIT 10;\n\
+                   SELECT * FROM (SELECT a, row_number() OVER (ORDER BY b) AS r FROM t LIMIT 5) s ORDER BY a LIMIT 1;";
+        let diags = lint(sql, &config);
+        let limits: Vec<_> = diags.iter()
+            .filter(|d| d.code == Some(NumberOrString::String("limit-without-order".to_string())))
+            .collect();
+        assert_eq!(limits.len(), 2);
+        assert_eq!(limits[0].severity, Some(DiagnosticSeverity::INFORMATION));
+        assert_eq!(limits[0].range.start, Position { line: 0, character: 16 });
+        assert_eq!(limits[1].range.start, Position { line: 1, character: 68 });
+    }
+
+    #[test]
+    fn test_limit_with_order() {
+        let mut config = default_config();
+        config.rules.limit_without_order = true.into();
+        let sql = "SELECT * FROM t ORDER BY a LIMIT 10;\n\
+                   SELECT * FROM (SELECT a FROM t ORDER BY a DESC LIMIT 5) s;";
+        let diags = lint(sql, &config);
+        assert!(!diags.iter().any(|d| d.code == Some(NumberOrString::String("limit-without-order".to_string()))));
+    }
+
+    #[test]
+    fn test_insert_select_arity() {
+        let sql = "INSERT INTO t (a, b, c) SELECT x, concat(y, z) FROM s;";
+        let diags = lint(sql, &default_config());
+        let arity: Vec<_> = diags.iter()
+            .filter(|d| d.code == Some(NumberOrString::String("insert-select-arity".to_string())))
+            .collect();
+        assert_eq!(arity.len(), 1);
+        assert_eq!(arity[0].severity, Some(DiagnosticSeverity::ERROR));
+        assert_eq!(arity[0].range.start, Position { line: 0, character: 24 });
+        assert_eq!(arity[0].message, "INSERT lists 3 target column(s) but SELECT returns 2");
+    }
+
+    #[test]
+    fn test_insert_select_arity_matches() {
+        let sql = "INSERT INTO t (a, b) SELECT x, concat(y, z) FROM s;\n\
+                   INSERT INTO t (a, b, c) SELECT * FROM s;\n\
+                   INSERT INTO t (a, b, c) SELECT s.* FROM s;\n\
+                   INSERT INTO t SELECT x FROM s;";
+        let diags = lint(sql, &default_config());
+        assert!(!diags.iter().any(|d| d.code == Some(NumberOrString::String("insert-select-arity".to_string()))));
+    }
+
+    #[test]
+    fn test_insert_select_arity_nested_projections() {
+        let sql = "INSERT INTO t (a, b) SELECT if(x > 0, x, (y + 1)), count(*) FROM s;";
+        let diags = lint(sql, &default_config());
+        assert!(!diags.iter().any(|d| d.code == Some(NumberOrString::String("insert-select-arity".to_string()))));
+    }
+
+    #[test]
+    fn test_duplicate_select_column() {
+        let sql = "SELECT a, t.b, count(x, y), A, T.b, count(x,y), b AS a FROM t;";
+        let diags = lint(sql, &default_config());
+        let duplicates: Vec<_> = diags.iter()
+            .filter(|d| d.code == Some(NumberOrString::String("duplicate-column".to_string())))
+            .collect();
+        assert_eq!(duplicates.len(), 4);
+        assert_eq!(duplicates[0].severity, Some(DiagnosticSeverity::WARNING));
+        assert_eq!(duplicates[0].range.start, Position { line: 0, character: 28 });
+        assert_eq!(duplicates[0].message, "'A' duplicates an earlier column in the SELECT list");
+        assert_eq!(duplicates[2].message, "'count(x,y)' duplicates an earlier column in the SELECT list");
+        assert_eq!(duplicates[3].message, "'b AS a' duplicates an earlier column in the SELECT list");
     }
 
     #[test]
-    fn test_missing_semicolon_multi_query() {
-        let sql = "SELECT * FROM users WHERE id = 1\n\nSELECT * FROM orders WHERE status = 'active';";
+    fn test_distinct_select_columns() {
+        let sql = "SELECT a AS x, a AS y, *, t.*, s.*, f(a, a), a FROM t JOIN s ON t.id = s.id;";
         let diags = lint(sql, &default_config());
-        let msgs = get_messages(&diags);
-        assert!(msgs.iter().any(|m| m.contains("Missing semicolon")));
+        assert!(!diags.iter().any(|d| d.code == Some(NumberOrString::String("duplicate-column".to_string()))));
     }
 
     #[test]
-    fn test_semicolon_valid() {
-        let sql = "SELECT * FROM users; SELECT * FROM orders;";
+    fn test_truncate_external() {
+        let schema: Schema = serde_json::from_str(r#"{ "tables": [
+            { "name": "raw.events", "external": true }, { "name": "mart.daily" } ] }"#).unwrap();
+        let sql = "TRUNCATE TABLE raw.events;\nTRUNCATE TABLE events PARTITION (dt = '2024-01-01');\nTRUNCATE TABLE mart.daily;";
+        let diags = lint_with_schema(sql, &default_config(), Some(&schema));
+        let truncates: Vec<_> = diags.iter()
+            .filter(|d| d.code == Some(NumberOrString::String("truncate-external".to_string())))
+            .collect();
+        assert_eq!(truncates.len(), 2);
+        assert_eq!(truncates[0].severity, Some(DiagnosticSeverity::WARNING));
+        assert_eq!(truncates[0].range.start, Position { line: 0, character: 15 });
+        assert_eq!(truncates[0].range.end, Position { line: 0, character: 25 });
+        assert_eq!(truncates[0].message, "'raw.events' is an external table; Hive can't TRUNCATE it");
+        assert_eq!(truncates[1].range.start.line, 1);
+    }
+
+    #[test]
+    fn test_cluster_order_conflict() {
+        let sql = "SELECT * FROM t CLUSTER BY a ORDER BY a;\n\
+                   SELECT * FROM (SELECT * FROM t ORDER BY b DISTRIBUTE BY b) s;";
         let diags = lint(sql, &default_config());
-        let msgs = get_messages(&diags);
-        assert!(!msgs.iter().any(|m| m.contains("Missing semicolon")));
+        let conflicts: Vec<_> = diags.iter()
+            .filter(|d| d.code == Some(NumberOrString::String("cluster-order-conflict".to_string())))
+            .collect();
+        assert_eq!(conflicts.len(), 2);
+        assert_eq!(conflicts[0].severity, Some(DiagnosticSeverity::WARNING));
+        assert_eq!(conflicts[0].range.start, Position { line: 0, character: 29 });
+        assert_eq!(
+            conflicts[0].message,
+            "CLUSTER BY conflicts with ORDER BY in the same query; ORDER BY already sorts all rows in a single reducer"
+        );
+        assert!(conflicts[1].message.starts_with("DISTRIBUTE BY"));
     }
 
-    // Ported from SemicolonRule - DDL and Subqueries
     #[test]
-    fn test_semicolon_subquery_no_flag() {
-        let sql = "SELECT *\nFROM users\nWHERE id IN (\n  SELECT user_id\n  FROM orders\n)\nAND created_date > '2024';";
+    fn test_cluster_or_order_alone() {
+        let sql = "SELECT * FROM t CLUSTER BY a;\n\
+                   SELECT * FROM t ORDER BY a;\n\
+                   SELECT * FROM t DISTRIBUTE BY a SORT BY a;\n\
+                   SELECT * FROM (SELECT * FROM t CLUSTER BY a) s ORDER BY a;\n\
+                   SELECT row_number() OVER (ORDER BY a) FROM t CLUSTER BY a;";
         let diags = lint(sql, &default_config());
-        let msgs = get_messages(&diags);
-        assert!(!msgs.iter().any(|m| m.contains("Missing semicolon")));
+        assert!(!diags.iter().any(|d| d.code == Some(NumberOrString::String("cluster-order-conflict".to_string()))));
     }
 
     #[test]
-    fn test_semicolon_ddl_no_flag() {
-        let sql = "CREATE TABLE sales (id INT)\nPARTITIONED BY (year INT)\nSTORED AS PARQUET\nLOCATION '/data';";
+    fn test_implicit_default_db() {
+        let sql = "INSERT OVERWRITE TABLE daily SELECT * FROM raw.events;\n\
+                   CREATE EXTERNAL TABLE IF NOT EXISTS staging (id INT);\n\
+                   CREATE TEMPORARY TABLE scratch (id INT);\n\
+                   DROP TABLE mart.daily;\n\
+                   CREATE DATABASE IF NOT EXISTS mart;";
         let diags = lint(sql, &default_config());
-        let msgs = get_messages(&diags);
-        assert!(!msgs.iter().any(|m| m.contains("Missing semicolon")));
+        let implicit: Vec<_> = diags.iter()
+            .filter(|d| d.code == Some(NumberOrString::String("implicit-default-db".to_string())))
+            .collect();
+        assert_eq!(implicit.len(), 2);
+        assert_eq!(implicit[0].severity, Some(DiagnosticSeverity::INFORMATION));
+        assert_eq!(implicit[0].range.start, Position { line: 0, character: 23 });
+        assert_eq!(implicit[0].message, "'daily' isn't qualified with a database and no USE precedes it, so it resolves to 'default'");
+        assert_eq!(implicit[1].range.start.line, 1);
     }
 
     #[test]
-    fn test_unbalanced_parentheses() {
-        let sql = "SELECT * FROM users WHERE (id = 1";
+    fn test_implicit_default_db_after_use() {
+        let sql = "USE mart;\nINSERT INTO daily SELECT * FROM raw.events;\nDROP VIEW IF EXISTS v;";
         let diags = lint(sql, &default_config());
-        let msgs = get_messages(&diags);
-        assert!(msgs.iter().any(|m| m.contains("Unbalanced parentheses")));
+        assert!(!diags.iter().any(|d| d.code == Some(NumberOrString::String("implicit-default-db".to_string()))));
     }
 
     #[test]
-    fn test_balanced_parentheses_complex() {
-        let sql = "SELECT * FROM users WHERE id IN (SELECT user_id FROM orders WHERE (amt > 100))";
+    fn test_constant_select_item() {
+        let sql = "SELECT 'x' AS l, col, -1 n, NULL, upper('y') FROM t;";
         let diags = lint(sql, &default_config());
-        let msgs = get_messages(&diags);
-        assert!(!msgs.iter().any(|m| m.contains("Unbalanced parentheses")));
+        let constants: Vec<_> = diags.iter()
+            .filter(|d| d.code == Some(NumberOrString::String("constant-select-item".to_string())))
+            .collect();
+        assert_eq!(constants.len(), 3);
+        assert_eq!(constants[0].severity, Some(DiagnosticSeverity::HINT));
+        assert_eq!(constants[0].range.start, Position { line: 0, character: 7 });
+        assert_eq!(constants[0].range.end, Position { line: 0, character: 15 });
+        assert_eq!(constants[1].range.start, Position { line: 0, character: 22 });
     }
 
     #[test]
-    fn test_trailing_whitespace() {
-        let sql = "SELECT * FROM users \nWHERE id = 1";
+    fn test_all_constant_select() {
+        let sql = "SELECT 1;\nSELECT 'a' AS x, 2 FROM t;\nSELECT * FROM t;";
         let diags = lint(sql, &default_config());
-        let msgs = get_messages(&diags);
-        assert!(msgs.iter().any(|m| m.contains("Trailing whitespace")));
+        assert!(!diags.iter().any(|d| d.code == Some(NumberOrString::String("constant-select-item".to_string()))));
     }
 
     #[test]
-    fn test_hive_variables() {
-        let sql = "SELECT ${hiveconf:my_var} FROM table";
+    fn test_no_tabs() {
+        let mut config = default_config();
+        config.rules.no_tabs = true.into();
+        let sql = "SELECT\ta,\n\tb\nFROM t;";
+        let diags = lint(sql, &config);
+        let tabs: Vec<_> = diags.iter().filter(|d| d.code == Some(NumberOrString::String("no-tabs".to_string()))).collect();
+        assert_eq!(tabs.len(), 2);
+        assert_eq!(tabs[0].range.start, Position { line: 0, character: 6 });
+        assert_eq!(tabs[0].range.end, Position { line: 0, character: 7 });
+        assert_eq!(tabs[0].data, Some("    ".into()));
+    }
+
+    #[test]
+    fn test_no_tabs_ignores_string_literals() {
+        let mut config = default_config();
+        config.rules.no_tabs = true.into();
+        let sql = "SELECT 'a\tb' FROM t; -- note\there";
+        let diags = lint(sql, &config);
+        assert!(!diags.iter().any(|d| d.code == Some(NumberOrString::String("no-tabs".to_string()))));
+    }
+
+    #[test]
+    fn test_unterminated_comment() {
+        let sql = "SELECT 1; /* done */\nSELECT 2; /* open\nSELECT 3;";
         let diags = lint(sql, &default_config());
-        let msgs = get_messages(&diags);
-        assert!(!msgs.iter().any(|m| m.contains("Invalid Hive variable")));
+        assert_eq!(diags.len(), 1);
+        assert_eq!(diags[0].code, Some(NumberOrString::String("unterminated-comment".to_string())));
+        assert_eq!(diags[0].range.start, Position { line: 1, character: 10 });
+        assert_eq!(diags[0].range.end, Position { line: 1, character: 12 });
     }
 
     #[test]
-    fn test_hive_variables_invalid() {
-        let sql = "SELECT ${invalid:var} FROM table";
+    fn test_comment_opener_inside_string() {
+        let unterminated = |sql: &str, config: &LintingConfig| -> Vec<Diagnostic> {
+            lint(sql, config)
+                .into_iter()
+                .filter(|d| d.code == Some(NumberOrString::String("unterminated-comment".to_string())))
+                .collect()
+        };
+        let sql = "SELECT '/* not a comment', \"/*\" FROM t; -- /* nor this\nSELECT 2 /* open";
+        let diags = unterminated(sql, &default_config());
+        assert_eq!(diags.len(), 1);
+        assert_eq!(diags[0].range.start, Position { line: 1, character: 9 });
+
+        // Hive doesn't escape with backslashes, so the second quote opens a string that never closes
+        let sql = "SELECT 'x\\', '/* y";
+        assert!(unterminated(sql, &default_config()).is_empty());
+        assert!(get_messages(&lint(sql, &default_config())).iter().any(|m| m.contains("Unterminated string literal")));
+
+        // Where it does, `\'` stays inside the string
+        let config = LintingConfig { dialect: "mysql".to_string(), ..default_config() };
+        let diags = unterminated("SELECT 'it\\'s /*' FROM t;\nSELECT 2 /* open", &config);
+        assert_eq!(diags.len(), 1);
+        assert_eq!(diags[0].range.start, Position { line: 1, character: 9 });
+    }
+
+    #[test]
+    fn test_too_many_name_parts() {
+        let sql = "SELECT t.address.city.zip FROM a.b.c.d t JOIN db.u ON t.id = u.id;\nINSERT INTO x.y.z SELECT 1;";
         let diags = lint(sql, &default_config());
-        let msgs = get_messages(&diags);
-        assert!(msgs.iter().any(|m| m.contains("Invalid namespace")));
+        let flagged: Vec<_> = diags
+            .iter()
+            .filter(|d| d.code == Some(NumberOrString::String("too-many-name-parts".to_string())))
+            .collect();
+        assert_eq!(flagged.len(), 1);
+        assert_eq!(flagged[0].range.start, Position { line: 0, character: 31 });
+        assert_eq!(flagged[0].range.end, Position { line: 0, character: 38 });
+        assert_eq!(flagged[0].message, "Table name has 4 parts; at most 3 are allowed");
     }
 
     #[test]
-    fn test_hive_variables_empty() {
-        let sql = "SELECT ${} FROM table";
+    fn test_comma_join() {
+        let mut config = default_config();
+        config.rules.comma_join = true.into();
+        let comma_joins = |sql| {
+            lint(sql, &config)
+                .into_iter()
+                .filter(|d| d.code == Some(NumberOrString::String("comma-join".to_string())))
+                .collect::<Vec<_>>()
+        };
+
+        let flagged = comma_joins("SELECT a.x, b.y FROM a, b WHERE a.id = b.id;");
+        assert_eq!(flagged.len(), 1);
+        assert_eq!(flagged[0].range.start, Position { line: 0, character: 22 });
+        assert_eq!(flagged[0].severity, Some(DiagnosticSeverity::INFORMATION));
+
+        assert!(comma_joins("SELECT a.x, b.y FROM a JOIN b ON a.id = b.id WHERE a.z IN (1, 2) GROUP BY a.x, b.y;").is_empty());
+    }
+
+    #[test]
+    fn test_equality_operator() {
+        let config = default_config();
+        let equality = |sql| {
+            lint(sql, &config)
+                .into_iter()
+                .filter(|d| d.code == Some(NumberOrString::String("equality-operator".to_string())))
+                .collect::<Vec<_>>()
+        };
+
+        let flagged = equality("SELECT * FROM t WHERE a == b;");
+        assert_eq!(flagged.len(), 1);
+        assert_eq!(flagged[0].range.start, Position { line: 0, character: 24 });
+        assert_eq!(flagged[0].range.end, Position { line: 0, character: 26 });
+        assert_eq!(flagged[0].severity, Some(DiagnosticSeverity::ERROR));
+
+        let spaced = equality("SELECT * FROM t WHERE a = = b;");
+        assert_eq!(spaced.len(), 1);
+        assert_eq!(spaced[0].range.end, Position { line: 0, character: 27 });
+
+        assert!(equality("SELECT * FROM t WHERE a = b;").is_empty());
+        // Reported as a foreign operator instead
+        assert!(equality("SELECT * FROM t WHERE a === b;").is_empty());
+    }
+
+    #[test]
+    fn test_between_bounds() {
+        let config = default_config();
+        let between = |sql| {
+            lint(sql, &config)
+                .into_iter()
+                .filter(|d| d.code == Some(NumberOrString::String("between-bounds".to_string())))
+                .collect::<Vec<_>>()
+        };
+
+        let reversed = between("SELECT * FROM t WHERE x BETWEEN 10 AND 5;");
+        assert_eq!(reversed.len(), 1);
+        assert_eq!(reversed[0].range.start, Position { line: 0, character: 24 });
+        assert_eq!(reversed[0].range.end, Position { line: 0, character: 31 });
+        assert_eq!(reversed[0].severity, Some(DiagnosticSeverity::WARNING));
+        assert_eq!(between("SELECT * FROM t WHERE x BETWEEN -1 AND -2.5;").len(), 1);
+
+        let equal = between("SELECT * FROM t WHERE x BETWEEN 5 AND 5;");
+        assert_eq!(equal.len(), 1);
+        assert_eq!(equal[0].severity, Some(DiagnosticSeverity::HINT));
+        assert!(equal[0].message.ends_with("'= 5'"), "{}", equal[0].message);
+
+        // NOT BETWEEN with reversed bounds keeps every row rather than none
+        let negated = between("SELECT * FROM t WHERE x NOT BETWEEN 10 AND 5;");
+        assert_eq!(negated.len(), 1);
+        assert!(negated[0].message.ends_with("NOT BETWEEN matches every non-NULL value"), "{}", negated[0].message);
+        assert!(!reversed[0].message.contains("NOT BETWEEN"));
+        assert!(between("SELECT * FROM t WHERE x NOT BETWEEN 5 AND 5;")[0].message.ends_with("'<> 5'"));
+
+        assert!(between("SELECT * FROM t WHERE x BETWEEN 5 AND 10;").is_empty());
+        // Bounds that aren't plain literals are left alone
+        assert!(between("SELECT * FROM t WHERE x BETWEEN 10 AND 5 + 10;").is_empty());
+        assert!(between("SELECT * FROM t WHERE x BETWEEN y AND 5;").is_empty());
+    }
+
+    #[test]
+    fn test_union_dedup() {
+        let mut config = default_config();
+        config.rules.union_dedup = true.into();
+        let unions = |sql| {
+            lint(sql, &config)
+                .into_iter()
+                .filter(|d| d.code == Some(NumberOrString::String("union-dedup".to_string())))
+                .collect::<Vec<_>>()
+        };
+
+        let flagged = unions("SELECT a FROM t\nunion\nSELECT a FROM s;");
+        assert_eq!(flagged.len(), 1);
+        assert_eq!(flagged[0].range.start, Position { line: 1, character: 0 });
+        assert_eq!(flagged[0].range.end, Position { line: 1, character: 5 });
+        assert_eq!(flagged[0].severity, Some(DiagnosticSeverity::INFORMATION));
+
+        assert!(unions("SELECT a FROM t UNION ALL SELECT a FROM s;").is_empty());
+        assert!(unions("SELECT a FROM t UNION DISTINCT SELECT a FROM s;").is_empty());
+        // Off by default
+        let defaults = lint("SELECT a FROM t UNION SELECT a FROM s;", &default_config());
+        assert!(!defaults.iter().any(|d| d.code == Some(NumberOrString::String("union-dedup".to_string()))));
+    }
+
+    #[test]
+    fn test_lint_cancellable() {
+        let text = "select a b from t";
+        let tokenized = Tokenizer::new(&*syntax::dialect("hive"), text).tokenize_with_location();
+        let config = default_config();
+
+        let complete = lint_cancellable(text, &tokenized, &config, None, None, &|| false).unwrap();
+        assert_eq!(complete, lint(text, &config));
+
+        // Cancelled part way through the rules
+        let checks = std::cell::Cell::new(0);
+        let cancelled = || {
+            checks.set(checks.get() + 1);
+            checks.get() > 3
+        };
+        assert_eq!(lint_cancellable(text, &tokenized, &config, None, None, &cancelled), None);
+        assert_eq!(checks.get(), 4);
+    }
+
+    #[test]
+    fn test_severity_floor() {
+        let sql = "SELECT a FROM t ORDER BY 1;  ";
+        let codes = |severity: &str| -> Vec<String> {
+            let config = LintingConfig { severity: severity.to_string(), ..default_config() };
+            lint(sql, &config)
+                .into_iter()
+                .filter_map(|d| match d.code {
+                    Some(NumberOrString::String(code)) => Some(code),
+                    _ => None,
+                })
+                .collect()
+        };
+        // trailing-whitespace and positional-reference are hints
+        assert!(codes("Hint").contains(&"trailing-whitespace".to_string()));
+        assert!(codes("hint").contains(&"positional-reference".to_string()));
+        assert!(codes("Warning").is_empty());
+        assert!(codes("Error").is_empty());
+        // An unknown name doesn't filter anything
+        assert_eq!(codes("loud"), codes("Hint"));
+
+        // Escalated severities don't count: the rule's own severity is compared
+        let config = LintingConfig { severity: "Warning".to_string(), treat_hints_as_errors: true, ..default_config() };
+        assert!(lint(sql, &config).is_empty());
+    }
+
+    #[test]
+    fn test_statement_chunks() {
+        let text = "SELECT ';' FROM t; -- a;b\nSELECT `x;y` /* ; */ FROM u;\nSELECT 3";
+        let statements: Vec<&str> = statement_chunks(text, 1, false).into_iter().map(|chunk| &text[chunk]).collect();
+        assert_eq!(statements, vec!["SELECT ';' FROM t;", " -- a;b\nSELECT `x;y` /* ; */ FROM u;", "\nSELECT 3"]);
+
+        let grouped: Vec<&str> = statement_chunks(text, 60, false).into_iter().map(|chunk| &text[chunk]).collect();
+        assert_eq!(grouped, vec!["SELECT ';' FROM t; -- a;b\nSELECT `x;y` /* ; */ FROM u;", "\nSELECT 3"]);
+
+        // A backslash before the closing quote only escapes it where the dialect says so
+        let text = "SELECT 'a\\'; b';";
+        let hive: Vec<&str> = statement_chunks(text, 1, false).into_iter().map(|chunk| &text[chunk]).collect();
+        assert_eq!(hive, vec!["SELECT 'a\\';", " b';"]);
+        let escaped: Vec<&str> = statement_chunks(text, 1, true).into_iter().map(|chunk| &text[chunk]).collect();
+        assert_eq!(escaped, vec!["SELECT 'a\\'; b';"]);
+    }
+
+    #[test]
+    fn test_large_file_is_linted_in_chunks() {
+        let mut text = "SELECT a, b FROM t WHERE c = 'x;y';\n".repeat(3000);
+        text.push_str("SELECT 1; SELECT a, FROM t;\n");
+        text.push_str(&"SELECT a, b FROM t WHERE c = 'x;y';\n".repeat(3000));
+        let config = LintingConfig { max_file_size: 16 * 1024, ..default_config() };
+        assert!(text.len() > 10 * config.max_file_size as usize);
+
+        let diags = lint(&text, &config);
+        assert_eq!(diags[0].range, Range::default());
+        assert_eq!(diags[0].severity, Some(DiagnosticSeverity::WARNING));
+        assert!(diags[0].message.contains("linted a few statements at a time"));
+        assert_eq!(diags[0].code, Some(NumberOrString::String("max-file-size".to_string())));
+
+        // Same findings as linting the whole file at once
+        let trailing_comma = |diags: &[Diagnostic]| -> Vec<Range> {
+            diags.iter()
+                .filter(|d| d.code == Some(NumberOrString::String("trailing-comma".to_string())))
+                .map(|d| d.range)
+                .collect()
+        };
+        let whole = lint(&text, &LintingConfig { max_file_size: u64::MAX, ..default_config() });
+        assert_eq!(trailing_comma(&diags), trailing_comma(&whole));
+        assert_eq!(trailing_comma(&diags)[0].start, Position { line: 3000, character: 18 });
+
+        // Past the hard cap nothing is linted
+        let capped = LintingConfig { max_chunked_file_size: 64 * 1024, ..config };
+        assert!(lint(&text, &capped).is_empty());
+    }
+
+    #[test]
+    fn test_distinct_with_group_by() {
+        let config = default_config();
+        let redundant = |sql| {
+            lint(sql, &config)
+                .into_iter()
+                .filter(|d| d.code == Some(NumberOrString::String("distinct-with-group-by".to_string())))
+                .collect::<Vec<_>>()
+        };
+
+        let flagged = redundant("SELECT DISTINCT a, b FROM t GROUP BY a, b;");
+        assert_eq!(flagged.len(), 1);
+        assert_eq!(flagged[0].range.start, Position { line: 0, character: 7 });
+        assert_eq!(flagged[0].severity, Some(DiagnosticSeverity::WARNING));
+
+        assert!(redundant("SELECT DISTINCT a, b FROM t;").is_empty());
+        // Only the subquery groups
+        assert!(redundant("SELECT DISTINCT a FROM (SELECT a, count(*) AS n FROM t GROUP BY a) s;").is_empty());
+        let inner = redundant("SELECT a FROM (SELECT DISTINCT a FROM t GROUP BY a) s GROUP BY a;");
+        assert_eq!(inner.len(), 1);
+        assert_eq!(inner[0].range.start, Position { line: 0, character: 22 });
+    }
+
+    #[test]
+    fn test_ambiguous_column() {
+        let sql = "SELECT id, amount, o.id AS order_id\nFROM sales.orders o JOIN customers c ON o.id = c.id\nWHERE id > 0;";
+        let diags = lint_with_schema(sql, &default_config(), Some(&test_schema()));
+        let ambiguous: Vec<_> = diags.iter()
+            .filter(|d| d.code == Some(NumberOrString::String("ambiguous-column".to_string())))
+            .collect();
+        assert_eq!(ambiguous.len(), 2);
+        assert_eq!(ambiguous[0].severity, Some(DiagnosticSeverity::WARNING));
+        assert_eq!(ambiguous[0].range.start, Position { line: 0, character: 7 });
+        assert_eq!(ambiguous[0].message, "Column 'id' exists in customers and sales.orders; qualify it with a table name or alias");
+        assert_eq!(ambiguous[1].range.start, Position { line: 2, character: 6 });
+    }
+
+    #[test]
+    fn test_ambiguous_column_needs_two_tables() {
+        // One table in scope, or no schema at all
+        let sql = "SELECT id FROM sales.orders;";
+        let diags = lint_with_schema(sql, &default_config(), Some(&test_schema()));
+        assert!(!diags.iter().any(|d| d.code == Some(NumberOrString::String("ambiguous-column".to_string()))));
+        let sql = "SELECT id FROM sales.orders o JOIN customers c ON o.id = c.id;";
         let diags = lint(sql, &default_config());
-        let msgs = get_messages(&diags);
-        assert!(msgs.iter().any(|m| m.contains("Empty Hive variable")));
+        assert!(!diags.iter().any(|d| d.code == Some(NumberOrString::String("ambiguous-column".to_string()))));
     }
 
     #[test]
-    fn test_missing_comma() {
-        let sql = "SELECT id name FROM users;";
+    fn test_positional_reference() {
+        let sql = "SELECT a, b, count(*) FROM t\nGROUP BY 1, b\nORDER BY 2 DESC, a;";
         let diags = lint(sql, &default_config());
-        let msgs = get_messages(&diags);
-        // Heuristic only checks multiline to avoid flagging valid aliases 'SELECT col alias'
-        // So this should NOT have a warning.
-        assert!(msgs.is_empty());
+        let positional: Vec<_> = diags.iter()
+            .filter(|d| d.code == Some(NumberOrString::String("positional-reference".to_string())))
+            .collect();
+        assert_eq!(positional.len(), 2);
+        assert_eq!(positional[0].severity, Some(DiagnosticSeverity::HINT));
+        assert_eq!(positional[0].range.start, Position { line: 1, character: 9 });
+        assert_eq!(positional[0].message, "GROUP BY 1 refers to a column by position; name the column instead");
+        assert_eq!(positional[1].range.start, Position { line: 2, character: 9 });
     }
 
     #[test]
-    fn test_missing_comma_multiline() {
-        let sql = "SELECT\n  id\n  name\nFROM users";
+    fn test_named_order_by() {
+        for sql in [
+            "SELECT a FROM t ORDER BY col;",
+            "SELECT a FROM t ORDER BY a + 1, substr(a, 1, 2) LIMIT 10;",
+            "SELECT a, count(*) FROM t GROUP BY a HAVING count(*) > 1;",
+        ] {
+            let diags = lint(sql, &default_config());
+            assert!(!diags.iter().any(|d| d.code == Some(NumberOrString::String("positional-reference".to_string()))), "{}", sql);
+        }
+    }
+
+    #[test]
+    fn test_null_comparison() {
+        let sql = "SELECT a FROM t\nWHERE b = NULL OR NULL <> c;";
         let diags = lint(sql, &default_config());
-        let msgs = get_messages(&diags);
-        assert!(msgs.iter().any(|m| m.contains("Possible missing comma")));
+        let comparisons: Vec<_> = diags.iter()
+            .filter(|d| d.code == Some(NumberOrString::String("null-comparison".to_string())))
+            .collect();
+        assert_eq!(comparisons.len(), 2);
+        assert_eq!(comparisons[0].severity, Some(DiagnosticSeverity::WARNING));
+        assert_eq!(comparisons[0].range.start, Position { line: 1, character: 8 });
+        assert_eq!(comparisons[0].range.end, Position { line: 1, character: 9 });
+        assert_eq!(comparisons[0].message, "Comparing with NULL using '=' is never true; use IS NULL");
+        assert_eq!(comparisons[1].range.start, Position { line: 1, character: 23 });
+        assert_eq!(comparisons[1].message, "Comparing with NULL using '<>' is never true; use IS NOT NULL");
     }
 
     #[test]
-    fn test_missing_comma_with_comma_present() {
-        let sql = "SELECT\n  p.product_id,\n  p.product_name,\n  i.quantity\nFROM products p;";
+    fn test_is_null_is_clean() {
+        for sql in [
+            "SELECT a FROM t WHERE b IS NULL AND c IS NOT NULL;",
+            "SELECT a FROM t WHERE b <=> NULL;",
+            "UPDATE t SET a = NULL WHERE b = 1;",
+        ] {
+            let diags = lint(sql, &default_config());
+            assert!(!diags.iter().any(|d| d.code == Some(NumberOrString::String("null-comparison".to_string()))), "{}", sql);
+        }
+    }
+
+    #[test]
+    fn test_semicolon_in_parentheses() {
+        let sql = "SELECT a FROM t\nWHERE b IN (SELECT b FROM u;);\nSELECT 1;";
         let diags = lint(sql, &default_config());
-        let msgs = get_messages(&diags);
-        assert!(msgs.is_empty());
+        let misplaced: Vec<_> = diags.iter()
+            .filter(|d| d.code == Some(NumberOrString::String("semicolon-in-parentheses".to_string())))
+            .collect();
+        assert_eq!(misplaced.len(), 1);
+        assert_eq!(misplaced[0].severity, Some(DiagnosticSeverity::ERROR));
+        assert_eq!(misplaced[0].range.start, Position { line: 1, character: 27 });
+
+        // Statement terminators, and semicolons in strings, are fine
+        let diags = lint("SELECT concat(a, ';') FROM t;\nSELECT (1 + 2);", &default_config());
+        assert!(!diags.iter().any(|d| d.code == Some(NumberOrString::String("semicolon-in-parentheses".to_string()))));
+    }
+
+    #[test]
+    fn test_semicolon_in_parentheses_does_not_carry_over() {
+        // An unclosed paren is reported once, not at every later statement's terminator
+        let diags = lint("SELECT count(a FROM t;\nSELECT 1;\nSELECT 2;", &default_config());
+        let misplaced: Vec<_> = diags.iter()
+            .filter(|d| d.code == Some(NumberOrString::String("semicolon-in-parentheses".to_string())))
+            .collect();
+        assert_eq!(misplaced.len(), 1);
+        assert_eq!(misplaced[0].range.start, Position { line: 0, character: 21 });
+    }
+
+    #[test]
+    fn test_keyword_alias() {
+        let sql = "SELECT sum(x) AS count, ts AS timestamp, z AS total FROM t;";
+        let diags = lint(sql, &default_config());
+        let aliases: Vec<_> = diags.iter()
+            .filter(|d| d.code == Some(NumberOrString::String("keyword-alias".to_string())))
+            .collect();
+        assert_eq!(aliases.len(), 2);
+        assert_eq!(aliases[0].severity, Some(DiagnosticSeverity::WARNING));
+        assert_eq!(aliases[0].range.start, Position { line: 0, character: 17 });
+        assert_eq!(aliases[0].message, "Alias 'count' is a Hive keyword; quote it as `count`");
+        assert!(aliases[1].message.contains("'timestamp'"));
+        // Reported once, not again as a reserved identifier
+        assert!(!diags.iter().any(|d| d.code == Some(NumberOrString::String("reserved-identifier".to_string()))));
+    }
+
+    #[test]
+    fn test_keyword_alias_quoted_or_contextual() {
+        // Contextual keywords are ordinary identifiers outside their clauses
+        let sql = "SELECT sum(x) AS `count`, a AS location, b AS comment, c AS format, d AS lines, CAST(y AS STRING) AS y FROM t;";
+        let diags = lint(sql, &default_config());
+        assert!(!diags.iter().any(|d| d.code == Some(NumberOrString::String("keyword-alias".to_string()))));
+    }
+
+    #[test]
+    fn test_group_by_consistency() {
+        let mut config = default_config();
+        config.rules.group_by_consistency = true.into();
+        let sql = "SELECT dept, name, COUNT(*) FROM emp GROUP BY dept;";
+        let diags: Vec<_> = lint(sql, &config)
+            .into_iter()
+            .filter(|d| d.code == Some(NumberOrString::String("group-by-consistency".to_string())))
+            .collect();
+        assert_eq!(diags.len(), 1);
+        assert_eq!(diags[0].range.start, Position { line: 0, character: 13 });
+        assert_eq!(diags[0].message, "Column 'name' is selected but not in the GROUP BY; add it to the GROUP BY or aggregate it");
+
+        // Off by default
+        assert!(!lint(sql, &default_config()).iter().any(|d| d.code == Some(NumberOrString::String("group-by-consistency".to_string()))));
+    }
+
+    #[test]
+    fn test_group_by_consistency_clean() {
+        let mut config = default_config();
+        config.rules.group_by_consistency = true.into();
+        for sql in [
+            "SELECT e.dept, SUM(salary) AS total FROM emp e GROUP BY e.dept;",
+            "SELECT dept, name, MAX(salary) FROM emp GROUP BY dept, name;",
+            "SELECT dept, name FROM emp GROUP BY 1, 2;",
+            "SELECT name, COUNT(*) FROM emp GROUP BY upper(name);",
+            "SELECT name, x FROM emp;",
+            "SELECT dept, (SELECT MAX(id) FROM t) FROM emp GROUP BY dept;",
+            "SELECT dept, name FROM emp GROUP BY dept, name GROUPING SETS ((dept), (dept, name));",
+        ] {
+            assert!(
+                !lint(sql, &config).iter().any(|d| d.code == Some(NumberOrString::String("group-by-consistency".to_string()))),
+                "{}",
+                sql
+            );
+        }
+    }
+
+    #[test]
+    fn test_missing_over() {
+        let sql = "SELECT id, ROW_NUMBER() AS rn, lag(amount, 1) FROM t;";
+        let diags: Vec<_> = lint(sql, &default_config())
+            .into_iter()
+            .filter(|d| d.code == Some(NumberOrString::String("missing-over".to_string())))
+            .collect();
+        assert_eq!(diags.len(), 2);
+        assert_eq!(diags[0].severity, Some(DiagnosticSeverity::ERROR));
+        assert_eq!(diags[0].range.start, Position { line: 0, character: 11 });
+        assert_eq!(diags[0].range.end, Position { line: 0, character: 21 });
+        assert_eq!(diags[0].message, "ROW_NUMBER is an analytic function and needs an OVER (...) clause");
+        assert!(diags[1].message.starts_with("LAG "));
+    }
+
+    #[test]
+    fn test_missing_over_clean() {
+        let sql = "SELECT ROW_NUMBER() OVER (PARTITION BY a ORDER BY b) AS rn,\n  rank() -- ranked\n  OVER w, udf.rank(x), `rank` FROM t;";
+        assert!(!lint(sql, &default_config()).iter().any(|d| d.code == Some(NumberOrString::String("missing-over".to_string()))));
+    }
+
+    #[test]
+    fn test_missing_stored_as() {
+        let sql = "CREATE EXTERNAL TABLE t (\n  id INT,\n  tags ARRAY<STRING> COMMENT 'stored as tags'\n)\nPARTITIONED BY (dt STRING)\nLOCATION '/data/t';";
+        let diags: Vec<_> = lint(sql, &default_config())
+            .into_iter()
+            .filter(|d| d.code == Some(NumberOrString::String("missing-stored-as".to_string())))
+            .collect();
+        assert_eq!(diags.len(), 1);
+        assert_eq!(diags[0].severity, Some(DiagnosticSeverity::INFORMATION));
+        assert_eq!(diags[0].range.start, Position { line: 0, character: 0 });
+        assert_eq!(diags[0].range.end, Position { line: 0, character: 21 });
+    }
+
+    #[test]
+    fn test_missing_stored_as_clean() {
+        for sql in [
+            "CREATE TABLE t (id INT, amount DECIMAL(10, 2))\nPARTITIONED BY (dt STRING)\nSTORED AS ORC;",
+            "CREATE TABLE t (id INT) ROW FORMAT DELIMITED FIELDS TERMINATED BY ','\nSTORED AS TEXTFILE;",
+            "CREATE TABLE t (id INT) STORED BY 'org.apache.hadoop.hive.hbase.HBaseStorageHandler';",
+            "CREATE TABLE t AS SELECT * FROM s;",
+            "CREATE TABLE t LIKE s;",
+            "CREATE VIEW v AS SELECT 1;",
+        ] {
+            assert!(
+                !lint(sql, &default_config()).iter().any(|d| d.code == Some(NumberOrString::String("missing-stored-as".to_string()))),
+                "{}",
+                sql
+            );
+        }
+    }
+
+    #[test]
+    fn test_duplicate_alias() {
+        let sql = "SELECT t.id\nFROM a\nJOIN b t ON a.id = t.id\nJOIN c AS t ON a.id = t.id;";
+        let diags: Vec<_> = lint(sql, &default_config())
+            .into_iter()
+            .filter(|d| d.code == Some(NumberOrString::String("duplicate-alias".to_string())))
+            .collect();
+        assert_eq!(diags.len(), 1);
+        assert_eq!(diags[0].range.start, Position { line: 3, character: 10 });
+        assert_eq!(diags[0].message, "Alias 't' is already defined in this query; give this table its own alias");
+        let related = diags[0].related_information.as_ref().unwrap();
+        assert_eq!(related[0].location.range.start, Position { line: 2, character: 7 });
+    }
+
+    #[test]
+    fn test_duplicate_alias_clean() {
+        for sql in [
+            "SELECT x.id FROM a x JOIN b y ON x.id = y.id;",
+            "SELECT t.id FROM a t WHERE t.id IN (SELECT t.id FROM b t);",
+            "SELECT t.id FROM a t UNION ALL SELECT t.id FROM b t;",
+            "SELECT t.id FROM a t;\nSELECT t.id FROM b t;",
+        ] {
+            assert!(
+                !lint(sql, &default_config()).iter().any(|d| d.code == Some(NumberOrString::String("duplicate-alias".to_string()))),
+                "{}",
+                sql
+            );
+        }
+    }
+
+    #[test]
+    fn test_deprecated_function() {
+        let sql = "SELECT java_method('java.util.UUID', 'randomUUID'), unix_timestamp(), unix_timestamp(ts) FROM t;";
+        let diags: Vec<_> = lint(sql, &default_config())
+            .into_iter()
+            .filter(|d| d.code == Some(NumberOrString::String("deprecated-function".to_string())))
+            .collect();
+        assert_eq!(diags.len(), 2);
+        assert_eq!(diags[0].severity, Some(DiagnosticSeverity::WARNING));
+        assert_eq!(diags[0].range.start, Position { line: 0, character: 7 });
+        assert_eq!(diags[0].message, "'java_method' is deprecated; use REFLECT instead");
+        assert_eq!(diags[1].message, "'unix_timestamp' is deprecated; use CURRENT_TIMESTAMP instead");
+    }
+
+    #[test]
+    fn test_deprecated_function_configured() {
+        let mut config = default_config();
+        config.rules.deprecated_function = serde_json::from_value(serde_json::json!({
+            "functions": { "old_hash": "sha2" },
+            "allow": ["java_method"]
+        }))
+        .unwrap();
+        let sql = "SELECT OLD_HASH(x), java_method('a', 'b'), old_hash FROM t;";
+        let messages: Vec<_> = lint(sql, &config)
+            .into_iter()
+            .filter(|d| d.code == Some(NumberOrString::String("deprecated-function".to_string())))
+            .map(|d| d.message)
+            .collect();
+        assert_eq!(messages, vec!["'OLD_HASH' is deprecated; use sha2 instead"]);
+    }
+
+    #[test]
+    fn test_constant_predicate() {
+        let mut config = default_config();
+        config.rules.constant_predicate = true.into();
+        let sql = "SELECT a FROM t WHERE 1=1 AND a = b;\nSELECT a FROM t WHERE a = b OR 1 = 0;";
+        let diags: Vec<_> = lint(sql, &config)
+            .into_iter()
+            .filter(|d| d.code == Some(NumberOrString::String("constant-predicate".to_string())))
+            .collect();
+        assert_eq!(diags.len(), 2);
+        assert_eq!(diags[0].severity, Some(DiagnosticSeverity::HINT));
+        assert_eq!(diags[0].range.start, Position { line: 0, character: 22 });
+        assert_eq!(diags[0].range.end, Position { line: 0, character: 25 });
+        assert_eq!(diags[0].message, "1 = 1 is always true and filters nothing");
+        assert_eq!(diags[1].message, "1 = 0 is always false, so no rows match");
+
+        // Off by default
+        assert!(!lint(sql, &default_config()).iter().any(|d| d.code == Some(NumberOrString::String("constant-predicate".to_string()))));
+    }
+
+    #[test]
+    fn test_constant_predicate_clean() {
+        let mut config = default_config();
+        config.rules.constant_predicate = true.into();
+        for sql in [
+            "SELECT a FROM t WHERE a = b;",
+            "SELECT a FROM t WHERE a + 1 = 1;",
+            "SELECT a FROM t WHERE 1 = 1 + a;",
+            "SELECT a FROM t WHERE x IN (SELECT 1 = 1);",
+        ] {
+            assert!(
+                !lint(sql, &config).iter().any(|d| d.code == Some(NumberOrString::String("constant-predicate".to_string()))),
+                "{}",
+                sql
+            );
+        }
+    }
+
+    #[test]
+    fn test_type_casing() {
+        let mut config = default_config();
+        config.rules.type_casing = true.into();
+        let sql = "CREATE TABLE t (id int, tags Array<String>, s STRUCT<date:string>, `timestamp` BIGINT)\nPARTITIONED BY (dt string);";
+        let diags: Vec<_> = lint(sql, &config)
+            .into_iter()
+            .filter(|d| d.code == Some(NumberOrString::String("type-casing".to_string())))
+            .collect();
+        let messages: Vec<&str> = diags.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(messages, vec![
+            "Type 'int' should be uppercase",
+            "Type 'Array' should be uppercase",
+            "Type 'String' should be uppercase",
+            "Type 'string' should be uppercase",
+            "Type 'string' should be uppercase",
+        ]);
+        assert_eq!(diags[0].range.start, Position { line: 0, character: 19 });
+        assert_eq!(diags[0].range.end, Position { line: 0, character: 22 });
+        assert_eq!(diags[4].range.start.line, 1);
+    }
+
+    #[test]
+    fn test_type_casing_clean() {
+        let mut config = default_config();
+        config.rules.type_casing = true.into();
+        for sql in [
+            "CREATE TABLE t (id INT, name STRING);",
+            "CREATE TABLE t (date DATE, string STRING);",
+            "INSERT INTO t (date, string) VALUES (1, 2);",
+            "SELECT CAST(a AS int) FROM t;",
+        ] {
+            assert!(
+                !lint(sql, &config).iter().any(|d| d.code == Some(NumberOrString::String("type-casing".to_string()))),
+                "{}",
+                sql
+            );
+        }
+        // Off by default
+        assert!(!lint("CREATE TABLE t (id int);", &default_config()).iter().any(|d| d.code == Some(NumberOrString::String("type-casing".to_string()))));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_type_mismatch() {
+        let schema: Schema = serde_json::from_value(serde_json::json!({ "tables": [
+            { "name": "sales.orders", "columns": [ { "name": "id", "type": "bigint" }, { "name": "code", "type": "varchar(10)" } ] }
+        ] }))
+        .unwrap();
+        let mut config = default_config();
+        config.rules.type_mismatch = true.into();
+        let sql = "SELECT id FROM sales.orders o\nWHERE o.id = '5' AND 7 < code AND id = 5 AND code = '7' AND id + 1 = '5';";
+        let mismatches: Vec<_> = lint_with_schema(sql, &config, Some(&schema))
+            .into_iter()
+            .filter(|d| d.code == Some(NumberOrString::String("type-mismatch".to_string())))
+            .collect();
+        assert_eq!(mismatches.len(), 2);
+        assert_eq!(mismatches[0].severity, Some(DiagnosticSeverity::WARNING));
+        assert_eq!(mismatches[0].range.start, Position { line: 1, character: 6 });
+        assert_eq!(mismatches[0].range.end, Position { line: 1, character: 16 });
+        assert_eq!(mismatches[0].message, "'id' is BIGINT but is compared with the string '5'; Hive converts both to DOUBLE");
+        assert_eq!(
+            mismatches[1].message,
+            "'code' is VARCHAR(10) but is compared with the number 7; Hive converts both to DOUBLE, so quote it as '7'"
+        );
+
+        // Needs a schema, and is off by default
+        assert!(!lint(sql, &config).iter().any(|d| d.code == Some(NumberOrString::String("type-mismatch".to_string()))));
+        assert!(!lint_with_schema(sql, &default_config(), Some(&schema))
+            .iter()
+            .any(|d| d.code == Some(NumberOrString::String("type-mismatch".to_string()))));
+    }
+
+    #[test]
+    fn test_select_distinct_star() {
+        let sql = "SELECT DISTINCT * FROM t;\nSELECT DISTINCT a FROM t;\nSELECT * FROM t;";
+        let diags = lint(sql, &default_config());
+        let distinct: Vec<_> = diags.iter()
+            .filter(|d| d.code == Some(NumberOrString::String("select-distinct-star".to_string())))
+            .collect();
+        assert_eq!(distinct.len(), 1);
+        assert_eq!(distinct[0].severity, Some(DiagnosticSeverity::INFORMATION));
+        assert_eq!(distinct[0].range.start, Position { line: 0, character: 7 });
+        assert_eq!(distinct[0].range.end, Position { line: 0, character: 17 });
+
+        // Both rules report the same statement
+        let mut config = default_config();
+        config.rules.select_star = true.into();
+        let diags = lint("SELECT DISTINCT * FROM t;", &config);
+        assert!(diags.iter().any(|d| d.code == Some(NumberOrString::String("select-star".to_string()))));
+        assert!(diags.iter().any(|d| d.code == Some(NumberOrString::String("select-distinct-star".to_string()))));
+    }
+
+    #[test]
+    fn test_inconsistent_quoting() {
+        let mut config = default_config();
+        config.rules.inconsistent_quoting = true.into();
+        let sql = "SELECT `id`, name FROM t WHERE ID > 0 AND `Name` <> '';\nSELECT id FROM `t`;";
+        let diags: Vec<_> = lint(sql, &config)
+            .into_iter()
+            .filter(|d| d.code == Some(NumberOrString::String("inconsistent-quoting".to_string())))
+            .collect();
+        assert_eq!(diags.len(), 2);
+        assert_eq!(diags[0].severity, Some(DiagnosticSeverity::HINT));
+        assert_eq!(diags[0].range.start, Position { line: 0, character: 31 });
+        assert_eq!(diags[0].message, "'ID' is unquoted here but quoted with backticks where it is first used");
+        let related = diags[0].related_information.as_ref().unwrap();
+        assert_eq!(related[0].location.range.start, Position { line: 0, character: 7 });
+        assert_eq!(related[0].message, "First used here");
+        assert_eq!(diags[1].message, "'Name' is quoted here but not where it is first used");
+
+        // Off by default
+        assert!(!lint(sql, &default_config()).iter().any(|d| d.code == Some(NumberOrString::String("inconsistent-quoting".to_string()))));
+    }
+
+    #[test]
+    fn test_missing_partition() {
+        let schema: Schema = serde_json::from_str(r#"{ "tables": [
+            { "name": "mart.daily", "partitionedBy": ["dt"] }, { "name": "mart.totals" } ] }"#).unwrap();
+        let mut config = default_config();
+        config.rules.missing_partition = true.into();
+        let sql = "INSERT OVERWRITE TABLE mart.daily SELECT * FROM s;\n\
+                   INSERT OVERWRITE TABLE mart.daily PARTITION (dt = '2024-01-01') SELECT a FROM s;\n\
+                   INSERT OVERWRITE TABLE daily PARTITION (dt) SELECT a, dt FROM s;\n\
+                   INSERT OVERWRITE TABLE mart.totals SELECT * FROM s;\n\
+                   INSERT INTO TABLE mart.daily SELECT * FROM s;\n\
+                   INSERT OVERWRITE DIRECTORY '/tmp/x' SELECT * FROM s;";
+        let missing: Vec<_> = lint_with_schema(sql, &config, Some(&schema))
+            .into_iter()
+            .filter(|d| d.code == Some(NumberOrString::String("missing-partition".to_string())))
+            .collect();
+        assert_eq!(missing.len(), 1);
+        assert_eq!(missing[0].severity, Some(DiagnosticSeverity::WARNING));
+        assert_eq!(missing[0].range.start, Position { line: 0, character: 23 });
+        assert_eq!(missing[0].range.end, Position { line: 0, character: 33 });
+        assert_eq!(
+            missing[0].message,
+            "'mart.daily' is partitioned by dt; add PARTITION (...) to say which partitions INSERT OVERWRITE replaces"
+        );
+
+        // Off by default
+        assert!(!lint_with_schema(sql, &default_config(), Some(&schema))
+            .iter()
+            .any(|d| d.code == Some(NumberOrString::String("missing-partition".to_string()))));
+    }
+
+    #[test]
+    fn test_like_without_wildcard() {
+        let sql = "SELECT * FROM t\nWHERE a LIKE 'abc' AND b NOT LIKE \"x\" AND c LIKE 'a\\%';";
+        let diagnostics: Vec<_> = lint(sql, &default_config())
+            .into_iter()
+            .filter(|d| d.code == Some(NumberOrString::String("like-without-wildcard".to_string())))
+            .collect();
+        assert_eq!(diagnostics.len(), 3);
+        assert_eq!(diagnostics[0].range.start, Position { line: 1, character: 13 });
+        assert_eq!(diagnostics[0].range.end, Position { line: 1, character: 18 });
+        assert!(diagnostics[0].message.contains("use = instead of LIKE"));
+        assert!(diagnostics[1].message.contains("use <> instead of NOT LIKE"));
+        assert_eq!(diagnostics[0].severity, Some(DiagnosticSeverity::INFORMATION));
+
+        for clean in [
+            "SELECT * FROM t WHERE a LIKE 'a%';",
+            "SELECT * FROM t WHERE a NOT LIKE 'a_c';",
+            "SELECT * FROM t WHERE a LIKE '${hivevar:prefix}';",
+            "SELECT * FROM t WHERE a LIKE b;",
+            "CREATE TABLE t2 LIKE t;",
+            "SHOW TABLES LIKE 'sales*';",
+            "SHOW DATABASES LIKE 'tmp|stage';",
+        ] {
+            assert!(
+                !lint(clean, &default_config()).iter().any(|d| d.code == Some(NumberOrString::String("like-without-wildcard".to_string()))),
+                "{}",
+                clean
+            );
+        }
+    }
+
+    #[test]
+    fn test_diagnostic_tags() {
+        let mut config = default_config();
+        config.rules.deprecated_function = true.into();
+        config.rules.select_star = true.into();
+        let diagnostics = lint("SELECT java_method('Math', 'abs', x) FROM t; \nSELECT * FROM t;\n", &config);
+        let tags = |code: &str| {
+            diagnostics
+                .iter()
+                .find(|d| d.code == Some(NumberOrString::String(code.to_string())))
+                .and_then(|d| d.tags.clone())
+        };
+        assert_eq!(tags("deprecated-function"), Some(vec![DiagnosticTag::DEPRECATED]));
+        assert_eq!(tags("trailing-whitespace"), Some(vec![DiagnosticTag::UNNECESSARY]));
+        assert_eq!(tags("select-star"), Some(vec![DiagnosticTag::UNNECESSARY]));
+    }
+
+    #[test]
+    fn test_configured_source() {
+        let mut config = default_config();
+        assert!(lint("SELECT (1;", &config).iter().all(|d| d.source.as_deref() == Some("hql-ls")));
+        config.source = "hive-lint".to_string();
+        let diagnostics = lint("SELECT (1;", &config);
+        assert!(!diagnostics.is_empty());
+        assert!(diagnostics.iter().all(|d| d.source.as_deref() == Some("hive-lint")));
+    }
+
+    #[test]
+    fn test_multi_column_count_distinct() {
+        let mut config = default_config();
+        config.rules.multi_column_count_distinct = true.into();
+        let count_distinct = |sql: &str, config: &LintingConfig| -> Vec<Diagnostic> {
+            lint(sql, config)
+                .into_iter()
+                .filter(|d| d.code == Some(NumberOrString::String("multi-column-count-distinct".to_string())))
+                .collect()
+        };
+
+        let sql = "SELECT COUNT(DISTINCT a, concat(b, c)) FROM t;";
+        let diagnostics = count_distinct(sql, &config);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].range.start, Position { line: 0, character: 7 });
+        assert_eq!(diagnostics[0].range.end, Position { line: 0, character: 38 });
+        assert!(diagnostics[0].message.contains("over 2 columns"));
+
+        assert!(count_distinct("SELECT COUNT(DISTINCT a) FROM t;", &config).is_empty());
+        assert!(count_distinct("SELECT COUNT(DISTINCT concat(a, b)) FROM t;", &config).is_empty());
+        assert!(count_distinct("SELECT COUNT(a), COUNT(b) FROM t;", &config).is_empty());
+        // Off by default, and only for Hive
+        assert!(count_distinct(sql, &default_config()).is_empty());
+        config.dialect = "generic".to_string();
+        assert!(count_distinct(sql, &config).is_empty());
+    }
+
+    #[test]
+    fn test_batch_separator() {
+        let batch_separators = |sql: &str| -> Vec<Diagnostic> {
+            lint(sql, &default_config())
+                .into_iter()
+                .filter(|d| d.code == Some(NumberOrString::String("batch-separator".to_string())))
+                .collect()
+        };
+
+        let diagnostics = batch_separators("SELECT a FROM t\ngo\nSELECT b FROM u;\nGO -- end of batch\n");
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].range.start, Position { line: 1, character: 0 });
+        assert_eq!(diagnostics[0].range.end, Position { line: 1, character: 2 });
+        assert_eq!(diagnostics[0].severity, Some(DiagnosticSeverity::ERROR));
+        assert_eq!(diagnostics[1].range.start.line, 3);
+
+        // A column named go, on its own line or not
+        assert!(batch_separators("SELECT go FROM t;").is_empty());
+        assert!(batch_separators("SELECT\n  go\nFROM t;").is_empty());
+        assert!(batch_separators("SELECT a,\n  go\nFROM t;").is_empty());
+        assert!(batch_separators("SELECT `go`\nFROM t;").is_empty());
+    }
+
+    #[test]
+    fn test_union_column_mismatch() {
+        let mismatches = |sql: &str| -> Vec<Diagnostic> {
+            lint(sql, &default_config())
+                .into_iter()
+                .filter(|d| d.code == Some(NumberOrString::String("union-column-mismatch".to_string())))
+                .collect()
+        };
+
+        let diagnostics = mismatches("SELECT a, b FROM t\nUNION ALL\nSELECT c FROM u;");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].range.start, Position { line: 2, character: 0 });
+        assert_eq!(diagnostics[0].message, "SELECT returns 1 column(s) but the first branch returns 2");
+
+        // Only the branch that differs from the first
+        let diagnostics = mismatches("SELECT a, b FROM t UNION SELECT c, d FROM u UNION SELECT e FROM v;");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].range.start.character, 50);
+
+        for clean in [
+            "SELECT a, concat(b, c) FROM t UNION ALL SELECT d, e FROM u;",
+            "SELECT * FROM t UNION ALL SELECT a FROM u;",
+            "SELECT a FROM (SELECT a, b FROM t UNION ALL SELECT c, d FROM u) x;",
+            "SELECT a, b FROM t; SELECT c FROM u;",
+        ] {
+            assert!(mismatches(clean).is_empty(), "{}", clean);
+        }
+        // Nested unions are compared within their own parentheses
+        assert_eq!(mismatches("SELECT a FROM (SELECT a, b FROM t UNION ALL SELECT c FROM u) x;").len(), 1);
+    }
+
+    #[test]
+    fn test_unbalanced_case() {
+        let unbalanced = |sql: &str| -> Vec<Diagnostic> {
+            lint(sql, &default_config())
+                .into_iter()
+                .filter(|d| d.code == Some(NumberOrString::String("unbalanced-case".to_string())))
+                .collect()
+        };
+
+        let diagnostics = unbalanced("SELECT\n  CASE WHEN a THEN 1 ELSE 0 AS flag\nFROM t;\nSELECT 1 END FROM t;");
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].message, "CASE without a matching END");
+        assert_eq!(diagnostics[0].range.start, Position { line: 1, character: 2 });
+        assert_eq!(diagnostics[1].message, "END without a matching CASE");
+        assert_eq!(diagnostics[1].range.start.line, 3);
+        assert_eq!(diagnostics[0].severity, Some(DiagnosticSeverity::ERROR));
+
+        // Closed inside its own parentheses, or not at all
+        assert_eq!(unbalanced("SELECT (CASE WHEN a THEN 1) FROM t;").len(), 1);
+
+        for clean in [
+            "SELECT CASE WHEN a THEN CASE b WHEN 1 THEN 'x' END ELSE 'y' END AS c FROM t;",
+            "SELECT (CASE WHEN a THEN 1 END) + 1, t.end, `end` FROM t;",
+        ] {
+            assert!(unbalanced(clean).is_empty(), "{}", clean);
+        }
+    }
+
+    #[test]
+    fn test_subquery_depth() {
+        let depth = |sql: &str, config: &LintingConfig| -> Vec<(Position, String)> {
+            lint(sql, config)
+                .into_iter()
+                .filter(|d| d.code == Some(NumberOrString::String("subquery-depth".to_string())))
+                .map(|d| (d.range.start, d.message))
+                .collect()
+        };
+        let nested = "SELECT a FROM (SELECT a FROM (SELECT a FROM (SELECT a FROM (SELECT a FROM (SELECT a FROM t) s5) s4) s3) s2) s1;";
+        assert_eq!(
+            depth(nested, &default_config()),
+            vec![(
+                Position { line: 0, character: 75 },
+                "Subquery is nested 5 levels deep (limit 4); consider rewriting the inner queries as CTEs".to_string()
+            )]
+        );
+
+        // Four levels is within the default limit, and parentheses that don't start a SELECT don't count
+        let within = "SELECT a FROM (SELECT a FROM (SELECT a FROM (SELECT MAX(a) AS a FROM (SELECT a FROM t WHERE (a > 1)) s4) s3) s2) s1;";
+        assert!(depth(within, &default_config()).is_empty());
+
+        let mut config = default_config();
+        config.rules.subquery_depth.options.insert("maxDepth".to_string(), 3.into());
+        assert_eq!(depth(within, &config).len(), 1);
+    }
+
+    #[test]
+    fn test_set_property() {
+        let sql = "SET hive.exec.dynamic.partition=true;\n\
+                   SET hive.exce.dynamic.partition.mode=nonstrict;\n\
+                   SET mapred.reduce.tasks = 10;\n\
+                   SET hivevar:dt=2024-01-01;\n\
+                   SET tez.grouping.max-size=1073741824;\n\
+                   SET hive.spark.client.connect.timeout=30000ms;\n\
+                   SET hive.hashtable.initialCapacity=100000;\n\
+                   SET hive.lock.numretries=10;\n\
+                   SET hive.script.operator.truncate.env=true;\n\
+                   SET mapreduce.framework.name=yarn;\n\
+                   SET hive.compat=0.12;\n\
+                   SET hive.fileformat.check=true;\n\
+                   SET;";
+        let diags: Vec<_> = lint(sql, &default_config())
+            .into_iter()
+            .filter(|d| d.code == Some(NumberOrString::String("set-property".to_string())))
+            .collect();
+        assert_eq!(diags.len(), 2);
+        assert_eq!(diags[0].severity, Some(DiagnosticSeverity::HINT));
+        assert_eq!(diags[0].message, "'hive.exce.dynamic.partition.mode' doesn't start with a known property prefix; is it misspelled?");
+        assert_eq!(diags[0].range.start, Position { line: 1, character: 4 });
+        assert_eq!(diags[0].range.end, Position { line: 1, character: 36 });
+        assert_eq!(diags[1].severity, Some(DiagnosticSeverity::WARNING));
+        assert_eq!(diags[1].message, "'mapred.reduce.tasks' is deprecated; use mapreduce.job.reduces instead");
+        assert_eq!(diags[1].tags, Some(vec![DiagnosticTag::DEPRECATED]));
+    }
+
+    #[test]
+    fn test_set_property_configured() {
+        let mut config = default_config();
+        config.rules.set_property = serde_json::from_value(serde_json::json!({
+            "prefixes": ["acme."],
+            "deprecated": { "hive.exec.parallel": "hive.exec.parallel.thread.number" }
+        }))
+        .unwrap();
+        let sql = "SET acme.team=data;\nSET hive.exec.parallel=true;\nSET acm.team=data;";
+        let messages: Vec<_> = lint(sql, &config)
+            .into_iter()
+            .filter(|d| d.code == Some(NumberOrString::String("set-property".to_string())))
+            .map(|d| d.message)
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                "'hive.exec.parallel' is deprecated; use hive.exec.parallel.thread.number instead",
+                "'acm.team' doesn't start with a known property prefix; is it misspelled?",
+            ]
+        );
+    }
+}
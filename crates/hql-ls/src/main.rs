@@ -2,35 +2,178 @@ use tower_lsp::jsonrpc::Result;
 use tower_lsp::lsp_types::*;
 use tower_lsp::{Client, LanguageServer, LspService, Server};
 use dashmap::DashMap;
+use sqlparser::tokenizer::Tokenizer;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Duration;
 use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
 
+mod cli;
+mod report;
+mod rules;
 mod linter;
 mod formatter;
 mod config;
 mod completion;
+mod code_actions;
+mod code_lens;
+mod document;
+mod syntax;
+mod references;
+mod rename;
+mod symbols;
+mod folding;
+mod highlight;
+mod links;
+mod inlay_hints;
+mod hover;
+mod cte_graph;
+mod keywords;
+mod selection_range;
+mod semantic_tokens;
+mod signature_help;
+mod suppression;
+mod publish_cache;
+mod incremental;
+mod metrics;
+mod token_cache;
+mod schema;
 
-use config::HqlConfig;
+use config::{ConfigSources, FolderConfigs, HqlConfig, LintingConfig, CONFIG_FILE_NAME};
+use metrics::RuleMetrics;
+use publish_cache::PublishCache;
+use token_cache::{TokenCache, Tokenized};
+use schema::{WorkspaceSchema, SCHEMA_FILE_NAME};
 
-#[derive(Debug)]
+/// Cheap to clone, so work such as a debounced lint can run on its own task.
+#[derive(Debug, Clone)]
 struct Backend {
     client: Client,
-    document_map: DashMap<String, ropey::Rope>,
+    document_map: Arc<DashMap<String, ropey::Rope>>,
+    /// Client version of each open document, for pull diagnostics and the token cache
+    versions: Arc<DashMap<String, i32>>,
+    tokens: Arc<TokenCache>,
     config: Arc<RwLock<HqlConfig>>,
+    /// Settings layers `config` is resolved from (defaults < `.hqlint.toml` < client settings)
+    config_sources: Arc<RwLock<ConfigSources>>,
+    /// Per-folder configs in a multi-root workspace; documents outside them use `config`
+    folder_configs: Arc<RwLock<FolderConfigs>>,
+    /// Whether the client answers `workspace/configuration`, for folder-scoped settings
+    pull_configuration: Arc<AtomicBool>,
+    published: Arc<PublishCache>,
+    /// Bumped when config or schema changes invalidate every pulled diagnostic report
+    lint_generation: Arc<AtomicU64>,
+    /// The lint scheduled after each document's last change and the version it lints,
+    /// replaced by the next change and removed once it has run
+    pending_lints: Arc<DashMap<String, (i32, JoinHandle<()>)>>,
+    /// Time spent in each lint rule, for `hql/metrics`
+    metrics: Arc<RuleMetrics>,
+    /// Tables and columns from the workspace `schema.json`, used for completion
+    schema: Arc<RwLock<WorkspaceSchema>>,
 }
 
 #[tower_lsp::async_trait]
 impl LanguageServer for Backend {
-    async fn initialize(&self, _: InitializeParams) -> Result<InitializeResult> {
+    async fn initialize(&self, params: InitializeParams) -> Result<InitializeResult> {
+        #[allow(deprecated)] // `root_uri` is still what most clients send
+        let root = params
+            .workspace_folders
+            .as_ref()
+            .and_then(|folders| folders.first())
+            .map(|folder| &folder.uri)
+            .or(params.root_uri.as_ref())
+            .and_then(|uri| uri.to_file_path().ok());
+
+        {
+            let mut sources = self.config_sources.write().await;
+            *sources = ConfigSources::new(root.as_deref());
+            if let Err(e) = sources.reload_file() {
+                self.client.log_message(MessageType::ERROR, format!("Failed to load {}: {}", CONFIG_FILE_NAME, e)).await;
+            }
+        }
+        self.refresh_config().await;
+
+        // Folder-scoped client settings are pulled once the client is initialized
+        let pull_configuration = params.capabilities.workspace.as_ref().and_then(|w| w.configuration).unwrap_or(false);
+        for folder in params.workspace_folders.iter().flatten() {
+            self.load_folder(folder.uri.clone()).await;
+        }
+        self.pull_configuration.store(pull_configuration, Ordering::Relaxed);
+
+        {
+            let mut schema = self.schema.write().await;
+            *schema = WorkspaceSchema::new(root.as_deref());
+            if let Err(e) = schema.reload() {
+                self.client.log_message(MessageType::ERROR, format!("Failed to load {}: {}", SCHEMA_FILE_NAME, e)).await;
+            }
+        }
+
         Ok(InitializeResult {
             capabilities: ServerCapabilities {
                 text_document_sync: Some(TextDocumentSyncCapability::Kind(
-                    TextDocumentSyncKind::FULL,
+                    TextDocumentSyncKind::INCREMENTAL,
                 )),
                 document_formatting_provider: Some(OneOf::Left(true)),
-                completion_provider: Some(CompletionOptions {
+                document_range_formatting_provider: Some(OneOf::Left(true)),
+                hover_provider: Some(HoverProviderCapability::Simple(true)),
+                definition_provider: Some(OneOf::Left(true)),
+                references_provider: Some(OneOf::Left(true)),
+                document_highlight_provider: Some(OneOf::Left(true)),
+                rename_provider: Some(OneOf::Right(RenameOptions {
+                    prepare_provider: Some(true),
+                    work_done_progress_options: Default::default(),
+                })),
+                document_symbol_provider: Some(OneOf::Left(true)),
+                workspace: Some(WorkspaceServerCapabilities {
+                    workspace_folders: Some(WorkspaceFoldersServerCapabilities {
+                        supported: Some(true),
+                        change_notifications: Some(OneOf::Left(true)),
+                    }),
+                    file_operations: None,
+                }),
+                workspace_symbol_provider: Some(OneOf::Left(true)),
+                document_link_provider: Some(DocumentLinkOptions {
                     resolve_provider: Some(false),
-                    trigger_characters: Some(vec![".".to_string()]),
+                    work_done_progress_options: Default::default(),
+                }),
+                code_lens_provider: Some(CodeLensOptions { resolve_provider: Some(false) }),
+                execute_command_provider: Some(ExecuteCommandOptions {
+                    commands: vec![formatter::FORMAT_WORKSPACE.to_string()],
+                    work_done_progress_options: WorkDoneProgressOptions { work_done_progress: Some(true) },
+                }),
+                folding_range_provider: Some(FoldingRangeProviderCapability::Simple(true)),
+                selection_range_provider: Some(SelectionRangeProviderCapability::Simple(true)),
+                inlay_hint_provider: Some(OneOf::Left(true)),
+                diagnostic_provider: Some(DiagnosticServerCapabilities::Options(DiagnosticOptions {
+                    identifier: Some("hql-ls".to_string()),
+                    inter_file_dependencies: false,
+                    workspace_diagnostics: false,
+                    work_done_progress_options: Default::default(),
+                })),
+                semantic_tokens_provider: Some(SemanticTokensServerCapabilities::SemanticTokensOptions(
+                    SemanticTokensOptions {
+                        legend: semantic_tokens::legend(),
+                        full: Some(SemanticTokensFullOptions::Bool(true)),
+                        ..Default::default()
+                    },
+                )),
+                document_on_type_formatting_provider: Some(DocumentOnTypeFormattingOptions {
+                    first_trigger_character: ";".to_string(),
+                    more_trigger_character: None,
+                }),
+                code_action_provider: Some(CodeActionProviderCapability::Options(CodeActionOptions {
+                    code_action_kinds: Some(vec![CodeActionKind::QUICKFIX, CodeActionKind::SOURCE_FIX_ALL]),
+                    ..Default::default()
+                })),
+                signature_help_provider: Some(SignatureHelpOptions {
+                    trigger_characters: Some(vec!["(".to_string(), ",".to_string()]),
+                    retrigger_characters: None,
+                    work_done_progress_options: Default::default(),
+                }),
+                completion_provider: Some(CompletionOptions {
+                    resolve_provider: Some(true),
+                    trigger_characters: Some(vec![".".to_string(), "$".to_string(), "{".to_string()]),
                     work_done_progress_options: Default::default(),
                     all_commit_characters: None,
                     ..Default::default()
@@ -49,6 +192,31 @@ impl LanguageServer for Backend {
         // Fetch initial configuration
         // We rely on client pushing didChangeConfiguration shortly after init, 
         // but we can also log that we are ready.
+
+        // Watch the workspace config and schema files so edits to them apply without a restart
+        let watchers = DidChangeWatchedFilesRegistrationOptions {
+            watchers: [CONFIG_FILE_NAME, SCHEMA_FILE_NAME]
+                .into_iter()
+                .map(|name| FileSystemWatcher {
+                    glob_pattern: GlobPattern::String(format!("**/{}", name)),
+                    kind: None,
+                })
+                .collect(),
+        };
+        let registration = Registration {
+            id: "hqlint-config-watcher".to_string(),
+            method: "workspace/didChangeWatchedFiles".to_string(),
+            register_options: serde_json::to_value(watchers).ok(),
+        };
+        if let Err(e) = self.client.register_capability(vec![registration]).await {
+            self.client.log_message(MessageType::WARNING, format!("Could not watch {}: {}", CONFIG_FILE_NAME, e)).await;
+        }
+
+        if self.pull_configuration.load(Ordering::Relaxed) {
+            self.reload_folders().await;
+            // Documents opened before the folder settings arrived were linted with the global ones
+            self.relint_open_documents().await;
+        }
     }
 
     async fn did_change_configuration(&self, params: DidChangeConfigurationParams) {
@@ -64,20 +232,77 @@ impl LanguageServer for Backend {
         // VS Code sends the whole settings object usually.
         let hql_settings = settings.get("hql").cloned().unwrap_or(settings);
         
-        match serde_json::from_value::<HqlConfig>(hql_settings) {
-            Ok(new_config) => {
-                let mut write_lock = self.config.write().await;
-                *write_lock = new_config;
-                self.client.log_message(MessageType::INFO, "HQL configuration updated").await;
-            },
-            Err(e) => {
-                self.client.log_message(MessageType::ERROR, format!("Failed to parse configuration: {}", e)).await;
+        // Client settings are the top layer, overriding `.hqlint.toml` key by key
+        let previous = {
+            let mut sources = self.config_sources.write().await;
+            sources.client.replace(hql_settings)
+        };
+        if self.refresh_config().await {
+            self.client.log_message(MessageType::INFO, "HQL configuration updated").await;
+        } else {
+            // Keep the last good settings rather than a layer that doesn't parse
+            self.config_sources.write().await.client = previous;
+        }
+        // Folders pick up their scoped settings, or the new global ones
+        self.reload_folders().await;
+
+        // Rule settings may have changed, so neither published nor pulled reports still hold
+        self.relint_open_documents().await;
+    }
+
+    async fn did_change_watched_files(&self, params: DidChangeWatchedFilesParams) {
+        let changed_paths: Vec<_> = params
+            .changes
+            .iter()
+            .filter_map(|change| change.uri.to_file_path().ok())
+            .collect();
+
+        let schema_path = self.schema.read().await.file_path.clone();
+        if schema_path.is_some_and(|path| changed_paths.contains(&path)) {
+            let reloaded = self.schema.write().await.reload();
+            match reloaded {
+                Ok(()) => {
+                    self.client.log_message(MessageType::INFO, format!("Reloaded {}", SCHEMA_FILE_NAME)).await;
+                    // Schema-aware rules may now report differently
+                    self.relint_open_documents().await;
+                }
+                Err(e) => self.client.log_message(MessageType::ERROR, format!("Failed to load {}: {}", SCHEMA_FILE_NAME, e)).await,
             }
         }
-        
-        // Re-lint all open documents with new settings
-        // Iterate over document_map keys... iterating dashmap async is tricky.
-        // For now, next edit will trigger re-lint.
+
+        let changed_folders = self.folder_configs.read().await.watching(&changed_paths);
+        for uri in &changed_folders {
+            self.load_folder(uri.clone()).await;
+        }
+
+        let file_path = self.config_sources.read().await.file_path.clone();
+        if !file_path.is_some_and(|path| changed_paths.contains(&path)) {
+            if !changed_folders.is_empty() {
+                self.client.log_message(MessageType::INFO, format!("Reloaded {}", CONFIG_FILE_NAME)).await;
+                self.relint_open_documents().await;
+            }
+            return;
+        }
+
+        let reloaded = self.config_sources.write().await.reload_file();
+        if let Err(e) = reloaded {
+            self.client.log_message(MessageType::ERROR, format!("Failed to load {}: {}", CONFIG_FILE_NAME, e)).await;
+            return;
+        }
+        if self.refresh_config().await {
+            self.client.log_message(MessageType::INFO, format!("Reloaded {}", CONFIG_FILE_NAME)).await;
+            self.relint_open_documents().await;
+        }
+    }
+
+    async fn did_change_workspace_folders(&self, params: DidChangeWorkspaceFoldersParams) {
+        for folder in &params.event.removed {
+            self.folder_configs.write().await.remove(&folder.uri);
+        }
+        for folder in params.event.added {
+            self.load_folder(folder.uri).await;
+        }
+        self.relint_open_documents().await;
     }
 
     async fn shutdown(&self) -> Result<()> {
@@ -88,28 +313,53 @@ impl LanguageServer for Backend {
         self.client
             .log_message(MessageType::INFO, format!("Opened: {}", params.text_document.uri))
             .await;
-        self.on_change(TextDocumentItem {
-            uri: params.text_document.uri,
-            text: params.text_document.text,
-            version: params.text_document.version,
-            language_id: "hql".to_string(),
-        })
-        .await;
+        // Documents in other languages are never tracked, so every feature handler skips them
+        if !self.config_for(params.text_document.uri.as_str()).await.accepts_language(&params.text_document.language_id) {
+            return;
+        }
+        // The client may have dropped diagnostics for a closed document, so always publish on open
+        self.published.forget(params.text_document.uri.as_str());
+        self.on_change(params.text_document).await;
     }
 
     async fn did_change(&self, params: DidChangeTextDocumentParams) {
         self.client
             .log_message(MessageType::INFO, format!("Changed: {}", params.text_document.uri))
             .await;
-        // For Full sync, content_changes has one element with the full text
-        if let Some(change) = params.content_changes.first() {
-             self.on_change(TextDocumentItem {
-                uri: params.text_document.uri,
-                text: change.text.clone(),
-                version: params.text_document.version,
-                language_id: "hql".to_string(),
-            })
-            .await;
+        let uri = params.text_document.uri;
+        self.tokens.invalidate(uri.as_str());
+        let text = {
+            // Not opened, or opened in a language the server doesn't handle
+            let Some(mut rope) = self.document_map.get_mut(uri.as_str()) else { return };
+            // Changes are applied in order; ranged changes edit in place, others replace the text
+            for change in &params.content_changes {
+                document::apply_change(&mut rope, change);
+            }
+            rope.to_string()
+        };
+
+        let version = params.text_document.version;
+        let previous_version = self.versions.insert(uri.to_string(), version);
+
+        // Re-lint once per batch rather than per change, and once typing pauses
+        let config = self.config_for(uri.as_str()).await;
+        let debounce = config.linting.debounce_ms;
+        if debounce == 0 {
+            self.lint_and_publish(uri, &text, Some(version)).await;
+            return;
+        }
+        // Line rules are cheap, so the edited lines are re-checked straight away
+        self.publish_edited_lines(&uri, &text, &params.content_changes, previous_version, version, &config.linting).await;
+        let backend = self.clone();
+        let key = uri.to_string();
+        let lint = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(debounce)).await;
+            let key = uri.to_string();
+            backend.lint_and_publish(uri, &text, Some(version)).await;
+            backend.pending_lints.remove_if(&key, |_, (pending, _)| *pending == version);
+        });
+        if let Some((_, previous)) = self.pending_lints.insert(key, (version, lint)) {
+            previous.abort();
         }
     }
 
@@ -118,57 +368,828 @@ impl LanguageServer for Backend {
         if let Some(rope) = self.document_map.get(uri.as_str()) {
             let text = rope.to_string();
             
-            let config = self.config.read().await;
+            let config = self.config_for(uri.as_str()).await;
             if !config.formatting.enabled {
                 return Ok(None);
             }
             
-            let edits = formatter::format_text(&text, params.options, &config.formatting);
-            
-            // Adjust the range to cover the actual document
-            let full_range = Range {
-                start: Position { line: 0, character: 0 },
-                end: Position { 
-                    line: (rope.len_lines() - 1) as u32, 
-                    character: rope.line(rope.len_lines() - 1).len_chars() as u32 
+            let (edits, kept) = formatter::format_text(&text, params.options, &config.formatting);
+            if kept > 0 {
+                self.client
+                    .log_message(
+                        MessageType::WARNING,
+                        format!("Left {} statement(s) unformatted: formatting would have changed a string literal", kept),
+                    )
+                    .await;
+            }
+            return Ok(Some(edits));
+        }
+        Ok(None)
+    }
+
+    async fn range_formatting(&self, params: DocumentRangeFormattingParams) -> Result<Option<Vec<TextEdit>>> {
+        let uri = params.text_document.uri;
+        let config = self.config_for(uri.as_str()).await;
+        if !config.formatting.enabled {
+            return Ok(None);
+        }
+
+        if let Some(rope) = self.document_map.get(uri.as_str()) {
+            let dialect = syntax::dialect(&config.linting.dialect);
+            let edit = formatter::format_range(&rope, params.range, params.options, &config.formatting, &*dialect);
+            return Ok(edit.map(|e| vec![e]));
+        }
+        Ok(None)
+    }
+
+    async fn on_type_formatting(&self, params: DocumentOnTypeFormattingParams) -> Result<Option<Vec<TextEdit>>> {
+        let uri = params.text_document_position.text_document.uri;
+        let config = self.config_for(uri.as_str()).await;
+        if !config.formatting.enabled || params.ch != ";" {
+            return Ok(None);
+        }
+
+        if let Some(rope) = self.document_map.get(uri.as_str()) {
+            let position = params.text_document_position.position;
+            let dialect = syntax::dialect(&config.linting.dialect);
+            let edit = formatter::format_on_type(&rope, position, params.options, &config.formatting, &*dialect);
+            return Ok(edit.map(|e| vec![e]));
+        }
+        Ok(None)
+    }
+
+    async fn completion(&self, params: CompletionParams) -> Result<Option<CompletionResponse>> {
+        let uri = params.text_document_position.text_document.uri;
+        let position = params.text_document_position.position;
+        let workspace_schema = self.schema.read().await;
+        let config = self.config_for(uri.as_str()).await;
+        let Some(rope) = self.document_map.get(uri.as_str()) else {
+            return Ok(Some(completion::get_completions(&config)));
+        };
+        if let Some(response) = completion::variable_completions(
+            &rope,
+            position,
+            &config.linting.hive_variable_namespaces,
+            &config.linting.hive_variables,
+        ) {
+            return Ok(Some(response));
+        }
+        Ok(Some(completion::completions(&rope, position, workspace_schema.schema.as_ref(), &config)))
+    }
+
+    async fn completion_resolve(&self, item: CompletionItem) -> Result<CompletionItem> {
+        Ok(completion::resolve(item))
+    }
+
+    async fn signature_help(&self, params: SignatureHelpParams) -> Result<Option<SignatureHelp>> {
+        let uri = params.text_document_position_params.text_document.uri;
+        if let Some(rope) = self.document_map.get(uri.as_str()) {
+            let position = params.text_document_position_params.position;
+            return Ok(signature_help::signature_help(&rope, position));
+        }
+        Ok(None)
+    }
+
+    async fn goto_definition(&self, params: GotoDefinitionParams) -> Result<Option<GotoDefinitionResponse>> {
+        let uri = params.text_document_position_params.text_document.uri;
+        if let Some(rope) = self.document_map.get(uri.as_str()) {
+            let position = params.text_document_position_params.position;
+            let definition = references::find_cte_definition(&rope.to_string(), position);
+            return Ok(definition.map(|range| GotoDefinitionResponse::Scalar(Location::new(uri, range))));
+        }
+        Ok(None)
+    }
+
+    async fn references(&self, params: ReferenceParams) -> Result<Option<Vec<Location>>> {
+        let uri = params.text_document_position.text_document.uri;
+        if let Some(rope) = self.document_map.get(uri.as_str()) {
+            let position = params.text_document_position.position;
+            let locations = references::find_table_references(&rope.to_string(), position)
+                .into_iter()
+                .map(|range| Location::new(uri.clone(), range))
+                .collect();
+            return Ok(Some(locations));
+        }
+        Ok(None)
+    }
+
+    async fn document_highlight(&self, params: DocumentHighlightParams) -> Result<Option<Vec<DocumentHighlight>>> {
+        let uri = params.text_document_position_params.text_document.uri;
+        if let Some(rope) = self.document_map.get(uri.as_str()) {
+            let position = params.text_document_position_params.position;
+            return Ok(Some(highlight::document_highlights(&rope.to_string(), position)));
+        }
+        Ok(None)
+    }
+
+    async fn prepare_rename(&self, params: TextDocumentPositionParams) -> Result<Option<PrepareRenameResponse>> {
+        if let Some(rope) = self.document_map.get(params.text_document.uri.as_str()) {
+            let range = rename::prepare_rename(&rope.to_string(), params.position);
+            return Ok(range.map(PrepareRenameResponse::Range));
+        }
+        Ok(None)
+    }
+
+    async fn rename(&self, params: RenameParams) -> Result<Option<WorkspaceEdit>> {
+        let uri = params.text_document_position.text_document.uri;
+        let Some(rope) = self.document_map.get(uri.as_str()) else { return Ok(None) };
+        let position = params.text_document_position.position;
+        let edits = rename::rename(&rope.to_string(), position, &params.new_name)
+            .map_err(tower_lsp::jsonrpc::Error::invalid_params)?;
+        Ok(edits.map(|edits| WorkspaceEdit {
+            changes: Some(std::collections::HashMap::from([(uri, edits)])),
+            ..Default::default()
+        }))
+    }
+
+    async fn document_symbol(&self, params: DocumentSymbolParams) -> Result<Option<DocumentSymbolResponse>> {
+        let Some((_, tokenized)) = self.document(params.text_document.uri.as_str()).await else { return Ok(None) };
+        let symbols = tokenized.as_deref().map(symbols::document_symbols).unwrap_or_default();
+        Ok(Some(DocumentSymbolResponse::Nested(symbols)))
+    }
+
+    async fn symbol(&self, params: WorkspaceSymbolParams) -> Result<Option<Vec<SymbolInformation>>> {
+        let uris: Vec<String> = self.document_map.iter().map(|entry| entry.key().clone()).collect();
+        let mut documents = Vec::new();
+        for uri in uris {
+            if let (Ok(url), Some((_, tokenized))) = (Url::parse(&uri), self.document(&uri).await) {
+                documents.push((url, tokenized.as_deref().map(symbols::document_symbols).unwrap_or_default()));
+            }
+        }
+        Ok(Some(symbols::workspace_symbols(documents, &params.query)))
+    }
+
+    async fn document_link(&self, params: DocumentLinkParams) -> Result<Option<Vec<DocumentLink>>> {
+        let Some((_, tokenized)) = self.document(params.text_document.uri.as_str()).await else { return Ok(None) };
+        Ok(Some(tokenized.as_deref().map(links::document_links).unwrap_or_default()))
+    }
+
+    async fn code_lens(&self, params: CodeLensParams) -> Result<Option<Vec<CodeLens>>> {
+        let Some((_, tokenized)) = self.document(params.text_document.uri.as_str()).await else { return Ok(None) };
+        Ok(Some(tokenized.as_deref().map(code_lens::statement_lenses).unwrap_or_default()))
+    }
+
+    async fn folding_range(&self, params: FoldingRangeParams) -> Result<Option<Vec<FoldingRange>>> {
+        let Some((_, tokenized)) = self.document(params.text_document.uri.as_str()).await else { return Ok(None) };
+        Ok(Some(tokenized.as_deref().map(folding::folding_ranges).unwrap_or_default()))
+    }
+
+    async fn diagnostic(&self, params: DocumentDiagnosticParams) -> Result<DocumentDiagnosticReportResult> {
+        let uri = params.text_document.uri;
+        // Reports are identified by the lint generation and document version they were computed for
+        let result_id = self.versions.get(uri.as_str()).map(|version| {
+            format!("{}:{}", self.lint_generation.load(Ordering::Relaxed), *version)
+        });
+        if let Some(result_id) = &result_id
+            && params.previous_result_id.as_ref() == Some(result_id)
+        {
+            return Ok(DocumentDiagnosticReportResult::Report(DocumentDiagnosticReport::Unchanged(
+                RelatedUnchangedDocumentDiagnosticReport {
+                    related_documents: None,
+                    unchanged_document_diagnostic_report: UnchangedDocumentDiagnosticReport { result_id: result_id.clone() },
                 },
-            };
-            
-            let mut final_edits = edits;
-            if let Some(edit) = final_edits.first_mut() {
-                edit.range = full_range;
+            )));
+        }
+
+        let config = self.config_for(uri.as_str()).await;
+        let Some((text, tokenized)) = self.lint_document(uri.as_str(), &config.linting).await else {
+            return Err(tower_lsp::jsonrpc::Error::invalid_params(format!("Unknown document: {}", uri)));
+        };
+        let workspace_schema = self.schema.read().await;
+        let mut items = linter::lint_cancellable(
+            &text,
+            &tokenized,
+            &config.linting,
+            workspace_schema.schema.as_ref(),
+            Some(&self.metrics),
+            &|| false,
+        )
+        .unwrap_or_default();
+        linter::resolve_related(&uri, &mut items);
+        Ok(DocumentDiagnosticReportResult::Report(DocumentDiagnosticReport::Full(
+            RelatedFullDocumentDiagnosticReport {
+                related_documents: None,
+                full_document_diagnostic_report: FullDocumentDiagnosticReport { result_id, items },
+            },
+        )))
+    }
+
+    async fn hover(&self, params: HoverParams) -> Result<Option<Hover>> {
+        let uri = params.text_document_position_params.text_document.uri;
+        let position = params.text_document_position_params.position;
+        // The diagnostics on screen, unless they're out of date or weren't pushed
+        let version = self.versions.get(uri.as_str()).map(|version| *version);
+        let diagnostics = match self.published.last(uri.as_str(), version) {
+            Some(diagnostics) => diagnostics,
+            None => {
+                let config = self.config_for(uri.as_str()).await;
+                let Some((text, tokenized)) = self.lint_document(uri.as_str(), &config.linting).await else { return Ok(None) };
+                let workspace_schema = self.schema.read().await;
+                linter::lint_tokenized(&text, &tokenized, &config.linting, workspace_schema.schema.as_ref())
             }
-            
-            return Ok(Some(final_edits));
+        };
+        Ok(hover::hover(&diagnostics, position))
+    }
+
+    async fn inlay_hint(&self, params: InlayHintParams) -> Result<Option<Vec<InlayHint>>> {
+        // Hints only show values from a configured variable map
+        let config = self.config_for(params.text_document.uri.as_str()).await;
+        if config.linting.hive_variables.is_empty() {
+            return Ok(None);
+        }
+        if let Some(rope) = self.document_map.get(params.text_document.uri.as_str()) {
+            let hints = inlay_hints::variable_hints(&rope.to_string(), params.range, &config.linting.hive_variables);
+            return Ok(Some(hints));
+        }
+        Ok(None)
+    }
+
+    async fn selection_range(&self, params: SelectionRangeParams) -> Result<Option<Vec<SelectionRange>>> {
+        if let Some(rope) = self.document_map.get(params.text_document.uri.as_str()) {
+            return Ok(Some(selection_range::selection_ranges(&rope.to_string(), &params.positions)));
         }
         Ok(None)
     }
 
-    async fn completion(&self, _: CompletionParams) -> Result<Option<CompletionResponse>> {
-        Ok(Some(completion::get_completions()))
+    async fn semantic_tokens_full(&self, params: SemanticTokensParams) -> Result<Option<SemanticTokensResult>> {
+        let uri = params.text_document.uri.as_str();
+        let Some((text, tokenized)) = self.document(uri).await else { return Ok(None) };
+        let additional_keywords = self.config_for(uri).await.linting.additional_keywords;
+        let data = tokenized
+            .as_deref()
+            .map(|tokens| semantic_tokens::semantic_tokens(&text, tokens, &additional_keywords))
+            .unwrap_or_default();
+        Ok(Some(SemanticTokensResult::Tokens(SemanticTokens { result_id: None, data })))
+    }
+
+    async fn execute_command(&self, params: ExecuteCommandParams) -> Result<Option<serde_json::Value>> {
+        match params.command.as_str() {
+            formatter::FORMAT_WORKSPACE => {
+                self.format_workspace(params.arguments, params.work_done_progress_params.work_done_token).await
+            }
+            command => Err(tower_lsp::jsonrpc::Error::invalid_params(format!("Unknown command: {}", command))),
+        }
+    }
+
+    async fn code_action(&self, params: CodeActionParams) -> Result<Option<CodeActionResponse>> {
+        let uri = params.text_document.uri;
+        let config = self.config_for(uri.as_str()).await;
+        if let Some(rope) = self.document_map.get(uri.as_str()) {
+            let only = params.context.only.as_deref();
+            let mut actions = Vec::new();
+            if code_actions::kind_requested(only, &CodeActionKind::QUICKFIX) {
+                actions = code_actions::get_code_actions(
+                    &uri,
+                    &rope,
+                    params.range,
+                    &params.context.diagnostics,
+                    &config.linting,
+                );
+                actions.extend(code_actions::disable_actions(
+                    &uri,
+                    &rope,
+                    params.range,
+                    &params.context.diagnostics,
+                    &config.linting.source,
+                ));
+            }
+            // Fixing everything lints and formats the whole file, so it waits until the client
+            // asks for it by kind (e.g. on save) rather than running with every cursor move
+            if only.is_some() && code_actions::kind_requested(only, &CodeActionKind::SOURCE_FIX_ALL) {
+                let workspace_schema = self.schema.read().await;
+                actions.extend(code_actions::fix_all(&uri, &rope, &config, workspace_schema.schema.as_ref()));
+            }
+            return Ok(Some(actions));
+        }
+        Ok(None)
     }
 }
 
 impl Backend {
+    fn new(client: Client) -> Self {
+        Self {
+            client,
+            document_map: Arc::new(DashMap::new()),
+            versions: Arc::new(DashMap::new()),
+            tokens: Arc::new(TokenCache::default()),
+            config: Arc::new(RwLock::new(HqlConfig::default())),
+            config_sources: Arc::new(RwLock::new(ConfigSources::default())),
+            folder_configs: Arc::new(RwLock::new(FolderConfigs::default())),
+            pull_configuration: Arc::new(AtomicBool::new(false)),
+            schema: Arc::new(RwLock::new(WorkspaceSchema::default())),
+            published: Arc::new(PublishCache::default()),
+            lint_generation: Arc::new(AtomicU64::new(0)),
+            pending_lints: Arc::new(DashMap::new()),
+            metrics: Arc::new(RuleMetrics::default()),
+        }
+    }
+
+    /// `hql.formatWorkspace`: formats every open document with its own formatting config and
+    /// applies the result as one workspace edit. The optional argument is LSP `FormattingOptions`.
+    async fn format_workspace(&self, arguments: Vec<serde_json::Value>, token: Option<ProgressToken>) -> Result<Option<serde_json::Value>> {
+        let options = arguments
+            .into_iter()
+            .next()
+            .and_then(|options| serde_json::from_value(options).ok())
+            .unwrap_or_else(formatter::default_options);
+        // Snapshot first so no map guard is held across an await
+        let mut documents: Vec<(String, String)> = self
+            .document_map
+            .iter()
+            .map(|entry| (entry.key().clone(), entry.value().to_string()))
+            .collect();
+        documents.sort();
+
+        self.progress(&token, WorkDoneProgress::Begin(WorkDoneProgressBegin {
+            title: "Formatting open documents".to_string(),
+            percentage: Some(0),
+            ..Default::default()
+        }))
+        .await;
+        let mut batch = formatter::WorkspaceFormat::default();
+        for (n, (uri, text)) in documents.iter().enumerate() {
+            let Ok(url) = Url::parse(uri) else { continue };
+            let config = self.config_for(uri).await;
+            batch.add(url, text, &options, &config.formatting);
+            self.progress(&token, WorkDoneProgress::Report(WorkDoneProgressReport {
+                message: Some(uri.rsplit('/').next().unwrap_or(uri).to_string()),
+                percentage: Some(((n + 1) * 100 / documents.len()) as u32),
+                ..Default::default()
+            }))
+            .await;
+        }
+        self.progress(&token, WorkDoneProgress::End(WorkDoneProgressEnd { message: None })).await;
+
+        for uri in &batch.failed {
+            self.client.log_message(MessageType::ERROR, format!("Failed to format {}", uri)).await;
+        }
+        if batch.kept > 0 {
+            self.client
+                .log_message(
+                    MessageType::WARNING,
+                    format!("Left {} statement(s) unformatted: formatting would have changed a string literal", batch.kept),
+                )
+                .await;
+        }
+        if let Err(e) = self.client.apply_edit(batch.into_edit()).await {
+            self.client.log_message(MessageType::ERROR, format!("Failed to apply formatting: {}", e)).await;
+        }
+        Ok(None)
+    }
+
+    /// Reports work-done progress if the client asked for it with a token.
+    async fn progress(&self, token: &Option<ProgressToken>, progress: WorkDoneProgress) {
+        if let Some(token) = token {
+            self.client
+                .send_notification::<notification::Progress>(ProgressParams {
+                    token: token.clone(),
+                    value: ProgressParamsValue::WorkDone(progress),
+                })
+                .await;
+        }
+    }
+
+    /// `hql/listRules`: every lint rule with its code, defaults and description.
+    async fn list_rules(&self) -> Result<Vec<rules::RuleListing>> {
+        Ok(rules::listing())
+    }
+
+    /// `hql/refreshDiagnostics`: re-lints every open document and publishes the results even
+    /// if they haven't changed, e.g. after a client restart left stale diagnostics on screen.
+    /// Returns how many documents were published.
+    async fn refresh_diagnostics(&self) -> Result<usize> {
+        let reports = self.open_document_diagnostics().await;
+        let count = reports.len();
+        for (uri, diagnostics, version) in reports {
+            // Remember what was sent so the next change only republishes if it differs
+            self.published.should_publish(uri.as_str(), version, &diagnostics);
+            self.client.publish_diagnostics(uri, diagnostics, version).await;
+        }
+        Ok(count)
+    }
+
+    /// Fresh push diagnostics for every open document, with the version they're for.
+    async fn open_document_diagnostics(&self) -> Vec<(Url, Vec<Diagnostic>, Option<i32>)> {
+        // Snapshot first so no map guard is held across an await
+        let mut documents: Vec<(String, String)> = self
+            .document_map
+            .iter()
+            .map(|entry| (entry.key().clone(), entry.value().to_string()))
+            .collect();
+        documents.sort();
+
+        let mut reports = Vec::new();
+        for (uri, text) in documents {
+            let Ok(url) = Url::parse(&uri) else { continue };
+            let version = self.versions.get(&uri).map(|version| *version);
+            if let Some(diagnostics) = self.lint_for_publish(&url, &text, version).await {
+                reports.push((url, diagnostics, version));
+            }
+        }
+        reports
+    }
+
+    /// `hql/formatString`: formats `text` with the global config, for tools without a document.
+    async fn format_string(&self, params: formatter::FormatStringParams) -> Result<String> {
+        let config = self.config.read().await.clone();
+        if !config.formatting.enabled {
+            return Ok(params.text);
+        }
+        let options = params.options.unwrap_or_else(formatter::default_options);
+        Ok(formatter::format_string(&params.text, &options, &config.formatting))
+    }
+
+    /// `hql/metrics`: cumulative run time and run count of each lint rule, keyed by code.
+    async fn metrics(&self, params: metrics::MetricsParams) -> Result<std::collections::BTreeMap<String, metrics::RuleTiming>> {
+        Ok(self.metrics.snapshot(params.reset))
+    }
+
+    /// `hql/cteGraph`: the CTEs of the statement at a position and the CTEs each one reads,
+    /// with any cycles among them. `null` if the document isn't open or the position isn't
+    /// in a statement.
+    async fn cte_graph(&self, params: TextDocumentPositionParams) -> Result<Option<cte_graph::CteGraph>> {
+        if let Some(rope) = self.document_map.get(params.text_document.uri.as_str()) {
+            return Ok(cte_graph::cte_graph(&rope.to_string(), params.position));
+        }
+        Ok(None)
+    }
+
     async fn on_change(&self, params: TextDocumentItem) {
         let rope = ropey::Rope::from_str(&params.text);
-        self.document_map.insert(params.uri.to_string(), rope.clone());
-        
-        let config = self.config.read().await;
-        let diagnostics = linter::lint(&params.text, &config.linting);
-        self.client.publish_diagnostics(params.uri, diagnostics, Some(params.version)).await;
+        self.tokens.invalidate(params.uri.as_str());
+        self.document_map.insert(params.uri.to_string(), rope);
+        self.versions.insert(params.uri.to_string(), params.version);
+
+        self.lint_and_publish(params.uri, &params.text, Some(params.version)).await;
+    }
+
+    async fn lint_and_publish(&self, uri: Url, text: &str, version: Option<i32>) {
+        let Some(diagnostics) = self.lint_for_publish(&uri, text, version).await else { return };
+        if !self.published.should_publish(uri.as_str(), version, &diagnostics) {
+            return;
+        }
+        self.client.publish_diagnostics(uri, diagnostics, version).await;
+    }
+
+    /// Publishes fresh line-rule diagnostics for the lines `changes` touched, merged into the
+    /// diagnostics last published for `previous_version` (see `incremental::CarriedOver`).
+    /// The token rules wait for the debounced full lint.
+    async fn publish_edited_lines(
+        &self,
+        uri: &Url,
+        text: &str,
+        changes: &[TextDocumentContentChangeEvent],
+        previous_version: Option<i32>,
+        version: i32,
+        config: &LintingConfig,
+    ) {
+        if !config.push_diagnostics {
+            return;
+        }
+        let Some(previous) = self.published.last(uri.as_str(), previous_version) else { return };
+        let Some(carried) = incremental::carry_over(previous, uri, changes) else { return };
+        let Some(fresh) = linter::lint_lines(text, carried.dirty.clone(), config) else { return };
+        let diagnostics = carried.merge(fresh);
+        // Over the cap, the full lint decides which diagnostics are shown
+        if config.max_diagnostics != 0 && diagnostics.len() > config.max_diagnostics {
+            return;
+        }
+        if self.published.should_publish(uri.as_str(), Some(version), &diagnostics) {
+            self.client.publish_diagnostics(uri.clone(), diagnostics, Some(version)).await;
+        }
+    }
+
+    /// The diagnostics to push for `text`, or `None` if diagnostics aren't pushed for `uri` or
+    /// a newer version arrived while linting.
+    async fn lint_for_publish(&self, uri: &Url, text: &str, version: Option<i32>) -> Option<Vec<Diagnostic>> {
+        let config = self.config_for(uri.as_str()).await;
+        if !config.linting.push_diagnostics {
+            return None;
+        }
+        let workspace_schema = self.schema.read().await;
+        let tokenized = self.lint_tokens(uri.as_str(), text, version, &config.linting);
+        // Linting a large file takes a while; stop early if the user keeps typing, and run on
+        // this worker thread without holding up other tasks
+        let stale = || self.is_stale(uri.as_str(), version);
+        let diagnostics = tokio::task::block_in_place(|| {
+            linter::lint_cancellable(text, &tokenized, &config.linting, workspace_schema.schema.as_ref(), Some(&self.metrics), &stale)
+        });
+        // Only ever publish diagnostics for the latest version, so they don't flicker
+        let mut diagnostics = diagnostics.filter(|_| !stale())?;
+        linter::resolve_related(uri, &mut diagnostics);
+        Some(diagnostics)
+    }
+
+    /// Tokens to lint `text`, version `version` of `uri`, reused from the cache while the
+    /// version is unchanged. Files over `maxFileSize` are linted in chunks that tokenize
+    /// themselves, so their tokens are neither produced for the whole file nor cached.
+    fn lint_tokens(&self, uri: &str, text: &str, version: Option<i32>, config: &LintingConfig) -> Arc<Tokenized> {
+        if text.len() as u64 > config.max_file_size {
+            return Arc::new(Ok(Vec::new()));
+        }
+        let tokenize = || Tokenizer::new(&*syntax::dialect(&config.dialect), text).tokenize_with_location();
+        match version {
+            Some(version) => self.tokens.get_or_tokenize(uri, version, tokenize),
+            None => Arc::new(tokenize()),
+        }
+    }
+
+    /// The text of open document `uri` and the tokens to lint it with (see `lint_tokens`).
+    async fn lint_document(&self, uri: &str, config: &LintingConfig) -> Option<(String, Arc<Tokenized>)> {
+        // Read the version before the text, as in `document`
+        let version = self.versions.get(uri).map(|version| *version);
+        let text = self.document_map.get(uri)?.to_string();
+        let tokenized = self.lint_tokens(uri, &text, version, config);
+        Some((text, tokenized))
+    }
+
+    /// True once a newer version of `uri` than `version` has arrived.
+    fn is_stale(&self, uri: &str, version: Option<i32>) -> bool {
+        version.is_some_and(|version| self.versions.get(uri).is_some_and(|latest| *latest != version))
+    }
+
+    /// The text of open document `uri` and its tokens, reused from the cache while the
+    /// document's version is unchanged.
+    async fn document(&self, uri: &str) -> Option<(String, Arc<Tokenized>)> {
+        let dialect = syntax::dialect(&self.config_for(uri).await.linting.dialect);
+        // Read the version before the text: if an edit lands in between, the tokens are
+        // cached under the old version, which is never looked up again
+        let version = self.versions.get(uri).map(|version| *version);
+        let text = self.document_map.get(uri)?.to_string();
+        let tokenize = || Tokenizer::new(&*dialect, &text).tokenize_with_location();
+        let tokenized = match version {
+            Some(version) => self.tokens.get_or_tokenize(uri, version, tokenize),
+            None => Arc::new(tokenize()),
+        };
+        Some((text, tokenized))
+    }
+
+    async fn relint_open_documents(&self) {
+        // The dialect may have changed
+        self.tokens.clear();
+        self.lint_generation.fetch_add(1, Ordering::Relaxed);
+        if !self.config.read().await.linting.push_diagnostics {
+            // Pull clients re-request; older ones reject the refresh, which is harmless
+            let _ = self.client.workspace_diagnostic_refresh().await;
+            return;
+        }
+
+        // Snapshot first so no map guard is held across an await
+        let documents: Vec<(String, String)> = self
+            .document_map
+            .iter()
+            .map(|entry| (entry.key().clone(), entry.value().to_string()))
+            .collect();
+        for (uri, text) in documents {
+            if let Ok(uri) = Url::parse(&uri) {
+                self.lint_and_publish(uri, &text, None).await;
+            }
+        }
+    }
+
+    /// The config for document `uri`: its workspace folder's, or the global one.
+    async fn config_for(&self, uri: &str) -> HqlConfig {
+        if let Some(config) = self.folder_configs.read().await.config_for(uri) {
+            return config.clone();
+        }
+        self.config.read().await.clone()
+    }
+
+    /// (Re)loads the config of workspace folder `uri` from its `.hqlint.toml` and the client
+    /// settings scoped to it. A folder whose settings don't parse falls back to the global config.
+    async fn load_folder(&self, uri: Url) {
+        let root = uri.to_file_path().ok();
+        let mut sources = ConfigSources::new(root.as_deref());
+        if let Err(e) = sources.reload_file() {
+            self.client.log_message(MessageType::ERROR, format!("Failed to load {}: {}", CONFIG_FILE_NAME, e)).await;
+        }
+        sources.client = self.folder_settings(&uri).await;
+
+        match sources.resolve() {
+            Ok(config) => self.folder_configs.write().await.insert(uri, sources, config),
+            Err(e) => {
+                self.folder_configs.write().await.remove(&uri);
+                self.client.log_message(MessageType::ERROR, format!("Failed to parse configuration for {}: {}", uri, e)).await;
+            }
+        }
+    }
+
+    async fn reload_folders(&self) {
+        let uris = self.folder_configs.read().await.uris();
+        for uri in uris {
+            self.load_folder(uri).await;
+        }
+    }
+
+    /// The `hql` settings the client scopes to folder `uri`, or the global client settings if
+    /// it can't be asked.
+    async fn folder_settings(&self, uri: &Url) -> Option<serde_json::Value> {
+        if self.pull_configuration.load(Ordering::Relaxed) {
+            let item = ConfigurationItem { scope_uri: Some(uri.clone()), section: Some("hql".to_string()) };
+            match self.client.configuration(vec![item]).await {
+                Ok(mut values) => return values.pop().filter(|value| !value.is_null()),
+                Err(e) => {
+                    self.client.log_message(MessageType::WARNING, format!("Could not fetch settings for {}: {}", uri, e)).await;
+                }
+            }
+        }
+        self.config_sources.read().await.client.clone()
+    }
+
+    /// Re-resolves the effective config from its sources. Returns false (keeping the
+    /// current config) if the merged settings don't deserialize.
+    async fn refresh_config(&self) -> bool {
+        let resolved = self.config_sources.read().await.resolve();
+        match resolved {
+            Ok(new_config) => {
+                *self.config.write().await = new_config;
+                true
+            }
+            Err(e) => {
+                self.client.log_message(MessageType::ERROR, format!("Failed to parse configuration: {}", e)).await;
+                false
+            }
+        }
     }
 }
 
 #[tokio::main]
 async fn main() {
+    match cli::parse_args(std::env::args().skip(1)) {
+        Ok(Some(args)) => {
+            let code = cli::run(&args).unwrap_or_else(|e| {
+                eprintln!("hql-ls: {}", e);
+                2
+            });
+            std::process::exit(code);
+        }
+        Ok(None) => {}
+        Err(e) => {
+            eprintln!("hql-ls: {}", e);
+            std::process::exit(2);
+        }
+    }
+
     let stdin = tokio::io::stdin();
     let stdout = tokio::io::stdout();
 
-    let (service, socket) = LspService::new(|client| Backend {
-        client,
-        document_map: DashMap::new(),
-        config: Arc::new(RwLock::new(HqlConfig::default())),
-    });
+    let (service, socket) = LspService::build(Backend::new)
+        .custom_method("hql/listRules", Backend::list_rules)
+        .custom_method("hql/formatString", Backend::format_string)
+        .custom_method("hql/metrics", Backend::metrics)
+        .custom_method("hql/refreshDiagnostics", Backend::refresh_diagnostics)
+        .custom_method("hql/cteGraph", Backend::cte_graph)
+        .finish();
     Server::new(stdin, stdout, socket).serve(service).await;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::{SinkExt, StreamExt};
+    use tower::{Service, ServiceExt};
+    use tower_lsp::jsonrpc::{Request, Response};
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_format_workspace_command() {
+        let (mut service, mut socket) = LspService::new(Backend::new);
+        // Play the client: accept every edit the server applies and answer other requests with null
+        let applied = tokio::spawn(async move {
+            let mut edits = Vec::new();
+            while let Some(request) = socket.next().await {
+                let Some(id) = request.id().cloned() else { continue };
+                let result = if request.method() == "workspace/applyEdit" {
+                    let params: ApplyWorkspaceEditParams = serde_json::from_value(request.params().cloned().unwrap()).unwrap();
+                    edits.push(params.edit);
+                    serde_json::json!({ "applied": true })
+                } else {
+                    serde_json::Value::Null
+                };
+                socket.send(Response::from_ok(id, result)).await.unwrap();
+            }
+            edits
+        });
+        let mut call = async |request: Request| service.ready().await.unwrap().call(request).await.unwrap();
+
+        call(Request::build("initialize").params(serde_json::json!({ "capabilities": {} })).id(1).finish()).await;
+        for (uri, text) in [("file:///a.hql", "select a from t;"), ("file:///b.hql", "select 1;")] {
+            let document = serde_json::json!({ "uri": uri, "languageId": "hql", "version": 1, "text": text });
+            call(Request::build("textDocument/didOpen").params(serde_json::json!({ "textDocument": document })).finish()).await;
+        }
+
+        let format = serde_json::json!({ "command": formatter::FORMAT_WORKSPACE, "arguments": [] });
+        let response = call(Request::build("workspace/executeCommand").params(format).id(2).finish()).await.unwrap();
+        assert!(response.is_ok(), "{:?}", response);
+
+        let unknown = serde_json::json!({ "command": "hql.noSuchCommand", "arguments": [] });
+        let response = call(Request::build("workspace/executeCommand").params(unknown).id(3).finish()).await.unwrap();
+        let error = response.error().unwrap();
+        assert_eq!(error.code, tower_lsp::jsonrpc::ErrorCode::InvalidParams);
+        assert_eq!(error.message, "Unknown command: hql.noSuchCommand");
+
+        // Dropping the service closes the socket
+        drop(service);
+        let edits = applied.await.unwrap();
+        assert_eq!(edits.len(), 1);
+        let changes = edits[0].changes.as_ref().unwrap();
+        let mut uris: Vec<&str> = changes.keys().map(Url::as_str).collect();
+        uris.sort();
+        assert_eq!(uris, vec!["file:///a.hql", "file:///b.hql"]);
+        assert!(changes[&Url::parse("file:///b.hql").unwrap()][0].new_text.starts_with("SELECT"));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_refresh_covers_every_open_document() {
+        let (service, _socket) = LspService::new(Backend::new);
+        let backend = service.inner();
+        backend.document_map.insert("file:///a.hql".to_string(), ropey::Rope::from_str("SELECT (1;"));
+        backend.document_map.insert("file:///b.hql".to_string(), ropey::Rope::from_str("SELECT 1 \n;"));
+        backend.versions.insert("file:///b.hql".to_string(), 3);
+
+        let reports = backend.open_document_diagnostics().await;
+        let uris: Vec<&str> = reports.iter().map(|(uri, _, _)| uri.as_str()).collect();
+        assert_eq!(uris, vec!["file:///a.hql", "file:///b.hql"]);
+        assert!(reports.iter().all(|(_, diagnostics, _)| !diagnostics.is_empty()));
+        assert_eq!(reports[1].2, Some(3));
+
+        assert_eq!(backend.refresh_diagnostics().await.unwrap(), 2);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_settings_change_relints_open_documents() {
+        let (service, _) = LspService::new(Backend::new);
+        let backend = service.inner();
+        let uri = Url::parse("file:///a.hql").unwrap();
+        backend
+            .did_open(DidOpenTextDocumentParams {
+                text_document: TextDocumentItem::new(uri.clone(), "hql".to_string(), 1, "SELECT 1;  ".to_string()),
+            })
+            .await;
+        assert!(!backend.published.last(uri.as_str(), Some(1)).unwrap().is_empty());
+        let generation = backend.lint_generation.load(Ordering::Relaxed);
+
+        let settings = serde_json::json!({ "hql": { "linting": { "rules": { "trailingWhitespace": false } } } });
+        backend.did_change_configuration(DidChangeConfigurationParams { settings }).await;
+        // Pulled reports from before the change are stale too
+        assert!(backend.lint_generation.load(Ordering::Relaxed) > generation);
+        assert_eq!(backend.published.last(uri.as_str(), None), Some(Vec::new()));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_oversized_file_is_not_tokenized_whole() {
+        let (service, _socket) = LspService::new(Backend::new);
+        let backend = service.inner();
+        let config = LintingConfig { max_file_size: 8, ..LintingConfig::default() };
+        let tokenized = backend.lint_tokens("file:///big.hql", "SELECT 1;\nSELECT 2;", Some(1), &config);
+        assert!(tokenized.as_ref().as_ref().unwrap().is_empty());
+
+        // Nor are any tokens cached for that version
+        let mut ran = false;
+        backend.tokens.get_or_tokenize("file:///big.hql", 1, || {
+            ran = true;
+            Ok(Vec::new())
+        });
+        assert!(ran);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_edit_publishes_line_rules_before_the_full_lint() {
+        // Without a socket the client's log messages are dropped rather than queued
+        let (service, _) = LspService::new(Backend::new);
+        let backend = service.inner();
+        let uri = Url::parse("file:///a.hql").unwrap();
+        backend
+            .did_open(DidOpenTextDocumentParams {
+                text_document: TextDocumentItem::new(uri.clone(), "hql".to_string(), 1, "SELECT a  \nFROM t  \nWHERE (b = 1;".to_string()),
+            })
+            .await;
+        let opened = backend.published.last(uri.as_str(), Some(1)).unwrap();
+        assert!(opened.iter().any(|d| d.range.start.line == 0 && d.message == "Trailing whitespace"));
+
+        backend
+            .did_change(DidChangeTextDocumentParams {
+                text_document: VersionedTextDocumentIdentifier::new(uri.clone(), 2),
+                content_changes: vec![TextDocumentContentChangeEvent {
+                    range: Some(Range::new(Position::new(0, 8), Position::new(0, 10))),
+                    range_length: None,
+                    text: String::new(),
+                }],
+            })
+            .await;
+        // Published right away, before the debounced full lint
+        let edited = backend.published.last(uri.as_str(), Some(2)).unwrap();
+        assert!(!edited.iter().any(|d| d.range.start.line == 0 && d.message == "Trailing whitespace"));
+        let untouched = |diagnostics: &[Diagnostic]| diagnostics.iter().filter(|d| d.range.start.line > 0).count();
+        assert_eq!(untouched(&edited), untouched(&opened));
+
+        // The debounced lint forgets itself once it has run
+        assert!(backend.pending_lints.contains_key(uri.as_str()));
+        for _ in 0..100 {
+            if !backend.pending_lints.contains_key(uri.as_str()) {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        assert!(backend.pending_lints.is_empty());
+    }
+}
@@ -0,0 +1,72 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+
+/// Parameters of the `hql/metrics` request.
+#[derive(Debug, Default, Deserialize)]
+pub struct MetricsParams {
+    /// Clear the totals after reporting them
+    #[serde(default)]
+    pub reset: bool,
+}
+
+/// How often a rule ran and how long it took in total.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RuleTiming {
+    pub count: u64,
+    pub total_ms: f64,
+}
+
+/// Cumulative time spent in each lint rule since startup or the last reset, reported by
+/// `hql/metrics` to find the rule that makes a large file slow.
+#[derive(Debug, Default)]
+pub struct RuleMetrics {
+    timings: Mutex<BTreeMap<&'static str, RuleTiming>>,
+}
+
+impl RuleMetrics {
+    pub fn record(&self, code: &'static str, elapsed: Duration) {
+        let mut timings = self.timings.lock().unwrap_or_else(|e| e.into_inner());
+        let timing = timings.entry(code).or_default();
+        timing.count += 1;
+        timing.total_ms += elapsed.as_secs_f64() * 1000.0;
+    }
+
+    /// The totals per rule code, cleared afterwards if `reset` is set.
+    pub fn snapshot(&self, reset: bool) -> BTreeMap<String, RuleTiming> {
+        let mut timings = self.timings.lock().unwrap_or_else(|e| e.into_inner());
+        let snapshot = timings.iter().map(|(code, timing)| (code.to_string(), timing.clone())).collect();
+        if reset {
+            timings.clear();
+        }
+        snapshot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::LintingConfig;
+    use crate::linter;
+
+    #[test]
+    fn test_lint_pass_counts_rules() {
+        let metrics = RuleMetrics::default();
+        let text = "SELECT a FROM t;";
+        let tokenized = Ok(crate::syntax::tokenize(text).unwrap());
+        let config = LintingConfig::default();
+
+        linter::lint_cancellable(text, &tokenized, &config, None, Some(&metrics), &|| false).unwrap();
+        assert_eq!(metrics.snapshot(false)["missing-semicolon"].count, 1);
+
+        linter::lint_cancellable(text, &tokenized, &config, None, Some(&metrics), &|| false).unwrap();
+        let timings = metrics.snapshot(true);
+        assert_eq!(timings["missing-semicolon"].count, 2);
+        // Disabled rules don't run
+        assert!(!timings.contains_key("keyword-casing"));
+
+        assert!(metrics.snapshot(false).is_empty());
+    }
+}
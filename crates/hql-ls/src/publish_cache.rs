@@ -0,0 +1,94 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use dashmap::DashMap;
+use tower_lsp::lsp_types::Diagnostic;
+
+/// Remembers the diagnostics last published per document, so unchanged results (e.g.
+/// after typing inside a comment) aren't re-sent to the client, and an edit's line-rule
+/// diagnostics can be merged into them.
+#[derive(Debug, Default)]
+pub struct PublishCache {
+    published: DashMap<String, Published>,
+}
+
+#[derive(Debug)]
+struct Published {
+    hash: u64,
+    /// The document version the diagnostics are for
+    version: Option<i32>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl PublishCache {
+    /// Records `diagnostics` for `version` of `uri` and returns whether they differ from the
+    /// last published set.
+    pub fn should_publish(&self, uri: &str, version: Option<i32>, diagnostics: &[Diagnostic]) -> bool {
+        let hash = hash_diagnostics(diagnostics);
+        let published = Published { hash, version, diagnostics: diagnostics.to_vec() };
+        match self.published.insert(uri.to_string(), published) {
+            Some(previous) => previous.hash != hash,
+            None => true,
+        }
+    }
+
+    /// The diagnostics last published for `uri`, if they were for `version`.
+    pub fn last(&self, uri: &str, version: Option<i32>) -> Option<Vec<Diagnostic>> {
+        self.published
+            .get(uri)
+            .filter(|published| published.version == version)
+            .map(|published| published.diagnostics.clone())
+    }
+
+    /// Forces the next publish for `uri`, e.g. when the document is reopened.
+    pub fn forget(&self, uri: &str) {
+        self.published.remove(uri);
+    }
+}
+
+fn hash_diagnostics(diagnostics: &[Diagnostic]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    // Diagnostic doesn't implement Hash; its JSON form covers ranges, codes and messages
+    serde_json::to_string(diagnostics).unwrap_or_default().hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::LintingConfig;
+    use crate::linter;
+
+    #[test]
+    fn test_identical_diagnostics_not_republished() {
+        let cache = PublishCache::default();
+        let uri = "file:///test.hql";
+        let config = LintingConfig::default();
+
+        let before = linter::lint("SELECT (a FROM t; -- note", &config);
+        assert!(cache.should_publish(uri, None, &before));
+
+        // Typing inside the comment leaves the diagnostics unchanged
+        let after = linter::lint("SELECT (a FROM t; -- notes", &config);
+        assert!(!cache.should_publish(uri, None, &after));
+
+        let fixed = linter::lint("SELECT (a) FROM t; -- notes", &config);
+        assert!(cache.should_publish(uri, None, &fixed));
+    }
+
+    #[test]
+    fn test_forget_forces_publish() {
+        let cache = PublishCache::default();
+        assert!(cache.should_publish("file:///a.hql", None, &[]));
+        cache.forget("file:///a.hql");
+        assert!(cache.should_publish("file:///a.hql", None, &[]));
+    }
+
+    #[test]
+    fn test_last_is_for_one_version() {
+        let cache = PublishCache::default();
+        let diagnostics = linter::lint("SELECT (a FROM t;", &LintingConfig::default());
+        cache.should_publish("file:///a.hql", Some(2), &diagnostics);
+        assert_eq!(cache.last("file:///a.hql", Some(2)), Some(diagnostics));
+        assert_eq!(cache.last("file:///a.hql", Some(1)), None);
+    }
+}
@@ -0,0 +1,194 @@
+use sqlparser::tokenizer::{Token, TokenWithSpan};
+use tower_lsp::lsp_types::{Position, Range};
+use crate::syntax::{self, range_contains, span_to_range};
+
+/// A table name appearing in a table position (FROM, JOIN, INTO, TABLE, ...).
+#[derive(Debug, Clone)]
+pub struct TableReference {
+    pub database: Option<String>,
+    pub name: String,
+    pub range: Range,
+    /// Covers the qualifier too, for `db.table`
+    pub full_range: Range,
+}
+
+impl TableReference {
+    fn refers_to_same_table(&self, other: &TableReference) -> bool {
+        if !self.name.eq_ignore_ascii_case(&other.name) {
+            return false;
+        }
+        // An unqualified name may resolve to any database
+        match (&self.database, &other.database) {
+            (Some(a), Some(b)) => a.eq_ignore_ascii_case(b),
+            _ => true,
+        }
+    }
+}
+
+/// Returns the ranges of every reference to the table under `position`, or nothing
+/// if the cursor isn't on a table reference.
+pub fn find_table_references(text: &str, position: Position) -> Vec<Range> {
+    let Some(tokens) = syntax::tokenize(text) else { return vec![] };
+    let references = collect_table_references(&tokens);
+
+    let Some(target) = references.iter().find(|r| range_contains(&r.full_range, position)) else {
+        return vec![];
+    };
+
+    references
+        .iter()
+        .filter(|r| r.refers_to_same_table(target))
+        .map(|r| r.range)
+        .collect()
+}
+
+/// Returns the range of the CTE name that the FROM/JOIN reference under `position`
+/// resolves to, or nothing if it isn't a CTE reference.
+pub fn find_cte_definition(text: &str, position: Position) -> Option<Range> {
+    let tokens = syntax::tokenize(text)?;
+    let references = collect_table_references(&tokens);
+    let target = references.iter().find(|r| r.database.is_none() && range_contains(&r.range, position))?;
+    let statement = syntax::split_statements(&tokens)
+        .into_iter()
+        .find(|s| s.clone().any(|i| span_to_range(&tokens[i].span) == target.range))?;
+
+    syntax::collect_ctes(&tokens, &statement)
+        .into_iter()
+        .find(|cte| cte.name.eq_ignore_ascii_case(&target.name))
+        .map(|cte| span_to_range(&tokens[cte.name_idx].span))
+}
+
+pub fn collect_table_references(tokens: &[TokenWithSpan]) -> Vec<TableReference> {
+    // Keywords after which a table name follows directly
+    let table_introducers = ["FROM", "JOIN", "INTO", "TABLE", "UPDATE"];
+    // Keywords that end a comma-separated FROM list
+    let from_list_terminators = [
+        "WHERE", "GROUP", "ORDER", "HAVING", "LIMIT", "UNION", "LATERAL", "CLUSTER", "DISTRIBUTE",
+        "SORT", "WINDOW", "ON", "USING", "SELECT", "INSERT", "JOIN", "LEFT", "RIGHT", "INNER", "FULL", "CROSS",
+    ];
+
+    let mut references = Vec::new();
+
+    for statement in syntax::split_statements(tokens) {
+        let significant: Vec<usize> = statement
+            .clone()
+            .filter(|&i| !matches!(tokens[i].token, Token::Whitespace(_)))
+            .collect();
+
+        let mut in_from_list = false;
+        let mut depth = 0;
+        let mut from_depth = 0;
+
+        for (pos, &i) in significant.iter().enumerate() {
+            let expects_table = match &tokens[i].token {
+                Token::LParen => {
+                    depth += 1;
+                    false
+                }
+                Token::RParen => {
+                    depth -= 1;
+                    if depth < from_depth {
+                        in_from_list = false;
+                    }
+                    false
+                }
+                Token::Comma => in_from_list && depth == from_depth,
+                Token::Word(w) if w.quote_style.is_none() => {
+                    let upper = w.value.to_uppercase();
+                    if upper == "FROM" {
+                        in_from_list = true;
+                        from_depth = depth;
+                    } else if depth == from_depth && from_list_terminators.contains(&upper.as_str()) {
+                        in_from_list = false;
+                    }
+                    table_introducers.contains(&upper.as_str())
+                }
+                _ => false,
+            };
+
+            if expects_table && let Some(reference) = read_table_name(tokens, &significant[pos + 1..]) {
+                references.push(reference);
+            }
+        }
+    }
+
+    references
+}
+
+/// Reads `name` or `db.name` from the start of `indices`.
+fn read_table_name(tokens: &[TokenWithSpan], indices: &[usize]) -> Option<TableReference> {
+    let first = &tokens[*indices.first()?];
+    let Token::Word(first_word) = &first.token else { return None };
+    // `INSERT INTO TABLE`, `CREATE TABLE IF NOT EXISTS`, ... aren't names
+    if first_word.quote_style.is_none() && is_table_keyword(&first_word.value) {
+        return None;
+    }
+
+    let qualified = matches!(indices.get(1).map(|&i| &tokens[i].token), Some(Token::Period));
+    if qualified && let Some(&name_idx) = indices.get(2) && let Token::Word(name) = &tokens[name_idx].token {
+        let name_range = span_to_range(&tokens[name_idx].span);
+        return Some(TableReference {
+            database: Some(first_word.value.clone()),
+            name: name.value.clone(),
+            range: name_range,
+            full_range: Range { start: span_to_range(&first.span).start, end: name_range.end },
+        });
+    }
+
+    let range = span_to_range(&first.span);
+    Some(TableReference {
+        database: None,
+        name: first_word.value.clone(),
+        range,
+        full_range: range,
+    })
+}
+
+fn is_table_keyword(value: &str) -> bool {
+    matches!(value.to_uppercase().as_str(),
+        "TABLE" | "IF" | "NOT" | "EXISTS" | "SELECT" | "OVERWRITE" | "EXTERNAL" | "TEMPORARY" | "LOCAL" | "DIRECTORY"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_references_across_statements() {
+        let text = "INSERT INTO sales SELECT * FROM staging;\nSELECT s.id FROM db.sales s JOIN users u ON s.uid = u.id;\nSELECT sales FROM orders;";
+        let position = Position { line: 0, character: 13 };
+        let refs = find_table_references(text, position);
+        assert_eq!(refs.len(), 2);
+        assert_eq!(refs[0].start, Position { line: 0, character: 12 });
+        assert_eq!(refs[1].start, Position { line: 1, character: 20 });
+    }
+
+    #[test]
+    fn test_references_from_list() {
+        let text = "SELECT * FROM a, b WHERE a.id = b.id;\nDROP TABLE b;";
+        let refs = find_table_references(text, Position { line: 1, character: 11 });
+        assert_eq!(refs.len(), 2);
+    }
+
+    #[test]
+    fn test_no_references_for_column() {
+        let text = "SELECT sales FROM orders;";
+        let refs = find_table_references(text, Position { line: 0, character: 8 });
+        assert!(refs.is_empty());
+    }
+
+    #[test]
+    fn test_cte_definition() {
+        let text = "WITH a AS (SELECT 1 AS x),\n  b AS (SELECT x FROM a)\nSELECT * FROM b JOIN a ON b.x = a.x JOIN c ON c.x = a.x;";
+        let definition_of = |line, character| find_cte_definition(text, Position { line, character });
+        let a = Range { start: Position { line: 0, character: 5 }, end: Position { line: 0, character: 6 } };
+        let b = Range { start: Position { line: 1, character: 2 }, end: Position { line: 1, character: 3 } };
+        assert_eq!(definition_of(1, 22), Some(a));
+        assert_eq!(definition_of(2, 14), Some(b));
+        assert_eq!(definition_of(2, 21), Some(a));
+        // Not CTEs: a real table, and a column qualified by a CTE name
+        assert_eq!(definition_of(2, 41), None);
+        assert_eq!(definition_of(2, 26), None);
+    }
+}
@@ -0,0 +1,169 @@
+use std::ops::Range as IndexRange;
+use sqlparser::tokenizer::{Token, TokenWithSpan};
+use tower_lsp::lsp_types::{Position, Range, TextEdit};
+use crate::linter::is_reserved_word;
+use crate::references::collect_table_references;
+use crate::syntax::{self, is_keyword, is_significant, range_contains, span_to_range};
+
+/// The range of the table alias under `position`, if there is one to rename.
+pub fn prepare_rename(text: &str, position: Position) -> Option<Range> {
+    let tokens = syntax::tokenize(text)?;
+    let (_, target_idx) = alias_at(&tokens, position)?;
+    Some(span_to_range(&tokens[target_idx].span))
+}
+
+/// Renames the table alias under `position` within its statement.
+///
+/// Returns `Ok(None)` if the cursor isn't on an alias, and an error if `new_name` isn't a
+/// plain identifier or is a reserved word.
+pub fn rename(text: &str, position: Position, new_name: &str) -> Result<Option<Vec<TextEdit>>, String> {
+    let is_identifier = new_name.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && new_name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !is_identifier {
+        return Err(format!("'{}' is not a valid alias", new_name));
+    }
+    if is_reserved_word(new_name) {
+        return Err(format!("'{}' is a reserved word", new_name));
+    }
+
+    let Some(tokens) = syntax::tokenize(text) else { return Ok(None) };
+    let Some((statement, target_idx)) = alias_at(&tokens, position) else { return Ok(None) };
+    let Token::Word(alias) = &tokens[target_idx].token else { return Ok(None) };
+
+    // The alias's definitions and `alias.` qualifiers, skipping columns that merely share its
+    // name (`SELECT t1 ...`, `x.t1`)
+    let definitions = alias_definitions(&tokens, &statement);
+    let edits = statement
+        .clone()
+        .filter(|&i| matches!(&tokens[i].token, Token::Word(w) if w.value.eq_ignore_ascii_case(&alias.value)))
+        .filter(|&i| is_alias_use(&tokens, &statement, &definitions, i))
+        .map(|i| TextEdit { range: span_to_range(&tokens[i].span), new_text: new_name.to_string() })
+        .collect();
+    Ok(Some(edits))
+}
+
+/// Finds the statement around `position` and the index of the word under it, if that word
+/// is one of the statement's table aliases.
+fn alias_at(tokens: &[TokenWithSpan], position: Position) -> Option<(IndexRange<usize>, usize)> {
+    let statement = syntax::statement_at(tokens, position)?;
+
+    let target_idx = statement.clone().find(|&i| {
+        matches!(tokens[i].token, Token::Word(_)) && range_contains(&span_to_range(&tokens[i].span), position)
+    })?;
+    let Token::Word(target) = &tokens[target_idx].token else { return None };
+    let definitions = alias_definitions(tokens, &statement);
+    if !is_alias_use(tokens, &statement, &definitions, target_idx) {
+        return None;
+    }
+
+    let is_alias = definitions
+        .iter()
+        .any(|&i| matches!(&tokens[i].token, Token::Word(w) if w.value.eq_ignore_ascii_case(&target.value)));
+    is_alias.then_some((statement, target_idx))
+}
+
+/// Whether the word at `idx` is an alias's definition or qualifies a column (`t1.id`), rather
+/// than a column that happens to share its name (`t1` alone, or `x.t1`).
+fn is_alias_use(tokens: &[TokenWithSpan], statement: &IndexRange<usize>, definitions: &[usize], idx: usize) -> bool {
+    if definitions.contains(&idx) {
+        return true;
+    }
+    let previous = (statement.start..idx).rev().find(|&i| is_significant(&tokens[i].token));
+    let next = (idx + 1..statement.end).find(|&i| is_significant(&tokens[i].token));
+    let is_period = |i: usize| matches!(tokens[i].token, Token::Period);
+    !previous.is_some_and(is_period) && next.is_some_and(is_period)
+}
+
+/// Token indices of the aliases given to tables and FROM/JOIN subqueries in `statement`.
+pub fn alias_definitions(tokens: &[TokenWithSpan], statement: &IndexRange<usize>) -> Vec<usize> {
+    let significant: Vec<usize> = statement.clone().filter(|&i| is_significant(&tokens[i].token)).collect();
+    let alias_after = |pos: usize| -> Option<usize> {
+        let mut pos = pos + 1;
+        if significant.get(pos).is_some_and(|&i| is_keyword(&tokens[i].token, "AS")) {
+            pos += 1;
+        }
+        let &idx = significant.get(pos)?;
+        match &tokens[idx].token {
+            Token::Word(w) if w.quote_style.is_some() || syntax::can_be_table_alias(&w.value) => Some(idx),
+            _ => None,
+        }
+    };
+
+    let mut definitions = Vec::new();
+    // References are matched up by span, so the statement's own tokens are enough
+    for reference in collect_table_references(&tokens[statement.clone()]) {
+        let Some(pos) = significant.iter().position(|&i| span_to_range(&tokens[i].span).end == reference.full_range.end) else { continue };
+        definitions.extend(alias_after(pos));
+    }
+
+    // `FROM (SELECT ...) alias`
+    for (pos, &i) in significant.iter().enumerate() {
+        if !matches!(tokens[i].token, Token::LParen) || pos == 0 {
+            continue;
+        }
+        let opener = &tokens[significant[pos - 1]].token;
+        if !is_keyword(opener, "FROM") && !is_keyword(opener, "JOIN") {
+            continue;
+        }
+        let Some(close_idx) = syntax::matching_paren(tokens, i) else { continue };
+        let Some(close_pos) = significant.iter().position(|&j| j == close_idx) else { continue };
+        definitions.extend(alias_after(close_pos));
+    }
+
+    definitions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn renamed(text: &str, position: Position, new_name: &str) -> String {
+        let mut edits = rename(text, position, new_name).unwrap().unwrap();
+        edits.sort_by_key(|e| std::cmp::Reverse(e.range.start));
+        let mut lines: Vec<String> = text.lines().map(str::to_string).collect();
+        for edit in edits {
+            let line = &mut lines[edit.range.start.line as usize];
+            line.replace_range(edit.range.start.character as usize..edit.range.end.character as usize, &edit.new_text);
+        }
+        lines.join("\n")
+    }
+
+    #[test]
+    fn test_rename_alias_in_statement() {
+        let text = "SELECT t1.id, x.t1 FROM orders t1 JOIN other x ON t1.id = x.id;\nSELECT t1.id FROM users t1;";
+        let position = Position { line: 0, character: 8 };
+        assert_eq!(prepare_rename(text, position), Some(Range {
+            start: Position { line: 0, character: 7 },
+            end: Position { line: 0, character: 9 },
+        }));
+        assert_eq!(
+            renamed(text, position, "o"),
+            "SELECT o.id, x.t1 FROM orders o JOIN other x ON o.id = x.id;\nSELECT t1.id FROM users t1;"
+        );
+    }
+
+    #[test]
+    fn test_rename_subquery_alias_from_definition() {
+        let text = "SELECT s.a FROM (SELECT a FROM t) AS s WHERE s.a > 1;";
+        let position = Position { line: 0, character: 37 };
+        assert_eq!(renamed(text, position, "sub"), "SELECT sub.a FROM (SELECT a FROM t) AS sub WHERE sub.a > 1;");
+    }
+
+    #[test]
+    fn test_rename_rejections() {
+        let text = "SELECT t1.id FROM orders t1;";
+        assert!(rename(text, Position { line: 0, character: 8 }, "select").is_err());
+        assert!(rename(text, Position { line: 0, character: 8 }, "a b").is_err());
+        // Table and column names aren't aliases
+        assert!(prepare_rename(text, Position { line: 0, character: 20 }).is_none());
+        assert!(prepare_rename(text, Position { line: 0, character: 11 }).is_none());
+    }
+
+    #[test]
+    fn test_rename_skips_columns_named_like_the_alias() {
+        let text = "SELECT t1, t1.id FROM orders t1;";
+        assert_eq!(renamed(text, Position { line: 0, character: 29 }, "o"), "SELECT t1, o.id FROM orders o;");
+        // The bare column isn't the alias either
+        assert!(prepare_rename(text, Position { line: 0, character: 8 }).is_none());
+    }
+}
@@ -0,0 +1,202 @@
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::str::FromStr;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tower_lsp::lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString, Url};
+
+/// How the CLI reports diagnostics.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReportFormat {
+    Text,
+    Json,
+    Sarif,
+}
+
+impl FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            "sarif" => Ok(Self::Sarif),
+            _ => Err(format!("unknown report format '{}' (expected text, json or sarif)", value)),
+        }
+    }
+}
+
+/// One file's entry in the JSON report.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileReport {
+    pub uri: Url,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// One `path:line:col: severity: message [code]` line per diagnostic, with 1-based positions.
+/// Paths are printed as given.
+pub fn text(results: &[(&Path, &[Diagnostic])]) -> String {
+    let mut output = String::new();
+    for (path, diagnostics) in results {
+        let path = path.display();
+        for diagnostic in diagnostics.iter() {
+            let code = match code_of(diagnostic) {
+                Some(code) => format!(" [{}]", code),
+                None => String::new(),
+            };
+            output.push_str(&format!(
+                "{}:{}:{}: {}: {}{}\n",
+                path,
+                diagnostic.range.start.line + 1,
+                diagnostic.range.start.character + 1,
+                severity_name(diagnostic.severity),
+                diagnostic.message,
+                code
+            ));
+        }
+    }
+    output
+}
+
+/// The diagnostics as a JSON array of `{ "uri", "diagnostics" }`, using the LSP shapes.
+pub fn json(results: &[(Url, Vec<Diagnostic>)]) -> serde_json::Result<String> {
+    let reports: Vec<FileReport> = results
+        .iter()
+        .map(|(uri, diagnostics)| FileReport { uri: uri.clone(), diagnostics: diagnostics.clone() })
+        .collect();
+    serde_json::to_string_pretty(&reports)
+}
+
+/// A SARIF 2.1.0 log with a single run, as accepted by GitHub code scanning.
+///
+/// Files under the working directory are reported by relative path so they resolve against
+/// the repository root.
+pub fn sarif(results: &[(Url, Vec<Diagnostic>)]) -> serde_json::Result<String> {
+    let cwd = std::env::current_dir().ok();
+    let mut rule_ids = BTreeSet::new();
+    let mut sarif_results = Vec::new();
+
+    for (uri, diagnostics) in results {
+        let artifact_uri = uri
+            .to_file_path()
+            .ok()
+            .and_then(|path| Some(path.strip_prefix(cwd.as_ref()?).ok()?.to_string_lossy().replace('\\', "/")))
+            .unwrap_or_else(|| uri.to_string());
+
+        for diagnostic in diagnostics {
+            // SARIF needs a rule for every result; the few diagnostics without a code are
+            // filed under the tool's own name rather than borrowing a real rule's
+            let rule_id = code_of(diagnostic).unwrap_or_else(|| "hql-ls".to_string());
+            rule_ids.insert(rule_id.clone());
+            sarif_results.push(json!({
+                "ruleId": rule_id,
+                "level": sarif_level(diagnostic.severity),
+                "message": { "text": diagnostic.message },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": { "uri": artifact_uri },
+                        "region": {
+                            "startLine": diagnostic.range.start.line + 1,
+                            "startColumn": diagnostic.range.start.character + 1,
+                            "endLine": diagnostic.range.end.line + 1,
+                            "endColumn": diagnostic.range.end.character + 1,
+                        }
+                    }
+                }]
+            }));
+        }
+    }
+
+    let rules: Vec<Value> = rule_ids.into_iter().map(|id| json!({ "id": id })).collect();
+    serde_json::to_string_pretty(&json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "hql-ls",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": rules,
+                }
+            },
+            "results": sarif_results,
+        }]
+    }))
+}
+
+fn code_of(diagnostic: &Diagnostic) -> Option<String> {
+    match &diagnostic.code {
+        Some(NumberOrString::String(code)) => Some(code.clone()),
+        Some(NumberOrString::Number(code)) => Some(code.to_string()),
+        None => None,
+    }
+}
+
+pub(crate) fn severity_name(severity: Option<DiagnosticSeverity>) -> &'static str {
+    match severity {
+        Some(DiagnosticSeverity::ERROR) => "error",
+        Some(DiagnosticSeverity::INFORMATION) => "info",
+        Some(DiagnosticSeverity::HINT) => "hint",
+        _ => "warning",
+    }
+}
+
+fn sarif_level(severity: Option<DiagnosticSeverity>) -> &'static str {
+    match severity {
+        Some(DiagnosticSeverity::ERROR) => "error",
+        Some(DiagnosticSeverity::INFORMATION | DiagnosticSeverity::HINT) => "note",
+        _ => "warning",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::LintingConfig;
+    use crate::linter;
+
+    fn results() -> Vec<(Url, Vec<Diagnostic>)> {
+        let uri = Url::parse("file:///work/queries/a.hql").unwrap();
+        vec![(uri, linter::lint("SELECT a, FROM t\nWHERE (x", &LintingConfig::default()))]
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let results = results();
+        let parsed: Vec<FileReport> = serde_json::from_str(&json(&results).unwrap()).unwrap();
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(parsed[0].uri, results[0].0);
+        assert_eq!(parsed[0].diagnostics, results[0].1);
+    }
+
+    #[test]
+    fn test_text() {
+        let results = results();
+        let output = text(&[(Path::new("queries/a.hql"), &results[0].1)]);
+        assert!(output.contains("queries/a.hql:1:9: error: Trailing comma before 'FROM' [trailing-comma]\n"), "{}", output);
+    }
+
+    #[test]
+    fn test_sarif() {
+        let results = results();
+        let log: Value = serde_json::from_str(&sarif(&results).unwrap()).unwrap();
+        assert_eq!(log["version"], "2.1.0");
+        let run = &log["runs"][0];
+        assert_eq!(run["tool"]["driver"]["name"], "hql-ls");
+        assert_eq!(run["results"].as_array().unwrap().len(), results[0].1.len());
+
+        let trailing = run["results"].as_array().unwrap().iter().find(|r| r["ruleId"] == "trailing-comma").unwrap();
+        assert_eq!(trailing["level"], "error");
+        let location = &trailing["locations"][0]["physicalLocation"];
+        assert_eq!(location["artifactLocation"]["uri"], "file:///work/queries/a.hql");
+        assert_eq!(location["region"]["startLine"], 1);
+        assert_eq!(location["region"]["startColumn"], 9);
+        assert!(run["tool"]["driver"]["rules"].as_array().unwrap().iter().any(|r| r["id"] == "trailing-comma"));
+    }
+
+    #[test]
+    fn test_parse_format() {
+        assert_eq!("SARIF".parse(), Ok(ReportFormat::Sarif));
+        assert!("xml".parse::<ReportFormat>().is_err());
+    }
+}
@@ -0,0 +1,320 @@
+use serde::Serialize;
+use sqlparser::tokenizer::{TokenWithSpan, TokenizerError};
+use tower_lsp::lsp_types::{Diagnostic, DiagnosticSeverity};
+use crate::config::{LintingConfig, LintingRules, RuleConfig};
+use crate::linter;
+use crate::report::severity_name;
+use crate::schema::Schema;
+
+/// Everything a rule can look at: the document, its tokens and the lint settings.
+pub struct LintContext<'a> {
+    pub text: &'a str,
+    /// The document's tokens; empty if the tokenizer failed
+    pub tokens: &'a [TokenWithSpan],
+    pub tokenizer_error: Option<&'a TokenizerError>,
+    pub config: &'a LintingConfig,
+    pub schema: Option<&'a Schema>,
+}
+
+/// A lint rule. The linter runs each registered rule whose settings enable it.
+pub trait Rule: Send + Sync {
+    /// The code of the diagnostics the rule reports
+    fn id(&self) -> &'static str;
+    fn default_severity(&self) -> DiagnosticSeverity;
+    /// The rule's settings under `linting.rules`
+    fn settings<'a>(&self, rules: &'a LintingRules) -> &'a RuleConfig;
+    /// What the rule reads, and so which documents it runs on
+    fn input(&self) -> Input;
+    fn check(&self, ctx: &LintContext) -> Vec<Diagnostic>;
+}
+
+/// Which documents a rule runs on.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Input {
+    /// Every document; the rule only reads the text
+    Text,
+    /// Every document; the rule reads the text one line at a time, so after an edit only
+    /// the edited lines need checking again (see `linter::lint_lines`)
+    Lines,
+    /// Documents that tokenized
+    Tokens,
+    /// Documents the tokenizer rejected
+    TokenizerError,
+}
+
+/// A rule backed by a check function, which is given the rule's settings and its severity:
+/// the configured one, or the default from `RULES`.
+pub struct FnRule {
+    info: &'static RuleInfo,
+    input: Input,
+    settings: fn(&LintingRules) -> &RuleConfig,
+    check: fn(&LintContext, &RuleConfig, DiagnosticSeverity) -> Vec<Diagnostic>,
+}
+
+impl FnRule {
+    /// Panics if `code` has no entry in `RULES`.
+    pub fn boxed(
+        code: &str,
+        input: Input,
+        settings: fn(&LintingRules) -> &RuleConfig,
+        check: fn(&LintContext, &RuleConfig, DiagnosticSeverity) -> Vec<Diagnostic>,
+    ) -> Box<dyn Rule> {
+        let info = find(code).unwrap_or_else(|| panic!("rule '{}' is missing from RULES", code));
+        Box::new(Self { info, input, settings, check })
+    }
+}
+
+impl Rule for FnRule {
+    fn id(&self) -> &'static str {
+        self.info.code
+    }
+
+    fn default_severity(&self) -> DiagnosticSeverity {
+        self.info.default_severity
+    }
+
+    fn settings<'a>(&self, rules: &'a LintingRules) -> &'a RuleConfig {
+        (self.settings)(rules)
+    }
+
+    fn input(&self) -> Input {
+        self.input
+    }
+
+    fn check(&self, ctx: &LintContext) -> Vec<Diagnostic> {
+        let applies = match self.input {
+            Input::Text | Input::Lines => true,
+            Input::Tokens => ctx.tokenizer_error.is_none(),
+            Input::TokenizerError => ctx.tokenizer_error.is_some(),
+        };
+        if !applies {
+            return Vec::new();
+        }
+        let settings = (self.settings)(&ctx.config.rules);
+        (self.check)(ctx, settings, settings.severity_or(self.info.default_severity))
+    }
+}
+
+/// What the linter knows about one rule. Whether it is on by default comes from
+/// `LintingRules::default()` rather than being repeated here.
+#[derive(Debug, Clone, Copy)]
+pub struct RuleInfo {
+    /// The code of the diagnostics the rule reports
+    pub code: &'static str,
+    /// Key of the rule's settings under `linting.rules`, or for a note the `[linting]`
+    /// setting that controls it
+    pub config_key: &'static str,
+    /// Severity when none is configured; rules that pick one per finding list the stronger
+    pub default_severity: DiagnosticSeverity,
+    pub description: &'static str,
+    pub kind: Kind,
+}
+
+/// What reports a code.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Kind {
+    /// A rule in the linter's registry
+    Rule,
+    /// The linter itself, to say it checked less than usual, e.g. over `maxDiagnostics`.
+    /// Notes can be suppressed and named in `--error-on` like rules, but aren't listed
+    Note,
+}
+
+/// Every lint rule and note, sorted by code. `hql-ls rules`, `hql/listRules` and `--error-on`
+/// validation all read this table, and the linter's registry takes default severities from it.
+pub const RULES: &[RuleInfo] = &[
+    rule("alias-style-consistency", "aliasStyleConsistency", DiagnosticSeverity::HINT, "Table aliases that use AS when most in the file omit it, or the reverse"),
+    rule("ambiguous-column", "ambiguousColumn", DiagnosticSeverity::WARNING, "Unqualified columns that more than one joined table in the schema declares"),
+    rule("batch-separator", "batchSeparator", DiagnosticSeverity::ERROR, "GO on a line of its own, a SQL Server batch separator that Hive rejects"),
+    rule("between-bounds", "betweenBounds", DiagnosticSeverity::WARNING, "BETWEEN with numeric bounds that are reversed or equal"),
+    rule("boolean-literal-casing", "booleanLiteralCasing", DiagnosticSeverity::HINT, "TRUE, FALSE and NULL not in the configured case"),
+    rule("cartesian-join", "cartesianJoin", DiagnosticSeverity::WARNING, "Comma-separated tables with no join condition"),
+    rule("case-branch-types", "caseBranchTypes", DiagnosticSeverity::INFORMATION, "CASE or IF branches returning literals of different types"),
+    rule("cluster-order-conflict", "clusterOrderConflict", DiagnosticSeverity::WARNING, "CLUSTER, DISTRIBUTE or SORT BY combined with ORDER BY in one query"),
+    rule("comma-join", "commaJoin", DiagnosticSeverity::INFORMATION, "Implicit comma joins instead of explicit JOIN ... ON"),
+    rule("constant-predicate", "constantPredicate", DiagnosticSeverity::HINT, "WHERE conditions such as 1 = 1 or 1 = 0 that are always true or always false"),
+    rule("constant-select-item", "constantSelectItem", DiagnosticSeverity::HINT, "SELECT items that are the same constant for every row"),
+    rule("cte-missing-as", "cteMissingAs", DiagnosticSeverity::ERROR, "CTE definitions missing AS before the parenthesized query"),
+    rule("cte-order-by", "cteOrderBy", DiagnosticSeverity::INFORMATION, "ORDER BY inside a CTE, which does not order the final result"),
+    rule("deprecated-function", "deprecatedFunction", DiagnosticSeverity::WARNING, "Calls to deprecated functions, with the replacement to use"),
+    rule("distinct-with-group-by", "distinctWithGroupBy", DiagnosticSeverity::WARNING, "SELECT DISTINCT in a query that also has GROUP BY"),
+    rule("duplicate-alias", "duplicateAlias", DiagnosticSeverity::WARNING, "The same table alias defined twice in one query block"),
+    rule("duplicate-column", "duplicateSelectColumn", DiagnosticSeverity::WARNING, "The same expression selected more than once"),
+    rule("duplicate-set-column", "duplicateSetColumn", DiagnosticSeverity::WARNING, "A column assigned more than once in UPDATE ... SET"),
+    rule("empty-table-parens", "emptyTableParens", DiagnosticSeverity::WARNING, "Empty parentheses after a table name in FROM or JOIN"),
+    rule("empty-window", "emptyWindow", DiagnosticSeverity::HINT, "OVER () windows that compute over the whole result"),
+    rule("equality-operator", "equalityOperator", DiagnosticSeverity::ERROR, "'==' used instead of '='"),
+    rule("foreign-operator", "foreignOperator", DiagnosticSeverity::ERROR, "Operators from other languages, such as ':=', '&&', '===' and '=>'"),
+    rule("group-by-consistency", "groupByConsistency", DiagnosticSeverity::WARNING, "Plain columns in a grouped SELECT list that the GROUP BY doesn't mention (heuristic)"),
+    rule("hive-variable", "hiveVariable", DiagnosticSeverity::WARNING, "Malformed ${namespace:name} variables, unknown namespaces and unknown names"),
+    rule("implicit-default-db", "implicitDefaultDb", DiagnosticSeverity::INFORMATION, "Unqualified tables that resolve to 'default' because no USE precedes them"),
+    rule("inconsistent-quoting", "inconsistentQuoting", DiagnosticSeverity::HINT, "Identifiers written both with and without backticks in one statement"),
+    rule("insert-column-order", "insertColumnOrder", DiagnosticSeverity::HINT, "SELECT columns whose names suggest a different order than the INSERT column list"),
+    rule("insert-select-arity", "insertSelectArity", DiagnosticSeverity::ERROR, "INSERT column lists and SELECT projections of different lengths"),
+    rule("invalid-regex", "invalidRegex", DiagnosticSeverity::WARNING, "RLIKE and REGEXP patterns that don't compile"),
+    rule("keyword-alias", "keywordAlias", DiagnosticSeverity::WARNING, "Aliases after AS that are reserved words or aggregate names"),
+    rule("keyword-casing", "keywordCasing", DiagnosticSeverity::WARNING, "Keywords that aren't uppercase"),
+    rule("like-without-wildcard", "likeWithoutWildcard", DiagnosticSeverity::INFORMATION, "LIKE pattern without a % or _ wildcard, which is just an equality test"),
+    rule("limit-value", "limitValue", DiagnosticSeverity::ERROR, "Negative LIMIT values, and LIMIT 0 as a hint"),
+    rule("limit-without-order", "limitWithoutOrder", DiagnosticSeverity::INFORMATION, "LIMIT without ORDER BY, which returns arbitrary rows"),
+    note("max-diagnostics", "maxDiagnostics", DiagnosticSeverity::INFORMATION, "How many diagnostics were left out over maxDiagnostics"),
+    note("max-file-size", "maxFileSize", DiagnosticSeverity::WARNING, "Files over maxFileSize, which are linted a few statements at a time"),
+    rule("max-line-length", "maxLineLength", DiagnosticSeverity::HINT, "Lines longer than maxLength characters"),
+    rule("misplaced-distinct", "misplacedDistinct", DiagnosticSeverity::ERROR, "DISTINCT that doesn't immediately follow SELECT"),
+    rule("missing-comma", "missingComma", DiagnosticSeverity::WARNING, "Adjacent SELECT list items with no comma between them (heuristic)"),
+    rule("missing-over", "missingOver", DiagnosticSeverity::ERROR, "Analytic functions such as ROW_NUMBER or LAG called without OVER (...)"),
+    rule("missing-partition", "missingPartition", DiagnosticSeverity::WARNING, "INSERT OVERWRITE of a partitioned table without a PARTITION clause, per the schema"),
+    rule("missing-semicolon", "semicolon", DiagnosticSeverity::INFORMATION, "Statements not terminated with a semicolon"),
+    rule("missing-stored-as", "missingStoredAs", DiagnosticSeverity::INFORMATION, "CREATE TABLE without STORED AS, which falls back to the cluster's default format"),
+    rule("mixed-aggregate", "mixedAggregate", DiagnosticSeverity::ERROR, "Plain columns selected alongside aggregates without GROUP BY"),
+    rule("mixed-indentation", "indentation", DiagnosticSeverity::WARNING, "Indents mixing tabs and spaces, or not in the configured style"),
+    rule("multi-column-count-distinct", "multiColumnCountDistinct", DiagnosticSeverity::WARNING, "COUNT(DISTINCT ...) over several columns, which Hive only supports in some queries"),
+    rule("no-tabs", "noTabs", DiagnosticSeverity::HINT, "Tab characters outside string literals and comments"),
+    rule("non-join-on", "nonJoinOn", DiagnosticSeverity::HINT, "ON conditions that only reference one side of the join"),
+    rule("null-comparison", "nullComparison", DiagnosticSeverity::WARNING, "= NULL, != NULL or <> NULL, which are never true"),
+    rule("null-into-not-null", "nullIntoNotNull", DiagnosticSeverity::ERROR, "NULL inserted into a column the schema declares NOT NULL"),
+    rule("positional-reference", "orderByOrdinal", DiagnosticSeverity::HINT, "ORDER BY or GROUP BY items given as column positions"),
+    rule("redundant-cast", "redundantCast", DiagnosticSeverity::HINT, "CASTs to the type the schema already declares"),
+    rule("reserved-identifier", "reservedIdentifier", DiagnosticSeverity::WARNING, "Hive reserved words used unquoted as identifiers"),
+    rule("scalar-subquery-columns", "scalarSubqueryColumns", DiagnosticSeverity::ERROR, "Scalar subqueries selecting more than one column"),
+    rule("select-distinct-star", "selectDistinctStar", DiagnosticSeverity::INFORMATION, "SELECT DISTINCT *, which deduplicates on every column"),
+    rule("select-star", "selectStar", DiagnosticSeverity::WARNING, "SELECT * instead of an explicit column list"),
+    rule("semicolon-in-parentheses", "semicolonInParens", DiagnosticSeverity::ERROR, "A semicolon inside parentheses, e.g. ending a pasted subquery"),
+    rule("set-property", "setProperty", DiagnosticSeverity::WARNING, "SET of deprecated properties, and of keys with no known prefix as a hint"),
+    rule("statement-length", "statementLength", DiagnosticSeverity::INFORMATION, "Statements longer than maxStatementLines lines"),
+    rule("subquery-depth", "subqueryDepth", DiagnosticSeverity::INFORMATION, "Subqueries nested more than maxDepth levels deep"),
+    rule("syntax", "syntax", DiagnosticSeverity::ERROR, "Statements the SQL parser rejects"),
+    rule("tokenizer-error", "stringLiteral", DiagnosticSeverity::ERROR, "Text that can't be tokenized, such as an unterminated string literal"),
+    rule("too-many-name-parts", "tooManyNameParts", DiagnosticSeverity::WARNING, "Table names with more than maxParts dotted parts"),
+    rule("trailing-comma", "trailingComma", DiagnosticSeverity::ERROR, "A comma before FROM or the end of a list"),
+    rule("trailing-whitespace", "trailingWhitespace", DiagnosticSeverity::HINT, "Whitespace at the end of a line"),
+    rule("truncate-external", "truncateExternal", DiagnosticSeverity::WARNING, "TRUNCATE of a table the schema marks external"),
+    rule("tuning-set", "tuningSet", DiagnosticSeverity::INFORMATION, "Resource tuning SET statements without an explanatory comment"),
+    rule("type-casing", "typeCasing", DiagnosticSeverity::WARNING, "Lowercase or mixed-case data types in CREATE TABLE column lists"),
+    rule("type-mismatch", "typeMismatch", DiagnosticSeverity::WARNING, "Numeric columns compared with string literals, or string columns with numbers, per the schema"),
+    rule("unbalanced-case", "unbalancedCase", DiagnosticSeverity::ERROR, "CASE without a matching END, or END without a CASE"),
+    rule("unbalanced-parentheses", "parentheses", DiagnosticSeverity::ERROR, "Unclosed '(' or extra ')'"),
+    rule("union-column-mismatch", "unionColumnMismatch", DiagnosticSeverity::WARNING, "UNION branch selecting a different number of columns from the first"),
+    rule("union-dedup", "unionDedup", DiagnosticSeverity::INFORMATION, "UNION where UNION ALL may do, since UNION deduplicates"),
+    rule("unsafe-interpolation", "unsafeInterpolation", DiagnosticSeverity::WARNING, "Hive variables substituted unquoted where a string literal is expected"),
+    rule("unterminated-comment", "comment", DiagnosticSeverity::ERROR, "Block comments that are never closed"),
+    rule("unused-cte-column", "unusedCteColumn", DiagnosticSeverity::HINT, "CTE columns that are never referenced"),
+    rule("wide-group-by", "wideGroupBy", DiagnosticSeverity::INFORMATION, "GROUP BY with more than maxGroupByKeys keys"),
+];
+
+const fn rule(
+    code: &'static str,
+    config_key: &'static str,
+    default_severity: DiagnosticSeverity,
+    description: &'static str,
+) -> RuleInfo {
+    RuleInfo { code, config_key, default_severity, description, kind: Kind::Rule }
+}
+
+const fn note(
+    code: &'static str,
+    config_key: &'static str,
+    default_severity: DiagnosticSeverity,
+    description: &'static str,
+) -> RuleInfo {
+    RuleInfo { code, config_key, default_severity, description, kind: Kind::Note }
+}
+
+/// The rule that reports `code`.
+pub fn find(code: &str) -> Option<&'static RuleInfo> {
+    RULES.iter().find(|rule| rule.code == code)
+}
+
+/// A rule as listed by `hql-ls rules --format json` and the `hql/listRules` request.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RuleListing {
+    pub id: &'static str,
+    pub config_key: &'static str,
+    pub enabled_by_default: bool,
+    pub default_severity: &'static str,
+    pub description: &'static str,
+}
+
+/// The registered rules, sorted by id.
+pub fn listing() -> Vec<RuleListing> {
+    let defaults = LintingRules::default();
+    let mut listing: Vec<RuleListing> = linter::registry()
+        .iter()
+        .filter_map(|rule| {
+            let info = find(rule.id())?;
+            Some(RuleListing {
+                id: info.code,
+                config_key: info.config_key,
+                enabled_by_default: rule.settings(&defaults).enabled,
+                default_severity: severity_name(Some(rule.default_severity())),
+                description: info.description,
+            })
+        })
+        .collect();
+    listing.sort_by_key(|rule| rule.id);
+    listing
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn test_rules_match_config_and_linter() {
+        let codes: Vec<&str> = RULES.iter().map(|rule| rule.code).collect();
+        let mut sorted = codes.clone();
+        sorted.sort_unstable();
+        sorted.dedup();
+        assert_eq!(codes, sorted, "RULES must be sorted by code without duplicates");
+
+        // One rule per `LintingRules` field; notes name a `[linting]` setting
+        let defaults = serde_json::to_value(LintingRules::default()).unwrap();
+        let config_keys: BTreeSet<&str> = defaults.as_object().unwrap().keys().map(String::as_str).collect();
+        let registered: BTreeSet<&str> = RULES.iter().filter(|rule| rule.kind == Kind::Rule).map(|rule| rule.config_key).collect();
+        assert_eq!(registered, config_keys);
+        let linting = serde_json::to_value(LintingConfig::default()).unwrap();
+        for note in RULES.iter().filter(|rule| rule.kind == Kind::Note) {
+            assert!(linting.get(note.config_key).is_some(), "{}", note.code);
+        }
+
+        // Every code the linter reports is registered
+        let source = include_str!("linter.rs");
+        let reported: BTreeSet<&str> = source
+            .split("NumberOrString::String(\"")
+            .skip(1)
+            .filter_map(|rest| rest.split('"').next())
+            .collect();
+        let missing: Vec<&&str> = reported.iter().filter(|code| find(code).is_none()).collect();
+        assert!(missing.is_empty(), "unregistered codes: {:?}", missing);
+
+        // The linter runs each rule once, reading the settings under its `config_key`
+        let ids: BTreeSet<&str> = linter::registry().iter().map(|rule| rule.id()).collect();
+        assert_eq!(ids.len(), linter::registry().len());
+        assert_eq!(ids, RULES.iter().filter(|rule| rule.kind == Kind::Rule).map(|rule| rule.code).collect());
+        for rule in linter::registry() {
+            let key = find(rule.id()).unwrap().config_key;
+            let mut toggled = defaults.clone();
+            toggled[key]["enabled"] = (!rule.settings(&LintingRules::default()).enabled).into();
+            let toggled: LintingRules = serde_json::from_value(toggled).unwrap();
+            assert_ne!(rule.settings(&toggled).enabled, rule.settings(&LintingRules::default()).enabled, "{}", rule.id());
+        }
+    }
+
+    #[test]
+    fn test_listing() {
+        let listing = listing();
+        assert_eq!(listing.len(), RULES.iter().filter(|rule| rule.kind == Kind::Rule).count());
+
+        let trailing_comma = listing.iter().find(|rule| rule.id == "trailing-comma").unwrap();
+        assert!(trailing_comma.enabled_by_default);
+        assert_eq!(trailing_comma.default_severity, "error");
+        let keyword_casing = listing.iter().find(|rule| rule.id == "keyword-casing").unwrap();
+        assert!(!keyword_casing.enabled_by_default);
+
+        let json = serde_json::to_value(trailing_comma).unwrap();
+        assert_eq!(json["configKey"], "trailingComma");
+        assert_eq!(json["enabledByDefault"], true);
+    }
+}
@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use serde::Deserialize;
+
+pub const SCHEMA_FILE_NAME: &str = "schema.json";
+
+/// Tables and columns described by the workspace `schema.json`:
+///
+/// ```json
+/// { "tables": [ { "name": "sales.orders", "columns": [ { "name": "id", "type": "bigint" } ] } ] }
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Schema {
+    #[serde(default)]
+    pub tables: Vec<Table>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Table {
+    pub name: String,
+    #[serde(default)]
+    pub columns: Vec<Column>,
+    #[serde(default)]
+    pub external: bool,
+    /// Partition columns, e.g. `["dt"]`; empty for an unpartitioned table
+    #[serde(default, rename = "partitionedBy")]
+    pub partitioned_by: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Column {
+    pub name: String,
+    #[serde(default, rename = "type")]
+    pub data_type: Option<String>,
+    #[serde(default, rename = "notNull")]
+    pub not_null: bool,
+}
+
+impl Table {
+    pub fn column(&self, name: &str) -> Option<&Column> {
+        self.columns.iter().find(|c| c.name.eq_ignore_ascii_case(name))
+    }
+
+    /// The table name without its database qualifier.
+    pub fn short_name(&self) -> &str {
+        self.name.rsplit('.').next().unwrap_or(&self.name)
+    }
+}
+
+impl Schema {
+    /// Looks a table up by its qualified or unqualified name, ignoring case.
+    pub fn table(&self, name: &str) -> Option<&Table> {
+        self.tables
+            .iter()
+            .find(|t| t.name.eq_ignore_ascii_case(name))
+            .or_else(|| self.tables.iter().find(|t| t.short_name().eq_ignore_ascii_case(name)))
+    }
+
+    /// Tables qualified with `database`, keyed by their short name.
+    pub fn tables_in(&self, database: &str) -> HashMap<&str, &Table> {
+        self.tables
+            .iter()
+            .filter(|t| {
+                t.name
+                    .rsplit_once('.')
+                    .is_some_and(|(db, _)| db.eq_ignore_ascii_case(database))
+            })
+            .map(|t| (t.short_name(), t))
+            .collect()
+    }
+}
+
+/// The schema file in the workspace root and its last successfully parsed contents.
+#[derive(Debug, Default)]
+pub struct WorkspaceSchema {
+    pub file_path: Option<PathBuf>,
+    pub schema: Option<Schema>,
+}
+
+impl WorkspaceSchema {
+    pub fn new(workspace_root: Option<&Path>) -> Self {
+        Self {
+            file_path: workspace_root.map(|root| root.join(SCHEMA_FILE_NAME)),
+            ..Default::default()
+        }
+    }
+
+    /// (Re)reads the schema file. A missing file unloads the schema.
+    pub fn reload(&mut self) -> anyhow::Result<()> {
+        self.schema = None;
+        let Some(path) = &self.file_path else { return Ok(()) };
+        if !path.exists() {
+            return Ok(());
+        }
+        let contents = std::fs::read_to_string(path)?;
+        self.schema = Some(serde_json::from_str(&contents)?);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_table_lookup() {
+        let schema: Schema = serde_json::from_str(
+            r#"{ "tables": [
+                { "name": "sales.orders", "columns": [ { "name": "id", "type": "bigint" }, { "name": "amount" } ] },
+                { "name": "customers" }
+            ] }"#,
+        )
+        .unwrap();
+
+        let orders = schema.table("ORDERS").unwrap();
+        assert_eq!(orders.name, "sales.orders");
+        assert_eq!(orders.columns[0].data_type.as_deref(), Some("bigint"));
+        assert!(orders.columns[1].data_type.is_none());
+        assert!(schema.table("sales.orders").is_some());
+        assert!(schema.table("customers").unwrap().columns.is_empty());
+        assert!(schema.table("missing").is_none());
+        assert_eq!(schema.tables_in("sales").keys().collect::<Vec<_>>(), vec![&"orders"]);
+    }
+}
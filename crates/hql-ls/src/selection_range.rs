@@ -0,0 +1,174 @@
+use std::ops::Range as IndexRange;
+use sqlparser::tokenizer::{Token, TokenWithSpan};
+use tower_lsp::lsp_types::{Position, Range, SelectionRange};
+use crate::syntax::{self, is_significant, range_contains, span_to_range};
+
+/// Keywords that start a clause when expanding a selection.
+const CLAUSE_STARTERS: &[&str] = &[
+    "WITH", "SELECT", "FROM", "WHERE", "GROUP", "HAVING", "ORDER", "SORT", "CLUSTER", "DISTRIBUTE",
+    "WINDOW", "LIMIT", "UNION", "INSERT", "VALUES", "SET",
+];
+
+/// One selection range chain per position: token, enclosing parenthesized groups and the
+/// clauses within them, statement, file.
+pub fn selection_ranges(text: &str, positions: &[Position]) -> Vec<SelectionRange> {
+    let tokens = syntax::tokenize(text).unwrap_or_default();
+    let file = Range { start: Position::default(), end: end_of(text) };
+
+    positions
+        .iter()
+        .map(|&position| {
+            let ranges = expansions(&tokens, position).unwrap_or_else(|| vec![Range { start: position, end: position }]);
+            let mut chain = SelectionRange { range: file, parent: None };
+            for range in ranges.into_iter().rev() {
+                if range != chain.range {
+                    chain = SelectionRange { range, parent: Some(Box::new(chain)) };
+                }
+            }
+            chain
+        })
+        .collect()
+}
+
+/// Ranges from the token at `position` out to its statement, innermost first.
+fn expansions(tokens: &[TokenWithSpan], position: Position) -> Option<Vec<Range>> {
+    let target = token_near(tokens, position)?;
+    let statement = syntax::split_statements(tokens).into_iter().find(|s| s.contains(&target))?;
+    let range_of = |start: usize, end: usize| Range {
+        start: span_to_range(&tokens[start].span).start,
+        end: span_to_range(&tokens[end].span).end,
+    };
+
+    // Parentheses still open at the target, tracked as in `check_parentheses`
+    let mut open_parens = Vec::new();
+    for (i, token_with_span) in tokens.iter().enumerate().take(target).skip(statement.start) {
+        match token_with_span.token {
+            Token::LParen => open_parens.push(i),
+            Token::RParen => {
+                open_parens.pop();
+            }
+            _ => {}
+        }
+    }
+
+    let mut ranges = vec![range_of(target, target)];
+    let mut child = target;
+    for &open_idx in open_parens.iter().rev() {
+        let close_idx = syntax::matching_paren(tokens, open_idx).unwrap_or(statement.end - 1);
+        ranges.extend(clause_range(tokens, open_idx + 1..close_idx, child));
+        ranges.push(range_of(open_idx, close_idx));
+        child = open_idx;
+    }
+    ranges.extend(clause_range(tokens, statement.clone(), child));
+    ranges.push(range_of(statement.start, statement.end - 1));
+    Some(ranges)
+}
+
+/// The clause within `level` that contains the token at `child`, if `level` has clauses.
+fn clause_range(tokens: &[TokenWithSpan], level: IndexRange<usize>, child: usize) -> Option<Range> {
+    let mut start = None;
+    let mut end = None;
+    let mut depth = 0;
+    for i in level {
+        match &tokens[i].token {
+            Token::LParen => depth += 1,
+            Token::RParen => depth -= 1,
+            Token::Word(w) if depth == 0 && w.quote_style.is_none()
+                && CLAUSE_STARTERS.iter().any(|kw| w.value.eq_ignore_ascii_case(kw)) =>
+            {
+                if i > child {
+                    break;
+                }
+                start = Some(i);
+            }
+            _ => {}
+        }
+        if start.is_some() && is_significant(&tokens[i].token) && !matches!(tokens[i].token, Token::SemiColon) {
+            end = Some(i);
+        }
+    }
+
+    Some(Range {
+        start: span_to_range(&tokens[start?].span).start,
+        end: span_to_range(&tokens[end?].span).end,
+    })
+}
+
+/// The significant token at `position`, or the nearest one when it's on whitespace.
+fn token_near(tokens: &[TokenWithSpan], position: Position) -> Option<usize> {
+    let significant = || (0..tokens.len()).filter(|&i| is_significant(&tokens[i].token));
+    // Between two adjacent tokens, prefer the one starting at the cursor
+    let inside = |i: usize| {
+        let range = span_to_range(&tokens[i].span);
+        range.start <= position && position < range.end
+    };
+    let containing = significant()
+        .find(|&i| inside(i))
+        .or_else(|| significant().find(|&i| range_contains(&span_to_range(&tokens[i].span), position)));
+    if containing.is_some() {
+        return containing;
+    }
+
+    let previous = significant().rfind(|&i| span_to_range(&tokens[i].span).end < position);
+    let next = significant().find(|&i| span_to_range(&tokens[i].span).start > position);
+    let same_line = |i: usize| span_to_range(&tokens[i].span).start.line == position.line;
+    match (previous, next) {
+        (Some(p), Some(n)) if same_line(p) && same_line(n) => {
+            let before = position.character - span_to_range(&tokens[p].span).end.character;
+            let after = span_to_range(&tokens[n].span).start.character - position.character;
+            Some(if after < before { n } else { p })
+        }
+        (Some(p), Some(n)) if same_line(p) && !same_line(n) => Some(p),
+        (previous, next) => next.or(previous),
+    }
+}
+
+fn end_of(text: &str) -> Position {
+    let line = text.matches('\n').count() as u32;
+    let last_line = text.rsplit('\n').next().unwrap_or_default();
+    Position { line, character: last_line.chars().count() as u32 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chain(text: &str, line: u32, character: u32) -> Vec<String> {
+        let lines: Vec<&str> = text.lines().collect();
+        let mut ranges = Vec::new();
+        let mut current = Some(&selection_ranges(text, &[Position { line, character }])[0]).cloned();
+        while let Some(selection) = current {
+            let Range { start, end } = selection.range;
+            // The tests below keep every range but the file's on one line
+            ranges.push(if start.line == end.line {
+                lines[start.line as usize][start.character as usize..end.character as usize].to_string()
+            } else {
+                "<file>".to_string()
+            });
+            current = selection.parent.map(|parent| *parent);
+        }
+        ranges
+    }
+
+    #[test]
+    fn test_expands_through_groups_and_clauses() {
+        let text = "SELECT a FROM (SELECT b FROM t WHERE f(x) = 1) s;\nSELECT 2;";
+        assert_eq!(chain(text, 0, 39), vec![
+            "x",
+            "(x)",
+            "WHERE f(x) = 1",
+            "(SELECT b FROM t WHERE f(x) = 1)",
+            "FROM (SELECT b FROM t WHERE f(x) = 1) s",
+            "SELECT a FROM (SELECT b FROM t WHERE f(x) = 1) s;",
+            "<file>",
+        ]);
+    }
+
+    #[test]
+    fn test_snaps_from_whitespace() {
+        let text = "SELECT a,   b FROM t;\n\nSELECT 2;";
+        assert_eq!(chain(text, 0, 10)[0], ",");
+        assert_eq!(chain(text, 0, 11)[0], "b");
+        assert_eq!(chain(text, 1, 0)[0], "SELECT");
+    }
+}
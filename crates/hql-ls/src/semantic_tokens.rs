@@ -0,0 +1,168 @@
+use sqlparser::tokenizer::{Token, TokenWithSpan, Whitespace};
+use tower_lsp::lsp_types::{SemanticToken, SemanticTokenType, SemanticTokensLegend};
+use crate::keywords::{is_additional_keyword, is_keyword};
+use crate::linter::is_reserved_word;
+use crate::syntax::{self, is_significant};
+
+/// Token types in legend order; a token's type is its index here.
+pub const TOKEN_TYPES: [SemanticTokenType; 6] = [
+    SemanticTokenType::KEYWORD,
+    SemanticTokenType::FUNCTION,
+    SemanticTokenType::STRING,
+    SemanticTokenType::NUMBER,
+    SemanticTokenType::COMMENT,
+    SemanticTokenType::VARIABLE,
+];
+
+const KEYWORD: u32 = 0;
+const FUNCTION: u32 = 1;
+const STRING: u32 = 2;
+const NUMBER: u32 = 3;
+const COMMENT: u32 = 4;
+const VARIABLE: u32 = 5;
+
+/// Hive built-ins that are also keywords (`IF`, `MAP`, ...) are only functions when called.
+const HIVE_FUNCTIONS: &[&str] = &[
+    "ABS", "ARRAY", "ARRAY_CONTAINS", "AVG", "CAST", "CEIL", "COALESCE", "COLLECT_LIST", "COLLECT_SET",
+    "CONCAT", "CONCAT_WS", "COUNT", "CURRENT_DATE", "CURRENT_TIMESTAMP", "DATEDIFF", "DATE_ADD",
+    "DATE_FORMAT", "DATE_SUB", "DAY", "DENSE_RANK", "EXPLODE", "FIRST_VALUE", "FLOOR", "FROM_UNIXTIME",
+    "GET_JSON_OBJECT", "GREATEST", "HOUR", "IF", "INSTR", "ISNOTNULL", "ISNULL", "LAG", "LAST_VALUE",
+    "LEAD", "LEAST", "LENGTH", "LOWER", "LPAD", "LTRIM", "MAP", "MAX", "MIN", "MONTH", "NAMED_STRUCT",
+    "NVL", "POSEXPLODE", "RANK", "REGEXP_EXTRACT", "REGEXP_REPLACE", "ROUND", "ROW_NUMBER", "RPAD",
+    "RTRIM", "SIZE", "SPLIT", "STRUCT", "SUBSTR", "SUBSTRING", "SUM", "TO_DATE", "TRIM",
+    "UNIX_TIMESTAMP", "UPPER", "YEAR",
+];
+
+pub fn legend() -> SemanticTokensLegend {
+    SemanticTokensLegend {
+        token_types: TOKEN_TYPES.to_vec(),
+        token_modifiers: vec![],
+    }
+}
+
+/// Classifies every token and returns them in the delta-encoded form LSP expects.
+/// `additional_keywords` are the user's, highlighted like the built-in ones.
+pub fn semantic_tokens(text: &str, tokens: &[TokenWithSpan], additional_keywords: &[String]) -> Vec<SemanticToken> {
+    let line_lengths: Vec<u32> = syntax::lines(text).map(|line| line.chars().count() as u32).collect();
+
+    let mut data = Vec::new();
+    let mut previous = (0, 0);
+    for (i, token_with_span) in tokens.iter().enumerate() {
+        let Some(token_type) = classify(tokens, i, additional_keywords) else { continue };
+        let range = syntax::span_to_range(&token_with_span.span);
+
+        // Tokens may not span lines, so multi-line strings and comments are split per line
+        for line in range.start.line..=range.end.line {
+            let start = if line == range.start.line { range.start.character } else { 0 };
+            let end = if line == range.end.line {
+                range.end.character
+            } else {
+                line_lengths.get(line as usize).copied().unwrap_or(start)
+            };
+            if end <= start {
+                continue;
+            }
+
+            let (previous_line, previous_start) = previous;
+            data.push(SemanticToken {
+                delta_line: line - previous_line,
+                delta_start: if line == previous_line { start - previous_start } else { start },
+                length: end - start,
+                token_type,
+                token_modifiers_bitset: 0,
+            });
+            previous = (line, start);
+        }
+    }
+    data
+}
+
+fn classify(tokens: &[TokenWithSpan], idx: usize, additional_keywords: &[String]) -> Option<u32> {
+    match &tokens[idx].token {
+        Token::Word(w) if w.quote_style.is_some() => Some(VARIABLE),
+        Token::Word(w) => {
+            let called = (idx + 1..tokens.len())
+                .find(|&j| is_significant(&tokens[j].token))
+                .is_some_and(|j| matches!(tokens[j].token, Token::LParen));
+            let upper = w.value.to_uppercase();
+            if called && HIVE_FUNCTIONS.contains(&upper.as_str()) {
+                Some(FUNCTION)
+            } else if is_keyword(w) || is_reserved_word(&w.value) || is_additional_keyword(tokens, idx, additional_keywords) {
+                Some(KEYWORD)
+            } else if called {
+                // Anything else followed by `(` is a user-defined function
+                Some(FUNCTION)
+            } else {
+                Some(VARIABLE)
+            }
+        }
+        Token::SingleQuotedString(_) | Token::DoubleQuotedString(_) | Token::NationalStringLiteral(_)
+        | Token::HexStringLiteral(_) => Some(STRING),
+        Token::Number(_, _) => Some(NUMBER),
+        Token::Whitespace(Whitespace::SingleLineComment { .. } | Whitespace::MultiLineComment(_)) => Some(COMMENT),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn semantic_tokens_of(text: &str) -> Vec<SemanticToken> {
+        semantic_tokens(text, &syntax::tokenize(text).unwrap(), &[])
+    }
+
+    /// Decodes the delta encoding back to (line, start, length, type).
+    fn decode(data: &[SemanticToken]) -> Vec<(u32, u32, u32, u32)> {
+        let mut line = 0;
+        let mut start = 0;
+        data.iter()
+            .map(|t| {
+                if t.delta_line > 0 {
+                    line += t.delta_line;
+                    start = t.delta_start;
+                } else {
+                    start += t.delta_start;
+                }
+                (line, start, t.length, t.token_type)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_classification() {
+        let tokens = decode(&semantic_tokens_of("SELECT SUBSTR(name, 1), my_udf(x), 42 FROM t -- done"));
+        assert_eq!(tokens, vec![
+            (0, 0, 6, KEYWORD),
+            (0, 7, 6, FUNCTION),
+            (0, 14, 4, VARIABLE),
+            (0, 20, 1, NUMBER),
+            (0, 24, 6, FUNCTION),
+            (0, 31, 1, VARIABLE),
+            (0, 35, 2, NUMBER),
+            (0, 38, 4, KEYWORD),
+            (0, 43, 1, VARIABLE),
+            (0, 45, 7, COMMENT),
+        ]);
+    }
+
+    #[test]
+    fn test_additional_keywords() {
+        let text = "SELECT a FROM t QUALIFY x";
+        let tokens = decode(&semantic_tokens(text, &syntax::tokenize(text).unwrap(), &["qualify".to_string()]));
+        assert_eq!(tokens[4], (0, 16, 7, KEYWORD));
+        assert_eq!(decode(&semantic_tokens_of(text))[4], (0, 16, 7, VARIABLE));
+    }
+
+    #[test]
+    fn test_multiline_tokens_are_split() {
+        let tokens = decode(&semantic_tokens_of("SELECT 'a\nbc' /* x\n y */"));
+        assert_eq!(tokens, vec![
+            (0, 0, 6, KEYWORD),
+            (0, 7, 2, STRING),
+            (1, 0, 3, STRING),
+            (1, 4, 4, COMMENT),
+            (2, 0, 5, COMMENT),
+        ]);
+    }
+}
@@ -0,0 +1,132 @@
+use ropey::Rope;
+use sqlparser::tokenizer::Token;
+use tower_lsp::lsp_types::{
+    Documentation, ParameterInformation, ParameterLabel, Position, SignatureHelp, SignatureInformation,
+};
+use crate::document::position_to_char;
+use crate::syntax::{self, is_significant};
+
+/// Common Hive UDFs: name, parameter labels and a one-line description.
+/// A trailing `...` parameter repeats.
+pub(crate) const FUNCTIONS: &[(&str, &[&str], &str)] = &[
+    ("ARRAY_CONTAINS", &["array", "value"], "Returns true if the array contains value."),
+    ("COALESCE", &["value1", "value2", "..."], "Returns the first non-NULL value."),
+    ("CONCAT", &["str1", "str2", "..."], "Concatenates the strings."),
+    ("CONCAT_WS", &["separator", "str1", "str2", "..."], "Concatenates the strings with a separator."),
+    ("DATE_ADD", &["start_date", "num_days"], "Adds num_days days to start_date."),
+    ("DATE_FORMAT", &["date", "format"], "Formats a date/timestamp using a Java date format."),
+    ("DATE_SUB", &["start_date", "num_days"], "Subtracts num_days days from start_date."),
+    ("DATEDIFF", &["enddate", "startdate"], "Returns the number of days from startdate to enddate."),
+    ("FROM_UNIXTIME", &["unix_time", "[format]"], "Converts seconds since the epoch to a timestamp string."),
+    ("GET_JSON_OBJECT", &["json_string", "path"], "Extracts a JSON object from a JSON string by path."),
+    ("IF", &["condition", "value_true", "value_false_or_null"], "Returns value_true if condition is true, otherwise the third argument."),
+    ("INSTR", &["str", "substr"], "Returns the 1-based position of the first occurrence of substr in str."),
+    ("LENGTH", &["str"], "Returns the length of the string."),
+    ("LOWER", &["str"], "Converts the string to lower case."),
+    ("LPAD", &["str", "len", "pad"], "Left-pads str with pad to a length of len."),
+    ("NVL", &["value", "default_value"], "Returns default_value if value is NULL."),
+    ("REGEXP_EXTRACT", &["subject", "pattern", "[index]"], "Returns the group at index of the first regex match."),
+    ("REGEXP_REPLACE", &["initial_string", "pattern", "replacement"], "Replaces all regex matches with replacement."),
+    ("ROUND", &["x", "[d]"], "Rounds x to d decimal places."),
+    ("RPAD", &["str", "len", "pad"], "Right-pads str with pad to a length of len."),
+    ("SIZE", &["collection"], "Returns the number of elements in an array or map."),
+    ("SPLIT", &["str", "pattern"], "Splits str around matches of the regex pattern."),
+    ("SUBSTR", &["str", "pos", "[len]"], "Returns the substring of str starting at pos, optionally of length len."),
+    ("SUBSTRING", &["str", "pos", "[len]"], "Returns the substring of str starting at pos, optionally of length len."),
+    ("TO_DATE", &["timestamp"], "Returns the date part of a timestamp string."),
+    ("TRIM", &["str"], "Removes leading and trailing spaces."),
+    ("UNIX_TIMESTAMP", &["[date]", "[pattern]"], "Converts a date string to seconds since the epoch."),
+    ("UPPER", &["str"], "Converts the string to upper case."),
+];
+
+/// Returns the signature of the built-in call enclosing `position`, with the argument under the cursor active.
+pub fn signature_help(rope: &Rope, position: Position) -> Option<SignatureHelp> {
+    let offset = position_to_char(rope, position);
+    let text = rope.slice(..offset).to_string();
+    let tokens = syntax::tokenize(&text)?;
+
+    // Walk back to the unmatched `(`, counting top-level commas on the way
+    let mut depth = 0;
+    let mut commas = 0;
+    let mut open_idx = None;
+    for (i, token_with_span) in tokens.iter().enumerate().rev() {
+        match token_with_span.token {
+            Token::RParen => depth += 1,
+            Token::LParen if depth == 0 => {
+                open_idx = Some(i);
+                break;
+            }
+            Token::LParen => depth -= 1,
+            Token::Comma if depth == 0 => commas += 1,
+            Token::SemiColon => return None,
+            _ => {}
+        }
+    }
+
+    let name_idx = (0..open_idx?).rev().find(|&j| is_significant(&tokens[j].token))?;
+    let Token::Word(word) = &tokens[name_idx].token else { return None };
+    let &(name, params, description) = FUNCTIONS.iter().find(|(name, _, _)| name.eq_ignore_ascii_case(&word.value))?;
+
+    let label = format!("{}({})", name, params.join(", "));
+    let parameters = params
+        .iter()
+        .map(|param| ParameterInformation {
+            label: ParameterLabel::Simple(param.to_string()),
+            documentation: None,
+        })
+        .collect();
+    // Variadic functions keep highlighting the repeating parameter
+    let active_parameter = (commas as u32).min(params.len() as u32 - 1);
+
+    Some(SignatureHelp {
+        signatures: vec![SignatureInformation {
+            label,
+            documentation: Some(Documentation::String(description.to_string())),
+            parameters: Some(parameters),
+            active_parameter: Some(active_parameter),
+        }],
+        active_signature: Some(0),
+        active_parameter: Some(active_parameter),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn help_at_end(text: &str) -> Option<SignatureHelp> {
+        let rope = Rope::from_str(text);
+        let last_line = rope.len_lines() - 1;
+        let position = Position {
+            line: last_line as u32,
+            character: rope.line(last_line).len_chars() as u32,
+        };
+        signature_help(&rope, position)
+    }
+
+    #[test]
+    fn test_active_parameter() {
+        let help = help_at_end("SELECT SUBSTR(").unwrap();
+        assert_eq!(help.signatures[0].label, "SUBSTR(str, pos, [len])");
+        assert_eq!(help.active_parameter, Some(0));
+
+        // Commas inside nested calls don't count
+        let help = help_at_end("SELECT substr(concat(a, b), 1,\n  ").unwrap();
+        assert_eq!(help.signatures[0].label, "SUBSTR(str, pos, [len])");
+        assert_eq!(help.active_parameter, Some(2));
+    }
+
+    #[test]
+    fn test_innermost_call_and_variadic() {
+        let help = help_at_end("SELECT NVL(COALESCE(a, b, c, d").unwrap();
+        assert_eq!(help.signatures[0].label, "COALESCE(value1, value2, ...)");
+        assert_eq!(help.active_parameter, Some(2));
+    }
+
+    #[test]
+    fn test_no_help_outside_known_call() {
+        assert!(help_at_end("SELECT a FROM t WHERE x IN (").is_none());
+        assert!(help_at_end("SELECT SUBSTR(a, 1) ").is_none());
+        assert!(help_at_end("SELECT my_udf(").is_none());
+    }
+}
@@ -0,0 +1,232 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use regex::Regex;
+use sqlparser::tokenizer::{Token, Whitespace};
+use tower_lsp::lsp_types::{Diagnostic, NumberOrString};
+use crate::syntax;
+
+/// Which diagnostics a directive applies to: every code, or only the listed ones.
+#[derive(Debug, Clone, PartialEq)]
+enum Scope {
+    All,
+    Codes(Vec<String>),
+}
+
+/// Suppression state for a single line.
+#[derive(Debug, Clone, Default)]
+struct Disabled {
+    all: usize,
+    codes: HashMap<String, usize>,
+}
+
+impl Disabled {
+    fn add(&mut self, scope: &Scope) {
+        match scope {
+            Scope::All => self.all += 1,
+            Scope::Codes(codes) => {
+                for code in codes {
+                    *self.codes.entry(code.clone()).or_default() += 1;
+                }
+            }
+        }
+    }
+
+    fn remove(&mut self, scope: &Scope) {
+        match scope {
+            Scope::All => self.all = self.all.saturating_sub(1),
+            Scope::Codes(codes) => {
+                for code in codes {
+                    if let Some(count) = self.codes.get_mut(code) {
+                        *count = count.saturating_sub(1);
+                    }
+                }
+            }
+        }
+    }
+
+    fn suppresses(&self, code: Option<&str>) -> bool {
+        self.all > 0 || code.is_some_and(|c| self.codes.get(c).is_some_and(|&count| count > 0))
+    }
+}
+
+/// Drops diagnostics silenced by `-- hqlint-disable...` comments.
+///
+/// Supported directives:
+/// - `-- hqlint-disable-line [codes]` silences the line the comment is on
+/// - `-- hqlint-disable-next-line [codes]` silences the following line
+/// - `-- hqlint-disable [codes]` / `-- hqlint-enable [codes]` open and close a region; regions nest
+///
+/// Without codes a directive applies to every diagnostic. Directives are only read from `--`
+/// comments, so one quoted in a string literal does nothing.
+pub fn filter_suppressed(text: &str, diagnostics: Vec<Diagnostic>) -> Vec<Diagnostic> {
+    if !text.contains("hqlint-") {
+        return diagnostics;
+    }
+
+    let mut directives = directives(text);
+    let mut lines: Vec<Disabled> = Vec::new();
+    let mut region = Disabled::default();
+    let mut next_line: Vec<Scope> = Vec::new();
+
+    for n in 0..syntax::lines(text).count() {
+        let directive = directives.remove(&n);
+        if let Some((kind, scope)) = &directive {
+            match kind.as_str() {
+                "disable" => region.add(scope),
+                "enable" => region.remove(scope),
+                _ => {}
+            }
+        }
+
+        let mut state = region.clone();
+        for scope in next_line.drain(..) {
+            state.add(&scope);
+        }
+        if let Some((kind, scope)) = directive {
+            match kind.as_str() {
+                "disable-line" => state.add(&scope),
+                "disable-next-line" => next_line.push(scope),
+                _ => {}
+            }
+        }
+        lines.push(state);
+    }
+
+    diagnostics
+        .into_iter()
+        .filter(|d| {
+            let code = match &d.code {
+                Some(NumberOrString::String(c)) => Some(c.as_str()),
+                _ => None,
+            };
+            let line = d.range.start.line as usize;
+            match lines.get(line) {
+                Some(state) => !state.suppresses(code),
+                None => !region.suppresses(code),
+            }
+        })
+        .collect()
+}
+
+/// The directive of each line that has one, keyed by zero-based line number. Text that doesn't
+/// tokenize is scanned line by line instead.
+fn directives(text: &str) -> HashMap<usize, (String, Scope)> {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    let re = RE.get_or_init(|| {
+        Regex::new(r"--\s*hqlint-(disable-next-line|disable-line|disable|enable)\b([^\n]*)").unwrap()
+    });
+    let directive = |comment: &str| re.captures(comment).map(|cap| (cap[1].to_string(), parse_scope(&cap[2])));
+
+    let Some(tokens) = syntax::tokenize(text) else {
+        return syntax::lines(text)
+            .enumerate()
+            .filter_map(|(n, line)| Some((n, directive(line)?)))
+            .collect();
+    };
+    tokens
+        .iter()
+        .filter_map(|t| match &t.token {
+            Token::Whitespace(Whitespace::SingleLineComment { comment, prefix }) => {
+                Some((t.span.start.line as usize - 1, directive(&format!("{}{}", prefix, comment))?))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+fn parse_scope(rest: &str) -> Scope {
+    let codes: Vec<String> = rest
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|c| !c.is_empty())
+        .map(|c| c.to_string())
+        .collect();
+    if codes.is_empty() { Scope::All } else { Scope::Codes(codes) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tower_lsp::lsp_types::{Position, Range};
+
+    fn diagnostic(line: u32, code: &str) -> Diagnostic {
+        Diagnostic {
+            range: Range {
+                start: Position { line, character: 0 },
+                end: Position { line, character: 1 },
+            },
+            code: Some(NumberOrString::String(code.to_string())),
+            ..Default::default()
+        }
+    }
+
+    fn remaining(text: &str, diagnostics: Vec<Diagnostic>) -> Vec<(u32, String)> {
+        filter_suppressed(text, diagnostics)
+            .into_iter()
+            .map(|d| match d.code {
+                Some(NumberOrString::String(c)) => (d.range.start.line, c),
+                _ => (d.range.start.line, String::new()),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_disable_line_and_next_line() {
+        let text = "select a -- hqlint-disable-line keyword-casing\n\
+                    -- hqlint-disable-next-line missing-comma\n\
+                    SELECT a b\n\
+                    SELECT c d -- hqlint-disable-line";
+        let diagnostics = vec![
+            diagnostic(0, "keyword-casing"),
+            diagnostic(0, "missing-semicolon"),
+            diagnostic(2, "missing-comma"),
+            diagnostic(2, "keyword-casing"),
+            diagnostic(3, "missing-comma"),
+            diagnostic(3, "trailing-whitespace"),
+        ];
+        assert_eq!(
+            remaining(text, diagnostics),
+            vec![(0, "missing-semicolon".to_string()), (2, "keyword-casing".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_nested_regions() {
+        let text = "-- hqlint-disable\n\
+                    a\n\
+                    -- hqlint-disable missing-comma\n\
+                    b\n\
+                    -- hqlint-enable\n\
+                    c\n\
+                    -- hqlint-enable missing-comma\n\
+                    d";
+        let diagnostics = vec![
+            diagnostic(1, "keyword-casing"),
+            diagnostic(3, "missing-comma"),
+            diagnostic(5, "missing-comma"),
+            diagnostic(5, "keyword-casing"),
+            diagnostic(7, "missing-comma"),
+        ];
+        assert_eq!(
+            remaining(text, diagnostics),
+            vec![(5, "keyword-casing".to_string()), (7, "missing-comma".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_directives_in_string_literals_are_ignored() {
+        let text = "SELECT '-- hqlint-disable' AS a FROM t\n\
+                    SELECT 'x -- hqlint-disable-line' b\n\
+                    SELECT c d -- hqlint-disable-line";
+        let diagnostics = vec![
+            diagnostic(1, "missing-comma"),
+            diagnostic(2, "missing-comma"),
+        ];
+        assert_eq!(remaining(text, diagnostics), vec![(1, "missing-comma".to_string())]);
+    }
+
+    #[test]
+    fn test_no_directives_keeps_everything() {
+        let diagnostics = vec![diagnostic(0, "keyword-casing")];
+        assert_eq!(remaining("select 1", diagnostics).len(), 1);
+    }
+}
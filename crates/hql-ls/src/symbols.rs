@@ -0,0 +1,239 @@
+use sqlparser::tokenizer::{Token, TokenWithSpan};
+use tower_lsp::lsp_types::{DocumentSymbol, Location, Range, SymbolInformation, SymbolKind, Url};
+use crate::syntax::{self, is_keyword, is_significant, span_to_range};
+
+/// Builds the outline: one symbol per top-level statement, with CTEs nested under WITH queries.
+pub fn document_symbols(tokens: &[TokenWithSpan]) -> Vec<DocumentSymbol> {
+    let statements = syntax::split_statements(tokens);
+    let eof = tokens.last().map(|t| span_to_range(&t.span).end).unwrap_or_default();
+
+    let mut symbols = Vec::new();
+    for (n, statement) in statements.iter().enumerate() {
+        let first = &tokens[statement.start];
+        let last = &tokens[statement.end - 1];
+        let terminated = matches!(last.token, Token::SemiColon);
+        let is_last = n + 1 == statements.len();
+
+        let range = Range {
+            start: span_to_range(&first.span).start,
+            // An unterminated final statement runs to the end of the file
+            end: if !terminated && is_last { eof } else { span_to_range(&last.span).end },
+        };
+
+        let children: Vec<DocumentSymbol> = syntax::collect_ctes(tokens, statement)
+            .iter()
+            .map(|cte| {
+                let name_range = span_to_range(&tokens[cte.name_idx].span);
+                symbol(
+                    cte.name.clone(),
+                    Some("CTE".to_string()),
+                    SymbolKind::STRUCT,
+                    Range { start: name_range.start, end: span_to_range(&tokens[cte.close_idx].span).end },
+                    name_range,
+                    None,
+                )
+            })
+            .collect();
+
+        symbols.push(symbol(
+            statement_label(tokens, statement),
+            None,
+            statement_kind(&first.token),
+            range,
+            span_to_range(&first.span),
+            (!children.is_empty()).then_some(children),
+        ));
+    }
+
+    symbols
+}
+
+/// Most symbols a workspace query returns. Clients re-query as the user types, so a short
+/// query matching most of a large workspace is cut off rather than serialized in full.
+pub const MAX_WORKSPACE_SYMBOLS: usize = 256;
+
+/// Flattens each document's outline and keeps the symbols whose name contains `query`
+/// (case-insensitive), up to `MAX_WORKSPACE_SYMBOLS`. CTEs name their statement as container.
+pub fn workspace_symbols(
+    documents: impl IntoIterator<Item = (Url, Vec<DocumentSymbol>)>,
+    query: &str,
+) -> Vec<SymbolInformation> {
+    let query = query.to_lowercase();
+    let mut matches = Vec::new();
+    for (uri, symbols) in documents {
+        collect_matches(&uri, &symbols, None, &query, &mut matches);
+        if matches.len() >= MAX_WORKSPACE_SYMBOLS {
+            matches.truncate(MAX_WORKSPACE_SYMBOLS);
+            break;
+        }
+    }
+    matches
+}
+
+#[allow(deprecated)] // `SymbolInformation::deprecated` must still be initialized
+fn collect_matches(
+    uri: &Url,
+    symbols: &[DocumentSymbol],
+    container: Option<&str>,
+    query: &str,
+    matches: &mut Vec<SymbolInformation>,
+) {
+    for symbol in symbols {
+        if symbol.name.to_lowercase().contains(query) {
+            matches.push(SymbolInformation {
+                name: symbol.name.clone(),
+                kind: symbol.kind,
+                tags: None,
+                deprecated: None,
+                location: Location { uri: uri.clone(), range: symbol.selection_range },
+                container_name: container.map(str::to_string),
+            });
+        }
+        if let Some(children) = &symbol.children {
+            collect_matches(uri, children, Some(&symbol.name), query, matches);
+        }
+    }
+}
+
+/// Labels a statement by its leading keywords plus the object it targets, e.g. "CREATE TABLE sales".
+fn statement_label(tokens: &[TokenWithSpan], statement: &std::ops::Range<usize>) -> String {
+    let label_keywords = [
+        "CREATE", "OR", "REPLACE", "EXTERNAL", "TEMPORARY", "MATERIALIZED", "TABLE", "VIEW", "DATABASE",
+        "SCHEMA", "FUNCTION", "INSERT", "INTO", "OVERWRITE", "DROP", "ALTER", "TRUNCATE", "USE", "SET",
+        "DESCRIBE", "SHOW", "MSCK", "REPAIR", "ANALYZE", "LOAD", "DATA",
+    ];
+    let skipped = ["IF", "NOT", "EXISTS"];
+
+    let significant: Vec<&TokenWithSpan> = statement
+        .clone()
+        .map(|i| &tokens[i])
+        .filter(|t| is_significant(&t.token))
+        .collect();
+
+    let mut parts = Vec::new();
+    let mut iter = significant.iter().peekable();
+    while let Some(t) = iter.peek() {
+        match &t.token {
+            Token::Word(w) if w.quote_style.is_none() && skipped.contains(&w.value.to_uppercase().as_str()) => {}
+            Token::Word(w) if w.quote_style.is_none() && label_keywords.contains(&w.value.to_uppercase().as_str()) => {
+                parts.push(w.value.to_uppercase());
+            }
+            _ => break,
+        }
+        iter.next();
+    }
+
+    if parts.is_empty() {
+        return significant
+            .first()
+            .map(|t| t.token.to_string().to_uppercase())
+            .unwrap_or_default();
+    }
+
+    // Append the target name (`db.table` included) for statements that have one
+    if parts.len() > 1 || matches!(parts[0].as_str(), "USE" | "SET") {
+        let mut name = String::new();
+        for t in iter {
+            match &t.token {
+                Token::Word(w) => name.push_str(&w.value),
+                Token::Period => name.push('.'),
+                _ => break,
+            }
+        }
+        if !name.is_empty() {
+            parts.push(name);
+        }
+    }
+
+    parts.join(" ")
+}
+
+fn statement_kind(token: &Token) -> SymbolKind {
+    if is_keyword(token, "CREATE") || is_keyword(token, "ALTER") || is_keyword(token, "DROP") {
+        SymbolKind::CLASS
+    } else if is_keyword(token, "SET") {
+        SymbolKind::PROPERTY
+    } else if is_keyword(token, "USE") {
+        SymbolKind::NAMESPACE
+    } else {
+        SymbolKind::FUNCTION
+    }
+}
+
+#[allow(deprecated)] // `DocumentSymbol::deprecated` must still be initialized
+fn symbol(
+    name: String,
+    detail: Option<String>,
+    kind: SymbolKind,
+    range: Range,
+    selection_range: Range,
+    children: Option<Vec<DocumentSymbol>>,
+) -> DocumentSymbol {
+    DocumentSymbol {
+        name,
+        detail,
+        kind,
+        tags: None,
+        deprecated: None,
+        range,
+        selection_range,
+        children,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tower_lsp::lsp_types::Position;
+
+    #[test]
+    fn test_statement_symbols() {
+        let text = "USE analytics;\nCREATE TABLE IF NOT EXISTS db.sales (id INT);\nSELECT *\nFROM sales;";
+        let symbols = document_symbols(&syntax::tokenize(text).unwrap());
+        let names: Vec<&str> = symbols.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["USE analytics", "CREATE TABLE db.sales", "SELECT"]);
+        assert_eq!(symbols[2].range.start, Position { line: 2, character: 0 });
+        assert_eq!(symbols[2].range.end, Position { line: 3, character: 11 });
+    }
+
+    #[test]
+    fn test_cte_children() {
+        let text = "WITH a AS (SELECT 1), b AS (SELECT * FROM a)\nSELECT * FROM b;";
+        let symbols = document_symbols(&syntax::tokenize(text).unwrap());
+        assert_eq!(symbols.len(), 1);
+        let children = symbols[0].children.as_ref().unwrap();
+        let names: Vec<&str> = children.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["a", "b"]);
+    }
+
+    #[test]
+    fn test_unterminated_statement_ends_at_eof() {
+        let text = "SELECT 1;\nSELECT *\nFROM t\n\n";
+        let symbols = document_symbols(&syntax::tokenize(text).unwrap());
+        assert_eq!(symbols[1].range.end, Position { line: 4, character: 0 });
+    }
+
+    #[test]
+    fn test_workspace_symbols_match_across_documents() {
+        let orders = Url::parse("file:///orders.hql").unwrap();
+        let report = Url::parse("file:///report.hql").unwrap();
+        let documents = vec![
+            (orders.clone(), document_symbols(&syntax::tokenize("CREATE TABLE db.Sales (id INT);").unwrap())),
+            (report.clone(), document_symbols(&syntax::tokenize("WITH sales_2024 AS (SELECT 1)\nSELECT * FROM sales_2024;").unwrap())),
+        ];
+
+        let symbols = workspace_symbols(documents, "SALES");
+        let found: Vec<(&str, &Url, Option<&str>)> =
+            symbols.iter().map(|s| (s.name.as_str(), &s.location.uri, s.container_name.as_deref())).collect();
+        assert_eq!(found, vec![("CREATE TABLE db.Sales", &orders, None), ("sales_2024", &report, Some("WITH"))]);
+        assert_eq!(symbols[1].location.range.start, Position { line: 0, character: 5 });
+    }
+
+    #[test]
+    fn test_workspace_symbols_are_capped() {
+        let text = "SELECT 1;\n".repeat(MAX_WORKSPACE_SYMBOLS + 10);
+        let uri = Url::parse("file:///many.hql").unwrap();
+        let symbols = workspace_symbols([(uri, document_symbols(&syntax::tokenize(&text).unwrap()))], "select");
+        assert_eq!(symbols.len(), MAX_WORKSPACE_SYMBOLS);
+    }
+}
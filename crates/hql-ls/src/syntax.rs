@@ -0,0 +1,291 @@
+use std::collections::HashMap;
+use std::ops::Range as IndexRange;
+use sqlparser::dialect::{dialect_from_str, Dialect, HiveDialect, MySqlDialect};
+use sqlparser::tokenizer::{Span, Token, TokenWithSpan, Tokenizer};
+use tower_lsp::lsp_types::{Position, Range};
+
+/// Tokenizes `text` with the Hive dialect, returning `None` if the tokenizer fails.
+pub fn tokenize(text: &str) -> Option<Vec<TokenWithSpan>> {
+    Tokenizer::new(&HiveDialect {}, text).tokenize_with_location().ok()
+}
+
+/// The sqlparser dialect named by the `dialect` setting, falling back to Hive for unknown names.
+pub fn dialect(name: &str) -> Box<dyn Dialect> {
+    dialect_from_str(name).unwrap_or_else(|| Box::new(HiveDialect {}))
+}
+
+/// True if the `dialect` setting means Hive, including unknown names.
+pub fn is_hive(name: &str) -> bool {
+    dialect(name).is::<HiveDialect>()
+}
+
+/// True if `||` concatenates strings under the `dialect` setting; MySQL reads it as OR.
+pub fn pipe_is_concat(name: &str) -> bool {
+    !dialect(name).is::<MySqlDialect>()
+}
+
+/// Splits `text` into lines the way the tokenizer numbers them, without their `\n` or
+/// `\r\n` terminators. Unlike `str::lines`, a final line ending in a bare `\r` loses it too.
+pub fn lines(text: &str) -> impl Iterator<Item = &str> {
+    text.split('\n').map(|line| line.strip_suffix('\r').unwrap_or(line))
+}
+
+/// Converts a char-based column on `line`, as the tokenizer counts them, into the UTF-16
+/// code units LSP positions use. Columns past the end of the line count one unit each.
+pub fn utf16_column(line: &str, column: u32) -> u32 {
+    let mut chars = 0;
+    let mut units = 0;
+    for c in line.chars().take(column as usize) {
+        chars += 1;
+        units += c.len_utf16() as u32;
+    }
+    units + column - chars
+}
+
+/// Splits the token stream into top-level statements.
+///
+/// Each range starts at the statement's first significant token and ends after its
+/// terminating semicolon, or at the last significant token of an unterminated statement.
+pub fn split_statements(tokens: &[TokenWithSpan]) -> Vec<IndexRange<usize>> {
+    let mut statements = Vec::new();
+    let mut start: Option<usize> = None;
+    let mut last_significant = 0;
+    let mut depth = 0;
+
+    for (i, token_with_span) in tokens.iter().enumerate() {
+        if !is_significant(&token_with_span.token) {
+            continue;
+        }
+        match token_with_span.token {
+            Token::LParen => depth += 1,
+            Token::RParen if depth > 0 => depth -= 1,
+            _ => {}
+        }
+
+        if start.is_none() {
+            start = Some(i);
+        }
+        last_significant = i;
+
+        if matches!(token_with_span.token, Token::SemiColon) && depth == 0
+            && let Some(s) = start.take()
+        {
+            statements.push(s..i + 1);
+        }
+    }
+
+    if let Some(s) = start {
+        statements.push(s..last_significant + 1);
+    }
+    statements
+}
+
+/// The statement whose tokens span `position`.
+pub fn statement_at(tokens: &[TokenWithSpan], position: Position) -> Option<IndexRange<usize>> {
+    split_statements(tokens).into_iter().find(|s| {
+        let range = Range {
+            start: span_to_range(&tokens[s.start].span).start,
+            end: span_to_range(&tokens[s.end - 1].span).end,
+        };
+        range_contains(&range, position)
+    })
+}
+
+/// A common table expression: `name AS ( ... )` inside a WITH clause.
+#[derive(Debug, Clone)]
+pub struct Cte {
+    pub name: String,
+    pub name_idx: usize,
+    /// `None` when the body follows the name directly, which Hive rejects
+    pub as_idx: Option<usize>,
+    pub close_idx: usize,
+}
+
+/// Parses the top-level WITH clause of a statement into its CTE definitions.
+pub fn collect_ctes(tokens: &[TokenWithSpan], statement: &IndexRange<usize>) -> Vec<Cte> {
+    let mut ctes = Vec::new();
+    let significant: Vec<usize> = statement
+        .clone()
+        .filter(|&i| is_significant(&tokens[i].token))
+        .collect();
+
+    let mut depth = 0;
+    let mut with_pos = None;
+    for (pos, &i) in significant.iter().enumerate() {
+        match &tokens[i].token {
+            Token::LParen => depth += 1,
+            Token::RParen => depth -= 1,
+            token if depth == 0 && is_keyword(token, "WITH") => {
+                with_pos = Some(pos);
+                break;
+            }
+            _ => {}
+        }
+    }
+
+    let Some(mut pos) = with_pos.map(|p| p + 1) else { return ctes };
+    // `WITH RECURSIVE` isn't Hive, but don't mistake it for a CTE name
+    if significant.get(pos).is_some_and(|&i| is_keyword(&tokens[i].token, "RECURSIVE")) {
+        pos += 1;
+    }
+
+    while let Some(&name_idx) = significant.get(pos) {
+        let Token::Word(name) = &tokens[name_idx].token else { break };
+        let mut next = pos + 1;
+        let as_idx = significant.get(next).copied().filter(|&i| is_keyword(&tokens[i].token, "AS"));
+        if as_idx.is_some() {
+            next += 1;
+        }
+        let Some(&open_idx) = significant.get(next) else { break };
+        if !matches!(tokens[open_idx].token, Token::LParen) {
+            break;
+        }
+        let Some(close_idx) = matching_paren(tokens, open_idx) else { break };
+
+        ctes.push(Cte {
+            name: name.value.clone(),
+            name_idx,
+            as_idx,
+            close_idx,
+        });
+
+        // Continue with `, next_cte AS (...)`
+        let Some(after) = significant.iter().position(|&i| i > close_idx) else { break };
+        if !matches!(tokens[significant[after]].token, Token::Comma) {
+            break;
+        }
+        pos = after + 1;
+    }
+
+    ctes
+}
+
+/// Maps each table referenced by FROM/JOIN in `statement`, and each alias, to the table's
+/// name as written. Keys are lowercased, and a qualified `db.t` is also reachable as `t`.
+pub fn table_aliases(tokens: &[TokenWithSpan], statement: IndexRange<usize>) -> HashMap<String, String> {
+    let significant: Vec<&Token> = tokens[statement].iter().map(|t| &t.token).filter(|t| is_significant(t)).collect();
+    let mut aliases = HashMap::new();
+
+    for (i, token) in significant.iter().enumerate() {
+        if !is_keyword(token, "FROM") && !is_keyword(token, "JOIN") {
+            continue;
+        }
+
+        // `db.table`, then an optional `[AS] alias`
+        let mut pos = i + 1;
+        let mut parts = Vec::new();
+        while let Some(Token::Word(part)) = significant.get(pos) {
+            parts.push(part.value.clone());
+            if !matches!(significant.get(pos + 1), Some(Token::Period)) {
+                break;
+            }
+            pos += 2;
+        }
+        let Some(short_name) = parts.last().cloned() else { continue };
+        let name = parts.join(".");
+
+        let mut alias_pos = pos + 1;
+        if significant.get(alias_pos).is_some_and(|t| is_keyword(t, "AS")) {
+            alias_pos += 1;
+        }
+        if let Some(Token::Word(alias)) = significant.get(alias_pos)
+            && (alias.quote_style.is_some() || can_be_table_alias(&alias.value))
+        {
+            aliases.insert(alias.value.to_lowercase(), name.clone());
+        }
+        aliases.insert(short_name.to_lowercase(), name.clone());
+        aliases.insert(name.to_lowercase(), name);
+    }
+
+    aliases
+}
+
+/// False for words that end a table reference rather than alias it.
+pub fn can_be_table_alias(word: &str) -> bool {
+    !TABLE_ALIAS_TERMINATORS.iter().any(|kw| word.eq_ignore_ascii_case(kw))
+}
+
+/// Words that end a `FROM`/`JOIN` table reference rather than alias it.
+const TABLE_ALIAS_TERMINATORS: &[&str] = &[
+    "WHERE", "GROUP", "ORDER", "HAVING", "LIMIT", "JOIN", "LEFT", "RIGHT", "INNER", "FULL", "OUTER",
+    "CROSS", "ON", "USING", "LATERAL", "UNION", "CLUSTER", "DISTRIBUTE", "SORT", "WINDOW", "SELECT",
+];
+
+/// Returns the index of the `)` matching the `(` at `open_idx`, or `None` if there's no `(` there.
+pub fn matching_paren(tokens: &[TokenWithSpan], open_idx: usize) -> Option<usize> {
+    if !matches!(tokens.get(open_idx)?.token, Token::LParen) {
+        return None;
+    }
+    let mut depth = 0;
+    for (j, token_with_span) in tokens.iter().enumerate().skip(open_idx) {
+        match token_with_span.token {
+            Token::LParen => depth += 1,
+            Token::RParen => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(j);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+pub fn is_significant(token: &Token) -> bool {
+    !matches!(token, Token::Whitespace(_) | Token::EOF)
+}
+
+/// True for an unquoted word equal to `keyword`, ignoring case.
+pub fn is_keyword(token: &Token, keyword: &str) -> bool {
+    matches!(token, Token::Word(w) if w.quote_style.is_none() && w.value.eq_ignore_ascii_case(keyword))
+}
+
+/// Converts a 1-based tokenizer span into a 0-based LSP range.
+pub fn span_to_range(span: &Span) -> Range {
+    Range {
+        start: Position { line: (span.start.line - 1) as u32, character: (span.start.column - 1) as u32 },
+        end: Position { line: (span.end.line - 1) as u32, character: (span.end.column - 1) as u32 },
+    }
+}
+
+pub fn range_contains(range: &Range, position: Position) -> bool {
+    range.start <= position && position <= range.end
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_statements() {
+        let tokens = tokenize("SELECT 1;\n\nSELECT (2; 3)\nFROM t").unwrap();
+        let statements = split_statements(&tokens);
+        assert_eq!(statements.len(), 2);
+        let last = &statements[1];
+        assert_eq!(tokens[last.end - 1].token.to_string(), "t");
+    }
+
+    #[test]
+    fn test_lines_strip_crlf() {
+        let lines: Vec<&str> = lines("SELECT 1 \r\nFROM t;\r").collect();
+        assert_eq!(lines, vec!["SELECT 1 ", "FROM t;"]);
+    }
+
+    #[test]
+    fn test_utf16_column() {
+        assert_eq!(utf16_column("-- 😀 é\tx", 7), 8);
+        assert_eq!(utf16_column("ab", 4), 4);
+    }
+
+    #[test]
+    fn test_collect_ctes() {
+        let tokens = tokenize("WITH a AS (SELECT 1), b (SELECT * FROM a)\nSELECT * FROM b;").unwrap();
+        let statements = split_statements(&tokens);
+        let ctes = collect_ctes(&tokens, &statements[0]);
+        let names: Vec<&str> = ctes.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["a", "b"]);
+        assert!(ctes[0].as_idx.is_some());
+        assert!(ctes[1].as_idx.is_none());
+    }
+}
@@ -0,0 +1,176 @@
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+/// A scratch workspace under the system temp dir, removed on drop.
+struct Workspace(PathBuf);
+
+impl Workspace {
+    fn new(name: &str, config: Option<&str>) -> Self {
+        let root = std::env::temp_dir().join(format!("hql-ls-cli-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(root.join("queries")).unwrap();
+        if let Some(config) = config {
+            std::fs::write(root.join(".hqlint.toml"), config).unwrap();
+        }
+        Self(root)
+    }
+}
+
+impl Drop for Workspace {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Runs `hql-ls` with `args` and returns its stdout, stderr and exit code.
+fn run_files(args: &[&str]) -> (String, String, i32) {
+    let output = Command::new(env!("CARGO_BIN_EXE_hql-ls")).args(args).output().unwrap();
+    (
+        String::from_utf8(output.stdout).unwrap(),
+        String::from_utf8(output.stderr).unwrap(),
+        output.status.code().unwrap(),
+    )
+}
+
+/// Pipes `input` through `hql-ls` and returns its stdout and exit code.
+fn run(args: &[&str], input: &str) -> (String, i32) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_hql-ls"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+    let output = child.wait_with_output().unwrap();
+    (String::from_utf8(output.stdout).unwrap(), output.status.code().unwrap())
+}
+
+#[test]
+fn stdin_filepath_applies_workspace_config() {
+    let workspace = Workspace::new("lint", Some("[linting.rules]\nkeywordCasing = true\n"));
+    // The file doesn't exist; only its location matters for finding the config
+    let path = workspace.0.join("queries").join("report.hql");
+
+    let (stdout, code) = run(&["--stdin-filepath", path.to_str().unwrap()], "select 1;\n");
+    assert_eq!(code, 0);
+    assert!(stdout.contains("report.hql:1:1: warning: Keyword 'select' should be uppercase [keyword-casing]"), "{}", stdout);
+
+    let (stdout, code) = run(&["--stdin-filepath", path.to_str().unwrap()], "SELECT (1;\n");
+    assert_eq!(code, 1);
+    assert!(stdout.contains("error"), "{}", stdout);
+}
+
+#[test]
+fn stdin_filepath_without_config_uses_defaults() {
+    let workspace = Workspace::new("defaults", None);
+    let path = workspace.0.join("report.hql");
+
+    let (stdout, code) = run(&["--stdin-filepath", path.to_str().unwrap()], "select 1;\n");
+    assert_eq!(code, 0);
+    assert!(!stdout.contains("keyword-casing"), "{}", stdout);
+}
+
+#[test]
+fn stdin_filepath_formats_with_workspace_config() {
+    let path_in = |workspace: &Workspace| workspace.0.join("queries").join("report.hql");
+
+    let workspace = Workspace::new("format", None);
+    let (stdout, code) = run(&["--stdin-filepath", path_in(&workspace).to_str().unwrap(), "--format"], "select a from t;");
+    assert_eq!(code, 0);
+    assert!(stdout.starts_with("SELECT"), "{}", stdout);
+
+    // Disabled formatting passes the buffer through untouched
+    let workspace = Workspace::new("no-format", Some("[formatting]\nenabled = false\n"));
+    let (stdout, _) = run(&["--stdin-filepath", path_in(&workspace).to_str().unwrap(), "--format"], "select a from t;");
+    assert_eq!(stdout, "select a from t;");
+}
+
+#[test]
+fn error_on_promotes_warnings() {
+    let workspace = Workspace::new("error-on", None);
+    let path = workspace.0.join("report.hql");
+    let sql = "SELECT a FROM x, y;\n";
+
+    let (stdout, code) = run(&["--stdin-filepath", path.to_str().unwrap()], sql);
+    assert_eq!(code, 0);
+    assert!(stdout.contains("warning: Comma-separated tables with no join condition form a cross join [cartesian-join]"), "{}", stdout);
+
+    let (stdout, code) = run(&["--stdin-filepath", path.to_str().unwrap(), "--error-on", "cartesian-join"], sql);
+    assert_eq!(code, 1);
+    assert!(stdout.contains("error: Comma-separated tables with no join condition form a cross join [cartesian-join]"), "{}", stdout);
+}
+
+#[test]
+fn lint_files_reports_to_stderr() {
+    let workspace = Workspace::new("lint-files", Some("[linting.rules]\nkeywordCasing = true\n"));
+    let clean = workspace.0.join("queries").join("clean.hql");
+    let broken = workspace.0.join("queries").join("broken.hql");
+    std::fs::write(&clean, "SELECT 1;\n").unwrap();
+    std::fs::write(&broken, "select a, FROM t;\n").unwrap();
+
+    let (stdout, stderr, code) = run_files(&["lint", clean.to_str().unwrap()]);
+    assert_eq!((stdout.as_str(), stderr.as_str(), code), ("", "", 0));
+
+    let (_, stderr, code) = run_files(&["lint", clean.to_str().unwrap(), broken.to_str().unwrap()]);
+    assert_eq!(code, 1);
+    assert!(stderr.contains("broken.hql:1:1: warning: Keyword 'select' should be uppercase [keyword-casing]"), "{}", stderr);
+    assert!(stderr.contains("broken.hql:1:9: error: Trailing comma before 'FROM' [trailing-comma]"), "{}", stderr);
+}
+
+#[test]
+fn lint_files_json_report_goes_to_stdout() {
+    let workspace = Workspace::new("lint-json", None);
+    let file = workspace.0.join("broken.hql");
+    std::fs::write(&file, "SELECT a, FROM t;\n").unwrap();
+
+    let (stdout, stderr, code) = run_files(&["lint", "--format", "json", file.to_str().unwrap()]);
+    assert_eq!(code, 1);
+    assert_eq!(stderr, "");
+    let report: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    assert_eq!(report[0]["diagnostics"][0]["code"], "trailing-comma");
+}
+
+#[test]
+fn lint_directory_skips_excluded_files() {
+    let workspace = Workspace::new("lint-exclude", None);
+    let queries = workspace.0.join("queries");
+    std::fs::create_dir_all(queries.join("generated")).unwrap();
+    std::fs::write(queries.join("report.hql"), "SELECT 1;\n").unwrap();
+    std::fs::write(queries.join("generated").join("broken.hql"), "SELECT a, FROM t;\n").unwrap();
+    std::fs::write(queries.join("notes.txt"), "SELECT a, FROM t;\n").unwrap();
+
+    let (_, stderr, code) = run_files(&["lint", queries.to_str().unwrap()]);
+    assert_eq!(code, 1);
+    assert!(stderr.contains("broken.hql:1:9: error: Trailing comma before 'FROM' [trailing-comma]"), "{}", stderr);
+
+    let (_, stderr, code) = run_files(&["lint", queries.to_str().unwrap(), "--exclude", "generated/"]);
+    assert_eq!(code, 0);
+    assert_eq!(stderr, "Skipped 1 excluded file(s)\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn lint_directory_skips_symlinked_directories() {
+    let workspace = Workspace::new("lint-symlink", None);
+    let queries = workspace.0.join("queries");
+    std::fs::write(queries.join("broken.hql"), "SELECT a, FROM t;\n").unwrap();
+    std::os::unix::fs::symlink(&queries, queries.join("loop")).unwrap();
+
+    let (_, stderr, code) = run_files(&["lint", queries.to_str().unwrap()]);
+    assert_eq!(code, 1);
+    assert_eq!(stderr.matches("broken.hql:1:9:").count(), 1, "{}", stderr);
+}
+
+#[test]
+fn rules_lists_every_rule() {
+    let (stdout, _, code) = run_files(&["rules", "--format", "json"]);
+    assert_eq!(code, 0);
+    let listing: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    let keyword_casing = listing.as_array().unwrap().iter().find(|rule| rule["id"] == "keyword-casing").unwrap();
+    assert_eq!(keyword_casing["configKey"], "keywordCasing");
+    assert_eq!(keyword_casing["defaultSeverity"], "warning");
+
+    let (stdout, _, code) = run_files(&["rules"]);
+    assert_eq!(code, 0);
+    assert!(stdout.lines().any(|line| line.starts_with("select-star ")), "{}", stdout);
+}